@@ -19,9 +19,22 @@ class RustCore {
 
     private external fun start(pattern: String)
 
+    /** start in background, listener receives responses and notifications, 0 is success. */
+    external fun startWithOptions(path: String, wsAddr: String, p2pAddr: String, listener: RustListener?): Int
+
+    /** submit the jsonrpc request, 0 is success. */
+    external fun rpcRequest(request: String): Int
+
+    /** stop the core started by startWithOptions. */
+    external fun stop()
+
     fun daemon(path: String) = start(path)
 }
 
+interface RustListener {
+    fun onMessage(message: String)
+}
+
 /** EsseCorePlugin */
 class EsseCorePlugin: FlutterPlugin, MethodCallHandler {
   /// The MethodChannel that will the communication between Flutter and native Android
@@ -1,6 +1,10 @@
 #import <Flutter/Flutter.h>
 
 void start(const char *db_path);
+typedef void (*esse_callback)(const char *message);
+int start_with_options(const char *db_path, const char *ws_addr, const char *p2p_addr, esse_callback callback);
+int rpc_request(const char *request);
+void stop(void);
 
 @interface EsseCorePlugin : NSObject<FlutterPlugin>
 @end
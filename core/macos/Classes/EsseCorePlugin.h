@@ -1 +1,5 @@
-void start(const char *db_path);
\ No newline at end of file
+void start(const char *db_path);
+typedef void (*esse_callback)(const char *message);
+int start_with_options(const char *db_path, const char *ws_addr, const char *p2p_addr, esse_callback callback);
+int rpc_request(const char *request);
+void stop(void);
\ No newline at end of file
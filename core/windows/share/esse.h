@@ -1 +1,5 @@
 extern "C" void start(const char *db_path);
+extern "C" typedef void (*esse_callback)(const char *message);
+extern "C" int start_with_options(const char *db_path, const char *ws_addr, const char *p2p_addr, esse_callback callback);
+extern "C" int rpc_request(const char *request);
+extern "C" void stop(void);
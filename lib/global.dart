@@ -9,6 +9,7 @@ class Global {
   //static String wsRpc = '192.168.50.250:8081';   // test code
   static String optionCache = 'option';
   static String addr = '0x';
+  static String rpcToken = '';
 
   static String home = '.tdn';
   static String filePath   = home + '/' + gid + '/files/';
@@ -1,5 +1,6 @@
 import 'dart:async';
 import 'dart:convert';
+import 'dart:io';
 
 import 'package:web_socket_channel/web_socket_channel.dart';
 import 'package:http/http.dart' as http;
@@ -13,8 +14,26 @@ Map jsonrpc = {
   "gid": Global.gid,
   "method": "",
   "params": [],
+  "token": "",
 };
 
+/// full rpc token, daemon saved it in the home directory.
+/// the home is absolute data directory after started, not read the relative default.
+String rpcToken() {
+  final home = Directory(Global.home);
+  if (Global.rpcToken.isEmpty && home.isAbsolute) {
+    try {
+      final lines = File(home.path + '/rpc.token').readAsLinesSync();
+      for (final line in lines) {
+        if (line.startsWith('full ')) {
+          Global.rpcToken = line.substring(5).trim();
+        }
+      }
+    } catch (_) {}
+  }
+  return Global.rpcToken;
+}
+
 class Response {
   final bool isOk;
   final List params;
@@ -26,6 +45,7 @@ class Response {
 Future<Response> httpPost(String method, List params) async {
   jsonrpc['method'] = method;
   jsonrpc['params'] = params;
+  jsonrpc['token'] = rpcToken();
   //print(json.encode(jsonrpc));
 
   try {
@@ -112,6 +132,7 @@ class WebSocketsNotifications {
     jsonrpc["method"] = method;
     jsonrpc["params"] = params;
     jsonrpc["gid"] = Global.gid;
+    jsonrpc["token"] = rpcToken();
 
     if (_channel != null) {
       _channel!.sink.add(json.encode(jsonrpc));
@@ -9,8 +9,10 @@ use tdn_did::{generate_id, Keypair, Language};
 use tdn_storage::local::{DStorage, DsValue};
 
 use crate::utils::crypto::{
-    check_pin, decrypt, decrypt_key, encrypt_key, encrypt_multiple, hash_pin,
+    check_pin, decrypt, decrypt_key, encrypt_key, encrypt_multiple, hash_pin, is_legacy,
+    is_legacy_key, pin_outdated,
 };
+use crate::utils::sql::Query;
 
 fn _lang_to_i64(lang: Language) -> i64 {
     match lang {
@@ -60,6 +62,8 @@ pub(crate) struct Account {
     pub own_height: u64,  // own data consensus height.
     pub event: EventId,
     pub datetime: i64,
+    pub idle: i64,    // auto-lock idle seconds, 0 is never.
+    pub rotated: i64, // data key rotated time, 0 is never.
     plainkey: Vec<u8>,
 }
 
@@ -89,6 +93,8 @@ impl Account {
             own_height: 0,
             wallet: String::new(),
             event: EventId::default(),
+            idle: 0,
+            rotated: 0,
             gid,
             index,
             lang,
@@ -108,6 +114,18 @@ impl Account {
         lang_from_i64(self.lang)
     }
 
+    /// the identity id derived from the mnemonic at the index.
+    pub fn derive_id(index: u32, lang: i64, mnemonic: &str, pass: &str) -> Result<GroupId> {
+        let (gid, _sk) = generate_id(
+            lang_from_i64(lang),
+            mnemonic,
+            index,
+            0, // account default multiple address index is 0.
+            if pass.len() > 0 { Some(pass) } else { None },
+        )?;
+        Ok(gid)
+    }
+
     pub fn generate(
         index: u32,
         salt: &[u8], // &[u8; 32]
@@ -166,10 +184,26 @@ impl Account {
         Ok(())
     }
 
+    /// cache the new plain key, rotated in other device.
+    pub fn cache_key(&mut self, key: Vec<u8>) {
+        self.evict_plainkey();
+        self.plainkey = key;
+    }
+
     pub fn plainkey(&self) -> String {
         hex::encode(&self.plainkey)
     }
 
+    /// when idle timeout, clear the cached plain key, need lock to login again.
+    pub fn evict_plainkey(&mut self) {
+        self.plainkey.iter_mut().for_each(|b| *b = 0);
+        self.plainkey.clear();
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.plainkey.is_empty()
+    }
+
     pub fn pin(&mut self, salt: &[u8], old: &str, new: &str) -> Result<()> {
         self.check_lock(old)?;
         self.lock = hash_pin(new)?;
@@ -180,6 +214,42 @@ impl Account {
         Ok(())
     }
 
+    /// change the data key, the secret and mnemonic encrypted again.
+    pub fn rekey(&mut self, salt: &[u8], lock: &str, key: Vec<u8>) -> Result<()> {
+        self.check_lock(lock)?;
+        let secret = decrypt(salt, lock, &self.encrypt, &self.secret)?;
+        let mnemonic = decrypt(salt, lock, &self.encrypt, &self.mnemonic)?;
+
+        self.encrypt = encrypt_key(salt, lock, &key)?;
+        self.plainkey = key;
+        let mut ebytes = encrypt_multiple(salt, lock, &self.encrypt, vec![&secret, &mnemonic])?;
+        self.mnemonic = ebytes.pop().unwrap_or(vec![]);
+        self.secret = ebytes.pop().unwrap_or(vec![]);
+        Ok(())
+    }
+
+    /// encrypt legacy ciphertexts and hash pin again by current format,
+    /// return true if account changed. the format is checked by decrypt.
+    pub fn upgrade(&mut self, salt: &[u8], lock: &str) -> Result<bool> {
+        let legacy = is_legacy_key(salt, lock, &self.encrypt)?
+            || is_legacy(salt, lock, &self.encrypt, &self.secret)?
+            || is_legacy(salt, lock, &self.encrypt, &self.mnemonic)?;
+        let outdated = pin_outdated(&self.lock);
+        if !legacy && !outdated {
+            return Ok(false);
+        }
+
+        self.check_lock(lock)?;
+        if legacy {
+            let key = decrypt_key(salt, lock, &self.encrypt)?;
+            self.rekey(salt, lock, key)?;
+        }
+        if outdated {
+            self.lock = hash_pin(lock)?;
+        }
+        Ok(true)
+    }
+
     pub fn mnemonic(&self, salt: &[u8], lock: &str) -> Result<String> {
         self.check_lock(lock)?;
         let pbytes = decrypt(salt, lock, &self.encrypt, &self.mnemonic)?;
@@ -195,6 +265,8 @@ impl Account {
     /// here is zero-copy and unwrap is safe. checked.
     fn from_values(mut v: Vec<DsValue>) -> Account {
         Account {
+            rotated: v.pop().unwrap().as_i64(),
+            idle: v.pop().unwrap().as_i64(),
             datetime: v.pop().unwrap().as_i64(),
             event: EventId::from_hex(v.pop().unwrap().as_str()).unwrap_or(EventId::default()),
             own_height: v.pop().unwrap().as_i64() as u64,
@@ -216,11 +288,11 @@ impl Account {
     }
 
     pub fn get(db: &DStorage, gid: &GroupId) -> Result<Account> {
-        let sql = format!(
-            "SELECT id, gid, indx, lang, pass, name, lock, mnemonic, secret, encrypt, avatar, wallet, pub_height, own_height, event, datetime FROM accounts WHERE gid = '{}'",
-            gid.to_hex()
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new(
+            "SELECT id, gid, indx, lang, pass, name, lock, mnemonic, secret, encrypt, avatar, wallet, pub_height, own_height, event, datetime, idle, rotated FROM accounts WHERE gid = ?",
+        )
+        .bind(gid)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             Ok(Account::from_values(values))
@@ -231,7 +303,7 @@ impl Account {
 
     pub fn all(db: &DStorage) -> Result<Vec<Account>> {
         let matrix = db.query(
-            "SELECT id, gid, indx, lang, pass, name, lock, mnemonic, secret, encrypt, avatar, wallet, pub_height, own_height, event, datetime FROM accounts ORDER BY datetime DESC",
+            "SELECT id, gid, indx, lang, pass, name, lock, mnemonic, secret, encrypt, avatar, wallet, pub_height, own_height, event, datetime, idle, rotated FROM accounts ORDER BY datetime DESC",
         )?;
         let mut accounts = vec![];
         for values in matrix {
@@ -241,81 +313,191 @@ impl Account {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let mut unique_check = db.query(&format!(
-            "SELECT id from accounts WHERE gid = '{}'",
-            self.gid.to_hex()
-        ))?;
+        let mut unique_check = Query::new("SELECT id from accounts WHERE gid = ?")
+            .bind(&self.gid)
+            .query(db)?;
         if unique_check.len() > 0 {
             let id = unique_check.pop().unwrap().pop().unwrap().as_i64();
             self.id = id;
             self.update(db)?;
         } else {
-            let sql = format!("INSERT INTO accounts (gid, indx, lang, pass, name, lock, mnemonic, secret, encrypt, avatar, wallet, pub_height, own_height, event, datetime) VALUES ('{}', {}, {}, '{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', {}, {}, '{}', {})",
-            self.gid.to_hex(),
-            self.index,
-            self.lang,
-            self.pass,
-            self.name,
-            self.lock,
-            base64::encode(&self.mnemonic),
-            base64::encode(&self.secret),
-            base64::encode(&self.encrypt),
-            base64::encode(&self.avatar),
-            self.wallet,
-            self.pub_height,
-            self.own_height,
-            self.event.to_hex(),
-            self.datetime,
-        );
-            let id = db.insert(&sql)?;
+            let id = Query::new("INSERT INTO accounts (gid, indx, lang, pass, name, lock, mnemonic, secret, encrypt, avatar, wallet, pub_height, own_height, event, datetime) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+                .bind(&self.gid)
+                .bind(&self.index)
+                .bind(&self.lang)
+                .bind(&self.pass)
+                .bind(&self.name)
+                .bind(&self.lock)
+                .bind(&base64::encode(&self.mnemonic))
+                .bind(&base64::encode(&self.secret))
+                .bind(&base64::encode(&self.encrypt))
+                .bind(&base64::encode(&self.avatar))
+                .bind(&self.wallet)
+                .bind(&self.pub_height)
+                .bind(&self.own_height)
+                .bind(&self.event)
+                .bind(&self.datetime)
+                .insert(db)?;
             self.id = id;
         }
         Ok(())
     }
 
     pub fn update(&self, db: &DStorage) -> Result<usize> {
-        let sql = format!("UPDATE accounts SET name='{}', lock='{}', encrypt='{}', avatar='{}', wallet='{}', pub_height={}, own_height={}, event='{}', datetime={} WHERE id = {}",
-            self.name,
-            self.lock,
-            base64::encode(&self.encrypt),
-            base64::encode(&self.avatar),
-            self.wallet,
-            self.pub_height,
-            self.own_height,
-            self.datetime,
-            self.event.to_hex(),
-            self.id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE accounts SET name = ?, lock = ?, encrypt = ?, avatar = ?, wallet = ?, pub_height = ?, own_height = ?, event = ?, datetime = ? WHERE id = ?")
+            .bind(&self.name)
+            .bind(&self.lock)
+            .bind(&base64::encode(&self.encrypt))
+            .bind(&base64::encode(&self.avatar))
+            .bind(&self.wallet)
+            .bind(&self.pub_height)
+            .bind(&self.own_height)
+            .bind(&self.event)
+            .bind(&self.datetime)
+            .bind(&self.id)
+            .update(db)
+    }
+
+    pub fn update_key(&self, db: &DStorage) -> Result<usize> {
+        Query::new(
+            "UPDATE accounts SET lock = ?, encrypt = ?, secret = ?, mnemonic = ? WHERE id = ?",
+        )
+        .bind(&self.lock)
+        .bind(&base64::encode(&self.encrypt))
+        .bind(&base64::encode(&self.secret))
+        .bind(&base64::encode(&self.mnemonic))
+        .bind(&self.id)
+        .update(db)
+    }
+
+    pub fn update_idle(&self, db: &DStorage) -> Result<usize> {
+        Query::new("UPDATE accounts SET idle = ? WHERE id = ?")
+            .bind(&self.idle)
+            .bind(&self.id)
+            .update(db)
+    }
+
+    pub fn update_rotated(&self, db: &DStorage) -> Result<usize> {
+        Query::new("UPDATE accounts SET rotated = ? WHERE id = ?")
+            .bind(&self.rotated)
+            .bind(&self.id)
+            .update(db)
     }
 
     pub fn update_info(&self, db: &DStorage) -> Result<usize> {
-        let sql = format!(
-            "UPDATE accounts SET name='{}', avatar='{}', wallet='{}', pub_height={} WHERE id = {}",
-            self.name,
-            base64::encode(&self.avatar),
-            self.wallet,
-            self.pub_height,
-            self.id,
-        );
-        db.update(&sql)
+        Query::new(
+            "UPDATE accounts SET name = ?, avatar = ?, wallet = ?, pub_height = ? WHERE id = ?",
+        )
+        .bind(&self.name)
+        .bind(&base64::encode(&self.avatar))
+        .bind(&self.wallet)
+        .bind(&self.pub_height)
+        .bind(&self.id)
+        .update(db)
     }
 
-    pub fn _delete(&self, db: &DStorage) -> Result<usize> {
-        let sql = format!("DELETE FROM accounts WHERE id = {}", self.id);
-        db.delete(&sql)
+    pub fn delete(&self, db: &DStorage) -> Result<usize> {
+        Query::new("DELETE FROM accounts WHERE id = ?")
+            .bind(&self.id)
+            .delete(db)
     }
 
     pub fn update_consensus(&mut self, db: &DStorage, height: u64, eid: EventId) -> Result<usize> {
         self.own_height = height;
         self.event = eid;
-        let sql = format!(
-            "UPDATE accounts SET own_height={}, event='{}' WHERE id = {}",
-            self.own_height,
-            self.event.to_hex(),
-            self.id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE accounts SET own_height = ?, event = ? WHERE id = ?")
+            .bind(&self.own_height)
+            .bind(&self.event)
+            .bind(&self.id)
+            .update(db)
+    }
+}
+
+/// Data key rotation journal, for recovery when rotation broken.
+pub(crate) struct Rotation {
+    pub id: i64,
+    pub gid: GroupId,
+    /// old encrypted key.
+    pub old: Vec<u8>,
+    /// new encrypted key.
+    pub new: Vec<u8>,
+    /// databases had been encrypted by new key.
+    pub done: Vec<String>,
+    /// new key wrapped by account's secret, rotated in other device.
+    /// it is encrypted by pin when login.
+    pub wrapped: Vec<u8>,
+}
+
+impl Rotation {
+    pub fn new(gid: GroupId, old: Vec<u8>, new: Vec<u8>) -> Self {
+        Self {
+            id: 0,
+            gid,
+            old,
+            new,
+            done: vec![],
+            wrapped: vec![],
+        }
+    }
+
+    /// here is zero-copy and unwrap is safe. checked.
+    fn from_values(mut v: Vec<DsValue>) -> Self {
+        Self {
+            wrapped: base64::decode(v.pop().unwrap().as_str()).unwrap_or(vec![]),
+            done: v
+                .pop()
+                .unwrap()
+                .as_str()
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_owned())
+                .collect(),
+            new: base64::decode(v.pop().unwrap().as_str()).unwrap_or(vec![]),
+            old: base64::decode(v.pop().unwrap().as_str()).unwrap_or(vec![]),
+            gid: GroupId::from_hex(v.pop().unwrap().as_str()).unwrap_or(GroupId::default()),
+            id: v.pop().unwrap().as_i64(),
+        }
+    }
+
+    /// the unfinished rotation of the account.
+    pub fn get(db: &DStorage, gid: &GroupId) -> Result<Self> {
+        let mut matrix =
+            Query::new("SELECT id, gid, old, new, done, wrapped FROM rotations WHERE gid = ?")
+                .bind(gid)
+                .query(db)?;
+        if matrix.len() > 0 {
+            Ok(Self::from_values(matrix.pop().unwrap())) // safe unwrap()
+        } else {
+            Err(anyhow!("rotation missing"))
+        }
+    }
+
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        self.id = Query::new(
+            "INSERT INTO rotations (gid, old, new, done, wrapped) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&self.gid)
+        .bind(&base64::encode(&self.old))
+        .bind(&base64::encode(&self.new))
+        .bind(&self.done.join(","))
+        .bind(&base64::encode(&self.wrapped))
+        .insert(db)?;
+        Ok(())
+    }
+
+    /// the database had been encrypted by new key.
+    pub fn done(&mut self, db: &DStorage, name: &str) -> Result<usize> {
+        self.done.push(name.to_owned());
+        Query::new("UPDATE rotations SET done = ? WHERE id = ?")
+            .bind(&self.done.join(","))
+            .bind(&self.id)
+            .update(db)
+    }
+
+    pub fn delete(&self, db: &DStorage) -> Result<usize> {
+        Query::new("DELETE FROM rotations WHERE id = ?")
+            .bind(&self.id)
+            .delete(db)
     }
 }
 
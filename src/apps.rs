@@ -1,3 +1,6 @@
+use once_cell::sync::Lazy;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use tdn::types::{
     group::GroupId,
@@ -7,6 +10,7 @@ use tdn::types::{
 };
 use tokio::sync::RwLock;
 
+use crate::group::Group;
 use crate::layer::Layer;
 use crate::rpc::RpcState;
 
@@ -17,19 +21,150 @@ pub(crate) mod domain;
 pub(crate) mod file;
 pub(crate) mod group;
 pub(crate) mod jarvis;
+pub(crate) mod search;
 //pub(crate) mod dao;
 pub(crate) mod wallet;
 
+/// Future of app's layer handler.
+pub(crate) type LayerFuture<'a> = Pin<Box<dyn Future<Output = Result<HandleResult>> + 'a>>;
+
+/// An app of ESSE, a new app implements it and registers in `AppRegistry::builtin`.
+pub(crate) trait App: Send + Sync {
+    /// unique name of the app, use in sync events.
+    fn name(&self) -> &'static str;
+
+    /// the app's service group id in layer.
+    fn group_id(&self) -> Option<GroupId> {
+        None
+    }
+
+    /// account's database name and its migrations.
+    fn db(&self) -> Option<(&'static str, &'static [&'static str])> {
+        None
+    }
+
+    /// register the app's rpc methods.
+    fn rpc_inject(&self, _handler: &mut RpcHandler<RpcState>) {}
+
+    /// handle the layer messages which no app's group id matched, only one app.
+    fn is_default_layer(&self) -> bool {
+        false
+    }
+
+    /// handle layer message from the app's service (fgid is group id), or as default.
+    fn handle_peer<'a>(
+        &'a self,
+        _layer: &'a Arc<RwLock<Layer>>,
+        _fgid: GroupId,
+        _mgid: GroupId,
+        _msg: RecvType,
+    ) -> LayerFuture<'a> {
+        Box::pin(async { Err(anyhow!("app has no layer handler")) })
+    }
+
+    /// handle layer message to the app's service in this node (mgid is group id).
+    fn handle_server<'a>(
+        &'a self,
+        _layer: &'a Arc<RwLock<Layer>>,
+        _fgid: GroupId,
+        _mgid: GroupId,
+        _msg: RecvType,
+    ) -> LayerFuture<'a> {
+        Box::pin(async { Err(anyhow!("app has no service handler")) })
+    }
+
+    /// handle the app's sync event from other devices, return the changed model id.
+    fn sync(
+        &self,
+        _group: &mut Group,
+        _gid: &GroupId,
+        _data: Vec<u8>,
+        _results: &mut HandleResult,
+    ) -> Result<i64> {
+        Ok(0)
+    }
+}
+
+/// All apps running in the node.
+pub(crate) struct AppRegistry {
+    apps: Vec<Box<dyn App>>,
+}
+
+impl AppRegistry {
+    pub fn new() -> Self {
+        Self { apps: vec![] }
+    }
+
+    pub fn register(mut self, app: impl App + 'static) -> Self {
+        self.apps.push(Box::new(app));
+        self
+    }
+
+    /// the apps shipped with ESSE.
+    pub fn builtin() -> Self {
+        Self::new()
+            .register(device::DeviceApp)
+            .register(chat::ChatApp)
+            .register(jarvis::JarvisApp)
+            .register(domain::DomainApp)
+            .register(file::FileApp)
+            .register(group::GroupChatApp)
+            .register(wallet::WalletApp)
+            //.register(dao::DaoApp)
+            .register(cloud::CloudApp)
+            .register(search::SearchApp)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn App> {
+        self.apps
+            .iter()
+            .find(|app| app.name() == name)
+            .map(|app| app.as_ref())
+    }
+
+    /// apps' databases and migrations.
+    pub fn dbs(&self) -> Vec<(&'static str, &'static [&'static str])> {
+        self.apps.iter().filter_map(|app| app.db()).collect()
+    }
+
+    pub fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        for app in &self.apps {
+            app.rpc_inject(handler);
+        }
+    }
+
+    pub async fn layer_handle(
+        &self,
+        layer: &Arc<RwLock<Layer>>,
+        fgid: GroupId,
+        mgid: GroupId,
+        msg: RecvType,
+    ) -> Result<HandleResult> {
+        for app in &self.apps {
+            if app.group_id() == Some(fgid) {
+                return app.handle_peer(layer, fgid, mgid, msg).await;
+            }
+        }
+        for app in &self.apps {
+            if app.group_id() == Some(mgid) {
+                return app.handle_server(layer, fgid, mgid, msg).await;
+            }
+        }
+        match self.apps.iter().find(|app| app.is_default_layer()) {
+            Some(app) => app.handle_peer(layer, fgid, mgid, msg).await,
+            None => Err(anyhow!("no app handle the layer message")),
+        }
+    }
+}
+
+static REGISTRY: Lazy<AppRegistry> = Lazy::new(AppRegistry::builtin);
+
+pub(crate) fn registry() -> &'static AppRegistry {
+    &REGISTRY
+}
+
 pub(crate) fn app_rpc_inject(handler: &mut RpcHandler<RpcState>) {
-    device::new_rpc_handler(handler);
-    chat::new_rpc_handler(handler);
-    jarvis::new_rpc_handler(handler);
-    domain::new_rpc_handler(handler);
-    file::new_rpc_handler(handler);
-    group::new_rpc_handler(handler);
-    wallet::new_rpc_handler(handler);
-    //dao::new_rpc_handler(handler);
-    cloud::new_rpc_handler(handler);
+    registry().rpc_inject(handler);
 }
 
 pub(crate) async fn app_layer_handle(
@@ -38,20 +173,5 @@ pub(crate) async fn app_layer_handle(
     mgid: GroupId,
     msg: RecvType,
 ) -> Result<HandleResult> {
-    match (fgid, mgid) {
-        (group::GROUP_ID, _) => group::handle_peer(layer, mgid, msg).await,
-        (_, group::GROUP_ID) => group::handle_server(layer, fgid, msg).await,
-        //(dao::GROUP_ID, _) => dao::handle(layer, fgid, mgid, false, msg).await,
-        (domain::GROUP_ID, _) => domain::handle(layer, mgid, msg).await,
-        (cloud::GROUP_ID, _) => cloud::handle(layer, mgid, msg).await,
-        _ => chat::handle(layer, fgid, mgid, msg).await,
-    }
-}
-
-pub(crate) fn _app_group_handle() -> Result<HandleResult> {
-    todo!()
-}
-
-pub(crate) fn _app_migrate() -> Result<()> {
-    todo!()
+    registry().layer_handle(layer, fgid, mgid, msg).await
 }
@@ -3,29 +3,41 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tdn::types::{
     group::{EventId, GroupId},
-    message::{RecvType, SendType},
+    message::{RecvType, SendType, StreamType},
     primitive::{DeliveryType, HandleResult, Peer, PeerId, Result},
 };
 use tdn_did::Proof;
 use tdn_storage::local::DStorage;
 use tokio::sync::RwLock;
 
-use chat_types::{MessageType, NetworkMessage};
+use chat_types::{FileChunk, MessageType, NetworkMessage, FILE_CHUNK_SIZE};
 
 use crate::account::{Account, User};
-use crate::event::InnerEvent;
+use crate::apps::cloud::MailKey;
+use crate::event::{InnerEvent, StatusEvent};
 use crate::group::Group;
 use crate::layer::{Layer, Online};
-use crate::migrate::consensus::{FRIEND_TABLE_PATH, MESSAGE_TABLE_PATH, REQUEST_TABLE_PATH};
+use crate::migrate::consensus::{
+    FRIEND_TABLE_PATH, MESSAGE_EDIT_TABLE_PATH, MESSAGE_TABLE_PATH, REACTION_TABLE_PATH,
+    REQUEST_TABLE_PATH, SESSION_TABLE_PATH,
+};
 use crate::rpc::{
     notice_menu, session_connect, session_create, session_last, session_lost, session_suspend,
-    session_update_name,
+    session_ttl, session_update_name,
 };
 use crate::session::{connect_session, Session, SessionType};
-use crate::storage::write_avatar_sync;
+use crate::storage::{file_hashes, write_avatar_sync};
+use crate::utils::crypto::encrypt_bytes;
 
-use super::models::{handle_nmsg, raw_to_network_message, Friend, Message, Request};
+use super::models::{
+    from_model, handle_nmsg, raw_to_network_message, Friend, Message, MessageEdit, Outbox,
+    Reaction, Request, Share, SharePiece, Transfer,
+};
 use super::rpc;
+use super::stream::{
+    handle_chunk, keep_stream, stream_accept, stream_data, stream_id, stream_open, stream_send,
+    FileStream, TRANSFER_WINDOW,
+};
 
 /// Chat connect data structure.
 /// params: Friend about me height, connect_proof.
@@ -50,7 +62,25 @@ pub(crate) enum LayerEvent {
     /// reject friendship request.
     Reject,
     /// receiver gid, sender gid, message.
-    Message(EventId, NetworkMessage),
+    /// params: message hash, message, replied message hash.
+    Message(EventId, NetworkMessage, Option<EventId>),
+    /// edit a sent text message.
+    /// params: message hash, new content.
+    MessageEdit(EventId, String),
+    /// add or remove an emoji reaction to a message.
+    /// params: message hash, emoji, is_add.
+    MessageReact(EventId, String, bool),
+    /// messages retention timer, params: ttl seconds.
+    SessionTtl(i64),
+    /// friend had read messages up to this message hash.
+    Read(EventId),
+    /// friend is typing or stop.
+    Typing(bool),
+    /// my mailbox key, use to open my mails in cloud.
+    Mailbox([u8; 32]),
+    /// recovery share returned from the account's friend.
+    /// params: the account which recovering, serialized share piece.
+    RecoveryReturn(GroupId, Vec<u8>),
     /// request user info.
     InfoReq(i64),
     /// user full info.
@@ -92,6 +122,8 @@ pub(crate) async fn handle(
                 let data = bincode::serialize(&info).unwrap_or(vec![]);
                 let msg = SendType::Event(0, peer_id, data);
                 results.layers.push((mgid, fgid, msg));
+
+                connected(&mut layer, mgid, fgid, peer_id, &mut results).await;
             } else {
                 let msg = SendType::Result(0, addr, false, false, vec![]);
                 results.layers.push((mgid, fgid, msg));
@@ -107,6 +139,8 @@ pub(crate) async fn handle(
                     let data = bincode::serialize(&info).unwrap_or(vec![]);
                     let msg = SendType::Event(0, addr.id, data);
                     results.layers.push((mgid, fgid, msg));
+
+                    connected(&mut layer, mgid, fgid, addr.id, &mut results).await;
                 } else {
                     let msg = SendType::Result(0, addr, false, false, vec![]);
                     results.layers.push((mgid, fgid, msg));
@@ -123,8 +157,15 @@ pub(crate) async fn handle(
         RecvType::Event(addr, bytes) => {
             return LayerEvent::handle(fgid, mgid, &mut layer, addr, bytes).await;
         }
-        RecvType::Stream(_uid, _stream, _bytes) => {
-            // TODO stream
+        RecvType::Stream(uid, stream, bytes) => {
+            if let StreamType::Req(_) = stream {
+                results.layers.push((mgid, fgid, stream_accept(uid)));
+            }
+            // stream only carry file chunks.
+            if !bytes.is_empty() {
+                let chunk: FileChunk = bincode::deserialize(&bytes)?;
+                handle_file_chunk(&mut layer, mgid, fgid, uid, chunk, &mut results).await?;
+            }
         }
         RecvType::Delivery(t, tid, is_ok) => {
             debug!("delivery: tid: {}, is_ok: {}", tid, is_ok);
@@ -135,6 +176,7 @@ pub(crate) async fn handle(
                     let resp = match t {
                         DeliveryType::Event => {
                             Message::delivery(&db, db_id, true)?;
+                            Outbox::delete_by_message(&db, &db_id)?;
                             rpc::message_delivery(gid, db_id, true)
                         }
                         DeliveryType::Connect => {
@@ -294,36 +336,77 @@ impl LayerEvent {
                 }
                 drop(db);
             }
-            LayerEvent::Message(hash, m) => {
+            LayerEvent::Message(hash, m, parent) => {
+                let (_sid, fid) = layer.get_running_remote_id(&mgid, &fgid)?;
+                handle_message(layer, mgid, fgid, fid, hash, m, parent, &mut results).await?;
+            }
+            LayerEvent::Mailbox(key) => {
+                let db = layer.group.read().await.cloud_db(&mgid)?;
+                MailKey::save(&db, &fgid, &key)?;
+            }
+            LayerEvent::RecoveryReturn(owner, data) => {
+                let group_lock = layer.group.read().await;
+                let piece: SharePiece = bincode::deserialize(&data)?;
+                if piece.gid != owner {
+                    return Err(anyhow!("share is invalid"));
+                }
+                let db = group_lock.chat_db(&mgid)?;
+                let key = group_lock.share_key(&mgid)?;
+                drop(group_lock);
+
+                let mut share = Share::new(owner, fgid, encrypt_bytes(&key, &data)?, false);
+                share.insert(&db)?;
+                results.rpcs.push(rpc::recovery_share(mgid, &share));
+            }
+            LayerEvent::MessageEdit(hash, content) => {
                 let (_sid, fid) = layer.get_running_remote_id(&mgid, &fgid)?;
                 let db = layer.group.read().await.chat_db(&mgid)?;
-                if !Message::exist(&db, &hash)? {
-                    let msg = handle_nmsg(
-                        &layer.group,
-                        m.clone(),
-                        false,
-                        mgid,
-                        &layer.base,
-                        &db,
-                        fid,
-                        hash,
-                        &mut results,
-                    )
-                    .await?;
-                    layer.group.write().await.broadcast(
-                        &mgid,
-                        InnerEvent::SessionMessageCreate(fgid, false, hash, m),
-                        MESSAGE_TABLE_PATH,
-                        msg.id,
-                        &mut results,
-                    )?;
-                    results.rpcs.push(rpc::message_create(mgid, &msg));
+                let mut msg = Message::get_by_hash(&db, &hash)?;
+                // only friend's text message can be edited by friend.
+                if msg.fid != fid || msg.is_me || msg.m_type != MessageType::String {
+                    return Err(anyhow!("message cannot edit"));
+                }
+                let edit = msg.edit(&db, content.clone())?;
+                drop(db);
 
-                    // UPDATE SESSION.
-                    if let Ok(s_db) = layer.group.read().await.session_db(&mgid) {
-                        update_session(&s_db, &mgid, &fid, &msg, &mut results);
-                    }
+                layer.group.write().await.broadcast(
+                    &mgid,
+                    InnerEvent::SessionMessageEdit(hash, content),
+                    MESSAGE_EDIT_TABLE_PATH,
+                    edit.id,
+                    &mut results,
+                )?;
+                results
+                    .rpcs
+                    .push(rpc::message_edit(mgid, msg.id, &msg.content));
+            }
+            LayerEvent::MessageReact(hash, emoji, is_add) => {
+                let (_sid, fid) = layer.get_running_remote_id(&mgid, &fgid)?;
+                let db = layer.group.read().await.chat_db(&mgid)?;
+                let msg = Message::get_by_hash(&db, &hash)?;
+                if msg.fid != fid {
+                    return Err(anyhow!("message is missing"));
                 }
+                let rid = if is_add {
+                    let mut reaction = Reaction::new(msg.id, false, emoji.clone());
+                    reaction.insert(&db)?;
+                    reaction.id
+                } else {
+                    Reaction::remove(&db, &msg.id, false, &emoji)?
+                        .ok_or(anyhow!("reaction is missing."))?
+                };
+                drop(db);
+
+                layer.group.write().await.broadcast(
+                    &mgid,
+                    InnerEvent::SessionMessageReact(hash, false, emoji.clone(), is_add),
+                    REACTION_TABLE_PATH,
+                    rid,
+                    &mut results,
+                )?;
+                results
+                    .rpcs
+                    .push(rpc::message_react(mgid, msg.id, false, &emoji, is_add));
             }
             LayerEvent::InfoReq(height) => {
                 // check sync remote height.
@@ -366,6 +449,40 @@ impl LayerEvent {
                     &mut results,
                 )?;
             }
+            LayerEvent::Read(hash) => {
+                let (_sid, fid) = layer.get_running_remote_id(&mgid, &fgid)?;
+                let db = layer.group.read().await.chat_db(&mgid)?;
+                let msg = Message::get_by_hash(&db, &hash)?;
+                Message::read(&db, &fid, &msg.id, true)?;
+                drop(db);
+                results
+                    .rpcs
+                    .push(rpc::message_read(mgid, fid, msg.id, true));
+                layer.group.write().await.status(
+                    &mgid,
+                    StatusEvent::SessionMessageRead(fgid, hash, true),
+                    &mut results,
+                )?;
+            }
+            LayerEvent::Typing(is_typing) => {
+                let (_sid, fid) = layer.get_running_remote_id(&mgid, &fgid)?;
+                results.rpcs.push(rpc::friend_typing(mgid, fid, is_typing));
+            }
+            LayerEvent::SessionTtl(ttl) => {
+                // friend's ttl must be in the range which I accept.
+                Session::check_ttl(ttl)?;
+                let s_db = layer.group.read().await.session_db(&mgid)?;
+                let sid = Session::update_ttl_by_gid(&s_db, &fgid, &SessionType::Chat, ttl)?;
+                drop(s_db);
+                results.rpcs.push(session_ttl(mgid, &sid, ttl));
+                layer.group.write().await.broadcast(
+                    &mgid,
+                    InnerEvent::SessionTtl(fgid, SessionType::Chat.to_int(), ttl),
+                    SESSION_TABLE_PATH,
+                    sid,
+                    &mut results,
+                )?;
+            }
             LayerEvent::Close => {
                 let (_sid, fid) = layer.get_running_remote_id(&mgid, &fgid)?;
                 layer.group.write().await.broadcast(
@@ -398,12 +515,26 @@ impl LayerEvent {
         fid: i64,
         m_type: MessageType,
         content: &str,
+        parent: Option<EventId>,
     ) -> std::result::Result<(Message, NetworkMessage), tdn::types::rpc::RpcError> {
         // handle message's type.
         let db = group.read().await.chat_db(&mgid)?;
         let (nm_type, raw) = raw_to_network_message(group, base, &mgid, &m_type, content).await?;
         let mut msg = Message::new(&mgid, fid, true, m_type, raw, false);
+        msg.parent = parent;
         msg.insert(&db)?;
+
+        // big file use chunked transfer, message only carry the file name.
+        if let NetworkMessage::File(name, bytes) = &nm_type {
+            if bytes.is_empty() {
+                let (size, file_hash, chunks) =
+                    file_hashes(base, &mgid, name, FILE_CHUNK_SIZE).await?;
+                if size as usize > FILE_CHUNK_SIZE {
+                    let (hash, name, size) = (msg.hash, name.clone(), size as i64);
+                    Transfer::new(hash, fid, true, name, size, file_hash, chunks).insert(&db)?;
+                }
+            }
+        }
         Ok((msg, nm_type))
     }
 }
@@ -462,6 +593,258 @@ pub(crate) fn chat_conn(proof: Proof, addr: Peer) -> SendType {
     SendType::Connect(0, addr, data)
 }
 
+/// friend is online, failure of one task not break the connection.
+async fn connected(
+    layer: &mut Layer,
+    mgid: GroupId,
+    fgid: GroupId,
+    addr: PeerId,
+    results: &mut HandleResult,
+) {
+    // resume the unfinished file transfers.
+    if let Err(e) = resume_transfers(layer, mgid, fgid, addr, results).await {
+        warn!("resume transfers failure: {:?}", e);
+    }
+    // resend the undelivered messages.
+    if let Err(e) = resend_outbox(layer, mgid, fgid, addr, results).await {
+        warn!("resend outbox failure: {:?}", e);
+    }
+    // resend the edits and reactions when friend offline.
+    if let Err(e) = resend_changes(layer, mgid, fgid, addr, results).await {
+        warn!("resend changes failure: {:?}", e);
+    }
+    // share my mailbox key for cloud mails.
+    if let Err(e) = mailbox_message(layer, mgid, fgid, addr, results).await {
+        warn!("share mailbox key failure: {:?}", e);
+    }
+}
+
+/// resume the unfinished file transfers when friend online.
+async fn resume_transfers(
+    layer: &mut Layer,
+    mgid: GroupId,
+    fgid: GroupId,
+    addr: PeerId,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let db = layer.group.read().await.chat_db(&mgid)?;
+    let f = Friend::get_id(&db, &fgid)?;
+    for t in Transfer::list_unfinished(&db, &f.id)? {
+        let uid = stream_id(&t.hash, &layer.addr, &addr);
+        let chunk = if t.is_me {
+            t.to_start()
+        } else {
+            let stream = FileStream::new(mgid, None, (mgid, fgid), uid);
+            keep_stream(&mut layer.streams, t.hash, Some(true), stream);
+            FileChunk::Request(t.hash, t.missing(TRANSFER_WINDOW))
+        };
+        let msg = stream_open(uid, addr, stream_data(&None, &chunk));
+        results.layers.push((mgid, fgid, msg));
+    }
+    Ok(())
+}
+
+/// save friend's message, from online event or cloud mail.
+async fn handle_message(
+    layer: &mut Layer,
+    mgid: GroupId,
+    fgid: GroupId,
+    fid: i64,
+    hash: EventId,
+    m: NetworkMessage,
+    parent: Option<EventId>,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let db = layer.group.read().await.chat_db(&mgid)?;
+    if !Message::exist(&db, &hash)? {
+        // friend's recovery share is held encrypted, the message not keep it.
+        let m = match m {
+            NetworkMessage::Recovery(data) => {
+                let piece: SharePiece = bincode::deserialize(&data)?;
+                if piece.gid != fgid {
+                    return Err(anyhow!("share is invalid"));
+                }
+                let key = layer.group.read().await.share_key(&mgid)?;
+                let mut share = Share::new(fgid, fgid, encrypt_bytes(&key, &data)?, true);
+                share.insert(&db)?;
+                results.rpcs.push(rpc::recovery_share(mgid, &share));
+                NetworkMessage::Recovery(vec![])
+            }
+            m => m,
+        };
+        let msg = handle_nmsg(
+            &layer.group,
+            m.clone(),
+            false,
+            mgid,
+            &layer.base,
+            &db,
+            fid,
+            hash,
+            parent,
+            results,
+        )
+        .await?;
+        layer.group.write().await.broadcast(
+            &mgid,
+            InnerEvent::SessionMessageCreate(fgid, false, hash, m, parent),
+            MESSAGE_TABLE_PATH,
+            msg.id,
+            results,
+        )?;
+        results.rpcs.push(rpc::message_create(mgid, &msg));
+
+        // UPDATE SESSION.
+        if let Ok(s_db) = layer.group.read().await.session_db(&mgid) {
+            update_session(&s_db, &mgid, &fid, &msg, results);
+        }
+    }
+    Ok(())
+}
+
+/// handle the mail fetched from cloud, friend maybe offline.
+pub(crate) async fn handle_mail(
+    layer: &mut Layer,
+    mgid: GroupId,
+    fgid: GroupId,
+    bytes: Vec<u8>,
+    results: &mut HandleResult,
+) -> Result<()> {
+    match bincode::deserialize(&bytes)? {
+        LayerEvent::Message(hash, m, parent) => {
+            let db = layer.group.read().await.chat_db(&mgid)?;
+            let fid = Friend::get_id(&db, &fgid)?.id;
+            drop(db);
+            handle_message(layer, mgid, fgid, fid, hash, m, parent, results).await
+        }
+        _ => Err(anyhow!("cloud mail only carry message")),
+    }
+}
+
+/// share my mailbox key to friend, friend can open my mails in cloud.
+async fn mailbox_message(
+    layer: &Layer,
+    mgid: GroupId,
+    fgid: GroupId,
+    addr: PeerId,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let key = layer.group.read().await.mailbox_key(&mgid, &fgid)?;
+    let data = bincode::serialize(&LayerEvent::Mailbox(key)).unwrap_or(vec![]);
+    results
+        .layers
+        .push((mgid, fgid, SendType::Event(0, addr, data)));
+    Ok(())
+}
+
+/// share the mailbox keys to online friends again, when data key rotated.
+pub(crate) async fn mailbox_reshare(layer: &Layer, mgid: GroupId, results: &mut HandleResult) {
+    let onlines = match layer.running(&mgid) {
+        Ok(running) => running.onlines(),
+        Err(_) => return,
+    };
+    for (fgid, addr) in onlines {
+        if let Err(e) = mailbox_message(layer, mgid, *fgid, *addr, results).await {
+            warn!("mailbox key share failure: {}", e);
+        }
+    }
+}
+
+/// resend the undelivered messages in outbox when friend online.
+async fn resend_outbox(
+    layer: &mut Layer,
+    mgid: GroupId,
+    fgid: GroupId,
+    addr: PeerId,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let db = layer.group.read().await.chat_db(&mgid)?;
+    let f = Friend::get_id(&db, &fgid)?;
+    for mut o in Outbox::list(&db, &f.id)? {
+        let msg = if let Ok(msg) = Message::get(&db, &o.message) {
+            msg
+        } else {
+            let _ = Outbox::delete_by_message(&db, &o.message);
+            continue;
+        };
+        let (hash, parent) = (msg.hash, msg.parent);
+        // big file only send the name, chunks will resume by transfer.
+        let nw = match Transfer::get_by_hash(&db, &hash) {
+            Ok(t) => NetworkMessage::File(t.name, vec![]),
+            Err(_) => match from_model(&layer.base, &mgid, msg).await {
+                Ok(nw) => nw,
+                Err(e) => {
+                    warn!("outbox message {} load failure: {:?}", o.message, e);
+                    continue;
+                }
+            },
+        };
+        let event = LayerEvent::Message(hash, nw, parent);
+        let s = event_message(layer, o.message, mgid, addr, &event);
+        results.layers.push((mgid, fgid, s));
+        if let Err(e) = o.retry(&db) {
+            warn!("outbox message {} retry failure: {:?}", o.message, e);
+        }
+    }
+    Ok(())
+}
+
+/// resend the undelivered message edits and reactions when friend online.
+async fn resend_changes(
+    layer: &Layer,
+    mgid: GroupId,
+    fgid: GroupId,
+    addr: PeerId,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let db = layer.group.read().await.chat_db(&mgid)?;
+    let f = Friend::get_id(&db, &fgid)?;
+    for (id, hash, content) in MessageEdit::list_undelivered(&db, &f.id)? {
+        let data = bincode::serialize(&LayerEvent::MessageEdit(hash, content))?;
+        results
+            .layers
+            .push((mgid, fgid, SendType::Event(0, addr, data)));
+        MessageEdit::delivery(&db, &id)?;
+    }
+    for (id, hash, emoji, is_add) in Reaction::list_undelivered(&db, &f.id)? {
+        let data = bincode::serialize(&LayerEvent::MessageReact(hash, emoji, is_add))?;
+        results
+            .layers
+            .push((mgid, fgid, SendType::Event(0, addr, data)));
+        Reaction::delivery(&db, &id)?;
+    }
+    Ok(())
+}
+
+/// handle the file chunk in the friend's stream.
+async fn handle_file_chunk(
+    layer: &mut Layer,
+    mgid: GroupId,
+    fgid: GroupId,
+    uid: u32,
+    chunk: FileChunk,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let (_sid, fid) = layer.get_running_remote_id(&mgid, &fgid)?;
+    let db = layer.group.read().await.chat_db(&mgid)?;
+    let hash = *chunk.hash();
+    let handled = handle_chunk(&layer.base, &mgid, &db, fid, chunk).await?;
+    for chunk in &handled.replies {
+        let msg = stream_send(uid, stream_data(&None, chunk));
+        results.layers.push((mgid, fgid, msg));
+    }
+
+    let stream = FileStream::new(mgid, None, (mgid, fgid), uid);
+    keep_stream(&mut layer.streams, hash, handled.waiting, stream);
+
+    if let (Some((done, total)), Ok(msg)) = (handled.progress, Message::get_by_hash(&db, &hash)) {
+        results
+            .rpcs
+            .push(rpc::transfer_progress(mgid, msg.id, done, total));
+    }
+    Ok(())
+}
+
 pub(super) fn agree_message(proof: Proof, addr: PeerId) -> Result<SendType> {
     let data = bincode::serialize(&LayerEvent::Agree(proof)).unwrap_or(vec![]);
     Ok(SendType::Event(0, addr, data))
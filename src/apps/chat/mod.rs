@@ -1,12 +1,57 @@
 mod layer;
 mod models;
+mod stream;
+
+use std::sync::Arc;
+use tdn::types::{group::GroupId, message::RecvType, rpc::RpcHandler};
+use tokio::sync::RwLock;
+
+use crate::apps::{App, LayerFuture};
+use crate::layer::Layer;
+use crate::migrate::{CHAT_DB, CHAT_VERSIONS};
+use crate::rpc::RpcState;
 
 pub(crate) mod rpc;
-pub(crate) use layer::handle;
 pub(crate) use layer::LayerEvent;
-pub(crate) use layer::{chat_conn, event_message, update_session};
+pub(crate) use layer::{chat_conn, event_message, mailbox_reshare, update_session};
+pub(crate) use layer::{handle, handle_mail};
 pub(crate) use models::{
     from_model, from_network_message, raw_to_network_message, to_network_message, Friend,
-    InviteType, Message, Request,
+    InviteType, Message, MessageEdit, Outbox, Reaction, Request, Share, SharePiece, Transfer,
 };
 pub(crate) use rpc::new_rpc_handler;
+pub(crate) use stream::{
+    handle_chunk, keep_stream, retry_streams, stream_accept, stream_data, stream_id, stream_open,
+    stream_send, FileStream, TRANSFER_TIMEOUT, TRANSFER_WINDOW,
+};
+
+/// chat app.
+pub(crate) struct ChatApp;
+
+impl App for ChatApp {
+    fn name(&self) -> &'static str {
+        "chat"
+    }
+
+    fn db(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some((CHAT_DB, &CHAT_VERSIONS))
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+
+    fn is_default_layer(&self) -> bool {
+        true
+    }
+
+    fn handle_peer<'a>(
+        &'a self,
+        layer: &'a Arc<RwLock<Layer>>,
+        fgid: GroupId,
+        mgid: GroupId,
+        msg: RecvType,
+    ) -> LayerFuture<'a> {
+        Box::pin(handle(layer, fgid, mgid, msg))
+    }
+}
@@ -1,12 +1,20 @@
 mod friend;
 mod message;
+mod outbox;
+mod reaction;
 mod request;
+mod share;
+mod transfer;
 
 pub(crate) use self::friend::Friend;
 pub(crate) use self::message::{from_model, handle_nmsg, Message};
+pub(crate) use self::outbox::Outbox;
+pub(crate) use self::reaction::{MessageEdit, Reaction};
 pub(crate) use self::request::Request;
+pub(crate) use self::share::{Share, SharePiece};
+pub(crate) use self::transfer::Transfer;
 
-use chat_types::{MessageType, NetworkMessage};
+use chat_types::{CallSignal, MessageType, NetworkMessage, FILE_CHUNK_SIZE};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tdn::types::{
@@ -19,8 +27,9 @@ use crate::apps::group::GroupChat;
 use crate::group::Group;
 use crate::rpc::session_create;
 use crate::storage::{
-    read_avatar, read_db_file, read_file, read_image, read_record, write_avatar_sync, write_file,
-    write_file_sync, write_image, write_image_sync, write_record_sync,
+    copy_file, file_size, is_file_name, read_avatar, read_db_file, read_emoji, read_file,
+    read_image, read_record, write_avatar_sync, write_emoji_sync, write_file_sync, write_image,
+    write_image_sync, write_record_sync,
 };
 
 pub(crate) async fn from_network_message(
@@ -33,12 +42,21 @@ pub(crate) async fn from_network_message(
     match nmsg {
         NetworkMessage::String(content) => Ok((MessageType::String, content)),
         NetworkMessage::Transfer(content) => Ok((MessageType::Transfer, content)),
+        NetworkMessage::Recovery(data) => Ok((MessageType::Recovery, hex::encode(data))),
         NetworkMessage::Image(bytes) => {
             let image_name = write_image_sync(base, ogid, bytes)?;
             Ok((MessageType::Image, image_name))
         }
         NetworkMessage::File(old_name, bytes) => {
-            let filename = write_file_sync(base, ogid, &old_name, bytes)?;
+            // empty bytes is chunked transfer, file will write when all chunks received.
+            let filename = if bytes.is_empty() {
+                if !is_file_name(&old_name) {
+                    return Err(anyhow!("file name invalid"));
+                }
+                old_name
+            } else {
+                write_file_sync(base, ogid, &old_name, bytes)?
+            };
             Ok((MessageType::File, filename))
         }
         NetworkMessage::Contact(name, rgid, addr, avatar_bytes) => {
@@ -47,9 +65,9 @@ pub(crate) async fn from_network_message(
             let contact_values = format!("{};;{};;{}", tmp_name, rgid.to_hex(), addr.to_hex());
             Ok((MessageType::Contact, contact_values))
         }
-        NetworkMessage::Emoji => {
-            // TODO
-            Ok((MessageType::Emoji, "".to_owned()))
+        NetworkMessage::Emoji(name, bytes) => {
+            let emoji_name = write_emoji_sync(base, ogid, &name, bytes)?;
+            Ok((MessageType::Emoji, emoji_name))
         }
         NetworkMessage::Record(bytes, time) => {
             let record_name = write_record_sync(base, ogid, time, bytes)?;
@@ -75,14 +93,8 @@ pub(crate) async fn from_network_message(
 
             Ok((MessageType::Invite, content))
         }
-        NetworkMessage::Phone => {
-            // TODO
-            Ok((MessageType::Phone, "".to_owned()))
-        }
-        NetworkMessage::Video => {
-            // TODO
-            Ok((MessageType::Video, "".to_owned()))
-        }
+        NetworkMessage::Phone(signal) => Ok((MessageType::Phone, signal.to_content())),
+        NetworkMessage::Video(signal) => Ok((MessageType::Video, signal.to_content())),
     }
 }
 
@@ -105,13 +117,18 @@ pub(crate) async fn raw_to_network_message(
         }
         MessageType::File => {
             let file_path = PathBuf::from(content);
-            let bytes = read_file(&file_path).await?;
-            let old_name = file_path
+            let filename = file_path
                 .file_name()
                 .and_then(|s| s.to_str())
                 .unwrap_or("")
                 .to_owned();
-            let filename = write_file(base, ogid, &old_name, &bytes).await?;
+            let size = copy_file(&file_path, base, ogid, &filename).await?;
+            // big file use chunked transfer, not load it in memory.
+            let bytes = if size as usize > FILE_CHUNK_SIZE {
+                vec![]
+            } else {
+                read_db_file(base, ogid, &filename).await?
+            };
             Ok((NetworkMessage::File(filename.clone(), bytes), filename))
         }
         MessageType::Contact => {
@@ -143,16 +160,19 @@ pub(crate) async fn raw_to_network_message(
             Ok((NetworkMessage::Record(bytes, time), content.to_owned()))
         }
         MessageType::Emoji => {
-            // TODO
-            Ok((NetworkMessage::Emoji, content.to_owned()))
+            let bytes = read_emoji(base, ogid, content).await?;
+            Ok((
+                NetworkMessage::Emoji(content.to_owned(), bytes),
+                content.to_owned(),
+            ))
         }
         MessageType::Phone => {
-            // TODO
-            Ok((NetworkMessage::Phone, content.to_owned()))
+            let signal = CallSignal::from_content(content).ok_or(anyhow!("call signal invalid"))?;
+            Ok((NetworkMessage::Phone(signal), content.to_owned()))
         }
         MessageType::Video => {
-            // TODO
-            Ok((NetworkMessage::Video, content.to_owned()))
+            let signal = CallSignal::from_content(content).ok_or(anyhow!("call signal invalid"))?;
+            Ok((NetworkMessage::Video(signal), content.to_owned()))
         }
         MessageType::Invite => Ok((
             NetworkMessage::Invite(content.to_owned()),
@@ -162,6 +182,10 @@ pub(crate) async fn raw_to_network_message(
             NetworkMessage::Transfer(content.to_owned()),
             content.to_owned(),
         )),
+        MessageType::Recovery => Ok((
+            NetworkMessage::Recovery(hex::decode(content)?),
+            content.to_owned(),
+        )),
     }
 }
 
@@ -179,7 +203,13 @@ pub(crate) async fn to_network_message(
             Ok(NetworkMessage::Image(bytes))
         }
         MessageType::File => {
-            let bytes = read_db_file(base, gid, &content).await?;
+            // big file only send the name, chunks will send by transfer.
+            let size = file_size(base, gid, &content).await.unwrap_or(0);
+            let bytes = if size as usize > FILE_CHUNK_SIZE {
+                vec![]
+            } else {
+                read_db_file(base, gid, &content).await?
+            };
             Ok(NetworkMessage::File(content, bytes))
         }
         MessageType::Contact => {
@@ -205,9 +235,21 @@ pub(crate) async fn to_network_message(
         }
         MessageType::Invite => Ok(NetworkMessage::Invite(content)),
         MessageType::Transfer => Ok(NetworkMessage::Transfer(content)),
-        MessageType::Emoji => Ok(NetworkMessage::Emoji),
-        MessageType::Phone => Ok(NetworkMessage::Phone),
-        MessageType::Video => Ok(NetworkMessage::Video),
+        MessageType::Recovery => Ok(NetworkMessage::Recovery(hex::decode(content)?)),
+        MessageType::Emoji => {
+            let bytes = read_emoji(base, gid, &content).await?;
+            Ok(NetworkMessage::Emoji(content, bytes))
+        }
+        MessageType::Phone => {
+            let signal =
+                CallSignal::from_content(&content).ok_or(anyhow!("call signal invalid"))?;
+            Ok(NetworkMessage::Phone(signal))
+        }
+        MessageType::Video => {
+            let signal =
+                CallSignal::from_content(&content).ok_or(anyhow!("call signal invalid"))?;
+            Ok(NetworkMessage::Video(signal))
+        }
     }
 }
 
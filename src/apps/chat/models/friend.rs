@@ -2,11 +2,11 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tdn::types::{
     group::GroupId,
     primitive::{PeerId, Result},
-    rpc::{json, RpcParam},
 };
 use tdn_storage::local::{DStorage, DsValue};
 
 use crate::session::{Session, SessionType};
+use crate::utils::sql::Query;
 
 use super::Message;
 
@@ -101,36 +101,37 @@ impl Friend {
         )
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.gid.to_hex(),
-            self.addr.to_hex(),
-            self.name,
-            self.wallet,
-            self.remark,
-            self.is_closed,
-            self.datetime
-        ])
-    }
-
-    pub fn to_rpc_online(&self, online: bool) -> RpcParam {
-        json!([
-            self.id,
-            self.gid.to_hex(),
-            self.addr.to_hex(),
-            self.name,
-            self.wallet,
-            self.remark,
-            self.is_closed,
-            self.datetime,
-            online
-        ])
+    pub fn to_rpc(&self) -> rpc_types::chat::Friend {
+        rpc_types::chat::Friend {
+            id: self.id,
+            gid: self.gid.to_hex(),
+            addr: self.addr.to_hex(),
+            name: self.name.clone(),
+            wallet: self.wallet.clone(),
+            remark: self.remark.clone(),
+            is_closed: self.is_closed,
+            datetime: self.datetime,
+        }
+    }
+
+    pub fn to_rpc_online(&self, online: bool) -> rpc_types::chat::FriendOnline {
+        rpc_types::chat::FriendOnline {
+            id: self.id,
+            gid: self.gid.to_hex(),
+            addr: self.addr.to_hex(),
+            name: self.name.clone(),
+            wallet: self.wallet.clone(),
+            remark: self.remark.clone(),
+            is_closed: self.is_closed,
+            datetime: self.datetime,
+            online,
+        }
     }
 
     pub fn get_id(db: &DStorage, gid: &GroupId) -> Result<Friend> {
-        let sql = format!("SELECT id, gid, addr, name, wallet, height, remark, is_closed, datetime FROM friends WHERE gid = '{}'", gid.to_hex());
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id, gid, addr, name, wallet, height, remark, is_closed, datetime FROM friends WHERE gid = ?")
+            .bind(gid)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(Friend::from_values(matrix.pop().unwrap())) // safe unwrap()
         } else {
@@ -139,8 +140,9 @@ impl Friend {
     }
 
     pub fn get(db: &DStorage, id: &i64) -> Result<Friend> {
-        let sql = format!("SELECT id, gid, addr, name, wallet, height, remark, is_closed, datetime FROM friends WHERE id = {}", id);
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id, gid, addr, name, wallet, height, remark, is_closed, datetime FROM friends WHERE id = ?")
+            .bind(id)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(Friend::from_values(matrix.pop().unwrap())) // safe unwrap()
         } else {
@@ -161,73 +163,68 @@ impl Friend {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let sql = format!("INSERT INTO friends (gid, addr, name, wallet, height, remark, is_closed, datetime) VALUES ('{}', '{}', '{}', '{}', {}, '{}', {}, {})",
-            self.gid.to_hex(),
-            self.addr.to_hex(),
-            self.name,
-            self.wallet,
-            self.height,
-            self.remark,
-            self.is_closed,
-            self.datetime,
-        );
-        let id = db.insert(&sql)?;
+        let id = Query::new("INSERT INTO friends (gid, addr, name, wallet, height, remark, is_closed, datetime) VALUES (?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(&self.gid)
+            .bind(&self.addr)
+            .bind(&self.name)
+            .bind(&self.wallet)
+            .bind(&self.height)
+            .bind(&self.remark)
+            .bind(&self.is_closed)
+            .bind(&self.datetime)
+            .insert(db)?;
         self.id = id;
         Ok(())
     }
 
     pub fn update(&self, db: &DStorage) -> Result<usize> {
-        let sql = format!("UPDATE friends SET addr = '{}', name = '{}', wallet = '{}', height={}, remark = '{}', is_closed = {} WHERE id = {}",
-            self.addr.to_hex(),
-            self.name,
-            self.wallet,
-            self.height,
-            self.remark,
-            self.is_closed,
-            self.id
-        );
-        db.update(&sql)
+        Query::new("UPDATE friends SET addr = ?, name = ?, wallet = ?, height = ?, remark = ?, is_closed = ? WHERE id = ?")
+            .bind(&self.addr)
+            .bind(&self.name)
+            .bind(&self.wallet)
+            .bind(&self.height)
+            .bind(&self.remark)
+            .bind(&self.is_closed)
+            .bind(&self.id)
+            .update(db)
     }
 
     pub fn me_update(&mut self, db: &DStorage) -> Result<usize> {
-        let sql = format!(
-            "UPDATE friends SET remark='{}' WHERE id = {}",
-            self.remark, self.id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE friends SET remark = ? WHERE id = ?")
+            .bind(&self.remark)
+            .bind(&self.id)
+            .update(db)
     }
 
     pub fn addr_update(db: &DStorage, id: i64, addr: &PeerId) -> Result<usize> {
-        let sql = format!(
-            "UPDATE friends SET addr='{}' WHERE id = {}",
-            addr.to_hex(),
-            id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE friends SET addr = ? WHERE id = ?")
+            .bind(addr)
+            .bind(&id)
+            .update(db)
     }
 
     pub fn remote_update(&self, db: &DStorage) -> Result<usize> {
-        let sql = format!(
-            "UPDATE friends SET addr='{}', name='{}', wallet='{}', height={}, is_closed = false WHERE id = {}",
-            self.addr.to_hex(),
-            self.name,
-            self.wallet,
-            self.height,
-            self.id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE friends SET addr = ?, name = ?, wallet = ?, height = ?, is_closed = false WHERE id = ?")
+            .bind(&self.addr)
+            .bind(&self.name)
+            .bind(&self.wallet)
+            .bind(&self.height)
+            .bind(&self.id)
+            .update(db)
     }
 
     /// used in rpc, when what to delete a friend.
     pub fn close(&self, db: &DStorage) -> Result<usize> {
-        let sql = format!("UPDATE friends SET is_closed = true WHERE id = {}", self.id);
-        db.update(&sql)
+        Query::new("UPDATE friends SET is_closed = true WHERE id = ?")
+            .bind(&self.id)
+            .update(db)
     }
 
     /// used in rpc, when what to delete a friend.
     pub fn delete(db: &DStorage, id: &i64) -> Result<usize> {
-        let sql = format!("DELETE FROM friends WHERE id = {}", id);
-        db.update(&sql)?;
+        Query::new("DELETE FROM friends WHERE id = ?")
+            .bind(id)
+            .delete(db)?;
 
         // TODO delete friend avatar.
 
@@ -236,17 +233,16 @@ impl Friend {
     }
 
     pub fn is_friend(db: &DStorage, gid: &GroupId) -> Result<bool> {
-        let sql = format!(
-            "SELECT id FROM friends WHERE is_closed = false and gid = '{}'",
-            gid.to_hex()
-        );
-        let matrix = db.query(&sql)?;
+        let matrix = Query::new("SELECT id FROM friends WHERE is_closed = false and gid = ?")
+            .bind(gid)
+            .query(db)?;
         Ok(matrix.len() > 0)
     }
 
     /// used in layers, when receive remote had closed.
     pub fn id_close(db: &DStorage, id: i64) -> Result<usize> {
-        let sql = format!("UPDATE friends SET is_closed = true WHERE id = {}", id);
-        db.update(&sql)
+        Query::new("UPDATE friends SET is_closed = true WHERE id = ?")
+            .bind(&id)
+            .update(db)
     }
 }
@@ -4,16 +4,17 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tdn::types::{
     group::{EventId, GroupId},
     primitive::{HandleResult, Result},
-    rpc::{json, RpcParam},
 };
 use tdn_storage::local::{DStorage, DsValue};
 use tokio::sync::RwLock;
 
 use chat_types::{MessageType, NetworkMessage};
 
+use crate::apps::search::MessageIndex;
 use crate::group::Group;
+use crate::utils::sql::Query;
 
-use super::{from_network_message, to_network_message};
+use super::{from_network_message, to_network_message, MessageEdit, Outbox, Reaction, Transfer};
 
 pub(crate) async fn handle_nmsg(
     group: &Arc<RwLock<Group>>,
@@ -24,11 +25,13 @@ pub(crate) async fn handle_nmsg(
     db: &DStorage,
     fid: i64,
     hash: EventId,
+    parent: Option<EventId>,
     results: &mut HandleResult,
 ) -> Result<Message> {
     // handle event.
     let (m_type, raw) = from_network_message(group, nmsg, base, &gid, results).await?;
     let mut msg = Message::new_with_id(hash, fid, is_me, m_type, raw, true);
+    msg.parent = parent;
     msg.insert(db)?;
     Ok(msg)
 }
@@ -50,6 +53,10 @@ pub(crate) struct Message {
     pub content: String,
     pub is_delivery: bool,
     pub datetime: i64,
+    /// replied message's hash.
+    pub parent: Option<EventId>,
+    /// message is read by the receiver.
+    pub is_read: bool,
 }
 
 impl Message {
@@ -87,6 +94,8 @@ impl Message {
             content,
             is_delivery,
             datetime,
+            parent: None,
+            is_read: false,
         }
     }
 
@@ -113,12 +122,16 @@ impl Message {
             content,
             is_delivery,
             datetime,
+            parent: None,
+            is_read: false,
         }
     }
 
     /// here is zero-copy and unwrap is safe. checked.
     fn from_values(mut v: Vec<DsValue>) -> Message {
         Message {
+            is_read: v.pop().unwrap().as_bool(),
+            parent: EventId::from_hex(v.pop().unwrap().as_str()).ok(),
             datetime: v.pop().unwrap().as_i64(),
             is_delivery: v.pop().unwrap().as_bool(),
             content: v.pop().unwrap().as_string(),
@@ -130,22 +143,25 @@ impl Message {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.hash.to_hex(),
-            self.fid,
-            self.is_me,
-            self.m_type.to_int(),
-            self.content,
-            self.is_delivery,
-            self.datetime,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::chat::Message {
+        rpc_types::chat::Message {
+            id: self.id,
+            hash: self.hash.to_hex(),
+            fid: self.fid,
+            is_me: self.is_me,
+            m_type: self.m_type.to_int(),
+            content: self.content.clone(),
+            is_delivery: self.is_delivery,
+            datetime: self.datetime,
+            parent: self.parent.map(|p| p.to_hex()).unwrap_or(String::new()),
+            is_read: self.is_read,
+        }
     }
 
     pub fn get(db: &DStorage, id: &i64) -> Result<Message> {
-        let sql = format!("SELECT id, hash, fid, is_me, m_type, content, is_delivery, datetime FROM messages WHERE id = {}", id);
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id, hash, fid, is_me, m_type, content, is_delivery, datetime, parent, is_read FROM messages WHERE id = ?")
+            .bind(id)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(Message::from_values(matrix.pop().unwrap())) // safe unwrap()
         } else {
@@ -154,8 +170,9 @@ impl Message {
     }
 
     pub fn get_by_fid(db: &DStorage, fid: &i64) -> Result<Vec<Message>> {
-        let sql = format!("SELECT id, hash, fid, is_me, m_type, content, is_delivery, datetime FROM messages WHERE fid = {}", fid);
-        let matrix = db.query(&sql)?;
+        let matrix = Query::new("SELECT id, hash, fid, is_me, m_type, content, is_delivery, datetime, parent, is_read FROM messages WHERE fid = ?")
+            .bind(fid)
+            .query(db)?;
         let mut messages = vec![];
         for values in matrix {
             messages.push(Message::from_values(values));
@@ -164,8 +181,9 @@ impl Message {
     }
 
     pub fn get_by_hash(db: &DStorage, hash: &EventId) -> Result<Message> {
-        let sql = format!("SELECT id, hash, fid, is_me, m_type, content, is_delivery, datetime FROM messages WHERE hash = {}", hash.to_hex());
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id, hash, fid, is_me, m_type, content, is_delivery, datetime, parent, is_read FROM messages WHERE hash = ?")
+            .bind(hash)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(Message::from_values(matrix.pop().unwrap()))
         } else {
@@ -174,44 +192,94 @@ impl Message {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let sql = format!(
-            "INSERT INTO messages (hash, fid, is_me, m_type, content, is_delivery, datetime) VALUES ('{}',{},{},{},'{}',{},{})",
-            self.hash.to_hex(),
-            self.fid,
-            self.is_me,
-            self.m_type.to_int(),
-            self.content,
-            self.is_delivery,
-            self.datetime,
-        );
-        self.id = db.insert(&sql)?;
-        Ok(())
+        self.id = Query::new("INSERT INTO messages (hash, fid, is_me, m_type, content, is_delivery, datetime, parent) VALUES (?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(&self.hash)
+            .bind(&self.fid)
+            .bind(&self.is_me)
+            .bind(&self.m_type.to_int())
+            .bind(&self.content)
+            .bind(&self.is_delivery)
+            .bind(&self.datetime)
+            .bind(&self.parent.map(|p| p.to_hex()).unwrap_or(String::new()))
+            .insert(db)?;
+        MessageIndex::insert(db, self.id, &self.m_type, &self.content)
     }
 
     pub fn delivery(db: &DStorage, id: i64, is_delivery: bool) -> Result<usize> {
-        let sql = format!(
-            "UPDATE messages SET is_delivery={} WHERE id = {}",
-            is_delivery, id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE messages SET is_delivery = ? WHERE id = ?")
+            .bind(&is_delivery)
+            .bind(&id)
+            .update(db)
+    }
+
+    /// mark the messages before this message (include) as read.
+    pub fn read(db: &DStorage, fid: &i64, id: &i64, is_me: bool) -> Result<usize> {
+        Query::new(
+            "UPDATE messages SET is_read = 1 WHERE fid = ? AND is_me = ? AND id <= ? AND is_read = 0",
+        )
+        .bind(fid)
+        .bind(&is_me)
+        .bind(id)
+        .update(db)
+    }
+
+    /// edit message's content, keep the history.
+    pub fn edit(&mut self, db: &DStorage, content: String) -> Result<MessageEdit> {
+        let old_content = std::mem::replace(&mut self.content, content);
+        let mut edit = MessageEdit::new(self.id, old_content, self.content.clone());
+        edit.insert(db)?;
+        Query::new("UPDATE messages SET content = ? WHERE id = ?")
+            .bind(&self.content)
+            .bind(&self.id)
+            .update(db)?;
+        MessageIndex::update(db, self.id, &self.m_type, &self.content)?;
+        Ok(edit)
     }
 
     pub fn delete(db: &DStorage, id: &i64) -> Result<usize> {
-        let sql = format!("DELETE FROM messages WHERE id = {}", id);
+        MessageIndex::delete(db, id)?;
+        MessageEdit::delete_by_message(db, id)?;
+        Reaction::delete_by_message(db, id)?;
+        Transfer::delete_by_message(db, id)?;
+        Outbox::delete_by_message(db, id)?;
         // TODO delete content
-        db.delete(&sql)
+        Query::new("DELETE FROM messages WHERE id = ?")
+            .bind(id)
+            .delete(db)
     }
 
     pub fn delete_by_fid(db: &DStorage, fid: &i64) -> Result<usize> {
-        let sql = format!("DELETE FROM messages WHERE fid = {}", fid);
-        let size = db.delete(&sql)?;
+        MessageIndex::delete_by_fid(db, fid)?;
+        MessageEdit::delete_by_fid(db, fid)?;
+        Reaction::delete_by_fid(db, fid)?;
+        Transfer::delete_by_fid(db, fid)?;
+        Outbox::delete_by_fid(db, fid)?;
+        let size = Query::new("DELETE FROM messages WHERE fid = ?")
+            .bind(fid)
+            .delete(db)?;
         // TOOD delete content.
         Ok(size)
     }
 
+    /// delete messages created before the datetime, return the deleted messages id.
+    pub fn delete_before(db: &DStorage, fid: &i64, datetime: &i64) -> Result<Vec<i64>> {
+        let matrix = Query::new("SELECT id FROM messages WHERE fid = ? AND datetime < ?")
+            .bind(fid)
+            .bind(datetime)
+            .query(db)?;
+        let mut ids = vec![];
+        for mut v in matrix {
+            let id = v.pop().unwrap().as_i64(); // safe unwrap.
+            Message::delete(db, &id)?;
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
     pub fn exist(db: &DStorage, hash: &EventId) -> Result<bool> {
-        let sql = format!("SELECT id FROM messages WHERE hash = '{}'", hash.to_hex());
-        let matrix = db.query(&sql)?;
+        let matrix = Query::new("SELECT id FROM messages WHERE hash = ?")
+            .bind(hash)
+            .query(db)?;
         Ok(matrix.len() > 0)
     }
 }
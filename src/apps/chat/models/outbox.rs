@@ -0,0 +1,118 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use tdn::types::primitive::Result;
+use tdn_storage::local::{DStorage, DsValue};
+
+use crate::utils::sql::Query;
+
+/// Undelivered message, will resend when friend online.
+pub(crate) struct Outbox {
+    pub id: i64,
+    /// message's db id.
+    pub message: i64,
+    /// friend's db id.
+    pub fid: i64,
+    /// resend times.
+    pub retries: i64,
+    /// last send time.
+    pub datetime: i64,
+}
+
+impl Outbox {
+    pub fn new(message: i64, fid: i64) -> Outbox {
+        let start = SystemTime::now();
+        let datetime = start
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+
+        Outbox {
+            id: 0,
+            message,
+            fid,
+            retries: 0,
+            datetime,
+        }
+    }
+
+    /// here is zero-copy and unwrap is safe. checked.
+    fn from_values(mut v: Vec<DsValue>) -> Outbox {
+        Outbox {
+            datetime: v.pop().unwrap().as_i64(),
+            retries: v.pop().unwrap().as_i64(),
+            fid: v.pop().unwrap().as_i64(),
+            message: v.pop().unwrap().as_i64(),
+            id: v.pop().unwrap().as_i64(),
+        }
+    }
+
+    pub fn to_rpc(&self) -> rpc_types::chat::Outbox {
+        rpc_types::chat::Outbox {
+            id: self.id,
+            message: self.message,
+            fid: self.fid,
+            retries: self.retries,
+            datetime: self.datetime,
+        }
+    }
+
+    pub fn list(db: &DStorage, fid: &i64) -> Result<Vec<Outbox>> {
+        let matrix = Query::new(
+            "SELECT id, message, fid, retries, datetime FROM outbox WHERE fid = ? ORDER BY id",
+        )
+        .bind(fid)
+        .query(db)?;
+        let mut outbox = vec![];
+        for values in matrix {
+            outbox.push(Outbox::from_values(values));
+        }
+        Ok(outbox)
+    }
+
+    /// add message to outbox, if had, do nothing.
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        let mut unique_check = Query::new("SELECT id from outbox WHERE message = ?")
+            .bind(&self.message)
+            .query(db)?;
+        if unique_check.len() > 0 {
+            self.id = unique_check.pop().unwrap().pop().unwrap().as_i64();
+        } else {
+            self.id = Query::new(
+                "INSERT INTO outbox (message, fid, retries, datetime) VALUES (?, ?, ?, ?)",
+            )
+            .bind(&self.message)
+            .bind(&self.fid)
+            .bind(&self.retries)
+            .bind(&self.datetime)
+            .insert(db)?;
+        }
+        Ok(())
+    }
+
+    /// message resend again.
+    pub fn retry(&mut self, db: &DStorage) -> Result<usize> {
+        let start = SystemTime::now();
+        self.datetime = start
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+        self.retries += 1;
+
+        Query::new("UPDATE outbox SET retries = ?, datetime = ? WHERE id = ?")
+            .bind(&self.retries)
+            .bind(&self.datetime)
+            .bind(&self.id)
+            .update(db)
+    }
+
+    pub fn delete_by_message(db: &DStorage, message: &i64) -> Result<usize> {
+        Query::new("DELETE FROM outbox WHERE message = ?")
+            .bind(message)
+            .delete(db)
+    }
+
+    pub fn delete_by_fid(db: &DStorage, fid: &i64) -> Result<usize> {
+        Query::new("DELETE FROM outbox WHERE fid = ?")
+            .bind(fid)
+            .delete(db)
+    }
+}
@@ -0,0 +1,294 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use tdn::types::{group::EventId, primitive::Result};
+use tdn_storage::local::{DStorage, DsValue};
+
+use crate::utils::sql::Query;
+
+/// Message's edit history. every edit will keep the content before and after.
+pub(crate) struct MessageEdit {
+    pub id: i64,
+    /// message's db id.
+    pub message: i64,
+    pub old_content: String,
+    pub new_content: String,
+    pub datetime: i64,
+}
+
+impl MessageEdit {
+    pub fn new(message: i64, old_content: String, new_content: String) -> MessageEdit {
+        let start = SystemTime::now();
+        let datetime = start
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+
+        MessageEdit {
+            id: 0,
+            message,
+            old_content,
+            new_content,
+            datetime,
+        }
+    }
+
+    /// here is zero-copy and unwrap is safe. checked.
+    fn from_values(mut v: Vec<DsValue>) -> MessageEdit {
+        MessageEdit {
+            datetime: v.pop().unwrap().as_i64(),
+            new_content: v.pop().unwrap().as_string(),
+            old_content: v.pop().unwrap().as_string(),
+            message: v.pop().unwrap().as_i64(),
+            id: v.pop().unwrap().as_i64(),
+        }
+    }
+
+    pub fn to_rpc(&self) -> rpc_types::chat::MessageEdit {
+        rpc_types::chat::MessageEdit {
+            id: self.id,
+            message: self.message,
+            old_content: self.old_content.clone(),
+            new_content: self.new_content.clone(),
+            datetime: self.datetime,
+        }
+    }
+
+    pub fn get(db: &DStorage, id: &i64) -> Result<MessageEdit> {
+        let mut matrix = Query::new(
+            "SELECT id, message, old_content, new_content, datetime FROM message_edits WHERE id = ?",
+        )
+        .bind(id)
+        .query(db)?;
+        if matrix.len() > 0 {
+            Ok(MessageEdit::from_values(matrix.pop().unwrap())) // safe unwrap()
+        } else {
+            Err(anyhow!("message edit is missing."))
+        }
+    }
+
+    pub fn list(db: &DStorage, message: &i64) -> Result<Vec<MessageEdit>> {
+        let matrix = Query::new("SELECT id, message, old_content, new_content, datetime FROM message_edits WHERE message = ? ORDER BY id")
+            .bind(message)
+            .query(db)?;
+        let mut edits = vec![];
+        for values in matrix {
+            edits.push(MessageEdit::from_values(values));
+        }
+        Ok(edits)
+    }
+
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        self.id = Query::new("INSERT INTO message_edits (message, old_content, new_content, datetime) VALUES (?, ?, ?, ?)")
+            .bind(&self.message)
+            .bind(&self.old_content)
+            .bind(&self.new_content)
+            .bind(&self.datetime)
+            .insert(db)?;
+        Ok(())
+    }
+
+    /// edit not sent to friend, will resend when friend online.
+    pub fn undelivered(db: &DStorage, id: &i64) -> Result<usize> {
+        Query::new("UPDATE message_edits SET is_delivery = 0 WHERE id = ?")
+            .bind(id)
+            .update(db)
+    }
+
+    pub fn delivery(db: &DStorage, id: &i64) -> Result<usize> {
+        Query::new("UPDATE message_edits SET is_delivery = 1 WHERE id = ?")
+            .bind(id)
+            .update(db)
+    }
+
+    /// undelivered edits to friend, return (id, message's hash, new content).
+    pub fn list_undelivered(db: &DStorage, fid: &i64) -> Result<Vec<(i64, EventId, String)>> {
+        let matrix = Query::new(
+            "SELECT message_edits.id, messages.hash, message_edits.new_content FROM message_edits JOIN messages ON messages.id = message_edits.message WHERE messages.fid = ? AND message_edits.is_delivery = 0 ORDER BY message_edits.id",
+        )
+        .bind(fid)
+        .query(db)?;
+        let mut edits = vec![];
+        for mut v in matrix {
+            let content = v.pop().unwrap().as_string(); // safe unwrap.
+            let hash = EventId::from_hex(v.pop().unwrap().as_str()).unwrap_or(EventId::default());
+            edits.push((v.pop().unwrap().as_i64(), hash, content));
+        }
+        Ok(edits)
+    }
+
+    pub fn delete_by_message(db: &DStorage, message: &i64) -> Result<usize> {
+        Query::new("DELETE FROM message_edits WHERE message = ?")
+            .bind(message)
+            .delete(db)
+    }
+
+    pub fn delete_by_fid(db: &DStorage, fid: &i64) -> Result<usize> {
+        Query::new(
+            "DELETE FROM message_edits WHERE message IN (SELECT id FROM messages WHERE fid = ?)",
+        )
+        .bind(fid)
+        .delete(db)
+    }
+}
+
+/// Emoji reaction to a message. one emoji only once by every side.
+pub(crate) struct Reaction {
+    pub id: i64,
+    /// message's db id.
+    pub message: i64,
+    pub is_me: bool,
+    pub emoji: String,
+    pub datetime: i64,
+    /// removed, keep the row for sync.
+    pub is_deleted: bool,
+}
+
+impl Reaction {
+    pub fn new(message: i64, is_me: bool, emoji: String) -> Reaction {
+        let start = SystemTime::now();
+        let datetime = start
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+
+        Reaction {
+            id: 0,
+            message,
+            is_me,
+            emoji,
+            datetime,
+            is_deleted: false,
+        }
+    }
+
+    /// here is zero-copy and unwrap is safe. checked.
+    fn from_values(mut v: Vec<DsValue>) -> Reaction {
+        Reaction {
+            is_deleted: v.pop().unwrap().as_bool(),
+            datetime: v.pop().unwrap().as_i64(),
+            emoji: v.pop().unwrap().as_string(),
+            is_me: v.pop().unwrap().as_bool(),
+            message: v.pop().unwrap().as_i64(),
+            id: v.pop().unwrap().as_i64(),
+        }
+    }
+
+    pub fn to_rpc(&self) -> rpc_types::chat::Reaction {
+        rpc_types::chat::Reaction {
+            id: self.id,
+            message: self.message,
+            is_me: self.is_me,
+            emoji: self.emoji.clone(),
+            datetime: self.datetime,
+        }
+    }
+
+    pub fn get(db: &DStorage, id: &i64) -> Result<Reaction> {
+        let mut matrix = Query::new(
+            "SELECT id, message, is_me, emoji, datetime, is_deleted FROM reactions WHERE id = ?",
+        )
+        .bind(id)
+        .query(db)?;
+        if matrix.len() > 0 {
+            Ok(Reaction::from_values(matrix.pop().unwrap())) // safe unwrap()
+        } else {
+            Err(anyhow!("reaction is missing."))
+        }
+    }
+
+    pub fn list(db: &DStorage, message: &i64) -> Result<Vec<Reaction>> {
+        let matrix = Query::new(
+            "SELECT id, message, is_me, emoji, datetime, is_deleted FROM reactions WHERE message = ? AND is_deleted = 0 ORDER BY id",
+        )
+        .bind(message)
+        .query(db)?;
+        let mut reactions = vec![];
+        for values in matrix {
+            reactions.push(Reaction::from_values(values));
+        }
+        Ok(reactions)
+    }
+
+    /// add reaction, if had same reaction, return the old one.
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        let mut unique_check =
+            Query::new("SELECT id from reactions WHERE message = ? AND is_me = ? AND emoji = ? AND is_deleted = 0")
+                .bind(&self.message)
+                .bind(&self.is_me)
+                .bind(&self.emoji)
+                .query(db)?;
+        if unique_check.len() > 0 {
+            self.id = unique_check.pop().unwrap().pop().unwrap().as_i64();
+        } else {
+            self.id = Query::new(
+                "INSERT INTO reactions (message, is_me, emoji, datetime) VALUES (?, ?, ?, ?)",
+            )
+            .bind(&self.message)
+            .bind(&self.is_me)
+            .bind(&self.emoji)
+            .bind(&self.datetime)
+            .insert(db)?;
+        }
+        Ok(())
+    }
+
+    /// remove reaction, return the removed reaction's id, none if not had.
+    pub fn remove(db: &DStorage, message: &i64, is_me: bool, emoji: &str) -> Result<Option<i64>> {
+        let mut matrix =
+            Query::new("SELECT id from reactions WHERE message = ? AND is_me = ? AND emoji = ? AND is_deleted = 0")
+                .bind(message)
+                .bind(&is_me)
+                .bind(emoji)
+                .query(db)?;
+        if let Some(mut values) = matrix.pop() {
+            let id = values.pop().unwrap().as_i64(); // safe unwrap.
+            Query::new("UPDATE reactions SET is_deleted = 1 WHERE id = ?")
+                .bind(&id)
+                .update(db)?;
+            Ok(Some(id))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// reaction not sent to friend, will resend when friend online.
+    pub fn undelivered(db: &DStorage, id: &i64) -> Result<usize> {
+        Query::new("UPDATE reactions SET is_delivery = 0 WHERE id = ?")
+            .bind(id)
+            .update(db)
+    }
+
+    pub fn delivery(db: &DStorage, id: &i64) -> Result<usize> {
+        Query::new("UPDATE reactions SET is_delivery = 1 WHERE id = ?")
+            .bind(id)
+            .update(db)
+    }
+
+    /// my undelivered reactions to friend, return (id, message's hash, emoji, is_add).
+    pub fn list_undelivered(db: &DStorage, fid: &i64) -> Result<Vec<(i64, EventId, String, bool)>> {
+        let matrix = Query::new(
+            "SELECT reactions.id, messages.hash, reactions.emoji, reactions.is_deleted FROM reactions JOIN messages ON messages.id = reactions.message WHERE messages.fid = ? AND reactions.is_me = 1 AND reactions.is_delivery = 0 ORDER BY reactions.id",
+        )
+        .bind(fid)
+        .query(db)?;
+        let mut reactions = vec![];
+        for mut v in matrix {
+            let is_add = !v.pop().unwrap().as_bool(); // safe unwrap.
+            let emoji = v.pop().unwrap().as_string();
+            let hash = EventId::from_hex(v.pop().unwrap().as_str()).unwrap_or(EventId::default());
+            reactions.push((v.pop().unwrap().as_i64(), hash, emoji, is_add));
+        }
+        Ok(reactions)
+    }
+
+    pub fn delete_by_message(db: &DStorage, message: &i64) -> Result<usize> {
+        Query::new("DELETE FROM reactions WHERE message = ?")
+            .bind(message)
+            .delete(db)
+    }
+
+    pub fn delete_by_fid(db: &DStorage, fid: &i64) -> Result<usize> {
+        Query::new("DELETE FROM reactions WHERE message IN (SELECT id FROM messages WHERE fid = ?)")
+            .bind(fid)
+            .delete(db)
+    }
+}
@@ -2,10 +2,11 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tdn::types::{
     group::GroupId,
     primitive::{PeerId, Result},
-    rpc::{json, RpcParam},
 };
 use tdn_storage::local::{DStorage, DsValue};
 
+use crate::utils::sql::Query;
+
 #[derive(Clone)]
 pub(crate) struct Request {
     pub id: i64,
@@ -65,24 +66,25 @@ impl Request {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.gid.to_hex(),
-            self.addr.to_hex(),
-            self.name,
-            self.remark,
-            self.is_me,
-            self.is_ok,
-            self.is_over,
-            self.is_delivery,
-            self.datetime,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::chat::Request {
+        rpc_types::chat::Request {
+            id: self.id,
+            gid: self.gid.to_hex(),
+            addr: self.addr.to_hex(),
+            name: self.name.clone(),
+            remark: self.remark.clone(),
+            is_me: self.is_me,
+            is_ok: self.is_ok,
+            is_over: self.is_over,
+            is_delivery: self.is_delivery,
+            datetime: self.datetime,
+        }
     }
 
     pub fn get_id(db: &DStorage, gid: &GroupId) -> Result<Request> {
-        let sql = format!("SELECT id, gid, addr, name, remark, is_me, is_ok, is_over, is_delivery, datetime FROM requests WHERE gid = '{}'", gid.to_hex());
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id, gid, addr, name, remark, is_me, is_ok, is_over, is_delivery, datetime FROM requests WHERE gid = ?")
+            .bind(gid)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(Request::from_values(matrix.pop().unwrap())) // safe unwrap()
         } else {
@@ -91,8 +93,9 @@ impl Request {
     }
 
     pub fn get(db: &DStorage, id: &i64) -> Result<Request> {
-        let sql = format!("SELECT id, gid, addr, name, remark, is_me, is_ok, is_over, is_delivery, datetime FROM requests WHERE id = {}", id);
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id, gid, addr, name, remark, is_me, is_ok, is_over, is_delivery, datetime FROM requests WHERE id = ?")
+            .bind(id)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(Request::from_values(matrix.pop().unwrap())) // safe unwrap()
         } else {
@@ -110,50 +113,47 @@ impl Request {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let sql = format!("INSERT INTO requests (gid, addr, name, remark, is_me, is_ok, is_over, is_delivery, datetime) VALUES ('{}', '{}', '{}', '{}', {}, {}, {}, {}, {})",
-            self.gid.to_hex(),
-            self.addr.to_hex(),
-            self.name,
-            self.remark,
-            self.is_me,
-            self.is_ok,
-            self.is_over,
-            self.is_delivery,
-            self.datetime,
-        );
-        let id = db.insert(&sql)?;
+        let id = Query::new("INSERT INTO requests (gid, addr, name, remark, is_me, is_ok, is_over, is_delivery, datetime) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(&self.gid)
+            .bind(&self.addr)
+            .bind(&self.name)
+            .bind(&self.remark)
+            .bind(&self.is_me)
+            .bind(&self.is_ok)
+            .bind(&self.is_over)
+            .bind(&self.is_delivery)
+            .bind(&self.datetime)
+            .insert(db)?;
         self.id = id;
         Ok(())
     }
 
     pub fn update(&self, db: &DStorage) -> Result<usize> {
-        let sql = format!("UPDATE requests SET gid='{}', addr='{}', name='{}', remark='{}', is_me={}, is_ok={}, is_over={}, is_delivery={}, datetime={} WHERE id = {}",
-            self.gid.to_hex(),
-            self.addr.to_hex(),
-            self.name,
-            self.remark,
-            self.is_me,
-            self.is_ok,
-            self.is_over,
-            self.is_delivery,
-            self.datetime,
-            self.id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE requests SET gid = ?, addr = ?, name = ?, remark = ?, is_me = ?, is_ok = ?, is_over = ?, is_delivery = ?, datetime = ? WHERE id = ?")
+            .bind(&self.gid)
+            .bind(&self.addr)
+            .bind(&self.name)
+            .bind(&self.remark)
+            .bind(&self.is_me)
+            .bind(&self.is_ok)
+            .bind(&self.is_over)
+            .bind(&self.is_delivery)
+            .bind(&self.datetime)
+            .bind(&self.id)
+            .update(db)
     }
 
     pub fn delivery(db: &DStorage, id: i64, is_delivery: bool) -> Result<usize> {
-        let sql = format!(
-            "UPDATE requests SET is_delivery={} WHERE id = {}",
-            if is_delivery { 1 } else { 0 },
-            id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE requests SET is_delivery = ? WHERE id = ?")
+            .bind(&is_delivery)
+            .bind(&id)
+            .update(db)
     }
 
     pub fn delete(db: &DStorage, id: &i64) -> Result<usize> {
-        let sql = format!("DELETE FROM requests WHERE id = {}", id);
-        let size = db.delete(&sql)?;
+        let size = Query::new("DELETE FROM requests WHERE id = ?")
+            .bind(id)
+            .delete(db)?;
         // TODO delete avatar.
         Ok(size)
     }
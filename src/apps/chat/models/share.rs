@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tdn::types::{group::GroupId, primitive::Result};
+use tdn_storage::local::{DStorage, DsValue};
+
+use crate::utils::sql::Query;
+
+/// A piece of the friend's secret, split by shamir.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SharePiece {
+    /// the account which secret belongs to.
+    pub gid: GroupId,
+    /// how many pieces can combine the secret.
+    pub threshold: u8,
+    pub x: u8,
+    pub y: Vec<u8>,
+}
+
+/// Recovery share, held for friend or returned from friends when recovery.
+pub(crate) struct Share {
+    pub id: i64,
+    /// the account which secret belongs to.
+    pub owner: GroupId,
+    /// who send this share.
+    pub sender: GroupId,
+    /// encrypted share piece.
+    pub data: Vec<u8>,
+    /// held for friend, or returned to me.
+    pub is_held: bool,
+    pub datetime: i64,
+}
+
+impl Share {
+    pub fn new(owner: GroupId, sender: GroupId, data: Vec<u8>, is_held: bool) -> Share {
+        let start = SystemTime::now();
+        let datetime = start
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+
+        Share {
+            id: 0,
+            owner,
+            sender,
+            data,
+            is_held,
+            datetime,
+        }
+    }
+
+    /// here is zero-copy and unwrap is safe. checked.
+    fn from_values(mut v: Vec<DsValue>) -> Share {
+        Share {
+            datetime: v.pop().unwrap().as_i64(),
+            is_held: v.pop().unwrap().as_bool(),
+            data: hex::decode(v.pop().unwrap().as_str()).unwrap_or(vec![]),
+            sender: GroupId::from_hex(v.pop().unwrap().as_str()).unwrap_or(GroupId::default()),
+            owner: GroupId::from_hex(v.pop().unwrap().as_str()).unwrap_or(GroupId::default()),
+            id: v.pop().unwrap().as_i64(),
+        }
+    }
+
+    pub fn to_rpc(&self) -> rpc_types::chat::Share {
+        rpc_types::chat::Share {
+            id: self.id,
+            owner: self.owner.to_hex(),
+            sender: self.sender.to_hex(),
+            is_held: self.is_held,
+            datetime: self.datetime,
+        }
+    }
+
+    pub fn get(db: &DStorage, id: &i64) -> Result<Share> {
+        let mut matrix = Query::new(
+            "SELECT id, owner, sender, data, is_held, datetime FROM shares WHERE id = ?",
+        )
+        .bind(id)
+        .query(db)?;
+        if matrix.len() > 0 {
+            Ok(Share::from_values(matrix.pop().unwrap())) // safe unwrap()
+        } else {
+            Err(anyhow!("share is missing"))
+        }
+    }
+
+    /// shares which held for friends.
+    pub fn list_held(db: &DStorage) -> Result<Vec<Share>> {
+        let matrix = db.query(
+            "SELECT id, owner, sender, data, is_held, datetime FROM shares WHERE is_held = true",
+        )?;
+        let mut shares = vec![];
+        for values in matrix {
+            shares.push(Share::from_values(values));
+        }
+        Ok(shares)
+    }
+
+    /// shares which friends returned for the account.
+    pub fn list_returned(db: &DStorage, owner: &GroupId) -> Result<Vec<Share>> {
+        let matrix = Query::new(
+            "SELECT id, owner, sender, data, is_held, datetime FROM shares WHERE is_held = false AND owner = ?",
+        )
+        .bind(owner)
+        .query(db)?;
+        let mut shares = vec![];
+        for values in matrix {
+            shares.push(Share::from_values(values));
+        }
+        Ok(shares)
+    }
+
+    /// one share for each owner and sender, the new share replace the old.
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        let mut unique_check =
+            Query::new("SELECT id from shares WHERE owner = ? AND sender = ? AND is_held = ?")
+                .bind(&self.owner)
+                .bind(&self.sender)
+                .bind(&self.is_held)
+                .query(db)?;
+        if unique_check.len() > 0 {
+            self.id = unique_check.pop().unwrap().pop().unwrap().as_i64();
+            Query::new("UPDATE shares SET data = ?, datetime = ? WHERE id = ?")
+                .bind(&hex::encode(&self.data))
+                .bind(&self.datetime)
+                .bind(&self.id)
+                .update(db)?;
+        } else {
+            self.id = Query::new(
+                "INSERT INTO shares (owner, sender, data, is_held, datetime) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&self.owner)
+            .bind(&self.sender)
+            .bind(&hex::encode(&self.data))
+            .bind(&self.is_held)
+            .bind(&self.datetime)
+            .insert(db)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(db: &DStorage, id: &i64) -> Result<usize> {
+        Query::new("DELETE FROM shares WHERE id = ?")
+            .bind(id)
+            .delete(db)
+    }
+}
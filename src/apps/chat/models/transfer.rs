@@ -0,0 +1,226 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use tdn::types::{group::EventId, primitive::Result};
+use tdn_storage::local::{DStorage, DsValue};
+
+use chat_types::FileChunk;
+
+use crate::utils::sql::Query;
+
+/// Chunked file transfer. sender and receiver both keep it for resume.
+/// it is also used in group chat, then fid is the group chat's db id.
+pub(crate) struct Transfer {
+    pub id: i64,
+    /// message's hash.
+    pub hash: EventId,
+    /// friend's (or group chat's) db id.
+    pub fid: i64,
+    /// is sender.
+    pub is_me: bool,
+    /// file name.
+    pub name: String,
+    /// file size.
+    pub size: i64,
+    /// whole file's hash.
+    pub file_hash: [u8; 32],
+    /// every chunk's hash.
+    pub chunks: Vec<[u8; 32]>,
+    /// chunk received status.
+    pub received: Vec<bool>,
+    pub is_over: bool,
+    pub datetime: i64,
+}
+
+impl Transfer {
+    pub fn new(
+        hash: EventId,
+        fid: i64,
+        is_me: bool,
+        name: String,
+        size: i64,
+        file_hash: [u8; 32],
+        chunks: Vec<[u8; 32]>,
+    ) -> Transfer {
+        let start = SystemTime::now();
+        let datetime = start
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+
+        let received = vec![is_me; chunks.len()];
+        Transfer {
+            id: 0,
+            hash,
+            fid,
+            is_me,
+            name,
+            size,
+            file_hash,
+            chunks,
+            received,
+            is_over: false,
+            datetime,
+        }
+    }
+
+    /// here is zero-copy and unwrap is safe. checked.
+    fn from_values(mut v: Vec<DsValue>) -> Transfer {
+        let datetime = v.pop().unwrap().as_i64();
+        let is_over = v.pop().unwrap().as_bool();
+        let received = v
+            .pop()
+            .unwrap()
+            .as_str()
+            .chars()
+            .map(|c| c == '1')
+            .collect();
+        let chunks = hex::decode(v.pop().unwrap().as_str())
+            .unwrap_or(vec![])
+            .chunks_exact(32)
+            .map(|c| {
+                let mut bytes = [0u8; 32];
+                bytes.copy_from_slice(c);
+                bytes
+            })
+            .collect();
+        let mut file_hash = [0u8; 32];
+        if let Ok(bytes) = hex::decode(v.pop().unwrap().as_str()) {
+            if bytes.len() == 32 {
+                file_hash.copy_from_slice(&bytes);
+            }
+        }
+
+        Transfer {
+            datetime,
+            is_over,
+            received,
+            chunks,
+            file_hash,
+            size: v.pop().unwrap().as_i64(),
+            name: v.pop().unwrap().as_string(),
+            is_me: v.pop().unwrap().as_bool(),
+            fid: v.pop().unwrap().as_i64(),
+            hash: EventId::from_hex(v.pop().unwrap().as_str()).unwrap_or(EventId::default()),
+            id: v.pop().unwrap().as_i64(),
+        }
+    }
+
+    fn received_string(&self) -> String {
+        self.received
+            .iter()
+            .map(|r| if *r { '1' } else { '0' })
+            .collect()
+    }
+
+    pub fn to_rpc(&self) -> rpc_types::chat::Transfer {
+        let (done, total) = self.progress();
+        rpc_types::chat::Transfer {
+            id: self.id,
+            hash: self.hash.to_hex(),
+            fid: self.fid,
+            is_me: self.is_me,
+            name: self.name.clone(),
+            size: self.size,
+            done: done as u64,
+            total: total as u64,
+            is_over: self.is_over,
+        }
+    }
+
+    /// the sender start message.
+    pub fn to_start(&self) -> FileChunk {
+        FileChunk::Start(
+            self.hash,
+            self.name.clone(),
+            self.size as u64,
+            self.file_hash,
+            self.chunks.clone(),
+        )
+    }
+
+    /// received chunks and all chunks.
+    pub fn progress(&self) -> (usize, usize) {
+        let done = self.received.iter().filter(|r| **r).count();
+        (done, self.chunks.len())
+    }
+
+    /// the first missing chunks, limit by window size.
+    pub fn missing(&self, limit: usize) -> Vec<u32> {
+        self.received
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| !**r)
+            .take(limit)
+            .map(|(i, _)| i as u32)
+            .collect()
+    }
+
+    /// check the chunk with the hash from sender.
+    pub fn check(&self, index: u32, bytes: &[u8]) -> bool {
+        if let Some(hash) = self.chunks.get(index as usize) {
+            blake3::hash(bytes).as_bytes() == hash
+        } else {
+            false
+        }
+    }
+
+    pub fn get_by_hash(db: &DStorage, hash: &EventId) -> Result<Transfer> {
+        let mut matrix = Query::new("SELECT id, hash, fid, is_me, name, size, file_hash, chunks, received, is_over, datetime FROM transfers WHERE hash = ?")
+            .bind(hash)
+            .query(db)?;
+        if matrix.len() > 0 {
+            Ok(Transfer::from_values(matrix.pop().unwrap())) // safe unwrap()
+        } else {
+            Err(anyhow!("transfer is missing."))
+        }
+    }
+
+    /// list all unfinished transfers with the friend.
+    pub fn list_unfinished(db: &DStorage, fid: &i64) -> Result<Vec<Transfer>> {
+        let matrix = Query::new("SELECT id, hash, fid, is_me, name, size, file_hash, chunks, received, is_over, datetime FROM transfers WHERE fid = ? AND is_over = 0")
+            .bind(fid)
+            .query(db)?;
+        let mut transfers = vec![];
+        for values in matrix {
+            transfers.push(Transfer::from_values(values));
+        }
+        Ok(transfers)
+    }
+
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        let chunks: Vec<u8> = self.chunks.concat();
+        self.id = Query::new("INSERT INTO transfers (hash, fid, is_me, name, size, file_hash, chunks, received, is_over, datetime) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(&self.hash)
+            .bind(&self.fid)
+            .bind(&self.is_me)
+            .bind(&self.name)
+            .bind(&self.size)
+            .bind(&hex::encode(&self.file_hash))
+            .bind(&hex::encode(&chunks))
+            .bind(&self.received_string())
+            .bind(&self.is_over)
+            .bind(&self.datetime)
+            .insert(db)?;
+        Ok(())
+    }
+
+    /// save the chunks received status.
+    pub fn update(&self, db: &DStorage) -> Result<usize> {
+        Query::new("UPDATE transfers SET received = ?, is_over = ? WHERE id = ?")
+            .bind(&self.received_string())
+            .bind(&self.is_over)
+            .bind(&self.id)
+            .update(db)
+    }
+
+    pub fn delete_by_message(db: &DStorage, message: &i64) -> Result<usize> {
+        Query::new("DELETE FROM transfers WHERE hash IN (SELECT hash FROM messages WHERE id = ?)")
+            .bind(message)
+            .delete(db)
+    }
+
+    pub fn delete_by_fid(db: &DStorage, fid: &i64) -> Result<usize> {
+        Query::new("DELETE FROM transfers WHERE fid = ?")
+            .bind(fid)
+            .delete(db)
+    }
+}
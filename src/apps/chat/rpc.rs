@@ -1,21 +1,41 @@
-use std::collections::HashMap;
+use rpc_types::chat::{
+    ChatDetail, Delivery, EmojiAddParams, EmojiName, EmojiPack, FriendListParams, FriendParams,
+    FriendRemark, FriendTyping, MessageCreateParams, MessageEditContent, MessageEditParams,
+    MessageHistory, MessageReact, MessageReactParams, MessageRead, MessageReadParams, Recovered,
+    RecoveryCombineParams, RecoverySendParams, RecoverySplit, RecoverySplitParams, RequestAgree,
+    RequestCreateParams, TransferProgress,
+};
+use rpc_types::Id;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tdn::types::{
-    group::GroupId,
+    group::{EventId, GroupId},
     message::SendType,
-    primitive::{HandleResult, PeerId},
+    primitive::{HandleResult, PeerId, Result},
     rpc::{json, rpc_response, RpcError, RpcHandler, RpcParam},
 };
 
 use chat_types::MessageType;
 
-use crate::event::InnerEvent;
-use crate::migrate::consensus::{FRIEND_TABLE_PATH, MESSAGE_TABLE_PATH, REQUEST_TABLE_PATH};
-use crate::rpc::{session_create, sleep_waiting_close_stable, RpcState};
-use crate::storage::delete_avatar;
+use crate::account::Account;
+use crate::apps::cloud::deposit;
+use crate::event::{InnerEvent, StatusEvent};
+use crate::migrate::consensus::{
+    FRIEND_TABLE_PATH, MESSAGE_EDIT_TABLE_PATH, MESSAGE_TABLE_PATH, REACTION_TABLE_PATH,
+    REQUEST_TABLE_PATH,
+};
+use crate::rpc::{parse, session_create, sleep_waiting_close_stable, RpcState};
+use crate::storage::{add_emoji, delete_avatar, delete_emoji, list_emojis};
+use crate::utils::crypto::decrypt_bytes;
+use crate::utils::shamir;
 
 use super::layer::{agree_message, reject_message, req_message, update_session, LayerEvent};
-use super::{Friend, Message, Request};
+use super::stream::{stream_data, stream_id, stream_open};
+use super::{Friend, Message, MessageEdit, Outbox, Reaction, Request, Share, SharePiece, Transfer};
+
+/// combinations checked at most when combine the returned shares.
+const MAX_COMBINATIONS: usize = 1024;
 
 #[inline]
 pub(crate) fn friend_info(mgid: GroupId, friend: &Friend) -> RpcParam {
@@ -24,17 +44,21 @@ pub(crate) fn friend_info(mgid: GroupId, friend: &Friend) -> RpcParam {
 
 #[inline]
 pub(crate) fn friend_update(mgid: GroupId, fid: i64, remark: &str) -> RpcParam {
-    rpc_response(0, "chat-friend-update", json!([fid, remark]), mgid)
+    let data = json!(FriendRemark {
+        id: fid,
+        remark: remark.to_owned(),
+    });
+    rpc_response(0, "chat-friend-update", data, mgid)
 }
 
 #[inline]
 pub(crate) fn friend_close(mgid: GroupId, fid: i64) -> RpcParam {
-    rpc_response(0, "chat-friend-close", json!([fid]), mgid)
+    rpc_response(0, "chat-friend-close", json!(Id { id: fid }), mgid)
 }
 
 #[inline]
 pub(crate) fn friend_delete(mgid: GroupId, fid: i64) -> RpcParam {
-    rpc_response(0, "chat-friend-delete", json!([fid]), mgid)
+    rpc_response(0, "chat-friend-delete", json!(Id { id: fid }), mgid)
 }
 
 #[inline]
@@ -43,23 +67,32 @@ pub(crate) fn request_create(mgid: GroupId, req: &Request) -> RpcParam {
 }
 
 #[inline]
-pub(crate) fn request_delivery(mgid: GroupId, id: i64, is_d: bool) -> RpcParam {
-    rpc_response(0, "chat-request-delivery", json!([id, is_d]), mgid)
+pub(crate) fn request_delivery(mgid: GroupId, id: i64, is_delivery: bool) -> RpcParam {
+    rpc_response(
+        0,
+        "chat-request-delivery",
+        json!(Delivery { id, is_delivery }),
+        mgid,
+    )
 }
 
 #[inline]
 pub(crate) fn request_agree(mgid: GroupId, id: i64, friend: &Friend) -> RpcParam {
-    rpc_response(0, "chat-request-agree", json!([id, friend.to_rpc()]), mgid)
+    let data = json!(RequestAgree {
+        id,
+        friend: friend.to_rpc(),
+    });
+    rpc_response(0, "chat-request-agree", data, mgid)
 }
 
 #[inline]
 pub(crate) fn request_reject(mgid: GroupId, id: i64) -> RpcParam {
-    rpc_response(0, "chat-request-reject", json!([id]), mgid)
+    rpc_response(0, "chat-request-reject", json!(Id { id }), mgid)
 }
 
 #[inline]
 pub(crate) fn request_delete(mgid: GroupId, id: i64) -> RpcParam {
-    rpc_response(0, "chat-request-delete", json!([id]), mgid)
+    rpc_response(0, "chat-request-delete", json!(Id { id }), mgid)
 }
 
 #[inline]
@@ -68,13 +101,79 @@ pub(crate) fn message_create(mgid: GroupId, msg: &Message) -> RpcParam {
 }
 
 #[inline]
-pub(crate) fn message_delivery(mgid: GroupId, id: i64, is_d: bool) -> RpcParam {
-    rpc_response(0, "chat-message-delivery", json!([id, is_d]), mgid)
+pub(crate) fn message_delivery(mgid: GroupId, id: i64, is_delivery: bool) -> RpcParam {
+    rpc_response(
+        0,
+        "chat-message-delivery",
+        json!(Delivery { id, is_delivery }),
+        mgid,
+    )
 }
 
 #[inline]
 pub(crate) fn message_delete(mgid: GroupId, id: i64) -> RpcParam {
-    rpc_response(0, "chat-message-delete", json!([id]), mgid)
+    rpc_response(0, "chat-message-delete", json!(Id { id }), mgid)
+}
+
+#[inline]
+pub(crate) fn message_edit(mgid: GroupId, id: i64, content: &str) -> RpcParam {
+    let data = json!(MessageEditContent {
+        id,
+        content: content.to_owned(),
+    });
+    rpc_response(0, "chat-message-edit", data, mgid)
+}
+
+#[inline]
+pub(crate) fn message_react(
+    mgid: GroupId,
+    id: i64,
+    is_me: bool,
+    emoji: &str,
+    is_add: bool,
+) -> RpcParam {
+    let data = json!(MessageReact {
+        id,
+        is_me,
+        emoji: emoji.to_owned(),
+        is_add,
+    });
+    rpc_response(0, "chat-message-react", data, mgid)
+}
+
+#[inline]
+pub(crate) fn message_read(mgid: GroupId, fid: i64, id: i64, is_me: bool) -> RpcParam {
+    rpc_response(
+        0,
+        "chat-message-read",
+        json!(MessageRead { fid, id, is_me }),
+        mgid,
+    )
+}
+
+#[inline]
+pub(crate) fn friend_typing(mgid: GroupId, fid: i64, is_typing: bool) -> RpcParam {
+    rpc_response(
+        0,
+        "chat-friend-typing",
+        json!(FriendTyping { fid, is_typing }),
+        mgid,
+    )
+}
+
+#[inline]
+pub(crate) fn transfer_progress(mgid: GroupId, id: i64, done: usize, total: usize) -> RpcParam {
+    let data = json!(TransferProgress {
+        id,
+        done: done as u64,
+        total: total as u64,
+    });
+    rpc_response(0, "chat-transfer-progress", data, mgid)
+}
+
+#[inline]
+pub(crate) fn recovery_share(mgid: GroupId, share: &Share) -> RpcParam {
+    rpc_response(0, "chat-recovery-share", json!(share.to_rpc()), mgid)
 }
 
 #[inline]
@@ -95,13 +194,87 @@ fn message_list(messages: Vec<Message>) -> RpcParam {
     json!(results)
 }
 
+#[inline]
+fn history_list(edits: Vec<MessageEdit>, reactions: Vec<Reaction>) -> RpcParam {
+    json!(MessageHistory {
+        edits: edits.iter().map(|e| e.to_rpc()).collect(),
+        reactions: reactions.iter().map(|r| r.to_rpc()).collect(),
+    })
+}
+
 #[inline]
 fn detail_list(friend: Friend, messages: Vec<Message>) -> RpcParam {
-    let mut message_results = vec![];
-    for msg in messages {
-        message_results.push(msg.to_rpc());
+    json!(ChatDetail {
+        friend: friend.to_rpc(),
+        messages: messages.iter().map(|m| m.to_rpc()).collect(),
+    })
+}
+
+/// create the message to friend, it keeps in outbox until delivered,
+/// and deposit to cloud when friend is offline.
+async fn send_message(
+    state: &Arc<RpcState>,
+    gid: GroupId,
+    fid: i64,
+    fgid: GroupId,
+    m_type: MessageType,
+    content: &str,
+    parent: Option<EventId>,
+    results: &mut HandleResult,
+) -> Result<Message> {
+    let mut layer_lock = state.layer.write().await;
+    let base = layer_lock.base().clone();
+    // friend maybe offline, message will keep in outbox.
+    let faddr = layer_lock.running(&gid)?.online(&fgid).ok();
+
+    let (msg, nw) =
+        LayerEvent::from_message(&state.group, &base, gid, fid, m_type, content, parent).await?;
+    let event = LayerEvent::Message(msg.hash, nw, msg.parent);
+
+    let db = state.group.read().await.chat_db(&gid)?;
+    Outbox::new(msg.id, fid).insert(&db)?;
+
+    if let Some(faddr) = faddr {
+        let s = super::layer::event_message(&mut layer_lock, msg.id, gid, faddr, &event);
+        results.layers.push((gid, fgid, s));
+
+        // big file start chunked transfer.
+        if m_type == MessageType::File {
+            if let Ok(t) = Transfer::get_by_hash(&db, &msg.hash) {
+                let uid = stream_id(&t.hash, &layer_lock.addr, &faddr);
+                let data = stream_data(&None, &t.to_start());
+                results
+                    .layers
+                    .push((gid, fgid, stream_open(uid, faddr, data)));
+            }
+        }
+    } else {
+        // deposit to cloud, friend will fetch it when online.
+        let data = bincode::serialize(&event).unwrap_or(vec![]);
+        let _ = deposit(&state.group, gid, fgid, msg.hash, &data, results).await;
+    }
+    drop(db);
+    drop(layer_lock);
+
+    // UPDATE SESSION.
+    if let Ok(s_db) = state.group.read().await.session_db(&gid) {
+        update_session(&s_db, &gid, &fid, &msg, results);
+    }
+
+    match event {
+        LayerEvent::Message(hash, nw, parent) => {
+            state.group.write().await.broadcast(
+                &gid,
+                InnerEvent::SessionMessageCreate(fgid, true, hash, nw, parent),
+                MESSAGE_TABLE_PATH,
+                msg.id,
+                results,
+            )?;
+        }
+        _ => {}
     }
-    json!([friend.to_rpc(), message_results])
+
+    Ok(msg)
 }
 
 pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
@@ -112,7 +285,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-friend-list",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let need_online = params[0].as_bool().ok_or(RpcError::ParseError)?;
+            let FriendListParams { need_online } = parse(params)?;
 
             let db = state.group.read().await.chat_db(&gid)?;
             let friends = Friend::list(&db)?;
@@ -122,11 +295,11 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             if need_online {
                 for friend in friends {
                     let online = layer_lock.is_online(&gid, &friend.gid);
-                    results.push(friend.to_rpc_online(online));
+                    results.push(json!(friend.to_rpc_online(online)));
                 }
             } else {
                 for friend in friends {
-                    results.push(friend.to_rpc());
+                    results.push(json!(friend.to_rpc()));
                 }
             }
             drop(layer_lock);
@@ -138,13 +311,12 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-friend-update",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let remark = params[1].as_str().ok_or(RpcError::ParseError)?;
+            let FriendRemark { id, remark } = parse(params)?;
 
             let mut results = HandleResult::new();
             let db = state.group.read().await.chat_db(&gid)?;
             let mut f = Friend::get(&db, &id)?;
-            f.remark = remark.to_owned();
+            f.remark = remark;
             f.me_update(&db)?;
             drop(db);
             state.group.write().await.broadcast(
@@ -161,7 +333,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-friend-close",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let mut results = HandleResult::new();
 
@@ -198,7 +370,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-friend-delete",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let mut results = HandleResult::new();
 
@@ -248,10 +420,14 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-request-create",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let remote_gid = GroupId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
-            let remote_addr = PeerId::from_hex(params[1].as_str().ok_or(RpcError::ParseError)?)?;
-            let remote_name = params[2].as_str().ok_or(RpcError::ParseError)?.to_string();
-            let remark = params[3].as_str().ok_or(RpcError::ParseError)?.to_string();
+            let RequestCreateParams {
+                gid: remote_gid,
+                addr: remote_addr,
+                name: remote_name,
+                remark,
+            } = parse(params)?;
+            let remote_gid = GroupId::from_hex(&remote_gid)?;
+            let remote_addr = PeerId::from_hex(&remote_addr)?;
 
             let mut request = Request::new(
                 remote_gid,
@@ -299,7 +475,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-request-agree",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let mut group_lock = state.group.write().await;
             let db = group_lock.chat_db(&gid)?;
@@ -319,7 +495,10 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
 
             let friend =
                 Friend::from_remote(&db, request.gid, request.name, request.addr, "".to_owned())?;
-            results.rpcs.push(json!([id, friend.to_rpc()]));
+            results.rpcs.push(json!(RequestAgree {
+                id,
+                friend: friend.to_rpc(),
+            }));
 
             // ADD NEW SESSION.
             let s_db = group_lock.session_db(&gid)?;
@@ -338,7 +517,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-request-reject",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let db = state.group.read().await.chat_db(&gid)?;
             let mut req = Request::get(&db, &id)?;
@@ -365,7 +544,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-request-delete",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let group_lock = state.group.read().await;
             let db = group_lock.chat_db(&gid)?;
@@ -395,7 +574,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-detail",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let db = state.group.read().await.chat_db(&gid)?;
             let friend = Friend::get(&db, &id)?;
@@ -409,7 +588,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-message-list",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let fid = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let FriendParams { fid } = parse(params)?;
 
             let db = state.group.read().await.chat_db(&gid)?;
             let messages = Message::get_by_fid(&db, &fid)?;
@@ -421,42 +600,38 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-message-create",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let fid = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let fgid = GroupId::from_hex(params[1].as_str().ok_or(RpcError::ParseError)?)?;
-            let m_type = MessageType::from_int(params[2].as_i64().ok_or(RpcError::ParseError)?);
-            let content = params[3].as_str().ok_or(RpcError::ParseError)?;
-
-            let mut layer_lock = state.layer.write().await;
-            let base = layer_lock.base().clone();
-            let faddr = layer_lock.running(&gid)?.online(&fgid)?;
-
-            let (msg, nw) =
-                LayerEvent::from_message(&state.group, &base, gid, fid, m_type, content).await?;
-            let event = LayerEvent::Message(msg.hash, nw);
-            let s = super::layer::event_message(&mut layer_lock, msg.id, gid, faddr, &event);
-            drop(layer_lock);
-
-            let mut results = HandleResult::rpc(json!(msg.to_rpc()));
-            results.layers.push((gid, fgid, s));
-
-            // UPDATE SESSION.
-            if let Ok(s_db) = state.group.read().await.session_db(&gid) {
-                update_session(&s_db, &gid, &fid, &msg, &mut results);
-            }
-
-            match event {
-                LayerEvent::Message(hash, nw) => {
-                    state.group.write().await.broadcast(
-                        &gid,
-                        InnerEvent::SessionMessageCreate(fgid, true, hash, nw),
-                        MESSAGE_TABLE_PATH,
-                        msg.id,
-                        &mut results,
-                    )?;
-                }
-                _ => {}
+            let MessageCreateParams {
+                fid,
+                fgid,
+                m_type,
+                content,
+                parent,
+            } = parse(params)?;
+            let fgid = GroupId::from_hex(&fgid)?;
+            let m_type = MessageType::from_int(m_type);
+            // recovery share only created by split.
+            if m_type == MessageType::Recovery {
+                return Err(RpcError::Custom("message type is invalid".to_owned()));
             }
+            // optional replied message's hash.
+            let parent = match parent {
+                Some(s) if !s.is_empty() => Some(EventId::from_hex(&s)?),
+                _ => None,
+            };
 
+            let mut results = HandleResult::new();
+            let msg = send_message(
+                &state,
+                gid,
+                fid,
+                fgid,
+                m_type,
+                &content,
+                parent,
+                &mut results,
+            )
+            .await?;
+            results.rpcs.insert(0, json!(msg.to_rpc()));
             Ok(results)
         },
     );
@@ -464,7 +639,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "chat-message-delete",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let db = state.group.read().await.chat_db(&gid)?;
             let msg = Message::get(&db, &id)?;
@@ -481,4 +656,387 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             Ok(results)
         },
     );
+
+    handler.add_method(
+        "chat-message-edit",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let MessageEditParams { id, content } = parse(params)?;
+
+            let db = state.group.read().await.chat_db(&gid)?;
+            let mut msg = Message::get(&db, &id)?;
+            if !msg.is_me || msg.m_type != MessageType::String {
+                return Err(RpcError::Custom("only can edit my text message".to_owned()));
+            }
+            let friend = Friend::get(&db, &msg.fid)?;
+            let edit = msg.edit(&db, content.clone())?;
+
+            let mut results = HandleResult::rpc(json!(msg.to_rpc()));
+            if let Ok(faddr) = state.layer.read().await.running(&gid)?.online(&friend.gid) {
+                let event = LayerEvent::MessageEdit(msg.hash, content.clone());
+                let data = bincode::serialize(&event)?;
+                results
+                    .layers
+                    .push((gid, friend.gid, SendType::Event(0, faddr, data)));
+            } else {
+                // friend is offline, resend when connected.
+                MessageEdit::undelivered(&db, &edit.id)?;
+            }
+            drop(db);
+
+            state.group.write().await.broadcast(
+                &gid,
+                InnerEvent::SessionMessageEdit(msg.hash, content),
+                MESSAGE_EDIT_TABLE_PATH,
+                edit.id,
+                &mut results,
+            )?;
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "chat-message-react",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let MessageReactParams { id, emoji, is_add } = parse(params)?;
+
+            let db = state.group.read().await.chat_db(&gid)?;
+            let msg = Message::get(&db, &id)?;
+            let friend = Friend::get(&db, &msg.fid)?;
+            let rid = if is_add {
+                let mut reaction = Reaction::new(msg.id, true, emoji.clone());
+                reaction.insert(&db)?;
+                reaction.id
+            } else {
+                Reaction::remove(&db, &msg.id, true, &emoji)?
+                    .ok_or(anyhow!("reaction is missing."))?
+            };
+
+            let mut results = HandleResult::rpc(json!(MessageReact {
+                id: msg.id,
+                is_me: true,
+                emoji: emoji.clone(),
+                is_add,
+            }));
+            if let Ok(faddr) = state.layer.read().await.running(&gid)?.online(&friend.gid) {
+                let event = LayerEvent::MessageReact(msg.hash, emoji.clone(), is_add);
+                let data = bincode::serialize(&event)?;
+                results
+                    .layers
+                    .push((gid, friend.gid, SendType::Event(0, faddr, data)));
+            } else {
+                // friend is offline, resend when connected.
+                Reaction::undelivered(&db, &rid)?;
+            }
+            drop(db);
+
+            state.group.write().await.broadcast(
+                &gid,
+                InnerEvent::SessionMessageReact(msg.hash, true, emoji, is_add),
+                REACTION_TABLE_PATH,
+                rid,
+                &mut results,
+            )?;
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "chat-message-history",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let Id { id } = parse(params)?;
+
+            let db = state.group.read().await.chat_db(&gid)?;
+            let edits = MessageEdit::list(&db, &id)?;
+            let reactions = Reaction::list(&db, &id)?;
+            drop(db);
+            Ok(HandleResult::rpc(history_list(edits, reactions)))
+        },
+    );
+
+    handler.add_method(
+        "chat-message-read",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let MessageReadParams { fid, id } = parse(params)?;
+
+            let db = state.group.read().await.chat_db(&gid)?;
+            let msg = Message::get(&db, &id)?;
+            Message::read(&db, &fid, &id, false)?;
+            let friend = Friend::get(&db, &fid)?;
+            drop(db);
+
+            let mut results = HandleResult::new();
+            if let Ok(addr) = state.layer.read().await.running(&gid)?.online(&friend.gid) {
+                let data = bincode::serialize(&LayerEvent::Read(msg.hash))?;
+                results
+                    .layers
+                    .push((gid, friend.gid, SendType::Event(0, addr, data)));
+            }
+
+            state.group.write().await.status(
+                &gid,
+                StatusEvent::SessionMessageRead(friend.gid, msg.hash, false),
+                &mut results,
+            )?;
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "chat-friend-typing",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let FriendTyping { fid, is_typing } = parse(params)?;
+
+            let db = state.group.read().await.chat_db(&gid)?;
+            let friend = Friend::get(&db, &fid)?;
+            drop(db);
+
+            let mut results = HandleResult::new();
+            if let Ok(addr) = state.layer.read().await.running(&gid)?.online(&friend.gid) {
+                let data = bincode::serialize(&LayerEvent::Typing(is_typing))?;
+                results
+                    .layers
+                    .push((gid, friend.gid, SendType::Event(0, addr, data)));
+            }
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "chat-emoji-list",
+        |gid: GroupId, _params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let base = state.group.read().await.base().clone();
+            let packs: Vec<EmojiPack> = list_emojis(&base, &gid)
+                .await?
+                .into_iter()
+                .map(|(pack, stickers)| EmojiPack { pack, stickers })
+                .collect();
+            Ok(HandleResult::rpc(json!(packs)))
+        },
+    );
+
+    handler.add_method(
+        "chat-emoji-add",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let EmojiAddParams { pack, path } = parse(params)?;
+
+            let base = state.group.read().await.base().clone();
+            let name = add_emoji(&base, &gid, &pack, &PathBuf::from(path)).await?;
+            Ok(HandleResult::rpc(json!(EmojiName { name })))
+        },
+    );
+
+    handler.add_method(
+        "chat-emoji-delete",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let EmojiName { name } = parse(params)?;
+
+            let base = state.group.read().await.base().clone();
+            delete_emoji(&base, &gid, &name).await?;
+            Ok(HandleResult::rpc(json!(EmojiName { name })))
+        },
+    );
+
+    handler.add_method(
+        "chat-transfer-list",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let FriendParams { fid } = parse(params)?;
+
+            let db = state.group.read().await.chat_db(&gid)?;
+            let transfers = Transfer::list_unfinished(&db, &fid)?;
+            drop(db);
+            let mut results = vec![];
+            for transfer in transfers {
+                results.push(transfer.to_rpc());
+            }
+            Ok(HandleResult::rpc(json!(results)))
+        },
+    );
+
+    handler.add_method(
+        "chat-outbox-list",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let FriendParams { fid } = parse(params)?;
+
+            let db = state.group.read().await.chat_db(&gid)?;
+            let outbox = Outbox::list(&db, &fid)?;
+            drop(db);
+            let mut results = vec![];
+            for o in outbox {
+                results.push(o.to_rpc());
+            }
+            Ok(HandleResult::rpc(json!(results)))
+        },
+    );
+
+    handler.add_method(
+        "chat-recovery-split",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let RecoverySplitParams {
+                lock,
+                threshold,
+                fids,
+            } = parse(params)?;
+            if threshold < 1 || threshold as usize > fids.len() || fids.len() > 255 {
+                return Err(RpcError::Custom("shares threshold is invalid".to_owned()));
+            }
+            let unique: HashSet<&i64> = fids.iter().collect();
+            if unique.len() != fids.len() {
+                return Err(RpcError::Custom("shares friends is repeated".to_owned()));
+            }
+
+            let group_lock = state.group.read().await;
+            let mnemonic = group_lock.mnemonic(&gid, &lock)?;
+            let account = group_lock.account(&gid)?;
+            let secret =
+                bincode::serialize(&(account.lang, account.index, mnemonic, &account.pass))?;
+            let db = group_lock.chat_db(&gid)?;
+            drop(group_lock);
+
+            // check all friends before send any share.
+            let mut friends = vec![];
+            for fid in &fids {
+                friends.push(Friend::get(&db, fid)?);
+            }
+            drop(db);
+
+            // shares are messages, friend maybe offline, they will keep in outbox.
+            let pieces = shamir::split(&secret, threshold as u8, fids.len() as u8)?;
+            let mut results = HandleResult::new();
+            for (friend, (x, y)) in friends.iter().zip(pieces) {
+                let piece = SharePiece {
+                    gid,
+                    threshold: threshold as u8,
+                    x,
+                    y,
+                };
+                let content = hex::encode(bincode::serialize(&piece)?);
+                let m_type = MessageType::Recovery;
+                let msg = send_message(
+                    &state,
+                    gid,
+                    friend.id,
+                    friend.gid,
+                    m_type,
+                    &content,
+                    None,
+                    &mut results,
+                )
+                .await?;
+                results.rpcs.push(message_create(gid, &msg));
+            }
+            results.rpcs.insert(
+                0,
+                json!(RecoverySplit {
+                    threshold,
+                    count: fids.len() as u64,
+                }),
+            );
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "chat-recovery-list",
+        |gid: GroupId, _params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let db = state.group.read().await.chat_db(&gid)?;
+            let shares: Vec<_> = Share::list_held(&db)?.iter().map(|s| s.to_rpc()).collect();
+            Ok(HandleResult::rpc(json!(shares)))
+        },
+    );
+
+    handler.add_method(
+        "chat-recovery-send",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let RecoverySendParams {
+                id,
+                gid: target,
+                addr,
+            } = parse(params)?;
+            let target = GroupId::from_hex(&target)?;
+            let addr = PeerId::from_hex(&addr)?;
+
+            let group_lock = state.group.read().await;
+            let db = group_lock.chat_db(&gid)?;
+            let key = group_lock.share_key(&gid)?;
+            drop(group_lock);
+
+            let share = Share::get(&db, &id)?;
+            if !share.is_held {
+                return Err(RpcError::Custom("share is not held".to_owned()));
+            }
+            let piece = decrypt_bytes(&key, &share.data)?;
+
+            let mut results = HandleResult::rpc(json!(Id { id }));
+            let data = bincode::serialize(&LayerEvent::RecoveryReturn(share.owner, piece))?;
+            let msg = SendType::Event(0, addr, data);
+            results.layers.push((gid, target, msg));
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "chat-recovery-combine",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let RecoveryCombineParams { owner } = parse(params)?;
+            let owner = GroupId::from_hex(&owner)?;
+
+            let group_lock = state.group.read().await;
+            let db = group_lock.chat_db(&gid)?;
+            let key = group_lock.share_key(&gid)?;
+            drop(group_lock);
+
+            // broken or other account's shares are skipped, they can be deleted.
+            let mut thresholds = vec![];
+            let mut pieces = vec![];
+            for share in Share::list_returned(&db, &owner)? {
+                let piece = decrypt_bytes(&key, &share.data)
+                    .ok()
+                    .and_then(|bytes| bincode::deserialize::<SharePiece>(&bytes).ok());
+                if let Some(piece) = piece.filter(|p| p.gid == owner) {
+                    thresholds.push(piece.threshold as usize);
+                    pieces.push((piece.x, piece.y));
+                }
+            }
+            thresholds.sort();
+            thresholds.dedup();
+            if pieces.len() < thresholds.first().copied().unwrap_or(1) {
+                return Err(RpcError::Custom("shares is not enough".to_owned()));
+            }
+
+            // bogus shares maybe returned, check the combinations by the account id.
+            let check = |secret: &[u8]| {
+                bincode::deserialize::<(i64, i64, String, String)>(secret)
+                    .ok()
+                    .and_then(|(lang, index, mnemonic, pass)| {
+                        Account::derive_id(index as u32, lang, &mnemonic, &pass).ok()
+                    })
+                    .map(|id| id == owner)
+                    .unwrap_or(false)
+            };
+            let mut tries = MAX_COMBINATIONS;
+            let secret = thresholds
+                .iter()
+                .find_map(|t| shamir::combine_checked(&pieces, *t, &mut tries, check))
+                .ok_or(RpcError::Custom("shares is invalid".to_owned()))?;
+            let (lang, index, mnemonic, pass): (i64, i64, String, String) =
+                bincode::deserialize(&secret)?;
+
+            Ok(HandleResult::rpc(json!(Recovered {
+                lang,
+                index,
+                mnemonic,
+                pass,
+            })))
+        },
+    );
+
+    handler.add_method(
+        "chat-recovery-delete",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let Id { id } = parse(params)?;
+            let db = state.group.read().await.chat_db(&gid)?;
+            Share::delete(&db, &id)?;
+            Ok(HandleResult::new())
+        },
+    );
 }
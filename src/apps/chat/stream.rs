@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tdn::types::{
+    group::{EventId, GroupId},
+    message::{SendType, StreamType},
+    primitive::{HandleResult, Peer, PeerId, Result},
+};
+use tdn_storage::local::DStorage;
+
+use chat_types::{FileChunk, FILE_CHUNK_SIZE};
+
+use crate::layer::Layer;
+use crate::storage::{
+    commit_file_part, is_file_name, read_file_chunk, read_part_chunk, write_part_chunk,
+};
+
+use super::models::Transfer;
+
+/// chunks count requested from the sender one time.
+pub(crate) const TRANSFER_WINDOW: usize = 16;
+
+/// request the missing chunks again, when no chunk received in it (seconds).
+pub(crate) const TRANSFER_TIMEOUT: u64 = 30;
+
+/// stop requesting again, the transfer will resume when the peer online again.
+const TRANSFER_RETRIES: u8 = 10;
+
+/// the receiving transfer's stream, use to request the lost chunks again.
+pub(crate) struct FileStream {
+    /// account's gid, the transfer is in its database.
+    pub gid: GroupId,
+    /// group chat's gid, none is the friend's transfer.
+    pub gcd: Option<GroupId>,
+    /// the layer's route (from, to) of the stream.
+    pub route: (GroupId, GroupId),
+    /// stream id.
+    pub uid: u32,
+    /// latest received time.
+    pub active: u64,
+    pub retries: u8,
+}
+
+impl FileStream {
+    pub fn new(gid: GroupId, gcd: Option<GroupId>, route: (GroupId, GroupId), uid: u32) -> Self {
+        FileStream {
+            gid,
+            gcd,
+            route,
+            uid,
+            active: now(),
+            retries: 0,
+        }
+    }
+}
+
+/// the handled chunk's result.
+#[derive(Default)]
+pub(crate) struct Handled {
+    /// chunks reply in the stream.
+    pub replies: Vec<FileChunk>,
+    /// transfer's (done, total) chunks.
+    pub progress: Option<(usize, usize)>,
+    /// new receiving transfer created by the start.
+    pub created: bool,
+    /// waiting the chunks (true), or received all (false).
+    pub waiting: Option<bool>,
+}
+
+#[inline]
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|s| s.as_secs())
+        .unwrap_or(0) // safe for all life.
+}
+
+/// the stream id of the transfer, it is same in the both peers.
+pub(crate) fn stream_id(hash: &EventId, a: &PeerId, b: &PeerId) -> u32 {
+    let (a, b) = (a.to_hex(), b.to_hex());
+    let (a, b) = if a < b { (a, b) } else { (b, a) };
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&hash.0);
+    hasher.update(a.as_bytes());
+    hasher.update(b.as_bytes());
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&hasher.finalize().as_bytes()[..4]);
+    u32::from_le_bytes(bytes)
+}
+
+/// open the stream to the peer with the first chunk.
+pub(crate) fn stream_open(uid: u32, addr: PeerId, data: Vec<u8>) -> SendType {
+    SendType::Stream(uid, StreamType::Req(Peer::peer(addr)), data)
+}
+
+/// accept the stream opened by the peer.
+pub(crate) fn stream_accept(uid: u32) -> SendType {
+    SendType::Stream(uid, StreamType::Res(true), vec![])
+}
+
+pub(crate) fn stream_send(uid: u32, data: Vec<u8>) -> SendType {
+    SendType::Stream(uid, StreamType::Ok, data)
+}
+
+/// the stream's data, group chat's chunk with the group chat's gid.
+pub(crate) fn stream_data(gcd: &Option<GroupId>, chunk: &FileChunk) -> Vec<u8> {
+    match gcd {
+        Some(gcd) => bincode::serialize(&(gcd, chunk)),
+        None => bincode::serialize(chunk),
+    }
+    .unwrap_or(vec![])
+}
+
+/// keep the receiving transfer's stream, remove it when received all.
+pub(crate) fn keep_stream(
+    streams: &mut HashMap<EventId, FileStream>,
+    hash: EventId,
+    waiting: Option<bool>,
+    stream: FileStream,
+) {
+    match waiting {
+        Some(true) => {
+            streams.insert(hash, stream);
+        }
+        Some(false) => {
+            streams.remove(&hash);
+        }
+        None => {}
+    }
+}
+
+#[inline]
+fn owned(t: Transfer, fid: i64) -> Result<Transfer> {
+    if t.fid == fid {
+        Ok(t)
+    } else {
+        Err(anyhow!("transfer is missing."))
+    }
+}
+
+/// handle the chunk from the stream, the file is read and written in the
+/// disk by chunks. fid is the friend's or group chat's db id.
+pub(crate) async fn handle_chunk(
+    base: &PathBuf,
+    gid: &GroupId,
+    db: &DStorage,
+    fid: i64,
+    chunk: FileChunk,
+) -> Result<Handled> {
+    let mut handled = Handled::default();
+    match chunk {
+        FileChunk::Start(hash, name, size, file_hash, chunks) => {
+            let t = if let Ok(t) = Transfer::get_by_hash(db, &hash) {
+                owned(t, fid)?
+            } else {
+                let chunk_size = FILE_CHUNK_SIZE as u64;
+                let count = size / chunk_size + (size % chunk_size > 0) as u64;
+                if !is_file_name(&name) || chunks.is_empty() || chunks.len() as u64 != count {
+                    return Err(anyhow!("file transfer invalid."));
+                }
+                let mut t = Transfer::new(hash, fid, false, name, size as i64, file_hash, chunks);
+                t.insert(db)?;
+                handled.created = true;
+                t
+            };
+            if !t.is_me && !t.is_over {
+                let chunk = FileChunk::Request(hash, t.missing(TRANSFER_WINDOW));
+                handled.replies.push(chunk);
+                handled.waiting = Some(true);
+            }
+        }
+        FileChunk::Data(hash, index, bytes) => {
+            let mut t = owned(Transfer::get_by_hash(db, &hash)?, fid)?;
+            let i = index as usize;
+            if t.is_me || t.is_over || i >= t.received.len() {
+                return Ok(handled);
+            }
+            handled.waiting = Some(true);
+
+            if !t.received[i] {
+                if t.check(index, &bytes) {
+                    let offset = index as u64 * FILE_CHUNK_SIZE as u64;
+                    write_part_chunk(base, gid, &hash, offset, &bytes).await?;
+                    t.received[i] = true;
+                } else {
+                    warn!("file chunk {} verify failure, will request again.", index);
+                }
+            }
+
+            let (done, total) = t.progress();
+            if done == total {
+                let size = t.size as u64;
+                if commit_file_part(base, gid, &hash, &t.name, size, &t.file_hash).await? {
+                    t.is_over = true;
+                    t.update(db)?;
+                    handled.replies.push(FileChunk::Done(hash));
+                    handled.waiting = Some(false);
+                } else {
+                    // whole file is broken, transfer again.
+                    warn!("file {} verify failure, will transfer again.", t.name);
+                    t.received = vec![false; total];
+                    t.update(db)?;
+                    let chunk = FileChunk::Request(hash, t.missing(TRANSFER_WINDOW));
+                    handled.replies.push(chunk);
+                }
+            } else {
+                t.update(db)?;
+                // the window finished, request next window.
+                // the lost chunks will request again by timer.
+                let next = i + 1;
+                if next % TRANSFER_WINDOW == 0 || next == total {
+                    let chunk = FileChunk::Request(hash, t.missing(TRANSFER_WINDOW));
+                    handled.replies.push(chunk);
+                }
+            }
+            handled.progress = Some(t.progress());
+        }
+        FileChunk::Request(hash, indexes) => {
+            let t = owned(Transfer::get_by_hash(db, &hash)?, fid)?;
+            // empty request is asking the start.
+            if indexes.is_empty() {
+                handled.replies.push(t.to_start());
+                return Ok(handled);
+            }
+
+            let total = t.chunks.len();
+            for index in indexes.iter().take(TRANSFER_WINDOW) {
+                let i = *index as usize;
+                if i >= total {
+                    continue;
+                }
+                let offset = *index as u64 * FILE_CHUNK_SIZE as u64;
+                // the unfinished file can relay the received chunks.
+                let bytes = if t.is_me || t.is_over {
+                    read_file_chunk(base, gid, &t.name, offset, FILE_CHUNK_SIZE).await?
+                } else if t.received[i] {
+                    read_part_chunk(base, gid, &hash, offset, FILE_CHUNK_SIZE).await?
+                } else {
+                    continue;
+                };
+                handled.replies.push(FileChunk::Data(hash, *index, bytes));
+            }
+
+            // receiver always request the first missing chunks.
+            if let (true, Some(done)) = (t.is_me, indexes.first()) {
+                handled.progress = Some((*done as usize, total));
+            }
+        }
+        FileChunk::Done(hash) => {
+            let mut t = owned(Transfer::get_by_hash(db, &hash)?, fid)?;
+            if t.is_me && !t.is_over {
+                t.is_over = true;
+                t.update(db)?;
+                let total = t.chunks.len();
+                handled.progress = Some((total, total));
+            }
+        }
+    }
+
+    Ok(handled)
+}
+
+/// request the missing chunks again, when the stream has no chunks in timeout.
+pub(crate) async fn retry_streams(layer: &mut Layer) -> HandleResult {
+    let mut results = HandleResult::new();
+    let now = now();
+    let group = layer.group.read().await;
+    let mut overs = vec![];
+    for (hash, s) in layer.streams.iter_mut() {
+        if now < s.active + TRANSFER_TIMEOUT {
+            continue;
+        }
+        if s.retries >= TRANSFER_RETRIES {
+            overs.push(*hash);
+            continue;
+        }
+
+        let db = if s.gcd.is_some() {
+            group.group_db(&s.gid)
+        } else {
+            group.chat_db(&s.gid)
+        };
+        let t = match db.and_then(|db| Transfer::get_by_hash(&db, hash)) {
+            Ok(t) if !t.is_over => t,
+            _ => {
+                overs.push(*hash);
+                continue;
+            }
+        };
+
+        let chunk = FileChunk::Request(*hash, t.missing(TRANSFER_WINDOW));
+        let msg = stream_send(s.uid, stream_data(&s.gcd, &chunk));
+        results.layers.push((s.route.0, s.route.1, msg));
+        s.active = now;
+        s.retries += 1;
+    }
+    drop(group);
+
+    for hash in overs {
+        layer.streams.remove(&hash);
+    }
+    results
+}
@@ -6,16 +6,107 @@ use tdn::types::{
 };
 use tokio::sync::RwLock;
 
-use cloud_types::LayerServerEvent;
+use cloud_types::{
+    LayerPeerEvent, LayerServerEvent, PeerEvent, ServerEvent, DEPOSIT_QUOTA, FETCH_LIMIT,
+    MAILBOX_QUOTA, MAIL_EXPIRE, PROVIDER_QUOTA,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tdn_storage::local::DStorage;
 
+use crate::apps::chat::{handle_mail, Friend};
 use crate::layer::Layer;
+use crate::utils::crypto::decrypt_bytes;
+
+use super::models::{Mail, MailKey, Provider};
+use super::{add_layer, add_server_layer, is_provider, rpc};
 
 pub(crate) async fn handle(
-    _layer: &Arc<RwLock<Layer>>,
-    _ogid: GroupId,
+    layer: &Arc<RwLock<Layer>>,
+    ogid: GroupId,
+    msg: RecvType,
+) -> Result<HandleResult> {
+    let mut results = HandleResult::new();
+
+    match msg {
+        RecvType::Connect(..)
+        | RecvType::Leave(..)
+        | RecvType::Result(..)
+        | RecvType::ResultConnect(..)
+        | RecvType::Stream(..) => {
+            info!("cloud message nerver to here.")
+        }
+        RecvType::Event(addr, bytes) => {
+            let LayerServerEvent(event, _proof) = bincode::deserialize(&bytes)?;
+
+            let db = layer.read().await.group.read().await.cloud_db(&ogid)?;
+            // only accept the events from my providers.
+            let mut provider = Provider::get_by_addr(&db, &addr)?;
+
+            match event {
+                ServerEvent::Status(name, _support_request, used, quota) => {
+                    provider.ok(&db, name, used as i64, quota as i64)?;
+                    results.rpcs.push(rpc::provider_status(ogid, &provider));
+                }
+                ServerEvent::Deposited(hash, is_ok) => {
+                    results.rpcs.push(rpc::mail_deposited(ogid, &hash, is_ok));
+                }
+                ServerEvent::Mails(offset, mails, more) => {
+                    let mut layer_lock = layer.write().await;
+                    let chat_db = layer_lock.group.read().await.chat_db(&ogid)?;
+                    let mut acks = vec![];
+                    let mut kept = 0;
+                    for (hash, sender, data, _datetime) in mails {
+                        let key = match MailKey::get(&db, &sender) {
+                            Ok(key) => key,
+                            Err(_) => {
+                                // keep the mail in provider when friend's key not arrived,
+                                // it will expire in provider. others' mail is dropped.
+                                if Friend::get_id(&chat_db, &sender).is_ok() {
+                                    kept += 1;
+                                } else {
+                                    acks.push(hash);
+                                }
+                                continue;
+                            }
+                        };
+                        match decrypt_bytes(&key, &data) {
+                            Ok(bytes) => {
+                                if let Err(e) =
+                                    handle_mail(&mut layer_lock, ogid, sender, bytes, &mut results)
+                                        .await
+                                {
+                                    warn!("cloud mail handle failure: {}", e);
+                                }
+                            }
+                            Err(e) => warn!("cloud mail decrypt failure: {}", e),
+                        }
+                        acks.push(hash);
+                    }
+
+                    let next = if more { Some(offset + kept) } else { None };
+                    if acks.len() > 0 || next.is_some() {
+                        let proof = layer_lock.group.read().await.prove_addr(&ogid, &addr)?;
+                        let event = PeerEvent::Ack(acks, next);
+                        add_layer(&mut results, addr, event, ogid, proof)?;
+                    }
+                }
+            }
+        }
+        RecvType::Delivery(_t, _tid, _is_ok) => {
+            // MAYBE
+        }
+    }
+
+    Ok(results)
+}
+
+/// local provider, mails saved in node's account.db.
+pub(crate) async fn handle_server(
+    layer: &Arc<RwLock<Layer>>,
+    fgid: GroupId,
     msg: RecvType,
 ) -> Result<HandleResult> {
-    let results = HandleResult::new();
+    let mut results = HandleResult::new();
 
     match msg {
         RecvType::Connect(..)
@@ -25,8 +116,53 @@ pub(crate) async fn handle(
         | RecvType::Stream(..) => {
             info!("cloud message nerver to here.")
         }
-        RecvType::Event(_addr, bytes) => {
-            let LayerServerEvent(_event, _proof) = bincode::deserialize(&bytes)?;
+        RecvType::Event(addr, bytes) => {
+            let LayerPeerEvent(event, proof) = bincode::deserialize(&bytes)?;
+
+            let layer_lock = layer.read().await;
+            proof.verify(&fgid, &addr, &layer_lock.addr)?;
+            let name = layer_lock.addr.to_hex();
+            let db = layer_lock.group.read().await.account_db()?;
+            drop(layer_lock);
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|s| s.as_secs())
+                .unwrap_or(0) as i64; // safe for all life.
+            Mail::expire(&db, now - MAIL_EXPIRE)?;
+
+            let event = match event {
+                PeerEvent::Check => {
+                    let used = Mail::used(&db, &fgid)?;
+                    // not provider, no quota for deposit.
+                    let quota = if is_provider() { MAILBOX_QUOTA } else { 0 };
+                    ServerEvent::Status(name, false, used, quota)
+                }
+                PeerEvent::Deposit(gid, hash, data) => {
+                    let size = data.len() as u64;
+                    let is_ok = is_provider()
+                        && Mail::used(&db, &gid)? + size <= MAILBOX_QUOTA
+                        && Mail::deposited(&db, &fgid)? + size <= DEPOSIT_QUOTA
+                        && Mail::total(&db)? + size <= PROVIDER_QUOTA;
+                    if is_ok {
+                        Mail::new(hash, gid, fgid, data).insert(&db)?;
+                    }
+                    ServerEvent::Deposited(hash, is_ok)
+                }
+                PeerEvent::Fetch => mails_page(&db, &fgid, 0)?,
+                PeerEvent::Ack(hashes, next) => {
+                    Mail::ack(&db, &fgid, &hashes)?;
+                    if let Some(offset) = next {
+                        mails_page(&db, &fgid, offset)?
+                    } else {
+                        let used = Mail::used(&db, &fgid)?;
+                        let quota = if is_provider() { MAILBOX_QUOTA } else { 0 };
+                        ServerEvent::Status(name, false, used, quota)
+                    }
+                }
+            };
+            drop(db);
+            add_server_layer(&mut results, addr, event, fgid)?;
         }
         RecvType::Delivery(_t, _tid, _is_ok) => {
             // MAYBE
@@ -35,3 +171,12 @@ pub(crate) async fn handle(
 
     Ok(results)
 }
+
+/// a page of the receiver's mails, check one more to know if has more.
+fn mails_page(db: &DStorage, gid: &GroupId, offset: u32) -> Result<ServerEvent> {
+    let mut mails = Mail::list(db, gid, offset, FETCH_LIMIT + 1)?;
+    let more = mails.len() > FETCH_LIMIT;
+    mails.truncate(FETCH_LIMIT);
+    let mails = mails.into_iter().map(|m| m.to_network()).collect();
+    Ok(ServerEvent::Mails(offset, mails, more))
+}
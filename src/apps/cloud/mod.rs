@@ -2,29 +2,146 @@ mod layer;
 mod models;
 
 pub use cloud_types::CLOUD_ID as GROUP_ID;
-use cloud_types::{LayerPeerEvent, PeerEvent};
+use cloud_types::{LayerPeerEvent, LayerServerEvent, PeerEvent, ServerEvent};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tdn::types::{
-    group::GroupId,
-    message::SendType,
+    group::{EventId, GroupId},
+    message::{RecvType, SendType},
     primitive::{HandleResult, PeerId, Result},
+    rpc::RpcHandler,
 };
 use tdn_did::Proof;
+use tokio::sync::RwLock;
 
-/// Send to domain service.
+use crate::apps::{App, LayerFuture};
+use crate::group::Group;
+use crate::layer::Layer;
+use crate::migrate::{CLOUD_DB, CLOUD_VERSIONS};
+use crate::rpc::RpcState;
+use crate::utils::crypto::encrypt_bytes;
+
+use models::Provider;
+
+/// the node keeps mails for others, only when enabled in settings.
+static PROVIDER: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_provider(enabled: bool) {
+    PROVIDER.store(enabled, Ordering::SeqCst);
+}
+
+pub(crate) fn is_provider() -> bool {
+    PROVIDER.load(Ordering::SeqCst)
+}
+
+/// Send to cloud service.
 #[inline]
 pub(crate) fn add_layer(
     results: &mut HandleResult,
     addr: PeerId,
     event: PeerEvent,
     ogid: GroupId,
+    proof: Proof,
 ) -> Result<()> {
-    let proof = Proof::default();
     let data = bincode::serialize(&LayerPeerEvent(event, proof))?;
     let s = SendType::Event(0, addr, data);
     results.layers.push((ogid, GROUP_ID, s));
     Ok(())
 }
 
+/// Send to cloud peer from local service.
+#[inline]
+pub(crate) fn add_server_layer(
+    results: &mut HandleResult,
+    addr: PeerId,
+    event: ServerEvent,
+    gid: GroupId,
+) -> Result<()> {
+    let data = bincode::serialize(&LayerServerEvent(event, Proof::default()))?;
+    let s = SendType::Event(0, addr, data);
+    results.layers.push((GROUP_ID, gid, s));
+    Ok(())
+}
+
+/// deposit the data to default provider for the offline friend.
+pub(crate) async fn deposit(
+    group: &Arc<RwLock<Group>>,
+    ogid: GroupId,
+    fgid: GroupId,
+    hash: EventId,
+    data: &[u8],
+    results: &mut HandleResult,
+) -> Result<()> {
+    let group_lock = group.read().await;
+    let provider = Provider::get_default(&group_lock.cloud_db(&ogid)?)?;
+    if !provider.is_ok {
+        return Err(anyhow!("provider is not ok"));
+    }
+    let key = group_lock.mailbox_key(&ogid, &fgid)?;
+    let proof = group_lock.prove_addr(&ogid, &provider.addr)?;
+    drop(group_lock);
+
+    let data = encrypt_bytes(&key, data)?;
+    let event = PeerEvent::Deposit(fgid, hash, data);
+    add_layer(results, provider.addr, event, ogid, proof)
+}
+
+/// fetch my mails from default provider.
+pub(crate) async fn fetch(
+    group: &Arc<RwLock<Group>>,
+    ogid: GroupId,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let group_lock = group.read().await;
+    let provider = Provider::get_default(&group_lock.cloud_db(&ogid)?)?;
+    let proof = group_lock.prove_addr(&ogid, &provider.addr)?;
+    drop(group_lock);
+
+    add_layer(results, provider.addr, PeerEvent::Fetch, ogid, proof)
+}
+
 pub(crate) mod rpc;
-pub(crate) use layer::handle;
+pub(crate) use layer::{handle, handle_server};
+pub(crate) use models::MailKey;
 pub(crate) use rpc::new_rpc_handler;
+
+/// cloud app.
+pub(crate) struct CloudApp;
+
+impl App for CloudApp {
+    fn name(&self) -> &'static str {
+        "cloud"
+    }
+
+    fn group_id(&self) -> Option<GroupId> {
+        Some(GROUP_ID)
+    }
+
+    fn db(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some((CLOUD_DB, &CLOUD_VERSIONS))
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+
+    fn handle_peer<'a>(
+        &'a self,
+        layer: &'a Arc<RwLock<Layer>>,
+        _fgid: GroupId,
+        mgid: GroupId,
+        msg: RecvType,
+    ) -> LayerFuture<'a> {
+        Box::pin(handle(layer, mgid, msg))
+    }
+
+    fn handle_server<'a>(
+        &'a self,
+        layer: &'a Arc<RwLock<Layer>>,
+        fgid: GroupId,
+        _mgid: GroupId,
+        msg: RecvType,
+    ) -> LayerFuture<'a> {
+        Box::pin(handle_server(layer, fgid, msg))
+    }
+}
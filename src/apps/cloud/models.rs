@@ -0,0 +1,339 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use tdn::types::{
+    group::{EventId, GroupId},
+    primitive::{PeerId, Result},
+};
+use tdn_storage::local::{DStorage, DsValue};
+
+use crate::utils::sql::Query;
+
+/// Cloud provider Model.
+pub(crate) struct Provider {
+    /// db auto-increment id.
+    pub id: i64,
+    /// name.
+    name: String,
+    /// address.
+    pub addr: PeerId,
+    /// is add ok.
+    pub is_ok: bool,
+    /// is default.
+    is_default: bool,
+    /// mailbox used bytes.
+    used: i64,
+    /// mailbox quota bytes.
+    quota: i64,
+}
+
+impl Provider {
+    pub fn prepare(addr: PeerId) -> Self {
+        Self {
+            id: 0,
+            name: addr.to_hex(),
+            addr: addr,
+            is_ok: false,
+            is_default: false,
+            used: 0,
+            quota: 0,
+        }
+    }
+
+    pub fn to_rpc(&self) -> rpc_types::cloud::Provider {
+        rpc_types::cloud::Provider {
+            id: self.id,
+            name: self.name.clone(),
+            addr: self.addr.to_hex(),
+            is_ok: self.is_ok,
+            is_default: self.is_default,
+            used: self.used,
+            quota: self.quota,
+        }
+    }
+
+    fn from_values(mut v: Vec<DsValue>) -> Self {
+        Self {
+            quota: v.pop().unwrap().as_i64(),
+            used: v.pop().unwrap().as_i64(),
+            is_default: v.pop().unwrap().as_bool(),
+            is_ok: v.pop().unwrap().as_bool(),
+            addr: PeerId::from_hex(v.pop().unwrap().as_string()).unwrap_or(Default::default()),
+            name: v.pop().unwrap().as_string(),
+            id: v.pop().unwrap().as_i64(),
+        }
+    }
+
+    /// use in rpc when load providers.
+    pub fn list(db: &DStorage) -> Result<Vec<Self>> {
+        let matrix =
+            db.query("SELECT id, name, addr, is_ok, is_default, used, quota FROM providers")?;
+        let mut providers = vec![];
+        for values in matrix {
+            providers.push(Self::from_values(values));
+        }
+        Ok(providers)
+    }
+
+    /// use in rpc when load provider by id.
+    pub fn get(db: &DStorage, id: &i64) -> Result<Self> {
+        let mut matrix = Query::new(
+            "SELECT id, name, addr, is_ok, is_default, used, quota FROM providers WHERE id = ?",
+        )
+        .bind(id)
+        .query(db)?;
+        if matrix.len() > 0 {
+            let values = matrix.pop().unwrap(); // safe unwrap()
+            return Ok(Self::from_values(values));
+        }
+        Err(anyhow!("provider is missing"))
+    }
+
+    /// the provider which mails deposit to and fetch from.
+    pub fn get_default(db: &DStorage) -> Result<Self> {
+        let mut matrix = db.query("SELECT id, name, addr, is_ok, is_default, used, quota FROM providers WHERE is_default = true")?;
+        if matrix.len() > 0 {
+            let values = matrix.pop().unwrap(); // safe unwrap()
+            return Ok(Self::from_values(values));
+        }
+        Err(anyhow!("provider is missing"))
+    }
+
+    pub fn get_by_addr(db: &DStorage, addr: &PeerId) -> Result<Self> {
+        let mut matrix = Query::new(
+            "SELECT id, name, addr, is_ok, is_default, used, quota FROM providers WHERE addr = ?",
+        )
+        .bind(addr)
+        .query(db)?;
+        if matrix.len() > 0 {
+            let values = matrix.pop().unwrap(); // safe unwrap()
+            return Ok(Self::from_values(values));
+        }
+        Err(anyhow!("provider is missing"))
+    }
+
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        let mut unique_check = Query::new("SELECT id from providers WHERE addr = ?")
+            .bind(&self.addr)
+            .query(db)?;
+        if unique_check.len() > 0 {
+            self.id = unique_check.pop().unwrap().pop().unwrap().as_i64();
+        } else {
+            self.id = Query::new(
+                "INSERT INTO providers (name, addr, is_ok, is_default, used, quota) VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&self.name)
+            .bind(&self.addr)
+            .bind(&self.is_ok)
+            .bind(&self.is_default)
+            .bind(&self.used)
+            .bind(&self.quota)
+            .insert(db)?;
+        }
+        Ok(())
+    }
+
+    /// provider status checked, update the quota.
+    pub fn ok(&mut self, db: &DStorage, name: String, used: i64, quota: i64) -> Result<()> {
+        self.name = name;
+        self.used = used;
+        self.quota = quota;
+        self.is_ok = true;
+
+        Query::new("UPDATE providers SET name = ?, is_ok = true, used = ?, quota = ? WHERE id = ?")
+            .bind(&self.name)
+            .bind(&self.used)
+            .bind(&self.quota)
+            .bind(&self.id)
+            .update(db)?;
+        Ok(())
+    }
+
+    /// set default provider.
+    pub fn default(&self, db: &DStorage, default: bool) -> Result<()> {
+        Query::new("UPDATE providers SET is_default = ? WHERE id = ?")
+            .bind(&default)
+            .bind(&self.id)
+            .update(db)?;
+        Ok(())
+    }
+
+    /// delete provider.
+    pub fn delete(db: &DStorage, id: &i64) -> Result<()> {
+        Query::new("DELETE FROM providers WHERE id = ?")
+            .bind(id)
+            .delete(db)?;
+        Ok(())
+    }
+}
+
+/// Friend's mailbox key, use to open the mails from the friend.
+pub(crate) struct MailKey;
+
+impl MailKey {
+    pub fn get(db: &DStorage, gid: &GroupId) -> Result<[u8; 32]> {
+        let mut matrix = Query::new("SELECT key FROM keys WHERE gid = ?")
+            .bind(gid)
+            .query(db)?;
+        if matrix.len() > 0 {
+            let bytes = hex::decode(matrix.pop().unwrap().pop().unwrap().as_str())?;
+            if bytes.len() == 32 {
+                let mut key = [0u8; 32];
+                key.copy_from_slice(&bytes);
+                return Ok(key);
+            }
+        }
+        Err(anyhow!("mailbox key is missing"))
+    }
+
+    pub fn save(db: &DStorage, gid: &GroupId, key: &[u8; 32]) -> Result<()> {
+        let mut unique_check = Query::new("SELECT id from keys WHERE gid = ?")
+            .bind(gid)
+            .query(db)?;
+        if unique_check.len() > 0 {
+            let id = unique_check.pop().unwrap().pop().unwrap().as_i64();
+            Query::new("UPDATE keys SET key = ? WHERE id = ?")
+                .bind(&hex::encode(key))
+                .bind(&id)
+                .update(db)?;
+        } else {
+            Query::new("INSERT INTO keys (gid, key) VALUES (?, ?)")
+                .bind(gid)
+                .bind(&hex::encode(key))
+                .insert(db)?;
+        }
+        Ok(())
+    }
+}
+
+/// Mail saved in provider for offline receiver. saved in node's account.db.
+pub(crate) struct Mail {
+    /// db auto-increment id.
+    pub id: i64,
+    /// mail id from sender.
+    pub hash: EventId,
+    /// receiver.
+    pub gid: GroupId,
+    /// sender.
+    pub sender: GroupId,
+    /// encrypted data.
+    pub data: Vec<u8>,
+    pub datetime: i64,
+}
+
+impl Mail {
+    pub fn new(hash: EventId, gid: GroupId, sender: GroupId, data: Vec<u8>) -> Self {
+        let start = SystemTime::now();
+        let datetime = start
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+
+        Self {
+            id: 0,
+            hash,
+            gid,
+            sender,
+            data,
+            datetime,
+        }
+    }
+
+    fn from_values(mut v: Vec<DsValue>) -> Self {
+        Self {
+            datetime: v.pop().unwrap().as_i64(),
+            data: hex::decode(v.pop().unwrap().as_str()).unwrap_or(vec![]),
+            sender: GroupId::from_hex(v.pop().unwrap().as_str()).unwrap_or(GroupId::default()),
+            gid: GroupId::from_hex(v.pop().unwrap().as_str()).unwrap_or(GroupId::default()),
+            hash: EventId::from_hex(v.pop().unwrap().as_str()).unwrap_or(EventId::default()),
+            id: v.pop().unwrap().as_i64(),
+        }
+    }
+
+    pub fn to_network(self) -> (EventId, GroupId, Vec<u8>, i64) {
+        (self.hash, self.sender, self.data, self.datetime)
+    }
+
+    /// a page of the receiver's mails.
+    pub fn list(db: &DStorage, gid: &GroupId, offset: u32, limit: usize) -> Result<Vec<Self>> {
+        let matrix = Query::new(
+            "SELECT id, hash, gid, sender, data, datetime FROM mails WHERE gid = ? ORDER BY id LIMIT ? OFFSET ?",
+        )
+        .bind(gid)
+        .bind(&limit)
+        .bind(&offset)
+        .query(db)?;
+        let mut mails = vec![];
+        for values in matrix {
+            mails.push(Self::from_values(values));
+        }
+        Ok(mails)
+    }
+
+    /// receiver's mailbox used bytes.
+    pub fn used(db: &DStorage, gid: &GroupId) -> Result<u64> {
+        Self::sum(
+            Query::new("SELECT IFNULL(SUM(size), 0) FROM mails WHERE gid = ?").bind(gid),
+            db,
+        )
+    }
+
+    /// sender's deposited bytes in all mailboxes.
+    pub fn deposited(db: &DStorage, sender: &GroupId) -> Result<u64> {
+        Self::sum(
+            Query::new("SELECT IFNULL(SUM(size), 0) FROM mails WHERE sender = ?").bind(sender),
+            db,
+        )
+    }
+
+    /// all mails' bytes in the provider.
+    pub fn total(db: &DStorage) -> Result<u64> {
+        Self::sum(Query::new("SELECT IFNULL(SUM(size), 0) FROM mails"), db)
+    }
+
+    fn sum(query: Query, db: &DStorage) -> Result<u64> {
+        let mut matrix = query.query(db)?;
+        if let Some(mut values) = matrix.pop() {
+            if let Some(v) = values.pop() {
+                return Ok(v.as_i64() as u64);
+            }
+        }
+        Ok(0)
+    }
+
+    /// delete the mails deposited before the datetime.
+    pub fn expire(db: &DStorage, datetime: i64) -> Result<usize> {
+        Query::new("DELETE FROM mails WHERE datetime < ?")
+            .bind(&datetime)
+            .delete(db)
+    }
+
+    /// save mail, if had, do nothing.
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        let mut unique_check = Query::new("SELECT id from mails WHERE gid = ? AND hash = ?")
+            .bind(&self.gid)
+            .bind(&self.hash)
+            .query(db)?;
+        if unique_check.len() > 0 {
+            self.id = unique_check.pop().unwrap().pop().unwrap().as_i64();
+        } else {
+            self.id = Query::new(
+                "INSERT INTO mails (hash, gid, sender, data, size, datetime) VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&self.hash)
+            .bind(&self.gid)
+            .bind(&self.sender)
+            .bind(&hex::encode(&self.data))
+            .bind(&self.data.len())
+            .bind(&self.datetime)
+            .insert(db)?;
+        }
+        Ok(())
+    }
+
+    /// receiver had got the mails.
+    pub fn ack(db: &DStorage, gid: &GroupId, hashes: &[EventId]) -> Result<usize> {
+        Query::new("DELETE FROM mails WHERE gid = ? AND hash IN (?)")
+            .bind(gid)
+            .bind_list(hashes)
+            .delete(db)
+    }
+}
@@ -1,11 +1,30 @@
+use rpc_types::cloud::{MailDeposited, ProviderAddParams};
+use rpc_types::Id;
 use std::sync::Arc;
 use tdn::types::{
-    group::GroupId,
-    primitive::HandleResult,
-    rpc::{json, RpcHandler, RpcParam},
+    group::{EventId, GroupId},
+    primitive::{HandleResult, PeerId},
+    rpc::{json, rpc_response, RpcHandler, RpcParam},
 };
 
-use crate::rpc::RpcState;
+use cloud_types::PeerEvent;
+
+use super::{add_layer, fetch, models::Provider};
+use crate::rpc::{parse, RpcState};
+
+#[inline]
+pub(crate) fn provider_status(mgid: GroupId, provider: &Provider) -> RpcParam {
+    rpc_response(0, "cloud-provider-status", json!(provider.to_rpc()), mgid)
+}
+
+#[inline]
+pub(crate) fn mail_deposited(mgid: GroupId, hash: &EventId, is_ok: bool) -> RpcParam {
+    let data = json!(MailDeposited {
+        hash: hash.to_hex(),
+        is_ok,
+    });
+    rpc_response(0, "cloud-mail-deposited", data, mgid)
+}
 
 pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
@@ -14,4 +33,75 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             Ok(HandleResult::rpc(json!(params)))
         },
     );
+
+    handler.add_method(
+        "cloud-provider-list",
+        |gid: GroupId, _params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let db = state.group.read().await.cloud_db(&gid)?;
+            let providers: Vec<_> = Provider::list(&db)?.iter().map(|p| p.to_rpc()).collect();
+            Ok(HandleResult::rpc(json!(providers)))
+        },
+    );
+
+    handler.add_method(
+        "cloud-provider-add",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let ProviderAddParams { provider } = parse(params)?;
+            let provider = PeerId::from_hex(&provider)?;
+
+            let mut results = HandleResult::new();
+            let group_lock = state.group.read().await;
+            let db = group_lock.cloud_db(&gid)?;
+            let mut p = Provider::prepare(provider);
+            p.insert(&db)?;
+            // first provider is default.
+            if Provider::get_default(&db).is_err() {
+                p.default(&db, true)?;
+            }
+            let proof = group_lock.prove_addr(&gid, &provider)?;
+            drop(group_lock);
+
+            add_layer(&mut results, provider, PeerEvent::Check, gid, proof)?;
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "cloud-provider-default",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let Id { id } = parse(params)?;
+
+            let db = state.group.read().await.cloud_db(&gid)?;
+            let provider = Provider::get(&db, &id)?;
+            if let Ok(default) = Provider::get_default(&db) {
+                if default.id == provider.id {
+                    return Ok(HandleResult::new());
+                }
+                default.default(&db, false)?;
+            }
+            provider.default(&db, true)?;
+
+            Ok(HandleResult::new())
+        },
+    );
+
+    handler.add_method(
+        "cloud-provider-remove",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let Id { id } = parse(params)?;
+
+            let db = state.group.read().await.cloud_db(&gid)?;
+            Provider::delete(&db, &id)?;
+            Ok(HandleResult::new())
+        },
+    );
+
+    handler.add_method(
+        "cloud-mail-fetch",
+        |gid: GroupId, _params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let mut results = HandleResult::new();
+            fetch(&state.group, gid, &mut results).await?;
+            Ok(results)
+        },
+    );
 }
@@ -1,5 +1,23 @@
 mod models;
-
 pub(crate) mod rpc;
+
+use tdn::types::rpc::RpcHandler;
+
+use crate::apps::App;
+use crate::rpc::RpcState;
+
 pub(crate) use models::Device;
 pub(crate) use rpc::new_rpc_handler;
+
+/// device app.
+pub(crate) struct DeviceApp;
+
+impl App for DeviceApp {
+    fn name(&self) -> &'static str {
+        "device"
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+}
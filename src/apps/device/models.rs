@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tdn::types::primitive::{Peer, PeerId, Result};
-use tdn::types::rpc::{json, RpcParam};
 use tdn_storage::local::{DStorage, DsValue};
 
+use crate::utils::sql::Query;
+
 pub(crate) struct Device {
     pub id: i64,
     pub name: String,
@@ -43,15 +44,15 @@ impl Device {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.name,
-            self.info,
-            self.addr.to_hex(),
-            self.lasttime,
-            if self.online { "1" } else { "0" },
-        ])
+    pub fn to_rpc(&self) -> rpc_types::device::Device {
+        rpc_types::device::Device {
+            id: self.id,
+            name: self.name.clone(),
+            info: self.info.clone(),
+            addr: self.addr.to_hex(),
+            lasttime: self.lasttime,
+            online: if self.online { "1" } else { "0" }.to_owned(),
+        }
     }
 
     /// load account devices.
@@ -94,26 +95,39 @@ impl Device {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let sql = format!(
-            "INSERT INTO devices (name, info, addr, lasttime) VALUES ('{}', '{}', '{}', {})",
-            self.name,
-            self.info,
-            self.addr.to_hex(),
-            self.lasttime,
-        );
-        let id = db.insert(&sql)?;
+        let id = Query::new("INSERT INTO devices (name, info, addr, lasttime) VALUES (?, ?, ?, ?)")
+            .bind(&self.name)
+            .bind(&self.info)
+            .bind(&self.addr)
+            .bind(&self.lasttime)
+            .insert(db)?;
         self.id = id;
         Ok(())
     }
 
     pub fn _update(db: &DStorage, id: i64, name: &str) -> Result<usize> {
-        let sql = format!("UPDATE devices SET name='{}' WHERE id = {}", name, id);
-        db.update(&sql)
+        Query::new("UPDATE devices SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(&id)
+            .update(db)
+    }
+
+    /// clear all devices, used when account imported from backup.
+    pub fn delete_all(db: &DStorage) -> Result<usize> {
+        db.delete("DELETE FROM devices")
+    }
+
+    /// remove the lost device.
+    pub fn delete_by_addr(db: &DStorage, addr: &PeerId) -> Result<usize> {
+        Query::new("DELETE FROM devices WHERE addr = ?")
+            .bind(addr)
+            .delete(db)
     }
 
     /// used in rpc, when what to delete a friend.
     pub fn _delete(&self, db: &DStorage) -> Result<usize> {
-        let sql = format!("DELETE FROM devices WHERE id = {}", self.id);
-        db.update(&sql)
+        Query::new("DELETE FROM devices WHERE id = ?")
+            .bind(&self.id)
+            .delete(db)
     }
 }
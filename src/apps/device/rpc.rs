@@ -1,12 +1,14 @@
+use rpc_types::device::{DeviceAddr, DeviceStatus};
+use rpc_types::Id;
 use std::sync::Arc;
 use tdn::types::{
     group::GroupId,
     primitive::{HandleResult, Peer, PeerId},
-    rpc::{json, rpc_response, RpcError, RpcHandler, RpcParam},
+    rpc::{json, rpc_response, RpcHandler, RpcParam},
 };
 
 use crate::group::GroupEvent;
-use crate::rpc::RpcState;
+use crate::rpc::{parse, RpcState};
 use crate::utils::device_status::device_status as local_device_status;
 
 use super::Device;
@@ -17,18 +19,18 @@ pub(crate) fn device_create(mgid: GroupId, device: &Device) -> RpcParam {
 }
 
 #[inline]
-pub(crate) fn _device_remove(mgid: GroupId, id: i64) -> RpcParam {
-    rpc_response(0, "device-remove", json!([id]), mgid)
+pub(crate) fn device_remove(mgid: GroupId, id: i64) -> RpcParam {
+    rpc_response(0, "device-remove", json!(Id { id }), mgid)
 }
 
 #[inline]
 pub(crate) fn device_online(mgid: GroupId, id: i64) -> RpcParam {
-    rpc_response(0, "device-online", json!([id]), mgid)
+    rpc_response(0, "device-online", json!(Id { id }), mgid)
 }
 
 #[inline]
 pub(crate) fn device_offline(mgid: GroupId, id: i64) -> RpcParam {
-    rpc_response(0, "device-offline", json!([id]), mgid)
+    rpc_response(0, "device-offline", json!(Id { id }), mgid)
 }
 
 #[inline]
@@ -44,12 +46,18 @@ pub(crate) fn device_status(
     disk_p: u16,
     uptime: u32,
 ) -> RpcParam {
-    rpc_response(
-        0,
-        "device-status",
-        json!([cpu, memory, swap, disk, cpu_p, memory_p, swap_p, disk_p, uptime]),
-        mgid,
-    )
+    let status = DeviceStatus {
+        cpu,
+        memory,
+        swap,
+        disk,
+        cpu_p,
+        memory_p,
+        swap_p,
+        disk_p,
+        uptime,
+    };
+    rpc_response(0, "device-status", json!(status), mgid)
 }
 
 #[inline]
@@ -80,16 +88,26 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "device-status",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let addr = PeerId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
+            let DeviceAddr { addr } = parse(params)?;
+            let addr = PeerId::from_hex(&addr)?;
 
             let group_lock = state.group.read().await;
             if &addr == group_lock.addr() {
                 let uptime = group_lock.uptime(&gid)?;
                 let (cpu, memory, swap, disk, cpu_p, memory_p, swap_p, disk_p) =
                     local_device_status();
-                return Ok(HandleResult::rpc(json!([
-                    cpu, memory, swap, disk, cpu_p, memory_p, swap_p, disk_p, uptime
-                ])));
+                let status = DeviceStatus {
+                    cpu,
+                    memory,
+                    swap,
+                    disk,
+                    cpu_p,
+                    memory_p,
+                    swap_p,
+                    disk_p,
+                    uptime,
+                };
+                return Ok(HandleResult::rpc(json!(status)));
             }
             drop(group_lock);
 
@@ -106,7 +124,8 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "device-create",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let addr = PeerId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
+            let DeviceAddr { addr } = parse(params)?;
+            let addr = PeerId::from_hex(&addr)?;
 
             let msg = state
                 .group
@@ -120,7 +139,8 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "device-connect",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let addr = PeerId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
+            let DeviceAddr { addr } = parse(params)?;
+            let addr = PeerId::from_hex(&addr)?;
 
             let msg = state
                 .group
@@ -134,7 +154,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "device-delete",
         |_gid: GroupId, params: Vec<RpcParam>, _state: Arc<RpcState>| async move {
-            let _id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id: _id } = parse(params)?;
             // TODO delete a device.
             Ok(HandleResult::new())
         },
@@ -3,12 +3,23 @@ mod models;
 
 pub use domain_types::DOMAIN_ID as GROUP_ID;
 use domain_types::{LayerPeerEvent, PeerEvent};
+use std::sync::Arc;
 use tdn::types::{
     group::GroupId,
-    message::SendType,
+    message::{RecvType, SendType},
     primitive::{HandleResult, PeerId, Result},
+    rpc::RpcHandler,
 };
 use tdn_did::Proof;
+use tdn_storage::local::DStorage;
+use tokio::sync::RwLock;
+
+use crate::apps::{App, LayerFuture};
+use crate::layer::Layer;
+use crate::migrate::{DOMAIN_DB, DOMAIN_VERSIONS};
+use crate::rpc::RpcState;
+
+use models::{Name, Provider};
 
 /// Send to domain service.
 #[inline]
@@ -25,6 +36,47 @@ pub(crate) fn add_layer(
     Ok(())
 }
 
+/// delete all my names in providers. use when account deleted.
+pub(crate) fn remove_all(db: &DStorage, ogid: GroupId, results: &mut HandleResult) -> Result<()> {
+    for name in Name::list(db)? {
+        if let Ok(provider) = Provider::get(db, &name.provider) {
+            add_layer(results, provider.addr, PeerEvent::Delete(name.name), ogid)?;
+        }
+    }
+    Ok(())
+}
+
 pub(crate) mod rpc;
 pub(crate) use layer::handle;
 pub(crate) use rpc::new_rpc_handler;
+
+/// domain app.
+pub(crate) struct DomainApp;
+
+impl App for DomainApp {
+    fn name(&self) -> &'static str {
+        "domain"
+    }
+
+    fn group_id(&self) -> Option<GroupId> {
+        Some(GROUP_ID)
+    }
+
+    fn db(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some((DOMAIN_DB, &DOMAIN_VERSIONS))
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+
+    fn handle_peer<'a>(
+        &'a self,
+        layer: &'a Arc<RwLock<Layer>>,
+        _fgid: GroupId,
+        mgid: GroupId,
+        msg: RecvType,
+    ) -> LayerFuture<'a> {
+        Box::pin(handle(layer, mgid, msg))
+    }
+}
@@ -1,9 +1,8 @@
-use tdn::types::{
-    primitive::{PeerId, Result},
-    rpc::{json, RpcParam},
-};
+use tdn::types::primitive::{PeerId, Result};
 use tdn_storage::local::{DStorage, DsValue};
 
+use crate::utils::sql::Query;
+
 /// Provider Model.
 pub(crate) struct Provider {
     /// db auto-increment id.
@@ -35,16 +34,16 @@ impl Provider {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.name,
-            self.addr.to_hex(),
-            self.is_ok,
-            self.is_default,
-            self.is_proxy,
-            self.is_actived,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::domain::Provider {
+        rpc_types::domain::Provider {
+            id: self.id,
+            name: self.name.clone(),
+            addr: self.addr.to_hex(),
+            is_ok: self.is_ok,
+            is_default: self.is_default,
+            is_proxy: self.is_proxy,
+            is_actived: self.is_actived,
+        }
     }
 
     fn from_values(mut v: Vec<DsValue>) -> Self {
@@ -73,11 +72,11 @@ impl Provider {
 
     /// use in rpc when load provider by id.
     pub fn get(db: &DStorage, id: &i64) -> Result<Self> {
-        let sql = format!(
-            "SELECT id, name, addr, is_ok, is_default, is_proxy, is_actived FROM providers WHERE id = {}",
-            id
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new(
+            "SELECT id, name, addr, is_ok, is_default, is_proxy, is_actived FROM providers WHERE id = ?",
+        )
+        .bind(id)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -97,11 +96,11 @@ impl Provider {
 
     /// insert a new provider.
     pub fn get_by_addr(db: &DStorage, addr: &PeerId) -> Result<Self> {
-        let sql = format!(
-            "SELECT id, name, addr, is_ok, is_default, is_proxy, is_actived FROM providers WHERE addr = '{}'",
-            addr.to_hex()
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new(
+            "SELECT id, name, addr, is_ok, is_default, is_proxy, is_actived FROM providers WHERE addr = ?",
+        )
+        .bind(addr)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -110,34 +109,32 @@ impl Provider {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let mut unique_check = db.query(&format!(
-            "SELECT id from providers WHERE addr = '{}'",
-            self.addr.to_hex()
-        ))?;
+        let mut unique_check = Query::new("SELECT id from providers WHERE addr = ?")
+            .bind(&self.addr)
+            .query(db)?;
         if unique_check.len() > 0 {
             let id = unique_check.pop().unwrap().pop().unwrap().as_i64();
             self.id = id;
-            let sql = format!("UPDATE providers SET name = '{}', addr = '{}', is_ok = {}, is_default = {}, is_proxy = {}, is_actived = {} WHERE id = {}",
-                self.name,
-                self.addr.to_hex(),
-                self.is_ok,
-                self.is_default,
-                self.is_proxy,
-                self.is_actived,
-                self.id
-            );
-            db.update(&sql)?;
+            Query::new("UPDATE providers SET name = ?, addr = ?, is_ok = ?, is_default = ?, is_proxy = ?, is_actived = ? WHERE id = ?")
+                .bind(&self.name)
+                .bind(&self.addr)
+                .bind(&self.is_ok)
+                .bind(&self.is_default)
+                .bind(&self.is_proxy)
+                .bind(&self.is_actived)
+                .bind(&self.id)
+                .update(db)?;
         } else {
-            let sql = format!(
-                "INSERT INTO providers (name, addr, is_ok, is_default, is_proxy, is_actived) VALUES ('{}', '{}', {}, {}, {}, {})",
-                self.name,
-                self.addr.to_hex(),
-                self.is_ok,
-                self.is_default,
-                self.is_proxy,
-                self.is_actived,
-            );
-            let id = db.insert(&sql)?;
+            let id = Query::new(
+                "INSERT INTO providers (name, addr, is_ok, is_default, is_proxy, is_actived) VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&self.name)
+            .bind(&self.addr)
+            .bind(&self.is_ok)
+            .bind(&self.is_default)
+            .bind(&self.is_proxy)
+            .bind(&self.is_actived)
+            .insert(db)?;
             self.id = id;
         }
         Ok(())
@@ -149,29 +146,30 @@ impl Provider {
         self.is_actived = true;
         self.is_ok = true;
 
-        let sql = format!("UPDATE providers SET name = '{}', is_ok = true, is_proxy = {}, is_actived = true WHERE id = {}",
-                self.name,
-                self.is_proxy,
-                self.id
-            );
-        db.update(&sql)?;
+        Query::new(
+            "UPDATE providers SET name = ?, is_ok = true, is_proxy = ?, is_actived = true WHERE id = ?",
+        )
+        .bind(&self.name)
+        .bind(&self.is_proxy)
+        .bind(&self.id)
+        .update(db)?;
         Ok(())
     }
 
     /// set default provider.
     pub fn default(&self, db: &DStorage, default: bool) -> Result<()> {
-        let sql = format!(
-            "UPDATE providers SET is_default = {} WHERE id = {}",
-            default, self.id
-        );
-        db.update(&sql)?;
+        Query::new("UPDATE providers SET is_default = ? WHERE id = ?")
+            .bind(&default)
+            .bind(&self.id)
+            .update(db)?;
         Ok(())
     }
 
     /// delete provider.
     pub fn delete(db: &DStorage, id: &i64) -> Result<()> {
-        let sql = format!("DELETE FROM providers WHERE id = {}", id);
-        db.update(&sql)?;
+        Query::new("DELETE FROM providers WHERE id = ?")
+            .bind(id)
+            .delete(db)?;
         Ok(())
     }
 }
@@ -204,15 +202,15 @@ impl Name {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.provider,
-            self.name,
-            self.bio,
-            self.is_ok,
-            self.is_actived,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::domain::Name {
+        rpc_types::domain::Name {
+            id: self.id,
+            provider: self.provider,
+            name: self.name.clone(),
+            bio: self.bio.clone(),
+            is_ok: self.is_ok,
+            is_actived: self.is_actived,
+        }
     }
 
     fn from_values(mut v: Vec<DsValue>) -> Self {
@@ -240,11 +238,11 @@ impl Name {
 
     /// get name register.
     pub fn get_by_provider(db: &DStorage, provider: &i64) -> Result<Vec<Self>> {
-        let sql = format!(
-            "SELECT id, provider, name, bio, is_ok, is_actived FROM names WHERE provider = {}",
-            provider
-        );
-        let matrix = db.query(&sql)?;
+        let matrix = Query::new(
+            "SELECT id, provider, name, bio, is_ok, is_actived FROM names WHERE provider = ?",
+        )
+        .bind(provider)
+        .query(db)?;
         let mut names = vec![];
         for values in matrix {
             names.push(Self::from_values(values));
@@ -254,11 +252,12 @@ impl Name {
 
     /// get name register.
     pub fn get_by_name_provider(db: &DStorage, name: &str, provider: &i64) -> Result<Self> {
-        let sql = format!(
-            "SELECT id, provider, name, bio, is_ok, is_actived FROM names WHERE name = '{}' AND provider = {}",
-            name, provider
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new(
+            "SELECT id, provider, name, bio, is_ok, is_actived FROM names WHERE name = ? AND provider = ?",
+        )
+        .bind(name)
+        .bind(provider)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -267,28 +266,29 @@ impl Name {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let mut unique_check = db.query(&format!(
-            "SELECT id from names WHERE provider = {} AND name = '{}'",
-            self.provider, self.name
-        ))?;
+        let mut unique_check = Query::new("SELECT id from names WHERE provider = ? AND name = ?")
+            .bind(&self.provider)
+            .bind(&self.name)
+            .query(db)?;
         if unique_check.len() > 0 {
             let id = unique_check.pop().unwrap().pop().unwrap().as_i64();
             self.id = id;
-            let sql = format!(
-                "UPDATE names SET bio = '{}', is_ok = {}, is_actived = {} WHERE id = {}",
-                self.bio, self.is_ok, self.is_actived, self.id
-            );
-            db.update(&sql)?;
+            Query::new("UPDATE names SET bio = ?, is_ok = ?, is_actived = ? WHERE id = ?")
+                .bind(&self.bio)
+                .bind(&self.is_ok)
+                .bind(&self.is_actived)
+                .bind(&self.id)
+                .update(db)?;
         } else {
-            let sql = format!(
-                "INSERT INTO names (provider, name, bio, is_ok, is_actived) VALUES ({}, '{}', '{}', {}, {})",
-                self.provider,
-                self.name,
-                self.bio,
-                self.is_ok,
-                self.is_actived,
-            );
-            let id = db.insert(&sql)?;
+            let id = Query::new(
+                "INSERT INTO names (provider, name, bio, is_ok, is_actived) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&self.provider)
+            .bind(&self.name)
+            .bind(&self.bio)
+            .bind(&self.is_ok)
+            .bind(&self.is_actived)
+            .insert(db)?;
             self.id = id;
         }
         Ok(())
@@ -296,18 +296,18 @@ impl Name {
 
     /// delete the name.
     pub fn delete(&self, db: &DStorage) -> Result<()> {
-        let sql = format!("DELETE FROM names WHERE id = {}", self.id);
-        db.delete(&sql)?;
+        Query::new("DELETE FROM names WHERE id = ?")
+            .bind(&self.id)
+            .delete(db)?;
         Ok(())
     }
 
     /// active/suspend the name.
     pub fn active(db: &DStorage, id: &i64, active: bool) -> Result<()> {
-        let sql = format!(
-            "UPDATE names SET is_ok = true, is_actived = {} WHERE id = {}",
-            active, id
-        );
-        db.update(&sql)?;
+        Query::new("UPDATE names SET is_ok = true, is_actived = ? WHERE id = ?")
+            .bind(&active)
+            .bind(id)
+            .update(db)?;
         Ok(())
     }
 }
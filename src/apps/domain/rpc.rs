@@ -1,8 +1,13 @@
+use rpc_types::domain::{
+    DomainActiveParams, DomainList, DomainName, DomainRegisterParams, DomainRemoveParams,
+    DomainSearch, DomainSearchParams, ProviderAddParams,
+};
+use rpc_types::Id;
 use std::sync::Arc;
 use tdn::types::{
     group::GroupId,
     primitive::{HandleResult, PeerId},
-    rpc::{json, rpc_response, RpcError, RpcHandler, RpcParam},
+    rpc::{json, rpc_response, RpcHandler, RpcParam},
 };
 
 use domain_types::PeerEvent;
@@ -11,7 +16,7 @@ use super::{
     add_layer,
     models::{Name, Provider},
 };
-use crate::rpc::RpcState;
+use crate::rpc::{parse, RpcState};
 
 #[inline]
 pub(crate) fn add_provider(mgid: GroupId, provider: &Provider) -> RpcParam {
@@ -25,14 +30,19 @@ pub(crate) fn register_success(mgid: GroupId, name: &Name) -> RpcParam {
 
 #[inline]
 pub(crate) fn register_failure(mgid: GroupId, name: &str) -> RpcParam {
-    rpc_response(0, "domain-register-failure", json!([name]), mgid)
+    let data = json!(DomainName {
+        name: name.to_owned(),
+    });
+    rpc_response(0, "domain-register-failure", data, mgid)
 }
 
 #[inline]
 pub(crate) fn domain_list(mgid: GroupId, providers: &[Provider], names: &[Name]) -> RpcParam {
-    let providers: Vec<RpcParam> = providers.iter().map(|p| p.to_rpc()).collect();
-    let names: Vec<RpcParam> = names.iter().map(|p| p.to_rpc()).collect();
-    rpc_response(0, "domain-list", json!([providers, names]), mgid)
+    let data = json!(DomainList {
+        providers: providers.iter().map(|p| p.to_rpc()).collect(),
+        names: names.iter().map(|p| p.to_rpc()).collect(),
+    });
+    rpc_response(0, "domain-list", data, mgid)
 }
 
 #[inline]
@@ -44,27 +54,26 @@ pub(crate) fn search_result(
     bio: &str,
     avatar: &Vec<u8>,
 ) -> RpcParam {
-    rpc_response(
-        0,
-        "domain-search",
-        json!([
-            name,
-            gid.to_hex(),
-            addr.to_hex(),
-            bio,
-            if avatar.len() > 0 {
-                base64::encode(avatar)
-            } else {
-                "".to_owned()
-            }
-        ]),
-        mgid,
-    )
+    let data = json!(DomainSearch {
+        name: name.to_owned(),
+        gid: gid.to_hex(),
+        addr: addr.to_hex(),
+        bio: bio.to_owned(),
+        avatar: if avatar.len() > 0 {
+            base64::encode(avatar)
+        } else {
+            "".to_owned()
+        },
+    });
+    rpc_response(0, "domain-search", data, mgid)
 }
 
 #[inline]
 pub(crate) fn search_none(mgid: GroupId, name: &str) -> RpcParam {
-    rpc_response(0, "domain-search", json!([name]), mgid)
+    let data = json!(DomainName {
+        name: name.to_owned(),
+    });
+    rpc_response(0, "domain-search", data, mgid)
 }
 
 pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
@@ -74,20 +83,20 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             let db = state.group.read().await.domain_db(&gid)?;
 
             // list providers.
-            let providers: Vec<RpcParam> =
-                Provider::list(&db)?.iter().map(|p| p.to_rpc()).collect();
+            let providers = Provider::list(&db)?.iter().map(|p| p.to_rpc()).collect();
 
             // list names.
-            let names: Vec<RpcParam> = Name::list(&db)?.iter().map(|p| p.to_rpc()).collect();
+            let names = Name::list(&db)?.iter().map(|p| p.to_rpc()).collect();
 
-            Ok(HandleResult::rpc(json!([providers, names])))
+            Ok(HandleResult::rpc(json!(DomainList { providers, names })))
         },
     );
 
     handler.add_method(
         "domain-provider-add",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let provider = PeerId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
+            let ProviderAddParams { provider } = parse(params)?;
+            let provider = PeerId::from_hex(&provider)?;
 
             let mut results = HandleResult::new();
             let db = state.group.read().await.domain_db(&gid)?;
@@ -102,7 +111,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "domain-provider-default",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let db = state.group.read().await.domain_db(&gid)?;
             let provider = Provider::get(&db, &id)?;
@@ -121,7 +130,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "domain-provider-remove",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let db = state.group.read().await.domain_db(&gid)?;
             let names = Name::get_by_provider(&db, &id)?;
@@ -136,10 +145,13 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "domain-register",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let provider = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let addr = PeerId::from_hex(params[1].as_str().ok_or(RpcError::ParseError)?)?;
-            let name = params[2].as_str().ok_or(RpcError::ParseError)?.to_string();
-            let bio = params[3].as_str().ok_or(RpcError::ParseError)?.to_string();
+            let DomainRegisterParams {
+                provider,
+                addr,
+                name,
+                bio,
+            } = parse(params)?;
+            let addr = PeerId::from_hex(&addr)?;
 
             let me = state.group.read().await.clone_user(&gid)?;
 
@@ -159,9 +171,12 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "domain-active",
         |gid: GroupId, params: Vec<RpcParam>, _state: Arc<RpcState>| async move {
-            let name = params[0].as_str().ok_or(RpcError::ParseError)?.to_owned();
-            let provider = PeerId::from_hex(params[1].as_str().ok_or(RpcError::ParseError)?)?;
-            let active = params[2].as_bool().ok_or(RpcError::ParseError)?;
+            let DomainActiveParams {
+                name,
+                provider,
+                active,
+            } = parse(params)?;
+            let provider = PeerId::from_hex(&provider)?;
 
             let mut results = HandleResult::new();
             let event = if active {
@@ -178,8 +193,8 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "domain-remove",
         |gid: GroupId, params: Vec<RpcParam>, _state: Arc<RpcState>| async move {
-            let name = params[0].as_str().ok_or(RpcError::ParseError)?.to_owned();
-            let provider = PeerId::from_hex(params[1].as_str().ok_or(RpcError::ParseError)?)?;
+            let DomainRemoveParams { name, provider } = parse(params)?;
+            let provider = PeerId::from_hex(&provider)?;
 
             let mut results = HandleResult::new();
             let event = PeerEvent::Delete(name);
@@ -192,8 +207,8 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "domain-search",
         |gid: GroupId, params: Vec<RpcParam>, _state: Arc<RpcState>| async move {
-            let addr = PeerId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
-            let name = params[1].as_str().ok_or(RpcError::ParseError)?.to_owned();
+            let DomainSearchParams { addr, name } = parse(params)?;
+            let addr = PeerId::from_hex(&addr)?;
 
             let mut results = HandleResult::new();
 
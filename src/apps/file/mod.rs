@@ -1,5 +1,28 @@
 mod models;
 mod rpc;
 
+use tdn::types::rpc::RpcHandler;
+
+use crate::apps::App;
+use crate::migrate::{FILE_DB, FILE_VERSIONS};
+use crate::rpc::RpcState;
+
 pub(crate) use models::{FileDid, RootDirectory};
 pub(crate) use rpc::new_rpc_handler;
+
+/// file app.
+pub(crate) struct FileApp;
+
+impl App for FileApp {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn db(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some((FILE_DB, &FILE_VERSIONS))
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+}
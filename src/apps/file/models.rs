@@ -1,12 +1,11 @@
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tdn::types::{
-    primitive::Result,
-    rpc::{json, RpcParam},
-};
+use tdn::types::primitive::Result;
 use tdn_storage::local::{DStorage, DsValue};
 
+use crate::utils::sql::Query;
+
 #[derive(Serialize, Deserialize, Eq, PartialEq)]
 pub(crate) enum RootDirectory {
     Star,
@@ -116,16 +115,16 @@ impl File {
         todo!()
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.did.to_hex(),
-            self.parent,
-            self.root.to_i64(),
-            self.name,
-            self.starred,
-            self.datetime,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::file::File {
+        rpc_types::file::File {
+            id: self.id,
+            did: self.did.to_hex(),
+            parent: self.parent,
+            root: self.root.to_i64(),
+            name: self.name.clone(),
+            starred: self.starred,
+            datetime: self.datetime,
+        }
     }
 
     fn from_values(mut v: Vec<DsValue>) -> Self {
@@ -141,11 +140,11 @@ impl File {
     }
 
     pub fn get(db: &DStorage, id: &i64) -> Result<Self> {
-        let sql = format!(
-            "SELECT id, did, parent, root, name, starred, datetime FROM files WHERE id = {}",
-            id
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new(
+            "SELECT id, did, parent, root, name, starred, datetime FROM files WHERE id = ?",
+        )
+        .bind(id)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -155,18 +154,19 @@ impl File {
 
     pub fn list(db: &DStorage, root: &RootDirectory, parent: &i64) -> Result<Vec<Self>> {
         let sql = if root == &RootDirectory::Star {
-            format!(
-                "SELECT id, did, parent, root, name, starred, datetime FROM files WHERE starred = true AND root != {}",
-                RootDirectory::Trash.to_i64()
+            Query::new(
+                "SELECT id, did, parent, root, name, starred, datetime FROM files WHERE starred = true AND root != ?",
             )
+            .bind(&RootDirectory::Trash.to_i64())
         } else {
-            format!(
-                "SELECT id, did, parent, root, name, starred, datetime FROM files WHERE parent = {} AND root = {}",
-                parent, root.to_i64()
+            Query::new(
+                "SELECT id, did, parent, root, name, starred, datetime FROM files WHERE parent = ? AND root = ?",
             )
+            .bind(parent)
+            .bind(&root.to_i64())
         };
 
-        let matrix = db.query(&sql)?;
+        let matrix = sql.query(db)?;
         let mut files = vec![];
         for values in matrix {
             files.push(Self::from_values(values));
@@ -175,51 +175,50 @@ impl File {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let sql = format!(
-            "INSERT INTO files (did, parent, root, name, starred, device, datetime) VALUES ('{}', {}, {}, '{}', {}, '', {})",
-            self.did.to_hex(),
-            self.parent,
-            self.root.to_i64(),
-            self.name,
-            self.starred,
-            self.datetime,
-        );
-        let id = db.insert(&sql)?;
+        let id = Query::new(
+            "INSERT INTO files (did, parent, root, name, starred, device, datetime) VALUES (?, ?, ?, ?, ?, '', ?)",
+        )
+        .bind(&self.did.to_hex())
+        .bind(&self.parent)
+        .bind(&self.root.to_i64())
+        .bind(&self.name)
+        .bind(&self.starred)
+        .bind(&self.datetime)
+        .insert(db)?;
         self.id = id;
         Ok(())
     }
 
     pub fn star(db: &DStorage, id: &i64, starred: bool) -> Result<()> {
-        let sql = format!("UPDATE files SET starred = {} WHERE id = {}", starred, id);
-        db.update(&sql)?;
+        Query::new("UPDATE files SET starred = ? WHERE id = ?")
+            .bind(&starred)
+            .bind(id)
+            .update(db)?;
         Ok(())
     }
 
     pub fn trash(db: &DStorage, id: &i64) -> Result<()> {
-        let sql = format!(
-            "UPDATE files SET root = {} WHERE id = {}",
-            RootDirectory::Trash.to_i64(),
-            id
-        );
-        db.update(&sql)?;
+        Query::new("UPDATE files SET root = ? WHERE id = ?")
+            .bind(&RootDirectory::Trash.to_i64())
+            .bind(id)
+            .update(db)?;
         Ok(())
     }
 
     pub fn delete(db: &DStorage, id: &i64) -> Result<()> {
-        let sql = format!("DELETE FROM files WHERE id = {}", id);
-        db.delete(&sql)?;
+        Query::new("DELETE FROM files WHERE id = ?")
+            .bind(id)
+            .delete(db)?;
         Ok(())
     }
 
     pub fn update(&self, db: &DStorage) -> Result<()> {
-        let sql = format!(
-            "UPDATE files SET parent = {}, root = {}, name = '{}' WHERE id = {}",
-            self.parent,
-            self.root.to_i64(),
-            self.name,
-            self.id
-        );
-        db.update(&sql)?;
+        Query::new("UPDATE files SET parent = ?, root = ?, name = ? WHERE id = ?")
+            .bind(&self.parent)
+            .bind(&self.root.to_i64())
+            .bind(&self.name)
+            .bind(&self.id)
+            .update(db)?;
         Ok(())
     }
 }
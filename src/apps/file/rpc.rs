@@ -1,3 +1,7 @@
+use rpc_types::file::{
+    FileCreateParams, FileListParams, FileStarParams, FileUpdateParams, FileUploadParams,
+};
+use rpc_types::Id;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tdn::types::{
@@ -6,7 +10,7 @@ use tdn::types::{
     rpc::{json, RpcError, RpcHandler, RpcParam},
 };
 
-use crate::rpc::RpcState;
+use crate::rpc::{parse, RpcState};
 use crate::storage::{copy_file, write_file};
 
 use super::models::{File, RootDirectory};
@@ -19,11 +23,11 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "dc-list",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let root = RootDirectory::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let parent = params[1].as_i64().ok_or(RpcError::ParseError)?;
+            let FileListParams { root, parent } = parse(params)?;
+            let root = RootDirectory::from_i64(root);
 
             let db = state.group.read().await.file_db(&gid)?;
-            let files: Vec<RpcParam> = File::list(&db, &root, &parent)?
+            let files: Vec<_> = File::list(&db, &root, &parent)?
                 .iter()
                 .map(|p| p.to_rpc())
                 .collect();
@@ -35,9 +39,8 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "dc-file-create",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let root = RootDirectory::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let parent = params[1].as_i64().ok_or(RpcError::ParseError)?;
-            let name = params[2].as_str().ok_or(RpcError::ParseError)?.to_owned();
+            let FileCreateParams { root, parent, name } = parse(params)?;
+            let root = RootDirectory::from_i64(root);
 
             let group_lock = state.group.read().await;
             let base = group_lock.base().clone();
@@ -49,16 +52,15 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
 
             // create file on disk.
             let _ = write_file(&base, &gid, &file.storage_name(), &[]).await?;
-            Ok(HandleResult::rpc(file.to_rpc()))
+            Ok(HandleResult::rpc(json!(file.to_rpc())))
         },
     );
 
     handler.add_method(
         "dc-file-upload",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let root = RootDirectory::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let parent = params[1].as_i64().ok_or(RpcError::ParseError)?;
-            let path = params[2].as_str().ok_or(RpcError::ParseError)?;
+            let FileUploadParams { root, parent, path } = parse(params)?;
+            let root = RootDirectory::from_i64(root);
 
             let file_path = PathBuf::from(path);
             let name = file_path
@@ -76,33 +78,35 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             file.insert(&db)?;
             copy_file(&file_path, &base, &gid, &file.storage_name()).await?;
 
-            Ok(HandleResult::rpc(file.to_rpc()))
+            Ok(HandleResult::rpc(json!(file.to_rpc())))
         },
     );
 
     handler.add_method(
         "dc-folder-create",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let root = RootDirectory::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let parent = params[1].as_i64().ok_or(RpcError::ParseError)?;
-            let name = params[2].as_str().ok_or(RpcError::ParseError)?.to_owned();
+            let FileCreateParams { root, parent, name } = parse(params)?;
+            let root = RootDirectory::from_i64(root);
 
             // create new folder.
             let db = state.group.read().await.file_db(&gid)?;
             let mut file = File::generate(root, parent, name);
             file.insert(&db)?;
 
-            Ok(HandleResult::rpc(file.to_rpc()))
+            Ok(HandleResult::rpc(json!(file.to_rpc())))
         },
     );
 
     handler.add_method(
         "dc-file-update",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let root = RootDirectory::from_i64(params[1].as_i64().ok_or(RpcError::ParseError)?);
-            let parent = params[2].as_i64().ok_or(RpcError::ParseError)?;
-            let name = params[3].as_str().ok_or(RpcError::ParseError)?.to_owned();
+            let FileUpdateParams {
+                id,
+                root,
+                parent,
+                name,
+            } = parse(params)?;
+            let root = RootDirectory::from_i64(root);
 
             let db = state.group.read().await.file_db(&gid)?;
             let mut file = File::get(&db, &id)?;
@@ -111,15 +115,14 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             file.name = name;
             file.update(&db)?;
 
-            Ok(HandleResult::rpc(file.to_rpc()))
+            Ok(HandleResult::rpc(json!(file.to_rpc())))
         },
     );
 
     handler.add_method(
         "dc-file-star",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let starred = params[1].as_bool().ok_or(RpcError::ParseError)?;
+            let FileStarParams { id, starred } = parse(params)?;
 
             let db = state.group.read().await.file_db(&gid)?;
             File::star(&db, &id, starred)?;
@@ -130,7 +133,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "dc-file-trash",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             // TODO trash a directory.
 
@@ -143,7 +146,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "dc-file-delete",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             // TODO deleted file & directory.
 
@@ -1,17 +1,20 @@
 use std::sync::Arc;
 use tdn::types::{
     group::GroupId,
-    message::{RecvType, SendType},
+    message::{RecvType, SendType, StreamType},
     primitive::{HandleResult, Peer, PeerId, Result},
 };
 use tokio::sync::RwLock;
 
-use chat_types::MessageType;
+use chat_types::{FileChunk, MessageType, NetworkMessage};
 use group_types::{Event, LayerConnect, LayerEvent, LayerResult};
 use tdn_did::Proof;
 use tdn_storage::local::DStorage;
 
-use crate::apps::chat::Friend;
+use crate::apps::chat::{
+    handle_chunk, keep_stream, stream_accept, stream_data, stream_id, stream_open, stream_send,
+    FileStream, Friend, Transfer, TRANSFER_WINDOW,
+};
 use crate::layer::{Layer, Online};
 use crate::rpc::{
     session_close, session_connect, session_last, session_lost, session_suspend,
@@ -21,7 +24,7 @@ use crate::session::{connect_session, Session, SessionType};
 use crate::storage::{delete_avatar, write_avatar_sync};
 
 use super::models::{handle_network_message, GroupChat, Member, Message};
-use super::{add_layer, add_server_layer, rpc};
+use super::{add_layer, add_server_layer, rpc, GROUP_ID};
 
 // variable statement:
 // gcd: Group Chat ID.
@@ -60,8 +63,15 @@ pub(crate) async fn handle_server(
             handle_server_event(fgid, addr, event, layer, &mut results).await?;
             debug!("----------- DEBUG GROUP CHAT: SERVER OVER LAYER EVENT");
         }
-        RecvType::Stream(_uid, _stream, _bytes) => {
-            // TODO stream
+        RecvType::Stream(uid, stream, bytes) => {
+            if let StreamType::Req(_) = stream {
+                add_server_layer(&mut results, fgid, stream_accept(uid));
+            }
+            // stream only carry file chunks.
+            if !bytes.is_empty() {
+                let (gcd, chunk) = bincode::deserialize(&bytes)?;
+                handle_server_chunk(layer, fgid, gcd, uid, chunk, &mut results).await?;
+            }
         }
         RecvType::Result(..) => {}
         RecvType::ResultConnect(..) => {}
@@ -154,8 +164,15 @@ pub(crate) async fn handle_peer(
             handle_peer_event(ogid, addr, event, layer, &mut results).await?;
             debug!("----------- DEBUG GROUP CHAT: PEER OVER LAYER EVENT");
         }
-        RecvType::Stream(_uid, _stream, _bytes) => {
-            // TODO stream
+        RecvType::Stream(uid, stream, bytes) => {
+            if let StreamType::Req(_) = stream {
+                add_layer(&mut results, ogid, stream_accept(uid));
+            }
+            // stream only carry file chunks.
+            if !bytes.is_empty() {
+                let (gcd, chunk) = bincode::deserialize(&bytes)?;
+                handle_peer_chunk(layer, ogid, gcd, uid, chunk, &mut results).await?;
+            }
         }
         RecvType::Delivery(_t, _tid, _is_ok) => {
             // TODO
@@ -218,6 +235,96 @@ async fn handle_connect(
         add_layer(results, ogid, sync_online(gcd, addr.id));
     }
 
+    // 1.5 resume the unfinished file transfers.
+    for t in Transfer::list_unfinished(&db, &group.id)? {
+        let uid = stream_id(&t.hash, &layer.addr, &addr.id);
+        let chunk = if t.is_me {
+            t.to_start()
+        } else {
+            let stream = FileStream::new(ogid, Some(gcd), (ogid, GROUP_ID), uid);
+            keep_stream(&mut layer.streams, t.hash, Some(true), stream);
+            FileChunk::Request(t.hash, t.missing(TRANSFER_WINDOW))
+        };
+        let data = stream_data(&Some(gcd), &chunk);
+        add_layer(results, ogid, stream_open(uid, addr.id, data));
+    }
+
+    Ok(())
+}
+
+/// handle the file chunk in the member's stream. server keeps the uploaded
+/// file, and relay it to the members.
+async fn handle_server_chunk(
+    layer: &Arc<RwLock<Layer>>,
+    fgid: GroupId,
+    gcd: GroupId,
+    uid: u32,
+    chunk: FileChunk,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let mut layer_lock = layer.write().await;
+    let (ogid, _, id) = layer_lock.running(&gcd)?.owner_height_id();
+    let db = layer_lock.group.read().await.group_db(&ogid)?;
+    let _mdid = Member::get_id(&db, &id, &fgid)?;
+
+    let hash = *chunk.hash();
+    let handled = handle_chunk(&layer_lock.base, &ogid, &db, id, chunk).await?;
+    for chunk in &handled.replies {
+        let s = stream_send(uid, stream_data(&Some(gcd), chunk));
+        add_server_layer(results, fgid, s);
+    }
+
+    let stream = FileStream::new(ogid, Some(gcd), (GROUP_ID, fgid), uid);
+    keep_stream(&mut layer_lock.streams, hash, handled.waiting, stream);
+
+    // new uploaded file, online members start download from server.
+    if handled.created {
+        let start = Transfer::get_by_hash(&db, &hash)?.to_start();
+        let data = stream_data(&Some(gcd), &start);
+        for (mgid, maddr) in layer_lock.running(&gcd)?.onlines() {
+            if *mgid != fgid {
+                let uid = stream_id(&hash, &layer_lock.addr, maddr);
+                add_server_layer(results, *mgid, stream_open(uid, *maddr, data.clone()));
+            }
+        }
+    }
+
+    if let (Some((done, total)), Ok(msg)) = (handled.progress, Message::get_by_hash(&db, &hash)) {
+        results
+            .rpcs
+            .push(rpc::transfer_progress(ogid, msg.id, done, total));
+    }
+    Ok(())
+}
+
+/// handle the file chunk in the server's stream.
+async fn handle_peer_chunk(
+    layer: &Arc<RwLock<Layer>>,
+    ogid: GroupId,
+    gcd: GroupId,
+    uid: u32,
+    chunk: FileChunk,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let mut layer_lock = layer.write().await;
+    let (_sid, id) = layer_lock.get_running_remote_id(&ogid, &gcd)?;
+    let db = layer_lock.group.read().await.group_db(&ogid)?;
+
+    let hash = *chunk.hash();
+    let handled = handle_chunk(&layer_lock.base, &ogid, &db, id, chunk).await?;
+    for chunk in &handled.replies {
+        let s = stream_send(uid, stream_data(&Some(gcd), chunk));
+        add_layer(results, ogid, s);
+    }
+
+    let stream = FileStream::new(ogid, Some(gcd), (ogid, GROUP_ID), uid);
+    keep_stream(&mut layer_lock.streams, hash, handled.waiting, stream);
+
+    if let (Some((done, total)), Ok(msg)) = (handled.progress, Message::get_by_hash(&db, &hash)) {
+        results
+            .rpcs
+            .push(rpc::transfer_progress(ogid, msg.id, done, total));
+    }
     Ok(())
 }
 
@@ -315,11 +422,11 @@ async fn handle_server_event(
                     GroupChat::add_height(&db, id, h)?;
                     broadcast(&LayerEvent::Sync(gcd, h, event), layer, &gcd, results).await?;
                 }
-                Event::MessageCreate(mgid, nmsg, mtime) => {
+                Event::MessageCreate(mgid, nmsg, mtime, hash, parent) => {
                     debug!("Sync: create message start");
                     let _mdid = Member::get_id(&db, &id, &mgid)?;
 
-                    let new_e = Event::MessageCreate(mgid, nmsg.clone(), mtime);
+                    let new_e = Event::MessageCreate(mgid, nmsg.clone(), mtime, hash, parent);
                     let new_h = layer.write().await.running_mut(&gcd)?.increased();
                     broadcast(&LayerEvent::Sync(gcd, new_h, new_e), layer, &gcd, results).await?;
                     GroupChat::add_height(&db, id, new_h)?;
@@ -332,6 +439,8 @@ async fn handle_server_event(
                         &ogid,
                         nmsg,
                         mtime,
+                        hash,
+                        parent,
                         &base,
                         results,
                     )
@@ -508,7 +617,7 @@ async fn handle_peer_event(
                     // save consensus.
                     GroupChat::add_height(&db, id, height)?;
                 }
-                Event::MessageCreate(mgid, nmsg, mtime) => {
+                Event::MessageCreate(mgid, nmsg, mtime, hash, parent) => {
                     debug!("Sync: create message start");
                     let _mdid = Member::get_id(&db, &id, &mgid)?;
 
@@ -520,6 +629,8 @@ async fn handle_peer_event(
                         &ogid,
                         nmsg,
                         mtime,
+                        hash,
+                        parent,
                         &base,
                         results,
                     )
@@ -576,7 +687,9 @@ async fn handle_peer_event(
                 }
             }
 
-            for (height, mgid, nm, time) in messages {
+            for (height, mgid, nm, time, hash, parent) in messages {
+                // big file only carry the name, request the chunks from server.
+                let chunked = matches!(&nm, NetworkMessage::File(_, bytes) if bytes.is_empty());
                 if let Ok(msg) = handle_network_message(
                     &layer.read().await.group,
                     height,
@@ -585,11 +698,18 @@ async fn handle_peer_event(
                     &ogid,
                     nm,
                     time,
+                    hash,
+                    parent,
                     &base,
                     results,
                 )
                 .await
                 {
+                    if chunked && mgid != ogid && Transfer::get_by_hash(&db, &hash).is_err() {
+                        let uid = stream_id(&hash, &layer.read().await.addr, &addr);
+                        let data = stream_data(&Some(gcd), &FileChunk::Request(hash, vec![]));
+                        add_layer(results, ogid, stream_open(uid, addr, data));
+                    }
                     results.rpcs.push(rpc::message_create(ogid, &msg));
                     last_message = Some(msg);
                 }
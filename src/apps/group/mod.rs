@@ -2,7 +2,21 @@ mod layer;
 mod models;
 
 pub use group_types::GROUP_CHAT_ID as GROUP_ID;
-use tdn::types::{group::GroupId, message::SendType, primitive::HandleResult};
+use group_types::{Event, LayerEvent};
+use std::sync::Arc;
+use tdn::types::{
+    group::GroupId,
+    message::{RecvType, SendType},
+    primitive::{HandleResult, Result},
+    rpc::RpcHandler,
+};
+use tdn_storage::local::DStorage;
+use tokio::sync::RwLock;
+
+use crate::apps::{App, LayerFuture};
+use crate::layer::Layer;
+use crate::migrate::{GROUP_DB, GROUP_VERSIONS};
+use crate::rpc::RpcState;
 
 /// Send to group chat service.
 #[inline]
@@ -16,7 +30,76 @@ pub fn add_server_layer(results: &mut HandleResult, gid: GroupId, msg: SendType)
     results.layers.push((GROUP_ID, gid, msg));
 }
 
+/// leave all group chats, and dissolve the local group chats. use when account deleted.
+pub(crate) fn leave_all(
+    layer: &Layer,
+    db: &DStorage,
+    gid: GroupId,
+    results: &mut HandleResult,
+) -> Result<()> {
+    for g in GroupChat::all(db)? {
+        if g.close {
+            continue;
+        }
+        if g.local {
+            let d = bincode::serialize(&LayerEvent::GroupClose(g.g_id))?;
+            if let Ok(running) = layer.running(&g.g_id) {
+                for (mgid, maddr) in running.onlines() {
+                    let s = SendType::Event(0, *maddr, d.clone());
+                    add_server_layer(results, *mgid, s);
+                }
+            }
+        } else {
+            let d = bincode::serialize(&LayerEvent::Sync(g.g_id, 0, Event::MemberLeave(gid)))?;
+            let msg = SendType::Event(0, g.g_addr, d);
+            add_layer(results, gid, msg);
+        }
+    }
+    Ok(())
+}
+
 pub(crate) mod rpc;
 pub(crate) use layer::{group_conn, handle_peer, handle_server};
-pub(crate) use models::GroupChat;
+pub(crate) use models::{GroupChat, Message};
 pub(crate) use rpc::new_rpc_handler;
+
+/// group app.
+pub(crate) struct GroupChatApp;
+
+impl App for GroupChatApp {
+    fn name(&self) -> &'static str {
+        "group"
+    }
+
+    fn group_id(&self) -> Option<GroupId> {
+        Some(GROUP_ID)
+    }
+
+    fn db(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some((GROUP_DB, &GROUP_VERSIONS))
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+
+    fn handle_peer<'a>(
+        &'a self,
+        layer: &'a Arc<RwLock<Layer>>,
+        _fgid: GroupId,
+        mgid: GroupId,
+        msg: RecvType,
+    ) -> LayerFuture<'a> {
+        Box::pin(handle_peer(layer, mgid, msg))
+    }
+
+    fn handle_server<'a>(
+        &'a self,
+        layer: &'a Arc<RwLock<Layer>>,
+        fgid: GroupId,
+        _mgid: GroupId,
+        msg: RecvType,
+    ) -> LayerFuture<'a> {
+        Box::pin(handle_server(layer, fgid, msg))
+    }
+}
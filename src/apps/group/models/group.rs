@@ -3,11 +3,11 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tdn::types::{
     group::GroupId,
     primitive::{PeerId, Result},
-    rpc::{json, RpcParam},
 };
 use tdn_storage::local::{DStorage, DsValue};
 
 use crate::session::{Session, SessionType};
+use crate::utils::sql::Query;
 
 use super::{Member, Message};
 
@@ -73,15 +73,15 @@ impl GroupChat {
         )
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.g_id.to_hex(),
-            self.g_addr.to_hex(),
-            self.g_name,
-            self.close,
-            self.local,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::group::Group {
+        rpc_types::group::Group {
+            id: self.id,
+            gid: self.g_id.to_hex(),
+            addr: self.g_addr.to_hex(),
+            name: self.g_name.clone(),
+            close: self.close,
+            local: self.local,
+        }
     }
 
     fn from_values(mut v: Vec<DsValue>) -> Self {
@@ -118,11 +118,11 @@ impl GroupChat {
     }
 
     pub fn get(db: &DStorage, id: &i64) -> Result<GroupChat> {
-        let sql = format!(
-            "SELECT id, height, gcd, addr, name, is_close, is_local FROM groups WHERE id = {}",
-            id
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new(
+            "SELECT id, height, gcd, addr, name, is_close, is_local FROM groups WHERE id = ?",
+        )
+        .bind(id)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             Ok(Self::from_values(values))
@@ -132,11 +132,11 @@ impl GroupChat {
     }
 
     pub fn get_id(db: &DStorage, gid: &GroupId) -> Result<GroupChat> {
-        let sql = format!(
-            "SELECT id, height, gcd, addr, name, is_close, is_local FROM groups WHERE gcd = '{}'",
-            gid.to_hex()
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new(
+            "SELECT id, height, gcd, addr, name, is_close, is_local FROM groups WHERE gcd = ?",
+        )
+        .bind(gid)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             Ok(Self::from_values(values))
@@ -146,58 +146,61 @@ impl GroupChat {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let mut unique_check = db.query(&format!(
-            "SELECT id from groups WHERE gcd = '{}'",
-            self.g_id.to_hex()
-        ))?;
+        let mut unique_check = Query::new("SELECT id from groups WHERE gcd = ?")
+            .bind(&self.g_id)
+            .query(db)?;
         if unique_check.len() > 0 {
             let id = unique_check.pop().unwrap().pop().unwrap().as_i64();
             self.id = id;
-            let sql = format!(
-                "UPDATE groups SET height = {}, addr='{}', name = '{}' WHERE id = {}",
-                self.height,
-                self.g_addr.to_hex(),
-                self.g_name,
-                self.id
-            );
-            db.update(&sql)?;
+            Query::new("UPDATE groups SET height = ?, addr = ?, name = ? WHERE id = ?")
+                .bind(&self.height)
+                .bind(&self.g_addr)
+                .bind(&self.g_name)
+                .bind(&self.id)
+                .update(db)?;
         } else {
-            let sql = format!(
-                "INSERT INTO groups (height, gcd, addr, name, is_close, is_local) VALUES ({}, '{}', '{}', '{}', {}, {})",
-                self.height,
-                self.g_id.to_hex(),
-                self.g_addr.to_hex(),
-                self.g_name,
-                self.close,
-                self.local,
-            );
-            let id = db.insert(&sql)?;
+            let id = Query::new(
+                "INSERT INTO groups (height, gcd, addr, name, is_close, is_local) VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&self.height)
+            .bind(&self.g_id)
+            .bind(&self.g_addr)
+            .bind(&self.g_name)
+            .bind(&self.close)
+            .bind(&self.local)
+            .insert(db)?;
             self.id = id;
         }
         Ok(())
     }
 
     pub fn add_height(db: &DStorage, id: i64, height: i64) -> Result<usize> {
-        let sql = format!("UPDATE groups SET height={} WHERE id = {}", height, id);
-        db.update(&sql)
+        Query::new("UPDATE groups SET height = ? WHERE id = ?")
+            .bind(&height)
+            .bind(&id)
+            .update(db)
     }
 
     pub fn update_name(db: &DStorage, id: &i64, name: &str) -> Result<usize> {
-        let sql = format!("UPDATE groups SET name='{}' WHERE id = {}", name, id);
-        db.update(&sql)
+        Query::new("UPDATE groups SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(id)
+            .update(db)
     }
 
     pub fn close(db: &DStorage, gcd: &GroupId) -> Result<GroupChat> {
         let group = Self::get_id(db, gcd)?;
-        let sql = format!("UPDATE groups SET is_close = true WHERE id = {}", group.id);
-        db.update(&sql)?;
+        Query::new("UPDATE groups SET is_close = true WHERE id = ?")
+            .bind(&group.id)
+            .update(db)?;
         Ok(group)
     }
 
     pub fn delete(db: &DStorage, id: &i64) -> Result<GroupChat> {
         let group = Self::get(db, id)?;
-        let sql = format!("DELETE FROM groups WHERE id = {}", id);
-        db.delete(&sql)?;
+        Query::new("DELETE FROM groups WHERE id = ?")
+            .bind(id)
+            .delete(db)?;
 
         // delete all members and messages;
         let _ = Member::delete(db, id);
@@ -2,11 +2,11 @@ use std::path::PathBuf;
 use tdn::types::{
     group::GroupId,
     primitive::{PeerId, Result},
-    rpc::{json, RpcParam},
 };
 use tdn_storage::local::{DStorage, DsValue};
 
 use crate::storage::read_avatar;
+use crate::utils::sql::Query;
 
 /// Group Member Model.
 pub(crate) struct Member {
@@ -51,15 +51,15 @@ impl Member {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.fid,
-            self.m_id.to_hex(),
-            self.m_addr.to_hex(),
-            self.m_name,
-            self.leave,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::group::Member {
+        rpc_types::group::Member {
+            id: self.id,
+            fid: self.fid,
+            mid: self.m_id.to_hex(),
+            addr: self.m_addr.to_hex(),
+            name: self.m_name.clone(),
+            leave: self.leave,
+        }
     }
 
     fn from_values(mut v: Vec<DsValue>) -> Self {
@@ -75,10 +75,10 @@ impl Member {
     }
 
     pub fn list(db: &DStorage, fid: &i64) -> Result<Vec<Member>> {
-        let matrix = db.query(&format!(
-            "SELECT id, height, fid, mid, addr, name, leave FROM members WHERE fid = {}",
-            fid
-        ))?;
+        let matrix =
+            Query::new("SELECT id, height, fid, mid, addr, name, leave FROM members WHERE fid = ?")
+                .bind(fid)
+                .query(db)?;
         let mut groups = vec![];
         for values in matrix {
             groups.push(Self::from_values(values));
@@ -87,41 +87,39 @@ impl Member {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let mut unique_check = db.query(&format!(
-            "SELECT id from members WHERE fid = {} AND mid = '{}'",
-            self.fid,
-            self.m_id.to_hex()
-        ))?;
+        let mut unique_check = Query::new("SELECT id from members WHERE fid = ? AND mid = ?")
+            .bind(&self.fid)
+            .bind(&self.m_id)
+            .query(db)?;
         if unique_check.len() > 0 {
             let id = unique_check.pop().unwrap().pop().unwrap().as_i64();
             self.id = id;
-            let sql = format!(
-                "UPDATE members SET height = {}, addr='{}', name = '{}', leave = false WHERE id = {}",
-                self.height,
-                self.m_addr.to_hex(),
-                self.m_name,
-                self.id,
-            );
-            db.update(&sql)?;
+            Query::new(
+                "UPDATE members SET height = ?, addr = ?, name = ?, leave = false WHERE id = ?",
+            )
+            .bind(&self.height)
+            .bind(&self.m_addr)
+            .bind(&self.m_name)
+            .bind(&self.id)
+            .update(db)?;
         } else {
-            let sql = format!("INSERT INTO members (height, fid, mid, addr, name, leave) VALUES ({}, {}, '{}', '{}', '{}', false)",
-            self.height,
-            self.fid,
-            self.m_id.to_hex(),
-            self.m_addr.to_hex(),
-            self.m_name,
-        );
-            let id = db.insert(&sql)?;
+            let id = Query::new("INSERT INTO members (height, fid, mid, addr, name, leave) VALUES (?, ?, ?, ?, ?, false)")
+                .bind(&self.height)
+                .bind(&self.fid)
+                .bind(&self.m_id)
+                .bind(&self.m_addr)
+                .bind(&self.m_name)
+                .insert(db)?;
             self.id = id;
         }
         Ok(())
     }
 
     pub fn _get(db: &DStorage, id: &i64) -> Result<Member> {
-        let mut matrix = db.query(&format!(
-            "SELECT id, height, fid, mid, addr, name, leave FROM members WHERE id = {}",
-            id,
-        ))?;
+        let mut matrix =
+            Query::new("SELECT id, height, fid, mid, addr, name, leave FROM members WHERE id = ?")
+                .bind(id)
+                .query(db)?;
         if matrix.len() > 0 {
             Ok(Self::from_values(matrix.pop().unwrap())) // safe unwrap.
         } else {
@@ -130,11 +128,10 @@ impl Member {
     }
 
     pub fn get_id(db: &DStorage, fid: &i64, gid: &GroupId) -> Result<i64> {
-        let mut matrix = db.query(&format!(
-            "SELECT id FROM members WHERE fid = {} AND mid = '{}'",
-            fid,
-            gid.to_hex()
-        ))?;
+        let mut matrix = Query::new("SELECT id FROM members WHERE fid = ? AND mid = ?")
+            .bind(fid)
+            .bind(gid)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(matrix.pop().unwrap().pop().unwrap().as_i64()) // safe unwrap.
         } else {
@@ -144,13 +141,11 @@ impl Member {
 
     pub fn addr_update(db: &DStorage, fid: &i64, mid: &GroupId, addr: &PeerId) -> Result<i64> {
         let mdid = Self::get_id(db, fid, mid)?;
-        let sql = format!(
-            "UPDATE members SET addr='{}' WHERE fid = {} AND mid = '{}'",
-            addr.to_hex(),
-            fid,
-            mid.to_hex(),
-        );
-        db.update(&sql)?;
+        Query::new("UPDATE members SET addr = ? WHERE fid = ? AND mid = ?")
+            .bind(addr)
+            .bind(fid)
+            .bind(mid)
+            .update(db)?;
         Ok(mdid)
     }
 
@@ -161,27 +156,25 @@ impl Member {
         addr: &PeerId,
         name: &str,
     ) -> Result<usize> {
-        let sql = format!(
-            "UPDATE members SET height = {}, addr='{}', name='{}' WHERE id = {}",
-            height,
-            addr.to_hex(),
-            name,
-            id,
-        );
-        db.update(&sql)
+        Query::new("UPDATE members SET height = ?, addr = ?, name = ? WHERE id = ?")
+            .bind(height)
+            .bind(addr)
+            .bind(name)
+            .bind(id)
+            .update(db)
     }
 
     pub fn leave(db: &DStorage, id: &i64, height: &i64) -> Result<usize> {
-        let sql = format!(
-            "UPDATE members SET height = {}, leave = true WHERE id = {}",
-            height, id
-        );
-        db.update(&sql)
+        Query::new("UPDATE members SET height = ?, leave = true WHERE id = ?")
+            .bind(height)
+            .bind(id)
+            .update(db)
     }
 
     pub fn delete(db: &DStorage, fid: &i64) -> Result<usize> {
-        let sql = format!("DELETE FROM members WHERE fid = {}", fid);
-        db.delete(&sql)
+        Query::new("DELETE FROM members WHERE fid = ?")
+            .bind(fid)
+            .delete(db)
     }
 
     pub async fn sync(
@@ -195,8 +188,11 @@ impl Member {
         Vec<(i64, GroupId, PeerId, String, Vec<u8>)>,
         Vec<(i64, GroupId)>,
     )> {
-        let sql = format!("SELECT id, height, fid, mid, addr, name, leave FROM members WHERE fid = {} AND height BETWEEN {} AND {}", fid, from, to);
-        let matrix = db.query(&sql)?;
+        let matrix = Query::new("SELECT id, height, fid, mid, addr, name, leave FROM members WHERE fid = ? AND height BETWEEN ? AND ?")
+            .bind(fid)
+            .bind(from)
+            .bind(to)
+            .query(db)?;
         let mut adds = vec![];
         let mut leaves = vec![];
         for values in matrix {
@@ -3,17 +3,20 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tdn::types::{
-    group::GroupId,
+    group::{EventId, GroupId},
     primitive::{HandleResult, Result},
-    rpc::{json, RpcParam},
 };
 use tdn_storage::local::{DStorage, DsValue};
 use tokio::sync::RwLock;
 
 use chat_types::{MessageType, NetworkMessage};
 
-use crate::apps::chat::{from_network_message, raw_to_network_message, to_network_message as tnm};
+use crate::apps::chat::{
+    from_network_message, raw_to_network_message, to_network_message as tnm, Transfer,
+};
+use crate::apps::search::MessageIndex;
 use crate::group::Group;
+use crate::utils::sql::Query;
 
 use super::Member;
 
@@ -37,6 +40,10 @@ pub(crate) struct Message {
     is_delivery: bool,
     /// message created time.
     pub datetime: i64,
+    /// message hash, created by sender.
+    pub hash: EventId,
+    /// replied message's hash.
+    pub parent: Option<EventId>,
 }
 
 impl Message {
@@ -48,6 +55,8 @@ impl Message {
         m_type: MessageType,
         content: String,
         datetime: i64,
+        hash: EventId,
+        parent: Option<EventId>,
     ) -> Message {
         Self {
             fid,
@@ -55,6 +64,8 @@ impl Message {
             m_type,
             content,
             datetime,
+            hash,
+            parent,
             height,
             is_me,
             is_delivery: true,
@@ -62,9 +73,23 @@ impl Message {
         }
     }
 
+    /// build message hash by group, member, time, content and a random nonce.
+    pub(crate) fn new_hash(gcd: &GroupId, mid: &GroupId, datetime: i64, content: &str) -> EventId {
+        let nonce: [u8; 16] = rand::random();
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&gcd.0);
+        hasher.update(&mid.0);
+        hasher.update(&datetime.to_le_bytes());
+        hasher.update(content.as_bytes());
+        hasher.update(&nonce);
+        EventId(*hasher.finalize().as_bytes())
+    }
+
     /// here is zero-copy and unwrap is safe. checked.
     fn from_values(mut v: Vec<DsValue>) -> Message {
         Message {
+            parent: EventId::from_hex(v.pop().unwrap().as_str()).ok(),
+            hash: EventId::from_hex(v.pop().unwrap().as_str()).unwrap_or(EventId::default()),
             datetime: v.pop().unwrap().as_i64(),
             is_delivery: v.pop().unwrap().as_bool(),
             content: v.pop().unwrap().as_string(),
@@ -77,22 +102,26 @@ impl Message {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.height,
-            self.fid,
-            self.mid,
-            self.is_me,
-            self.m_type.to_int(),
-            self.content,
-            self.is_delivery,
-            self.datetime,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::group::Message {
+        rpc_types::group::Message {
+            id: self.id,
+            height: self.height,
+            fid: self.fid,
+            mid: self.mid,
+            is_me: self.is_me,
+            m_type: self.m_type.to_int(),
+            content: self.content.clone(),
+            is_delivery: self.is_delivery,
+            datetime: self.datetime,
+            hash: self.hash.to_hex(),
+            parent: self.parent.map(|p| p.to_hex()).unwrap_or(String::new()),
+        }
     }
 
     pub fn _get(db: &DStorage, id: &i64) -> Result<Message> {
-        let mut matrix = db.query(&format!("SELECT id, height, fid, mid, is_me, m_type, content, is_delivery, datetime FROM messages WHERE id = {}", id))?;
+        let mut matrix = Query::new("SELECT id, height, fid, mid, is_me, m_type, content, is_delivery, datetime, hash, parent FROM messages WHERE id = ?")
+            .bind(id)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(Message::from_values(matrix.pop().unwrap())) // safe unwrap.
         } else {
@@ -100,8 +129,21 @@ impl Message {
         }
     }
 
+    pub fn get_by_hash(db: &DStorage, hash: &EventId) -> Result<Message> {
+        let mut matrix = Query::new("SELECT id, height, fid, mid, is_me, m_type, content, is_delivery, datetime, hash, parent FROM messages WHERE hash = ?")
+            .bind(hash)
+            .query(db)?;
+        if matrix.len() > 0 {
+            Ok(Message::from_values(matrix.pop().unwrap())) // safe unwrap.
+        } else {
+            Err(anyhow!("message is missing."))
+        }
+    }
+
     pub fn list(db: &DStorage, fid: &i64) -> Result<Vec<Message>> {
-        let matrix = db.query(&format!("SELECT id, height, fid, mid, is_me, m_type, content, is_delivery, datetime FROM messages WHERE fid = {}", fid))?;
+        let matrix = Query::new("SELECT id, height, fid, mid, is_me, m_type, content, is_delivery, datetime, hash, parent FROM messages WHERE fid = ?")
+            .bind(fid)
+            .query(db)?;
         let mut groups = vec![];
         for values in matrix {
             groups.push(Message::from_values(values));
@@ -110,33 +152,56 @@ impl Message {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let mut unique_check = db.query(&format!(
-            "SELECT id from messages WHERE fid = {} AND height = {}",
-            self.fid, self.height
-        ))?;
+        let mut unique_check = Query::new("SELECT id from messages WHERE fid = ? AND height = ?")
+            .bind(&self.fid)
+            .bind(&self.height)
+            .query(db)?;
         if unique_check.len() > 0 {
             let id = unique_check.pop().unwrap().pop().unwrap().as_i64();
             self.id = id;
         } else {
-            let sql = format!("INSERT INTO messages (height, fid, mid, is_me, m_type, content, is_delivery, datetime) VALUES ({}, {}, {}, {}, {}, '{}', {}, {})",
-                self.height,
-                self.fid,
-                self.mid,
-                self.is_me,
-                self.m_type.to_int(),
-                self.content,
-                self.is_delivery,
-                self.datetime,
-            );
-            let id = db.insert(&sql)?;
+            let id = Query::new("INSERT INTO messages (height, fid, mid, is_me, m_type, content, is_delivery, datetime, hash, parent) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+                .bind(&self.height)
+                .bind(&self.fid)
+                .bind(&self.mid)
+                .bind(&self.is_me)
+                .bind(&self.m_type.to_int())
+                .bind(&self.content)
+                .bind(&self.is_delivery)
+                .bind(&self.datetime)
+                .bind(&self.hash)
+                .bind(&self.parent.map(|p| p.to_hex()).unwrap_or(String::new()))
+                .insert(db)?;
             self.id = id;
+            MessageIndex::insert(db, self.id, &self.m_type, &self.content)?;
         }
         Ok(())
     }
 
     pub fn delete(db: &DStorage, fid: &i64) -> Result<usize> {
-        let sql = format!("DELETE FROM messages WHERE fid = {}", fid);
-        db.delete(&sql)
+        MessageIndex::delete_by_fid(db, fid)?;
+        Transfer::delete_by_fid(db, fid)?;
+        Query::new("DELETE FROM messages WHERE fid = ?")
+            .bind(fid)
+            .delete(db)
+    }
+
+    /// delete messages created before the datetime, return the deleted messages id.
+    pub fn delete_before(db: &DStorage, fid: &i64, datetime: &i64) -> Result<Vec<i64>> {
+        let matrix = Query::new("SELECT id FROM messages WHERE fid = ? AND datetime < ?")
+            .bind(fid)
+            .bind(datetime)
+            .query(db)?;
+        let mut ids = vec![];
+        for mut v in matrix {
+            let id = v.pop().unwrap().as_i64(); // safe unwrap.
+            MessageIndex::delete(db, &id)?;
+            Query::new("DELETE FROM messages WHERE id = ?")
+                .bind(&id)
+                .delete(db)?;
+            ids.push(id);
+        }
+        Ok(ids)
     }
 
     pub async fn sync(
@@ -146,9 +211,10 @@ impl Message {
         fid: &i64,
         from: &i64,
         to: &i64,
-    ) -> Result<Vec<(i64, GroupId, NetworkMessage, i64)>> {
-        let sql = format!("SELECT id, mid FROM members WHERE fid = {}", fid);
-        let m = db.query(&sql)?;
+    ) -> Result<Vec<(i64, GroupId, NetworkMessage, i64, EventId, Option<EventId>)>> {
+        let m = Query::new("SELECT id, mid FROM members WHERE fid = ?")
+            .bind(fid)
+            .query(db)?;
         let mut members = HashMap::new();
         for mut v in m {
             let m_s = v.pop().unwrap().as_string();
@@ -157,14 +223,17 @@ impl Message {
             members.insert(id, mid);
         }
 
-        let sql = format!("SELECT id, height, fid, mid, is_me, m_type, content, is_delivery, datetime FROM messages WHERE fid = {} AND height BETWEEN {} AND {}", fid, from, to);
-        let matrix = db.query(&sql)?;
+        let matrix = Query::new("SELECT id, height, fid, mid, is_me, m_type, content, is_delivery, datetime, hash, parent FROM messages WHERE fid = ? AND height BETWEEN ? AND ?")
+            .bind(fid)
+            .bind(from)
+            .bind(to)
+            .query(db)?;
         let mut messages = vec![];
         for values in matrix {
             let msg = Message::from_values(values);
             if let Ok(nmsg) = tnm(base, gid, msg.m_type, msg.content).await {
                 let mid = members.get(&msg.mid).cloned().unwrap_or(GroupId::default());
-                messages.push((msg.height, mid, nmsg, msg.datetime))
+                messages.push((msg.height, mid, nmsg, msg.datetime, msg.hash, msg.parent))
             }
         }
 
@@ -197,6 +266,8 @@ pub(crate) async fn handle_network_message(
     mgid: &GroupId,
     msg: NetworkMessage,
     datetime: i64,
+    hash: EventId,
+    parent: Option<EventId>,
     base: &PathBuf,
     results: &mut HandleResult,
 ) -> Result<Message> {
@@ -204,7 +275,9 @@ pub(crate) async fn handle_network_message(
     let mdid = Member::get_id(&db, &gdid, &mid)?;
     let is_me = &mid == mgid;
     let (m_type, raw) = from_network_message(group, msg, base, mgid, results).await?;
-    let mut msg = Message::new_with_time(height, gdid, mdid, is_me, m_type, raw, datetime);
+    let mut msg = Message::new_with_time(
+        height, gdid, mdid, is_me, m_type, raw, datetime, hash, parent,
+    );
     msg.insert(&db)?;
     Ok(msg)
 }
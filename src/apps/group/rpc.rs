@@ -1,19 +1,25 @@
+use rpc_types::chat::TransferProgress;
+use rpc_types::group::{
+    GroupCreateParams, GroupCreated, GroupDetail, GroupMember, GroupMemberJoinParams,
+    GroupMessageCreateParams, GroupName, MemberOnline,
+};
+use rpc_types::Id;
 use std::sync::Arc;
 use tdn::types::{
-    group::GroupId,
+    group::{EventId, GroupId},
     message::{NetworkType, SendMessage, SendType},
     primitive::{HandleResult, PeerId},
     rpc::{json, rpc_response, RpcError, RpcHandler, RpcParam},
 };
 
-use chat_types::MessageType;
+use chat_types::{MessageType, NetworkMessage, FILE_CHUNK_SIZE};
 use group_types::{Event, LayerEvent};
 
-use crate::apps::chat::{Friend, InviteType};
+use crate::apps::chat::{stream_data, stream_id, stream_open, Friend, InviteType, Transfer};
 use crate::layer::Online;
-use crate::rpc::{session_create, session_delete, session_update_name, RpcState};
+use crate::rpc::{parse, session_create, session_delete, session_update_name, RpcState};
 use crate::session::{Session, SessionType};
-use crate::storage::{read_avatar, write_avatar};
+use crate::storage::{file_hashes, read_avatar, write_avatar};
 
 use super::layer::{broadcast, update_session};
 use super::models::{to_network_message, GroupChat, Member, Message};
@@ -26,27 +32,37 @@ pub(crate) fn member_join(mgid: GroupId, member: &Member) -> RpcParam {
 
 #[inline]
 pub(crate) fn member_leave(mgid: GroupId, id: i64, mid: i64) -> RpcParam {
-    rpc_response(0, "group-member-leave", json!([id, mid]), mgid)
-}
-
-#[inline]
-pub(crate) fn member_online(mgid: GroupId, id: i64, mid: i64, maddr: &PeerId) -> RpcParam {
     rpc_response(
         0,
-        "group-member-online",
-        json!([id, mid, maddr.to_hex()]),
+        "group-member-leave",
+        json!(GroupMember { id, mid }),
         mgid,
     )
 }
 
+#[inline]
+pub(crate) fn member_online(mgid: GroupId, id: i64, mid: i64, maddr: &PeerId) -> RpcParam {
+    let data = json!(MemberOnline {
+        id,
+        mid,
+        addr: maddr.to_hex(),
+    });
+    rpc_response(0, "group-member-online", data, mgid)
+}
+
 #[inline]
 pub(crate) fn member_offline(mgid: GroupId, gid: i64, mid: i64) -> RpcParam {
-    rpc_response(0, "group-member-offline", json!([gid, mid]), mgid)
+    let data = json!(GroupMember { id: gid, mid });
+    rpc_response(0, "group-member-offline", data, mgid)
 }
 
 #[inline]
 pub(crate) fn group_name(mgid: GroupId, gid: &i64, name: &str) -> RpcParam {
-    rpc_response(0, "group-name", json!([gid, name]), mgid)
+    let data = json!(GroupName {
+        id: *gid,
+        name: name.to_owned(),
+    });
+    rpc_response(0, "group-name", data, mgid)
 }
 
 #[inline]
@@ -54,6 +70,21 @@ pub(crate) fn message_create(mgid: GroupId, msg: &Message) -> RpcParam {
     rpc_response(0, "group-message-create", json!(msg.to_rpc()), mgid)
 }
 
+#[inline]
+pub(crate) fn transfer_progress(mgid: GroupId, id: i64, done: usize, total: usize) -> RpcParam {
+    let data = json!(TransferProgress {
+        id,
+        done: done as u64,
+        total: total as u64,
+    });
+    rpc_response(0, "group-transfer-progress", data, mgid)
+}
+
+#[inline]
+pub(crate) fn message_delete(mgid: GroupId, id: i64) -> RpcParam {
+    rpc_response(0, "group-message-delete", json!(Id { id }), mgid)
+}
+
 #[inline]
 fn group_list(groups: Vec<GroupChat>) -> RpcParam {
     let mut results = vec![];
@@ -66,17 +97,11 @@ fn group_list(groups: Vec<GroupChat>) -> RpcParam {
 
 #[inline]
 fn detail_list(group: GroupChat, members: Vec<Member>, messages: Vec<Message>) -> RpcParam {
-    let mut member_results = vec![];
-    for m in members {
-        member_results.push(m.to_rpc());
-    }
-
-    let mut message_results = vec![];
-    for msg in messages {
-        message_results.push(msg.to_rpc());
-    }
-
-    json!([group.to_rpc(), member_results, message_results])
+    json!(GroupDetail {
+        group: group.to_rpc(),
+        members: members.iter().map(|m| m.to_rpc()).collect(),
+        messages: messages.iter().map(|m| m.to_rpc()).collect(),
+    })
 }
 
 pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
@@ -91,7 +116,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "group-detail",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
             let db = state.group.read().await.group_db(&gid)?;
             let group = GroupChat::get(&db, &id)?;
             let members = Member::list(&db, &id)?;
@@ -103,7 +128,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "group-create",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let name = params[0].as_str().ok_or(RpcError::ParseError)?.to_owned();
+            let GroupCreateParams { name } = parse(params)?;
 
             let group_lock = state.group.read().await;
             let base = group_lock.base().clone();
@@ -140,7 +165,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             });
 
             // add to rpcs.
-            results.rpcs.push(json!([sid, gdid]));
+            results.rpcs.push(json!(GroupCreated { sid, id: gdid }));
 
             // Add frist member join.
             let mut layer_lock = state.layer.write().await;
@@ -169,8 +194,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "group-member-join",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let fid = params[1].as_i64().ok_or(RpcError::ParseError)?;
+            let GroupMemberJoinParams { id, fid } = parse(params)?;
 
             let group_lock = state.group.read().await;
             let base = group_lock.base().clone();
@@ -192,9 +216,10 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
                 fid,
                 MessageType::Invite,
                 &contact_values,
+                None,
             )
             .await?;
-            let event = crate::apps::chat::LayerEvent::Message(msg.hash, nw);
+            let event = crate::apps::chat::LayerEvent::Message(msg.hash, nw, None);
             let mut layer_lock = state.layer.write().await;
             let s = crate::apps::chat::event_message(&mut layer_lock, msg.id, gid, f.addr, &event);
             drop(layer_lock);
@@ -211,7 +236,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
 
                 let mut mem = Member::new(new_h, g.id, f.gid, f.addr, f.name);
                 mem.insert(&group_db)?;
-                results.rpcs.push(mem.to_rpc());
+                results.rpcs.push(json!(mem.to_rpc()));
                 GroupChat::add_height(&group_db, id, new_h)?;
 
                 // broadcast.
@@ -236,9 +261,21 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "group-message-create",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let m_type = MessageType::from_int(params[1].as_i64().ok_or(RpcError::ParseError)?);
-            let m_content = params[2].as_str().ok_or(RpcError::ParseError)?;
+            let GroupMessageCreateParams {
+                id,
+                m_type,
+                content: m_content,
+                parent,
+            } = parse(params)?;
+            let m_type = MessageType::from_int(m_type);
+            if m_type == MessageType::Recovery {
+                return Err(RpcError::Custom("message type is invalid".to_owned()));
+            }
+            // optional replied message's hash.
+            let parent = match parent {
+                Some(s) if !s.is_empty() => Some(EventId::from_hex(&s)?),
+                _ => None,
+            };
 
             let group_lock = state.group.read().await;
             let base = group_lock.base().clone();
@@ -252,16 +289,36 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
 
             let mut results = HandleResult::new();
             let (nmsg, datetime, raw) =
-                to_network_message(&state.group, &base, &gid, m_type, m_content).await?;
-            let event = Event::MessageCreate(gid, nmsg, datetime);
+                to_network_message(&state.group, &base, &gid, m_type, &m_content).await?;
+            let hash = Message::new_hash(&gcd, &gid, datetime, &raw);
+
+            // big file use chunked transfer, message only carry the file name.
+            let start = match &nmsg {
+                NetworkMessage::File(name, bytes) if bytes.is_empty() => {
+                    let (size, file_hash, chunks) =
+                        file_hashes(&base, &gid, name, FILE_CHUNK_SIZE).await?;
+                    if size as usize > FILE_CHUNK_SIZE {
+                        let (name, size) = (name.clone(), size as i64);
+                        let mut t = Transfer::new(hash, id, true, name, size, file_hash, chunks);
+                        t.insert(&db)?;
+                        Some(stream_data(&Some(gcd), &t.to_start()))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            let event = Event::MessageCreate(gid, nmsg, datetime, hash, parent);
 
             if group.local {
                 // local save.
                 let new_h = state.layer.write().await.running_mut(&gcd)?.increased();
 
-                let mut msg = Message::new_with_time(new_h, id, mid, true, m_type, raw, datetime);
+                let mut msg = Message::new_with_time(
+                    new_h, id, mid, true, m_type, raw, datetime, hash, parent,
+                );
                 msg.insert(&db)?;
-                results.rpcs.push(msg.to_rpc());
+                results.rpcs.push(json!(msg.to_rpc()));
                 GroupChat::add_height(&db, id, new_h)?;
 
                 // UPDATE SESSION.
@@ -275,11 +332,27 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
                     &mut results,
                 )
                 .await?;
+
+                // online members start download the file.
+                if let Some(data) = start {
+                    let layer_lock = state.layer.read().await;
+                    for (mgid, maddr) in layer_lock.running(&gcd)?.onlines() {
+                        let uid = stream_id(&hash, &layer_lock.addr, maddr);
+                        let s = stream_open(uid, *maddr, data.clone());
+                        add_server_layer(&mut results, *mgid, s);
+                    }
+                }
             } else {
                 // send to server.
                 let data = bincode::serialize(&LayerEvent::Sync(gcd, 0, event))?;
                 let msg = SendType::Event(0, group.g_addr, data);
                 add_layer(&mut results, gid, msg);
+
+                // upload the file to server.
+                if let Some(data) = start {
+                    let uid = stream_id(&hash, &state.layer.read().await.addr, &group.g_addr);
+                    add_layer(&mut results, gid, stream_open(uid, group.g_addr, data));
+                }
             }
 
             Ok(results)
@@ -289,8 +362,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "group-name",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let name = params[1].as_str().ok_or(RpcError::ParseError)?;
+            let GroupName { id, name } = parse(params)?;
 
             let mut results = HandleResult::new();
             let group_lock = state.group.read().await;
@@ -299,7 +371,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             drop(group_lock);
 
             let g = GroupChat::get(&db, &id)?;
-            let d = bincode::serialize(&LayerEvent::GroupName(g.g_id, name.to_owned()))?;
+            let d = bincode::serialize(&LayerEvent::GroupName(g.g_id, name.clone()))?;
 
             if g.local {
                 if let Ok(sid) = Session::update_name_by_id(&s_db, &id, &SessionType::Group, &name)
@@ -307,7 +379,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
                     results.rpcs.push(session_update_name(gid, &sid, &name));
                 }
 
-                results.rpcs.push(json!([id, name]));
+                results.rpcs.push(json!(GroupName { id, name }));
                 // dissolve group.
                 for (mgid, maddr) in state.layer.read().await.running(&g.g_id)?.onlines() {
                     let s = SendType::Event(0, *maddr, d.clone());
@@ -326,7 +398,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "group-delete",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let mut results = HandleResult::new();
 
@@ -1,4 +1,27 @@
 mod models;
 mod rpc;
 
+use tdn::types::rpc::RpcHandler;
+
+use crate::apps::App;
+use crate::migrate::{JARVIS_DB, JARVIS_VERSIONS};
+use crate::rpc::RpcState;
+
 pub(crate) use rpc::new_rpc_handler;
+
+/// jarvis app.
+pub(crate) struct JarvisApp;
+
+impl App for JarvisApp {
+    fn name(&self) -> &'static str {
+        "jarvis"
+    }
+
+    fn db(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some((JARVIS_DB, &JARVIS_VERSIONS))
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+}
@@ -1,12 +1,12 @@
 use std::time::{SystemTime, UNIX_EPOCH};
-use tdn::types::{
-    primitive::Result,
-    rpc::{json, RpcParam},
-};
+use tdn::types::primitive::Result;
 use tdn_storage::local::{DStorage, DsValue};
 
 use chat_types::MessageType;
 
+use crate::apps::search::MessageIndex;
+use crate::utils::sql::Query;
+
 pub(crate) struct Message {
     pub id: i64,
     pub is_me: bool,
@@ -43,19 +43,18 @@ impl Message {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.is_me,
-            self.m_type.to_int(),
-            self.content,
-            self.datetime,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::jarvis::Message {
+        rpc_types::jarvis::Message {
+            id: self.id,
+            is_me: self.is_me,
+            m_type: self.m_type.to_int(),
+            content: self.content.clone(),
+            datetime: self.datetime,
+        }
     }
 
     pub fn list(db: &DStorage) -> Result<Vec<Message>> {
-        let sql = format!("SELECT id, is_me, m_type, content, datetime FROM messages");
-        let matrix = db.query(&sql)?;
+        let matrix = db.query("SELECT id, is_me, m_type, content, datetime FROM messages")?;
         let mut messages = vec![];
         for values in matrix {
             messages.push(Message::from_values(values));
@@ -65,19 +64,21 @@ impl Message {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let sql = format!(
-            "INSERT INTO messages (is_me, m_type, content, datetime) VALUES ({}, {}, '{}',{})",
-            self.is_me,
-            self.m_type.to_int(),
-            self.content,
-            self.datetime,
-        );
-        self.id = db.insert(&sql)?;
-        Ok(())
+        self.id = Query::new(
+            "INSERT INTO messages (is_me, m_type, content, datetime) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&self.is_me)
+        .bind(&self.m_type.to_int())
+        .bind(&self.content)
+        .bind(&self.datetime)
+        .insert(db)?;
+        MessageIndex::insert(db, self.id, &self.m_type, &self.content)
     }
 
     pub fn delete(db: &DStorage, id: i64) -> Result<usize> {
-        let sql = format!("DELETE FROM messages WHERE id = {}", id);
-        db.delete(&sql)
+        MessageIndex::delete(db, &id)?;
+        Query::new("DELETE FROM messages WHERE id = ?")
+            .bind(&id)
+            .delete(db)
     }
 }
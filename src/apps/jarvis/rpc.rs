@@ -1,10 +1,12 @@
 use rand::Rng;
+use rpc_types::jarvis::JarvisCreateParams;
+use rpc_types::Id;
 use std::sync::Arc;
 use tdn::types::{
     group::GroupId,
     message::SendMessage,
     primitive::{HandleResult, Result},
-    rpc::{json, rpc_response, RpcError, RpcHandler, RpcParam},
+    rpc::{json, rpc_response, RpcHandler, RpcParam},
 };
 use tdn_did::Language;
 use tdn_storage::local::DStorage;
@@ -14,7 +16,7 @@ use chat_types::MessageType;
 
 use crate::account::lang_from_i64;
 use crate::apps::chat::raw_to_network_message;
-use crate::rpc::RpcState;
+use crate::rpc::{parse, RpcState};
 use crate::utils::answer::load_answer;
 
 use super::models::Message;
@@ -43,7 +45,7 @@ async fn reply(
     let mut reply = Message::new(msg.m_type, content, false);
     reply.insert(&db)?;
 
-    let res = rpc_response(0, "jarvis-create", reply.to_rpc(), gid);
+    let res = rpc_response(0, "jarvis-create", json!(reply.to_rpc()), gid);
     sender.send(SendMessage::Rpc(0, res, true)).await?;
     Ok(())
 }
@@ -66,9 +68,13 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "jarvis-create",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let lang = lang_from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let m_type = MessageType::from_int(params[1].as_i64().ok_or(RpcError::ParseError)?);
-            let content = params[2].as_str().ok_or(RpcError::ParseError)?;
+            let JarvisCreateParams {
+                lang,
+                m_type,
+                content,
+            } = parse(params)?;
+            let lang = lang_from_i64(lang);
+            let m_type = MessageType::from_int(m_type);
 
             let group_lock = state.group.read().await;
             let base = group_lock.base().clone();
@@ -77,11 +83,11 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             drop(group_lock);
 
             let (_, raw) =
-                raw_to_network_message(&state.group, &base, &gid, &m_type, content).await?;
+                raw_to_network_message(&state.group, &base, &gid, &m_type, &content).await?;
             let mut msg = Message::new(m_type, raw, true);
             msg.insert(&db)?;
 
-            let results = HandleResult::rpc(msg.to_rpc());
+            let results = HandleResult::rpc(json!(msg.to_rpc()));
             tokio::spawn(reply(sender, db, gid, lang, msg));
 
             Ok(results)
@@ -91,7 +97,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "jarvis-delete",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
             let db = state.group.read().await.jarvis_db(&gid)?;
             Message::delete(&db, id)?;
             db.close()?;
@@ -0,0 +1,23 @@
+mod models;
+mod rpc;
+
+use tdn::types::rpc::RpcHandler;
+
+use crate::apps::App;
+use crate::rpc::RpcState;
+
+pub(crate) use models::MessageIndex;
+pub(crate) use rpc::new_rpc_handler;
+
+/// search app.
+pub(crate) struct SearchApp;
+
+impl App for SearchApp {
+    fn name(&self) -> &'static str {
+        "search"
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+}
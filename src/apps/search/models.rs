@@ -0,0 +1,196 @@
+use tdn::types::primitive::Result;
+use tdn_storage::local::{DStorage, DsValue};
+
+use chat_types::MessageType;
+
+use crate::utils::sql::Query;
+
+/// Default max hits returned by once search.
+pub(crate) const SEARCH_LIMIT: i64 = 50;
+
+/// Which database the hit come from.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) enum SearchSource {
+    Chat,
+    Group,
+    Jarvis,
+}
+
+impl SearchSource {
+    pub fn to_int(&self) -> i64 {
+        match self {
+            SearchSource::Chat => 0,
+            SearchSource::Group => 1,
+            SearchSource::Jarvis => 2,
+        }
+    }
+
+    pub fn from_int(i: i64) -> Option<Self> {
+        match i {
+            0 => Some(SearchSource::Chat),
+            1 => Some(SearchSource::Group),
+            2 => Some(SearchSource::Jarvis),
+            _ => None,
+        }
+    }
+}
+
+/// Full-text index of the `messages` table. It is a fts5 table in the same
+/// database as the messages, rowid is the message's db id.
+pub(crate) struct MessageIndex;
+
+impl MessageIndex {
+    /// only text content can be searched.
+    fn indexable(m_type: &MessageType) -> bool {
+        match m_type {
+            MessageType::String | MessageType::File | MessageType::Transfer => true,
+            _ => false,
+        }
+    }
+
+    pub fn insert(db: &DStorage, id: i64, m_type: &MessageType, content: &str) -> Result<()> {
+        if !Self::indexable(m_type) {
+            return Ok(());
+        }
+        Query::new("INSERT INTO message_index (rowid, content) VALUES (?, ?)")
+            .bind(&id)
+            .bind(content)
+            .insert(db)?;
+        Ok(())
+    }
+
+    /// re-index the message after content changed.
+    pub fn update(db: &DStorage, id: i64, m_type: &MessageType, content: &str) -> Result<()> {
+        Self::delete(db, &id)?;
+        Self::insert(db, id, m_type, content)
+    }
+
+    pub fn delete(db: &DStorage, id: &i64) -> Result<usize> {
+        Query::new("DELETE FROM message_index WHERE rowid = ?")
+            .bind(id)
+            .delete(db)
+    }
+
+    /// delete all session's (friend or group) messages index.
+    pub fn delete_by_fid(db: &DStorage, fid: &i64) -> Result<usize> {
+        Query::new(
+            "DELETE FROM message_index WHERE rowid IN (SELECT id FROM messages WHERE fid = ?)",
+        )
+        .bind(fid)
+        .delete(db)
+    }
+
+    /// build fts5 match expression, every word is a prefix phrase, so user's
+    /// input never be parsed as fts5 syntax.
+    fn match_expr(query: &str) -> Option<String> {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|w| format!("\"{}\"*", w.replace("\"", "\"\"")))
+            .collect();
+        if terms.len() == 0 {
+            None
+        } else {
+            Some(terms.join(" "))
+        }
+    }
+}
+
+/// Search result.
+pub(crate) struct SearchHit {
+    pub source: SearchSource,
+    /// session's db id.
+    pub sid: i64,
+    /// friend's or group chat's db id. jarvis is 0.
+    pub fid: i64,
+    /// friend or group member's db id. jarvis is 0.
+    pub mid: i64,
+    /// friend or group member's name.
+    pub name: String,
+    /// message's db id.
+    pub id: i64,
+    pub is_me: bool,
+    pub m_type: MessageType,
+    pub snippet: String,
+    pub datetime: i64,
+}
+
+impl SearchHit {
+    pub fn to_rpc(&self) -> rpc_types::search::SearchHit {
+        rpc_types::search::SearchHit {
+            source: self.source.to_int(),
+            sid: self.sid,
+            fid: self.fid,
+            mid: self.mid,
+            name: self.name.clone(),
+            id: self.id,
+            is_me: self.is_me,
+            m_type: self.m_type.to_int(),
+            snippet: self.snippet.clone(),
+            datetime: self.datetime,
+        }
+    }
+
+    /// here is zero-copy and unwrap is safe. checked.
+    fn from_values(source: SearchSource, mut v: Vec<DsValue>) -> SearchHit {
+        SearchHit {
+            datetime: v.pop().unwrap().as_i64(),
+            snippet: v.pop().unwrap().as_string(),
+            m_type: MessageType::from_int(v.pop().unwrap().as_i64()),
+            is_me: v.pop().unwrap().as_bool(),
+            id: v.pop().unwrap().as_i64(),
+            name: v.pop().unwrap().as_string(),
+            mid: v.pop().unwrap().as_i64(),
+            fid: v.pop().unwrap().as_i64(),
+            sid: 0,
+            source,
+        }
+    }
+
+    /// search chat.db, fid is friend's id, mid is friend's id too.
+    pub fn chat(db: &DStorage, query: &str, limit: i64) -> Result<Vec<SearchHit>> {
+        let expr = if let Some(expr) = MessageIndex::match_expr(query) {
+            expr
+        } else {
+            return Ok(vec![]);
+        };
+        let sql = Query::new("SELECT messages.fid, messages.fid, IFNULL(friends.name, ''), messages.id, messages.is_me, messages.m_type, snippet(message_index, 0, '[', ']', '...', 12), messages.datetime FROM message_index JOIN messages ON messages.id = message_index.rowid LEFT JOIN friends ON friends.id = messages.fid WHERE message_index MATCH ? ORDER BY messages.datetime DESC LIMIT ?")
+            .bind(&expr)
+            .bind(&limit);
+        Self::query(db, SearchSource::Chat, sql)
+    }
+
+    /// search group.db, fid is group chat's id, mid is member's id.
+    pub fn group(db: &DStorage, query: &str, limit: i64) -> Result<Vec<SearchHit>> {
+        let expr = if let Some(expr) = MessageIndex::match_expr(query) {
+            expr
+        } else {
+            return Ok(vec![]);
+        };
+        let sql = Query::new("SELECT messages.fid, messages.mid, IFNULL(members.name, ''), messages.id, messages.is_me, messages.m_type, snippet(message_index, 0, '[', ']', '...', 12), messages.datetime FROM message_index JOIN messages ON messages.id = message_index.rowid LEFT JOIN members ON members.id = messages.mid WHERE message_index MATCH ? ORDER BY messages.datetime DESC LIMIT ?")
+            .bind(&expr)
+            .bind(&limit);
+        Self::query(db, SearchSource::Group, sql)
+    }
+
+    /// search jarvis.db.
+    pub fn jarvis(db: &DStorage, query: &str, limit: i64) -> Result<Vec<SearchHit>> {
+        let expr = if let Some(expr) = MessageIndex::match_expr(query) {
+            expr
+        } else {
+            return Ok(vec![]);
+        };
+        let sql = Query::new("SELECT 0, 0, '', messages.id, messages.is_me, messages.m_type, snippet(message_index, 0, '[', ']', '...', 12), messages.datetime FROM message_index JOIN messages ON messages.id = message_index.rowid WHERE message_index MATCH ? ORDER BY messages.datetime DESC LIMIT ?")
+            .bind(&expr)
+            .bind(&limit);
+        Self::query(db, SearchSource::Jarvis, sql)
+    }
+
+    fn query(db: &DStorage, source: SearchSource, sql: Query) -> Result<Vec<SearchHit>> {
+        let matrix = sql.query(db)?;
+        let mut hits = vec![];
+        for values in matrix {
+            hits.push(SearchHit::from_values(source, values));
+        }
+        Ok(hits)
+    }
+}
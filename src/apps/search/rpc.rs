@@ -0,0 +1,95 @@
+use rpc_types::search::SearchParams;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tdn::types::{
+    group::GroupId,
+    primitive::HandleResult,
+    rpc::{json, RpcHandler, RpcParam},
+};
+
+use crate::rpc::{parse, RpcState};
+use crate::session::{Session, SessionType};
+
+use super::models::{SearchHit, SearchSource, SEARCH_LIMIT};
+
+#[inline]
+fn hit_list(hits: Vec<SearchHit>) -> RpcParam {
+    let mut results = vec![];
+    for hit in hits {
+        results.push(hit.to_rpc());
+    }
+    json!(results)
+}
+
+pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
+    handler.add_method(
+        "search-messages",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let SearchParams {
+                query,
+                sources,
+                limit,
+            } = parse(params)?;
+            // optional: search sources, default is all.
+            let sources: Vec<SearchSource> = if let Some(s) = sources {
+                s.into_iter().filter_map(SearchSource::from_int).collect()
+            } else {
+                vec![
+                    SearchSource::Chat,
+                    SearchSource::Group,
+                    SearchSource::Jarvis,
+                ]
+            };
+            // optional: max hits number.
+            let limit = limit.unwrap_or(SEARCH_LIMIT);
+
+            let group_lock = state.group.read().await;
+            let mut hits = vec![];
+            for source in sources {
+                match source {
+                    SearchSource::Chat => {
+                        let db = group_lock.chat_db(&gid)?;
+                        hits.extend(SearchHit::chat(&db, &query, limit)?);
+                        db.close()?;
+                    }
+                    SearchSource::Group => {
+                        let db = group_lock.group_db(&gid)?;
+                        hits.extend(SearchHit::group(&db, &query, limit)?);
+                        db.close()?;
+                    }
+                    SearchSource::Jarvis => {
+                        let db = group_lock.jarvis_db(&gid)?;
+                        hits.extend(SearchHit::jarvis(&db, &query, limit)?);
+                        db.close()?;
+                    }
+                }
+            }
+
+            // bind the hits to sessions.
+            let s_db = group_lock.session_db(&gid)?;
+            drop(group_lock);
+            let mut sessions: HashMap<(i64, i64), i64> = HashMap::new();
+            for session in Session::list(&s_db)? {
+                sessions.insert((session.s_type.to_int(), session.fid), session.id);
+            }
+            drop(s_db);
+
+            for hit in hits.iter_mut() {
+                let s_type = match hit.source {
+                    SearchSource::Chat => SessionType::Chat,
+                    SearchSource::Group => SessionType::Group,
+                    SearchSource::Jarvis => SessionType::Jarvis,
+                };
+                hit.sid = sessions
+                    .get(&(s_type.to_int(), hit.fid))
+                    .cloned()
+                    .unwrap_or(0);
+            }
+
+            hits.sort_by(|a, b| b.datetime.cmp(&a.datetime));
+            hits.truncate(limit as usize);
+
+            Ok(HandleResult::rpc(hit_list(hits)))
+        },
+    );
+}
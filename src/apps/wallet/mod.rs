@@ -1,6 +1,12 @@
 mod models;
 mod rpc;
 
+use tdn::types::rpc::RpcHandler;
+
+use crate::apps::App;
+use crate::migrate::{WALLET_DB, WALLET_VERSIONS};
+use crate::rpc::RpcState;
+
 pub(crate) use rpc::new_rpc_handler;
 
 pub(crate) const ERC20_ABI: &'static str = r#"
@@ -567,3 +573,20 @@ pub(crate) const ERC721_ABI: &'static str = r#"
     }
 ]
 "#;
+
+/// wallet app.
+pub(crate) struct WalletApp;
+
+impl App for WalletApp {
+    fn name(&self) -> &'static str {
+        "wallet"
+    }
+
+    fn db(&self) -> Option<(&'static str, &'static [&'static str])> {
+        Some((WALLET_DB, &WALLET_VERSIONS))
+    }
+
+    fn rpc_inject(&self, handler: &mut RpcHandler<RpcState>) {
+        new_rpc_handler(handler);
+    }
+}
@@ -1,11 +1,10 @@
 use std::collections::HashMap;
-use tdn::types::{
-    primitive::Result,
-    rpc::{json, RpcParam},
-};
+use tdn::types::primitive::Result;
 
 use tdn_storage::local::{DStorage, DsValue};
 
+use crate::utils::sql::Query;
+
 #[rustfmt::skip]
 pub const ETH_NODE: &'static str =
     "https://mainnet.infura.io/v3/9aa3d95b3bc440fa88ea12eaa4456161";
@@ -226,17 +225,17 @@ impl Address {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.chain.to_i64(),
-            self.index,
-            self.name,
-            self.address,
-            self.is_gen(),
-            self.main,
-            self.balance,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::wallet::Address {
+        rpc_types::wallet::Address {
+            id: self.id,
+            chain: self.chain.to_i64(),
+            index: self.index,
+            name: self.name.clone(),
+            address: self.address.clone(),
+            is_gen: self.is_gen(),
+            main: self.main,
+            balance: self.balance.clone(),
+        }
     }
 
     fn from_values(mut v: Vec<DsValue>) -> Self {
@@ -253,26 +252,25 @@ impl Address {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let matrix = db.query(&format!(
-            "SELECT id FROM addresses WHERE chain = {} AND address = '{}'",
-            self.chain.to_i64(),
-            self.address
-        ))?;
+        let matrix = Query::new("SELECT id FROM addresses WHERE chain = ? AND address = ?")
+            .bind(&self.chain.to_i64())
+            .bind(&self.address)
+            .query(db)?;
         if matrix.len() > 0 {
             return Ok(());
         }
 
-        let sql = format!(
-            "INSERT INTO addresses (chain, indx, main, name, address, secret, balance) VALUES ({}, {}, {}, '{}', '{}', '{}', '{}')",
-            self.chain.to_i64(),
-            self.index,
-            self.main,
-            self.name,
-            self.address,
-            base64::encode(&self.secret),
-            self.balance,
-        );
-        let id = db.insert(&sql)?;
+        let id = Query::new(
+            "INSERT INTO addresses (chain, indx, main, name, address, secret, balance) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.chain.to_i64())
+        .bind(&self.index)
+        .bind(&self.main)
+        .bind(&self.name)
+        .bind(&self.address)
+        .bind(&base64::encode(&self.secret))
+        .bind(&self.balance)
+        .insert(db)?;
         self.id = id;
         Ok(())
     }
@@ -288,10 +286,11 @@ impl Address {
     }
 
     pub fn get(db: &DStorage, id: &i64) -> Result<Self> {
-        let mut matrix = db.query(&format!(
-            "SELECT id, chain, indx, main, name, address, secret, balance FROM addresses WHERE id = {}",
-            id
-        ))?;
+        let mut matrix = Query::new(
+            "SELECT id, chain, indx, main, name, address, secret, balance FROM addresses WHERE id = ?",
+        )
+        .bind(id)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -300,10 +299,11 @@ impl Address {
     }
 
     pub fn get_by_address(db: &DStorage, address: &str) -> Result<Self> {
-        let mut matrix = db.query(&format!(
-            "SELECT id, chain, indx, main, name, address, secret, balance FROM addresses WHERE address = '{}'",
-            address
-        ))?;
+        let mut matrix = Query::new(
+            "SELECT id, chain, indx, main, name, address, secret, balance FROM addresses WHERE address = ?",
+        )
+        .bind(address)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -312,10 +312,11 @@ impl Address {
     }
 
     pub fn next_index(db: &DStorage, chain: &ChainToken) -> Result<u32> {
-        let mut matrix = db.query(&format!(
-            "SELECT indx FROM addresses where chain = {} AND secret = '' ORDER BY indx ASC",
-            chain.to_i64()
-        ))?;
+        let mut matrix = Query::new(
+            "SELECT indx FROM addresses where chain = ? AND secret = '' ORDER BY indx ASC",
+        )
+        .bind(&chain.to_i64())
+        .query(db)?;
         if matrix.len() > 0 {
             let mut values = matrix.pop().unwrap(); // safe unwrap()
             let index = values.pop().unwrap().as_i64() as u32; // safe unwrap()
@@ -331,20 +332,18 @@ impl Address {
         network: &Network,
         balance: &str,
     ) -> Result<()> {
-        let mut matrix = db.query(&format!(
-            "SELECT balance FROM addresses where address = '{}'",
-            address
-        ))?;
+        let mut matrix = Query::new("SELECT balance FROM addresses where address = ?")
+            .bind(address)
+            .query(db)?;
         if matrix.len() > 0 {
             let mut values = matrix.pop().unwrap(); // safe unwrap()
             let old = values.pop().unwrap(); // safe unwrap()
             let new_b = Address::merge_balance(old.as_str(), network, balance);
 
-            let sql = format!(
-                "UPDATE addresses SET balance = '{}' WHERE address = '{}'",
-                new_b, address
-            );
-            db.update(&sql)?;
+            Query::new("UPDATE addresses SET balance = ? WHERE address = ?")
+                .bind(&new_b)
+                .bind(address)
+                .update(db)?;
         }
 
         Ok(())
@@ -352,16 +351,16 @@ impl Address {
 
     pub fn main(db: &DStorage, id: &i64) -> Result<()> {
         db.update("UPDATE addresses SET main = false")?;
-        db.update(&format!(
-            "UPDATE addresses SET main = true WHERE id = {}",
-            id
-        ))?;
+        Query::new("UPDATE addresses SET main = true WHERE id = ?")
+            .bind(id)
+            .update(db)?;
         Ok(())
     }
 
     pub fn _delete(db: &DStorage, id: &i64) -> Result<()> {
-        let sql = format!("DELETE FROM addresses WHERE id = {}", id);
-        db.delete(&sql)?;
+        Query::new("DELETE FROM addresses WHERE id = ?")
+            .bind(id)
+            .delete(db)?;
         Balance::delete_by_address(db, id)?;
         Ok(())
     }
@@ -394,15 +393,15 @@ impl Token {
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.chain.to_i64(),
-            self.network.to_i64(),
-            self.name,
-            self.contract,
-            self.decimal,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::wallet::Token {
+        rpc_types::wallet::Token {
+            id: self.id,
+            chain: self.chain.to_i64(),
+            network: self.network.to_i64(),
+            name: self.name.clone(),
+            contract: self.contract.clone(),
+            decimal: self.decimal,
+        }
     }
 
     fn from_values(mut v: Vec<DsValue>) -> Self {
@@ -417,34 +416,34 @@ impl Token {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let matrix = db.query(&format!(
-            "SELECT id FROM tokens WHERE network = {} AND contract = '{}'",
-            self.network.to_i64(),
-            self.contract
-        ))?;
+        let matrix = Query::new("SELECT id FROM tokens WHERE network = ? AND contract = ?")
+            .bind(&self.network.to_i64())
+            .bind(&self.contract)
+            .query(db)?;
         if matrix.len() > 0 {
             return Ok(());
         }
 
         // check exists
-        let sql = format!(
-            "INSERT INTO tokens (chain, network, name, contract, decimal) VALUES ({}, {}, '{}', '{}', {})",
-            self.chain.to_i64(),
-            self.network.to_i64(),
-            self.name,
-            self.contract,
-            self.decimal,
-        );
-        let id = db.insert(&sql)?;
+        let id = Query::new(
+            "INSERT INTO tokens (chain, network, name, contract, decimal) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&self.chain.to_i64())
+        .bind(&self.network.to_i64())
+        .bind(&self.name)
+        .bind(&self.contract)
+        .bind(&self.decimal)
+        .insert(db)?;
         self.id = id;
         Ok(())
     }
 
     pub fn list(db: &DStorage, network: &Network) -> Result<Vec<Self>> {
-        let matrix = db.query(&format!(
-            "SELECT id, chain, network, name, contract, decimal FROM tokens where network = {}",
-            network.to_i64()
-        ))?;
+        let matrix = Query::new(
+            "SELECT id, chain, network, name, contract, decimal FROM tokens where network = ?",
+        )
+        .bind(&network.to_i64())
+        .query(db)?;
         let mut tokens = vec![];
         for values in matrix {
             tokens.push(Self::from_values(values));
@@ -453,10 +452,11 @@ impl Token {
     }
 
     pub fn get(db: &DStorage, id: &i64) -> Result<Self> {
-        let mut matrix = db.query(&format!(
-            "SELECT id, chain, network, name, contract, decimal FROM tokens where id = {}",
-            id
-        ))?;
+        let mut matrix = Query::new(
+            "SELECT id, chain, network, name, contract, decimal FROM tokens where id = ?",
+        )
+        .bind(id)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -465,10 +465,12 @@ impl Token {
     }
 
     pub fn get_by_contract(db: &DStorage, network: &Network, c: &str) -> Result<Self> {
-        let mut matrix = db.query(&format!(
-            "SELECT id, chain, network, name, contract, decimal FROM tokens WHERE network = {} AND contract = '{}'",
-            network.to_i64(), c
-        ))?;
+        let mut matrix = Query::new(
+            "SELECT id, chain, network, name, contract, decimal FROM tokens WHERE network = ? AND contract = ?",
+        )
+        .bind(&network.to_i64())
+        .bind(c)
+        .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -477,8 +479,9 @@ impl Token {
     }
 
     pub fn _delete(db: &DStorage, id: &i64) -> Result<()> {
-        let sql = format!("DELETE FROM tokens WHERE id = {}", id);
-        db.delete(&sql)?;
+        Query::new("DELETE FROM tokens WHERE id = ?")
+            .bind(id)
+            .delete(db)?;
         Balance::delete_by_token(db, id)?;
         Ok(())
     }
@@ -510,10 +513,12 @@ impl Balance {
     }
 
     pub fn list(db: &DStorage, address: &i64, token: &i64) -> Result<Vec<Self>> {
-        let matrix = db.query(&format!(
-            "SELECT id, address, token, value FROM balances WHERE address = {} AND token = {}",
-            address, token
-        ))?;
+        let matrix = Query::new(
+            "SELECT id, address, token, value FROM balances WHERE address = ? AND token = ?",
+        )
+        .bind(address)
+        .bind(token)
+        .query(db)?;
         let mut balances = vec![];
         for values in matrix {
             balances.push(Self::from_values(values));
@@ -523,33 +528,35 @@ impl Balance {
 
     /// use for common and erc20.
     pub fn update(db: &DStorage, address: &i64, token: &i64, value: &str) -> Result<()> {
-        let matrix = db.query(&format!(
-            "SELECT id FROM balances WHERE address = {} AND token = {}",
-            address, token,
-        ))?;
+        let matrix = Query::new("SELECT id FROM balances WHERE address = ? AND token = ?")
+            .bind(address)
+            .bind(token)
+            .query(db)?;
         if matrix.len() > 0 {
-            let sql = format!(
-                "UPDATE balances SET value = '{}' WHERE address = {} AND token = {}",
-                value, address, token
-            );
-            db.update(&sql)?;
+            Query::new("UPDATE balances SET value = ? WHERE address = ? AND token = ?")
+                .bind(value)
+                .bind(address)
+                .bind(token)
+                .update(db)?;
             return Ok(());
         }
 
-        let sql = format!(
-            "INSERT INTO balances (address, token, value) VALUES ({}, {}, '{}')",
-            address, token, value,
-        );
-        let _id = db.insert(&sql)?;
+        let _id = Query::new("INSERT INTO balances (address, token, value) VALUES (?, ?, ?)")
+            .bind(address)
+            .bind(token)
+            .bind(value)
+            .insert(db)?;
         Ok(())
     }
 
     /// use for erc721 (NFT).
     pub fn add(db: &DStorage, address: i64, token: i64, value: String) -> Result<Self> {
-        let mut matrix = db.query(&format!(
-            "SELECT id FROM balances WHERE address = {} AND token = {} AND value = '{}'",
-            address, token, value
-        ))?;
+        let mut matrix =
+            Query::new("SELECT id FROM balances WHERE address = ? AND token = ? AND value = ?")
+                .bind(&address)
+                .bind(&token)
+                .bind(&value)
+                .query(db)?;
         if matrix.len() > 0 {
             let id = matrix.pop().unwrap().pop().unwrap().as_i64(); // safe unwrap()
             return Ok(Self {
@@ -560,11 +567,11 @@ impl Balance {
             });
         }
 
-        let sql = format!(
-            "INSERT INTO balances (address, token, value) VALUES ({}, {}, '{}')",
-            address, token, value
-        );
-        let id = db.insert(&sql)?;
+        let id = Query::new("INSERT INTO balances (address, token, value) VALUES (?, ?, ?)")
+            .bind(&address)
+            .bind(&token)
+            .bind(&value)
+            .insert(db)?;
         Ok(Self {
             id,
             address,
@@ -574,10 +581,9 @@ impl Balance {
     }
 
     pub fn _get(db: &DStorage, id: &i64) -> Result<Self> {
-        let mut matrix = db.query(&format!(
-            "SELECT id, address, token, value FROM balances where id = {}",
-            id
-        ))?;
+        let mut matrix = Query::new("SELECT id, address, token, value FROM balances where id = ?")
+            .bind(id)
+            .query(db)?;
         if matrix.len() > 0 {
             let values = matrix.pop().unwrap(); // safe unwrap()
             return Ok(Self::from_values(values));
@@ -586,20 +592,23 @@ impl Balance {
     }
 
     pub fn delete_by_hash(db: &DStorage, hash: &str) -> Result<()> {
-        let sql = format!("DELETE FROM balances WHERE value = '{}'", hash);
-        db.delete(&sql)?;
+        Query::new("DELETE FROM balances WHERE value = ?")
+            .bind(hash)
+            .delete(db)?;
         Ok(())
     }
 
     pub fn delete_by_address(db: &DStorage, address: &i64) -> Result<()> {
-        let sql = format!("DELETE FROM balances WHERE address = {}", address);
-        db.delete(&sql)?;
+        Query::new("DELETE FROM balances WHERE address = ?")
+            .bind(address)
+            .delete(db)?;
         Ok(())
     }
 
     pub fn delete_by_token(db: &DStorage, token: &i64) -> Result<()> {
-        let sql = format!("DELETE FROM balances WHERE token = {}", token);
-        db.delete(&sql)?;
+        Query::new("DELETE FROM balances WHERE token = ?")
+            .bind(token)
+            .delete(db)?;
         Ok(())
     }
 }
@@ -1,3 +1,9 @@
+use rpc_types::wallet::{
+    GasPrice, Nft, Nfts, TokenList, Transferred, WalletBalance, WalletGasPriceParams,
+    WalletGenerateParams, WalletImportParams, WalletNftParams, WalletTokenImportParams,
+    WalletTokenParams, WalletTransferParams,
+};
+use rpc_types::Id;
 use std::sync::Arc;
 use tdn::types::{
     group::GroupId,
@@ -16,7 +22,7 @@ use web3::{
     Web3,
 };
 
-use crate::rpc::RpcState;
+use crate::rpc::{parse, RpcState};
 
 use super::{
     models::{Address, Balance, ChainToken, Network, Token},
@@ -34,11 +40,10 @@ fn wallet_list(wallets: Vec<Address>) -> RpcParam {
 
 #[inline]
 fn token_list(network: Network, tokens: Vec<Token>) -> RpcParam {
-    let mut results = vec![];
-    for token in tokens {
-        results.push(token.to_rpc());
-    }
-    json!([network.to_i64(), results])
+    json!(TokenList {
+        network: network.to_i64(),
+        tokens: tokens.iter().map(|t| t.to_rpc()).collect(),
+    })
 }
 
 #[inline]
@@ -49,21 +54,13 @@ fn res_balance(
     balance: &str,
     token: Option<&Token>,
 ) -> RpcParam {
-    if let Some(t) = token {
-        rpc_response(
-            0,
-            "wallet-balance",
-            json!([address, network.to_i64(), balance, t.to_rpc()]),
-            gid,
-        )
-    } else {
-        rpc_response(
-            0,
-            "wallet-balance",
-            json!([address, network.to_i64(), balance]),
-            gid,
-        )
-    }
+    let data = json!(WalletBalance {
+        address: address.to_owned(),
+        network: network.to_i64(),
+        balance: balance.to_owned(),
+        token: token.map(|t| t.to_rpc()),
+    });
+    rpc_response(0, "wallet-balance", data, gid)
 }
 
 async fn loop_token(
@@ -337,11 +334,11 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "wallet-generate",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let chain = ChainToken::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let lock = params[1].as_str().ok_or(RpcError::ParseError)?;
+            let WalletGenerateParams { chain, lock } = parse(params)?;
+            let chain = ChainToken::from_i64(chain);
 
             let group_lock = state.group.read().await;
-            let mnemonic = group_lock.mnemonic(&gid, lock)?;
+            let mnemonic = group_lock.mnemonic(&gid, &lock)?;
             let account = group_lock.account(&gid)?;
             let lang = account.lang();
             let pass = account.pass.to_string();
@@ -371,7 +368,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             };
 
             address.insert(&db)?;
-            results.rpcs.push(address.to_rpc());
+            results.rpcs.push(json!(address.to_rpc()));
             if address.main {
                 let mut group_lock = state.group.write().await;
                 let a_db = group_lock.account_db()?;
@@ -392,37 +389,43 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "wallet-import",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let chain = ChainToken::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let secret = params[1].as_str().ok_or(RpcError::ParseError)?;
-            let lock = params[2].as_str().ok_or(RpcError::ParseError)?;
+            let WalletImportParams {
+                chain,
+                secret,
+                lock,
+            } = parse(params)?;
+            let chain = ChainToken::from_i64(chain);
 
             let sk: SecretKey = secret.parse().or(Err(RpcError::ParseError))?;
             let addr = format!("{:?}", (&sk).address());
 
             let group_lock = state.group.read().await;
-            let cbytes = group_lock.encrypt(&gid, lock, sk.as_ref())?;
+            let cbytes = group_lock.encrypt(&gid, &lock, sk.as_ref())?;
             let db = group_lock.wallet_db(&gid)?;
             drop(group_lock);
 
             let mut address = Address::import(chain, addr, cbytes);
             address.insert(&db)?;
-            Ok(HandleResult::rpc(address.to_rpc()))
+            Ok(HandleResult::rpc(json!(address.to_rpc())))
         },
     );
 
     handler.add_method(
         "wallet-token",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let network = Network::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let address = params[1].as_str().ok_or(RpcError::ParseError)?.to_owned();
+            let WalletTokenParams {
+                network,
+                address,
+                token,
+            } = parse(params)?;
+            let network = Network::from_i64(network);
 
             let group_lock = state.group.read().await;
             let db = group_lock.wallet_db(&gid)?;
             let sender = group_lock.sender();
             drop(group_lock);
 
-            let c_str = if params.len() == 4 {
-                let cid = params[2].as_i64().ok_or(RpcError::ParseError)?;
+            let c_str = if let Some(cid) = token {
                 let token = Token::get(&db, &cid)?;
                 Some(token)
             } else {
@@ -438,10 +441,14 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "wallet-token-import",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let chain = ChainToken::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let network = Network::from_i64(params[1].as_i64().ok_or(RpcError::ParseError)?);
-            let address = params[2].as_str().ok_or(RpcError::ParseError)?.to_owned();
-            let c_str = params[3].as_str().ok_or(RpcError::ParseError)?.to_owned();
+            let WalletTokenImportParams {
+                chain,
+                network,
+                address,
+                contract: c_str,
+            } = parse(params)?;
+            let chain = ChainToken::from_i64(chain);
+            let network = Network::from_i64(network);
 
             let group_lock = state.group.read().await;
             let db = group_lock.wallet_db(&gid)?;
@@ -457,28 +464,36 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "wallet-gas-price",
         |_gid: GroupId, params: Vec<RpcParam>, _state: Arc<RpcState>| async move {
-            let chain = ChainToken::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let network = Network::from_i64(params[1].as_i64().ok_or(RpcError::ParseError)?);
-            let from = params[2].as_str().ok_or(RpcError::ParseError)?;
-            let to = params[3].as_str().ok_or(RpcError::ParseError)?;
-            let amount = params[4].as_str().ok_or(RpcError::ParseError)?;
-            let c_str = params[5].as_str().ok_or(RpcError::ParseError)?;
-
-            let (price, gas) = token_gas(from, to, amount, c_str, &network, &chain).await?;
-            Ok(HandleResult::rpc(json!([price, gas])))
+            let WalletGasPriceParams {
+                chain,
+                network,
+                from,
+                to,
+                amount,
+                contract: c_str,
+            } = parse(params)?;
+            let chain = ChainToken::from_i64(chain);
+            let network = Network::from_i64(network);
+
+            let (price, gas) = token_gas(&from, &to, &amount, &c_str, &network, &chain).await?;
+            Ok(HandleResult::rpc(json!(GasPrice { price, gas })))
         },
     );
 
     handler.add_method(
         "wallet-transfer",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let chain = ChainToken::from_i64(params[0].as_i64().ok_or(RpcError::ParseError)?);
-            let network = Network::from_i64(params[1].as_i64().ok_or(RpcError::ParseError)?);
-            let from = params[2].as_i64().ok_or(RpcError::ParseError)?;
-            let to = params[3].as_str().ok_or(RpcError::ParseError)?;
-            let amount = params[4].as_str().ok_or(RpcError::ParseError)?;
-            let c_str = params[5].as_str().ok_or(RpcError::ParseError)?;
-            let lock = params[6].as_str().ok_or(RpcError::ParseError)?;
+            let WalletTransferParams {
+                chain,
+                network,
+                from,
+                to,
+                amount,
+                contract: c_str,
+                lock,
+            } = parse(params)?;
+            let chain = ChainToken::from_i64(chain);
+            let network = Network::from_i64(network);
 
             let group_lock = state.group.read().await;
             if !group_lock.check_lock(&gid, &lock) {
@@ -488,9 +503,9 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
             let address = Address::get(&db, &from)?;
 
             let (mnemonic, pbytes) = if address.is_gen() {
-                (group_lock.mnemonic(&gid, lock)?, vec![])
+                (group_lock.mnemonic(&gid, &lock)?, vec![])
             } else {
-                let pbytes = group_lock.decrypt(&gid, lock, address.secret.as_ref())?;
+                let pbytes = group_lock.decrypt(&gid, &lock, address.secret.as_ref())?;
                 (String::new(), pbytes)
             };
             let account = group_lock.account(&gid)?;
@@ -528,52 +543,65 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
                 sk
             };
 
-            let hash = token_transfer(&address.address, to, amount, c_str, &sk, &network, &chain)
-                .await
-                .map_err(|e| RpcError::Custom(format!("{:?}", e)))?;
+            let hash = token_transfer(
+                &address.address,
+                &to,
+                &amount,
+                &c_str,
+                &sk,
+                &network,
+                &chain,
+            )
+            .await
+            .map_err(|e| RpcError::Custom(format!("{:?}", e)))?;
 
             // NFT: delete old, add new if needed (between accounts).
-            if let Ok(token) = Token::get_by_contract(&db, &network, c_str) {
+            if let Ok(token) = Token::get_by_contract(&db, &network, &c_str) {
                 if token.chain == ChainToken::ERC721 {
-                    let _ = Balance::delete_by_hash(&db, amount);
+                    let _ = Balance::delete_by_hash(&db, &amount);
 
-                    if let Ok(new) = Address::get_by_address(&db, to) {
-                        let _ = Balance::add(&db, new.id, token.id, amount.to_owned());
+                    if let Ok(new) = Address::get_by_address(&db, &to) {
+                        let _ = Balance::add(&db, new.id, token.id, amount.clone());
                     }
                 }
             }
 
-            Ok(HandleResult::rpc(json!([
+            Ok(HandleResult::rpc(json!(Transferred {
                 from,
-                network.to_i64(),
-                [hash, to],
-            ])))
+                network: network.to_i64(),
+                tx: (hash, to),
+            })))
         },
     );
 
     handler.add_method(
         "wallet-nft",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let address = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let token = params[1].as_i64().ok_or(RpcError::ParseError)?;
+            let WalletNftParams { address, token } = parse(params)?;
 
             let db = state.group.read().await.wallet_db(&gid)?;
             let nfts = Balance::list(&db, &address, &token)?;
 
-            let mut results = vec![];
+            let mut hashes = vec![];
             for nft in nfts {
-                results.push(nft.value);
+                hashes.push(nft.value);
             }
-            Ok(HandleResult::rpc(json!([address, token, results])))
+            Ok(HandleResult::rpc(json!(Nfts {
+                address,
+                token,
+                hashes,
+            })))
         },
     );
 
     handler.add_method(
         "wallet-nft-add",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let address = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let token = params[1].as_i64().ok_or(RpcError::ParseError)?;
-            let hash = params[2].as_str().ok_or(RpcError::ParseError)?.to_owned();
+            let Nft {
+                address,
+                token,
+                hash,
+            } = parse(params)?;
 
             let db = state.group.read().await.wallet_db(&gid)?;
             let t = Token::get(&db, &token)?;
@@ -589,7 +617,11 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
 
             if owner == a.address {
                 let balance = Balance::add(&db, address, token, hash)?;
-                Ok(HandleResult::rpc(json!([address, token, balance.value])))
+                Ok(HandleResult::rpc(json!(Nft {
+                    address,
+                    token,
+                    hash: balance.value,
+                })))
             } else {
                 Err(RpcError::Custom("address is not NFT owner".to_owned()))
             }
@@ -599,7 +631,7 @@ pub(crate) fn new_rpc_handler(handler: &mut RpcHandler<RpcState>) {
     handler.add_method(
         "wallet-main",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
 
             let group_lock = state.group.read().await;
             let db = group_lock.wallet_db(&gid)?;
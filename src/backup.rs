@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use tdn::types::{
+    group::{EventId, GroupId},
+    message::SendMessage,
+    primitive::Result,
+};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncWriteExt},
+    sync::mpsc::Sender,
+};
+
+use crate::migrate::account_db_names;
+use crate::rpc::account_backup_progress;
+use crate::storage::{flush_writes, ACCOUNT_DIRS};
+use crate::utils::crypto::{decrypt_bytes, encrypt_bytes};
+
+/// backup archive magic bytes.
+const BACKUP_MAGIC: &[u8; 8] = b"ESSEBKUP";
+
+/// backup archive current version.
+const BACKUP_VERSION: u32 = 1;
+
+/// file entry is split to frames by the chunk size.
+const FRAME_CHUNK: usize = 1024 * 1024;
+
+/// max frame size in archive, the manifest frame is the biggest.
+const MAX_FRAME: u64 = 16 * 1024 * 1024;
+
+/// the first frame of archive, describe the account and all entries.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    pub gid: GroupId,
+    /// account's data key, all databases encrypted by it.
+    pub key: Vec<u8>,
+    /// account's own consensus height.
+    pub height: u64,
+    /// account's own consensus event.
+    pub event: EventId,
+    /// entries: relative path, size, blake3 hash.
+    pub entries: Vec<(String, u64, [u8; 32])>,
+}
+
+/// the key of backup archive, keyed from account secret.
+pub(crate) fn backup_key(secret: &[u8]) -> [u8; 32] {
+    blake3::derive_key("ESSE account backup", secret)
+}
+
+/// list all files in account's directory.
+async fn list_entries(base: &PathBuf, gid: &GroupId) -> Result<Vec<String>> {
+    let mut path = base.clone();
+    path.push(gid.to_hex());
+
+    let mut entries = vec![];
+    for name in account_db_names() {
+        if path.join(name).exists() {
+            entries.push(name.to_owned());
+        }
+    }
+
+    let mut dirs: Vec<String> = ACCOUNT_DIRS.iter().map(|d| d.to_string()).collect();
+    while let Some(dir) = dirs.pop() {
+        let dir_path = path.join(&dir);
+        if !dir_path.exists() {
+            continue;
+        }
+        let mut reads = fs::read_dir(dir_path).await?;
+        while let Some(entry) = reads.next_entry().await? {
+            let name = format!("{}/{}", dir, entry.file_name().to_string_lossy());
+            if entry.file_type().await?.is_dir() {
+                dirs.push(name);
+            } else {
+                entries.push(name);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// entry path must in account's directory.
+fn entry_path(root: &PathBuf, name: &str) -> Result<PathBuf> {
+    let path = Path::new(name);
+    if name.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(anyhow!("backup entry is invalid"));
+    }
+    Ok(root.join(path))
+}
+
+async fn write_frame(file: &mut fs::File, key: &[u8; 32], bytes: &[u8]) -> Result<()> {
+    let cbytes = encrypt_bytes(key, bytes)?;
+    if cbytes.len() as u64 > MAX_FRAME {
+        return Err(anyhow!("backup frame is too large"));
+    }
+    file.write_all(&(cbytes.len() as u64).to_le_bytes()).await?;
+    file.write_all(&cbytes).await?;
+    Ok(())
+}
+
+/// the frame length is checked before read, the archive maybe broken.
+async fn read_frame(file: &mut fs::File, key: &[u8; 32]) -> Result<Vec<u8>> {
+    let mut len = [0u8; 8];
+    file.read_exact(&mut len).await?;
+    let len = u64::from_le_bytes(len);
+    if len > MAX_FRAME {
+        return Err(anyhow!("backup frame is too large"));
+    }
+    let mut cbytes = vec![0u8; len as usize];
+    file.read_exact(&mut cbytes).await?;
+    decrypt_bytes(key, &cbytes)
+}
+
+/// read the next chunk of file, it is full chunk except the last one.
+async fn read_chunk(file: &mut fs::File, buf: &mut [u8]) -> Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        let size = file.read(&mut buf[n..]).await?;
+        if size == 0 {
+            break;
+        }
+        n += size;
+    }
+    Ok(n)
+}
+
+/// file's size and blake3 hash, read by chunks.
+async fn hash_file(path: &PathBuf) -> Result<(u64, [u8; 32])> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; FRAME_CHUNK];
+    let mut size = 0;
+    loop {
+        let n = read_chunk(&mut file, &mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((size, *hasher.finalize().as_bytes()))
+}
+
+/// the directory of staged import.
+fn staged_path(base: &PathBuf, gid: &GroupId) -> PathBuf {
+    let mut path = base.clone();
+    path.push(format!("{}.import", gid.to_hex()));
+    path
+}
+
+/// export account's databases and files to a encrypted archive.
+pub(crate) async fn export(
+    base: &PathBuf,
+    mut manifest: Manifest,
+    key: &[u8; 32],
+    target: &PathBuf,
+    sender: Sender<SendMessage>,
+) -> Result<()> {
+    let gid = manifest.gid;
+    let mut root = base.clone();
+    root.push(gid.to_hex());
+
+    let names = list_entries(base, &gid).await?;
+    let total = names.len();
+    for name in names {
+        let (size, hash) = hash_file(&root.join(&name)).await?;
+        manifest.entries.push((name, size, hash));
+    }
+
+    let mut file = fs::File::create(target).await?;
+    file.write_all(BACKUP_MAGIC).await?;
+    file.write_all(&BACKUP_VERSION.to_le_bytes()).await?;
+    write_frame(&mut file, key, &bincode::serialize(&manifest)?).await?;
+
+    let mut buf = vec![0u8; FRAME_CHUNK];
+    for (i, (name, size, hash)) in manifest.entries.iter().enumerate() {
+        let mut entry = fs::File::open(root.join(name)).await?;
+        let mut hasher = blake3::Hasher::new();
+        let mut remain = *size;
+        while remain > 0 {
+            let len = remain.min(FRAME_CHUNK as u64) as usize;
+            let n = read_chunk(&mut entry, &mut buf[..len]).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            write_frame(&mut file, key, &buf[..n]).await?;
+            remain -= n as u64;
+        }
+        if remain > 0 || hasher.finalize().as_bytes() != hash {
+            return Err(anyhow!("{} changed when backup", name));
+        }
+        let _ = sender
+            .send(SendMessage::Rpc(
+                0,
+                account_backup_progress(gid, true, i + 1, total),
+                true,
+            ))
+            .await;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+/// extract the archive to the staged directory, all entries checked.
+/// the account's directory is not changed, replaced by `swap`.
+pub(crate) async fn stage(
+    base: &PathBuf,
+    gid: &GroupId,
+    key: &[u8; 32],
+    source: &PathBuf,
+    sender: Sender<SendMessage>,
+) -> Result<Manifest> {
+    let tmp = staged_path(base, gid);
+    if tmp.exists() {
+        fs::remove_dir_all(&tmp).await?;
+    }
+
+    let res = extract(&tmp, gid, key, source, sender).await;
+    if res.is_err() {
+        let _ = fs::remove_dir_all(&tmp).await;
+    }
+    res
+}
+
+async fn extract(
+    tmp: &PathBuf,
+    gid: &GroupId,
+    key: &[u8; 32],
+    source: &PathBuf,
+    sender: Sender<SendMessage>,
+) -> Result<Manifest> {
+    let mut file = fs::File::open(source).await?;
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic).await?;
+    if &magic != BACKUP_MAGIC {
+        return Err(anyhow!("not a backup file"));
+    }
+    let mut version = [0u8; 4];
+    file.read_exact(&mut version).await?;
+    if u32::from_le_bytes(version) > BACKUP_VERSION {
+        return Err(anyhow!("backup version is not supported"));
+    }
+
+    let manifest: Manifest = bincode::deserialize(&read_frame(&mut file, key).await?)?;
+    if &manifest.gid != gid {
+        return Err(anyhow!("backup is not this account"));
+    }
+
+    for dir in ACCOUNT_DIRS {
+        fs::create_dir_all(tmp.join(dir)).await?;
+    }
+
+    let total = manifest.entries.len();
+    for (i, (name, size, hash)) in manifest.entries.iter().enumerate() {
+        let path = entry_path(tmp, name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut entry = fs::File::create(path).await?;
+        let mut hasher = blake3::Hasher::new();
+        let mut remain = *size;
+        while remain > 0 {
+            let bytes = read_frame(&mut file, key).await?;
+            if bytes.len() as u64 != remain.min(FRAME_CHUNK as u64) {
+                return Err(anyhow!("backup entry {} is broken", name));
+            }
+            hasher.update(&bytes);
+            entry.write_all(&bytes).await?;
+            remain -= bytes.len() as u64;
+        }
+        entry.flush().await?;
+        if hasher.finalize().as_bytes() != hash {
+            return Err(anyhow!("backup entry {} is broken", name));
+        }
+        let _ = sender
+            .send(SendMessage::Rpc(
+                0,
+                account_backup_progress(*gid, false, i + 1, total),
+                true,
+            ))
+            .await;
+    }
+
+    Ok(manifest)
+}
+
+/// replace account's directory by the staged, the account must be offline.
+/// the old directory is restored when failure.
+pub(crate) async fn swap(base: &PathBuf, gid: &GroupId) -> Result<()> {
+    flush_writes().await;
+
+    let tmp = staged_path(base, gid);
+    let mut root = base.clone();
+    root.push(gid.to_hex());
+    let mut old = base.clone();
+    old.push(format!("{}.old", gid.to_hex()));
+    if old.exists() {
+        fs::remove_dir_all(&old).await?;
+    }
+
+    fs::rename(&root, &old).await?;
+    if let Err(e) = fs::rename(&tmp, &root).await {
+        fs::rename(&old, &root).await?;
+        return Err(e.into());
+    }
+    if let Err(e) = fs::remove_dir_all(&old).await {
+        warn!("backup old directory remove failure: {}", e);
+    }
+    Ok(())
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tdn::types::{
+    primitive::{Peer, Result},
+    rpc::{json, RpcParam},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::primitives::parse_seeds;
+use crate::rpc::RPC_TOKEN_FILE;
+
+/// default data directory.
+const DEFAULT_DATA_DIR: &'static str = "./.tdn";
+
+/// default http rpc address of daemon.
+const DEFAULT_RPC_ADDR: &'static str = "127.0.0.1:8000";
+
+/// environment of the account's lock pin.
+const LOCK_ENV: &'static str = "ESSE_LOCK";
+
+/// environment of the account's seed words.
+const SEED_ENV: &'static str = "ESSE_MNEMONIC";
+
+/// command names, not taken as `esse [data-dir]`.
+const COMMANDS: [&'static str; 8] = [
+    "help", "run", "account", "peer", "setting", "config", "send", "shutdown",
+];
+
+pub(crate) const USAGE: &'static str = "ESSE daemon
+
+USAGE:
+    esse [data-dir]
+    esse run [--data-dir <dir>] [--ws-addr <addr>] [--p2p-addr <addr>] [--seeds <addr/transport,..>]
+    esse account list
+    esse account create --name <name> [--lock <pin>] [--seed <words>] [--pass <pass>] [--lang <n>] [--index <n>]
+    esse account restore --name <name> [--seed <words>] [--lock <pin>] [--pass <pass>] [--lang <n>] [--addr <peer>] [--count <n>]
+    esse account login --gid <gid> [--lock <pin>]
+    esse peer add <socket> [--transport <quic|tcp>]
+    esse peer list
+    esse peer remove <socket>
+    esse setting list
+    esse setting set <key> <value>
+    esse send --gid <gid> --fid <friend-id> --to <friend-gid> --text <text>
+    esse shutdown
+
+Commands talk to the running daemon by http rpc, use --rpc-addr <addr> to change it (default 127.0.0.1:8000),
+the rpc token is read from <data-dir>/rpc.token, or given by --token <token>.
+The lock pin and seed words are read from ESSE_LOCK and ESSE_MNEMONIC if not given, or else one line each
+from stdin (seed first), keep them out of the shell history.
+Settings (ws_addr, p2p_addr, pin_m_cost, pin_t_cost, pin_p_cost) work after restart, empty value is reset,
+`esse config show|set` are the aliases of `esse setting list|set`,
+environment ESSE_WS_ADDR, ESSE_P2P_ADDR, ESSE_PIN_*_COST and ESSE_SEEDS override them.";
+
+/// Command of daemon binary.
+pub(crate) enum Command {
+    /// start the daemon.
+    Run {
+        data_dir: String,
+        ws_addr: Option<SocketAddr>,
+        p2p_addr: Option<SocketAddr>,
+        seeds: Vec<Peer>,
+    },
+    /// call the rpc method of running daemon.
+    Rpc {
+        client: Client,
+        gid: String,
+        method: &'static str,
+        params: Vec<RpcParam>,
+    },
+    /// create account, the seed generated by daemon if not given.
+    AccountCreate {
+        client: Client,
+        lang: i64,
+        seed: Option<String>,
+        params: Vec<RpcParam>,
+    },
+    Help,
+}
+
+/// `--key value` options and positional arguments.
+struct Options {
+    flags: HashMap<String, String>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options> {
+        let mut flags = HashMap::new();
+        let mut positional = vec![];
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if let Some(key) = arg.strip_prefix("--") {
+                let value = iter.next().ok_or(anyhow!("missing value of --{}", key))?;
+                flags.insert(key.to_owned(), value.to_owned());
+            } else {
+                positional.push(arg.to_owned());
+            }
+        }
+        Ok(Options { flags, positional })
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.flags.get(key).cloned()
+    }
+
+    fn required(&self, key: &str) -> Result<String> {
+        self.get(key).ok_or(anyhow!("missing --{}", key))
+    }
+
+    /// `--key`, or the environment.
+    fn get_or_env(&self, key: &str, env: &str) -> Option<String> {
+        self.get(key).or(std::env::var(env).ok())
+    }
+
+    /// `--key`, or the environment, or a line from stdin.
+    fn secret(&self, key: &str, env: &str) -> Result<String> {
+        if let Some(value) = self.get_or_env(key, env) {
+            return Ok(value);
+        }
+        eprint!("{}: ", key);
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        let value = line.trim_end_matches(&['\r', '\n'][..]);
+        if value.is_empty() {
+            Err(anyhow!("missing --{}", key))
+        } else {
+            Ok(value.to_owned())
+        }
+    }
+
+    fn int(&self, key: &str, default: i64) -> Result<i64> {
+        match self.flags.get(key) {
+            Some(v) => Ok(v.parse()?),
+            None => Ok(default),
+        }
+    }
+
+    fn data_dir(&self) -> String {
+        self.get("data-dir").unwrap_or(DEFAULT_DATA_DIR.to_owned())
+    }
+
+    /// token is `--token`, or the full token in data directory.
+    fn client(&self) -> Result<Client> {
+        let addr = self
+            .get("rpc-addr")
+            .unwrap_or(DEFAULT_RPC_ADDR.to_owned())
+            .parse()?;
+        let token = match self.get("token") {
+            Some(token) => token,
+            None => {
+                let path = PathBuf::from(self.data_dir()).join(RPC_TOKEN_FILE);
+                std::fs::read_to_string(path)
+                    .unwrap_or(String::new())
+                    .lines()
+                    .find_map(|l| l.trim().strip_prefix("full ").map(|t| t.trim().to_owned()))
+                    .ok_or(anyhow!("missing rpc token, use --token or --data-dir"))?
+            }
+        };
+        Ok(Client { addr, token })
+    }
+
+    fn addr(&self, key: &str) -> Result<Option<SocketAddr>> {
+        match self.flags.get(key) {
+            Some(v) => Ok(Some(v.parse()?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Command {
+    pub fn parse(args: Vec<String>) -> Result<Command> {
+        let (cmd, sub) = (
+            args.get(0).map(|s| s.as_str()),
+            args.get(1).map(|s| s.as_str()),
+        );
+        match (cmd, sub) {
+            (None, _) => Ok(Command::Run {
+                data_dir: DEFAULT_DATA_DIR.to_owned(),
+                ws_addr: None,
+                p2p_addr: None,
+                seeds: vec![],
+            }),
+            (Some("help"), _) | (Some("--help"), _) | (Some("-h"), _) => Ok(Command::Help),
+            (Some("run"), _) => {
+                let opts = Options::parse(&args[1..])?;
+                Ok(Command::Run {
+                    data_dir: opts.data_dir(),
+                    ws_addr: opts.addr("ws-addr")?,
+                    p2p_addr: opts.addr("p2p-addr")?,
+                    seeds: parse_seeds(&opts.get("seeds").unwrap_or(String::new())),
+                })
+            }
+            (Some("account"), Some("list")) => {
+                let opts = Options::parse(&args[2..])?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "account-list",
+                    params: vec![],
+                })
+            }
+            (Some("account"), Some("create")) => {
+                let opts = Options::parse(&args[2..])?;
+                Ok(Command::AccountCreate {
+                    client: opts.client()?,
+                    lang: opts.int("lang", 0)?,
+                    seed: opts.get_or_env("seed", SEED_ENV),
+                    params: vec![
+                        json!(opts.get("pass").unwrap_or(String::new())),
+                        json!(opts.required("name")?),
+                        json!(opts.secret("lock", LOCK_ENV)?),
+                        json!(""),
+                        json!(opts.int("index", 0)?),
+                    ],
+                })
+            }
+            (Some("account"), Some("restore")) => {
+                let opts = Options::parse(&args[2..])?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "account-restore",
+                    params: vec![
+                        json!(opts.int("lang", 0)?),
+                        json!(opts.secret("seed", SEED_ENV)?),
+                        json!(opts.get("pass").unwrap_or(String::new())),
+                        json!(opts.required("name")?),
+                        json!(opts.secret("lock", LOCK_ENV)?),
+                        json!(opts.get("addr").unwrap_or(String::new())),
+                        json!(opts.int("count", 1)?),
+                    ],
+                })
+            }
+            (Some("account"), Some("login")) => {
+                let opts = Options::parse(&args[2..])?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "account-login",
+                    params: vec![
+                        json!(opts.required("gid")?),
+                        json!(opts.secret("lock", LOCK_ENV)?),
+                    ],
+                })
+            }
+            (Some("peer"), Some("add")) => {
+                let opts = Options::parse(&args[2..])?;
+                let socket = opts
+                    .positional
+                    .get(0)
+                    .ok_or(anyhow!("usage: esse peer add <socket>"))?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "add-bootstrap",
+                    params: vec![
+                        json!(socket),
+                        json!(opts.get("transport").unwrap_or("quic".to_owned())),
+                    ],
+                })
+            }
+            (Some("peer"), Some("list")) => {
+                let opts = Options::parse(&args[2..])?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "seed-list",
+                    params: vec![],
+                })
+            }
+            (Some("peer"), Some("remove")) => {
+                let opts = Options::parse(&args[2..])?;
+                let socket = opts
+                    .positional
+                    .get(0)
+                    .ok_or(anyhow!("usage: esse peer remove <socket>"))?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "seed-remove",
+                    params: vec![json!(socket)],
+                })
+            }
+            // config is the settings store too.
+            (Some("setting"), Some("list")) | (Some("config"), Some("show")) => {
+                let opts = Options::parse(&args[2..])?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "setting-list",
+                    params: vec![],
+                })
+            }
+            (Some("setting"), Some("set")) | (Some("config"), Some("set")) => {
+                let opts = Options::parse(&args[2..])?;
+                if opts.positional.len() != 2 {
+                    return Err(anyhow!("usage: esse setting set <key> <value>"));
+                }
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "setting-set",
+                    params: vec![json!(opts.positional[0]), json!(opts.positional[1])],
+                })
+            }
+            (Some("shutdown"), _) => {
+                let opts = Options::parse(&args[1..])?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: String::new(),
+                    method: "shutdown",
+                    params: vec![],
+                })
+            }
+            (Some("send"), _) => {
+                let opts = Options::parse(&args[1..])?;
+                Ok(Command::Rpc {
+                    client: opts.client()?,
+                    gid: opts.required("gid")?,
+                    method: "chat-message-create",
+                    params: vec![
+                        json!(opts.required("fid")?.parse::<i64>()?),
+                        json!(opts.required("to")?),
+                        json!(0), // text message.
+                        json!(opts.required("text")?),
+                    ],
+                })
+            }
+            (Some(cmd), _) if COMMANDS.contains(&cmd) => {
+                Err(anyhow!("usage: esse {} <subcommand>, see `esse help`", cmd))
+            }
+            // compatible with `esse [data-dir]`.
+            (Some(path), None) if !path.starts_with('-') => Ok(Command::Run {
+                data_dir: path.to_owned(),
+                ws_addr: None,
+                p2p_addr: None,
+                seeds: vec![],
+            }),
+            _ => Err(anyhow!("unknown command")),
+        }
+    }
+}
+
+/// Http rpc client of running daemon.
+pub(crate) struct Client {
+    addr: SocketAddr,
+    token: String,
+}
+
+impl Client {
+    /// call the rpc method of running daemon, return the result.
+    pub async fn call(&self, gid: &str, method: &str, params: Vec<RpcParam>) -> Result<RpcParam> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "gid": gid,
+            "method": method,
+            "params": params,
+            "token": self.token,
+        })
+        .to_string();
+
+        let mut stream = TcpStream::connect(self.addr).await?;
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.addr,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await?;
+        let mut bytes = vec![];
+        stream.read_to_end(&mut bytes).await?;
+
+        let response = String::from_utf8_lossy(&bytes);
+        let content = response
+            .split_once("\r\n\r\n")
+            .map(|(_, c)| c)
+            .ok_or(anyhow!("rpc response invalid"))?;
+        let mut value: RpcParam = serde_json::from_str(content)?;
+        if !value["error"].is_null() {
+            return Err(anyhow!("{}", value["error"]));
+        }
+        Ok(value["result"].take())
+    }
+}
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use tdn::types::{group::EventId, primitive::Result, rpc::RpcParam};
 use tdn_storage::local::DStorage;
 
+use crate::utils::sql::Query;
+
 #[derive(Serialize, Deserialize)]
 pub(crate) enum SyncModel {
     Request(RpcParam),
@@ -19,16 +21,17 @@ pub(crate) struct Event {
 
 impl Event {
     pub fn contains_hash(db: &DStorage, hash: &EventId) -> Result<bool> {
-        let sql = format!("SELECT id from events WHERE hash = '{}'", hash.to_hex());
-        Ok(db.query(&sql)?.len() > 0)
+        let matrix = Query::new("SELECT id from events WHERE hash = ?")
+            .bind(hash)
+            .query(db)?;
+        Ok(matrix.len() > 0)
     }
 
     pub fn get_nexts(db: &DStorage, id: u64) -> Result<Vec<Event>> {
-        let sql = format!(
-            "SELECT id, hash, db_table, row from events WHERE id >= {} ORDER BY id",
-            id
-        );
-        let matrix = db.query(&sql)?;
+        let matrix =
+            Query::new("SELECT id, hash, db_table, row from events WHERE id >= ? ORDER BY id")
+                .bind(&id)
+                .query(db)?;
         let mut events = vec![];
         for mut values in matrix {
             let row = values.pop().unwrap().as_i64(); // safe
@@ -49,22 +52,9 @@ impl Event {
     }
 
     pub fn get_assign_hash(db: &DStorage, assigns: &Vec<u64>) -> Result<Vec<EventId>> {
-        let sql = if assigns.len() == 1 {
-            format!("SELECT id, hash from events WHERE id = {}", assigns[0])
-        } else {
-            let last = assigns.len() - 1;
-            let mut sql = format!("SELECT id, hash from events WHERE id IN (");
-            for (k, u) in assigns.iter().enumerate() {
-                if last == k {
-                    sql.push_str(&format!("{})", u));
-                } else {
-                    sql.push_str(&format!("{},", u));
-                }
-            }
-            sql
-        };
-
-        let matrix = db.query(&sql)?;
+        let matrix = Query::new("SELECT id, hash from events WHERE id IN (?)")
+            .bind_list(assigns)
+            .query(db)?;
         let mut hashes = vec![];
         for mut values in matrix {
             hashes.push(
@@ -89,35 +79,30 @@ impl Event {
         index: u64,
     ) -> Result<()> {
         // check if height is had.
-        let check_sql = format!("SELECT id from events WHERE id = {}", index);
-        let check_matrix = db.query(&check_sql)?;
+        let check_matrix = Query::new("SELECT id from events WHERE id = ?")
+            .bind(&index)
+            .query(db)?;
         if check_matrix.len() > 0 {
-            let first_sql = format!(
-                "SELECT id from events WHERE id >= {} ORDER BY id DESC",
-                index
-            );
-            let matrix = db.query(&first_sql)?;
+            let matrix = Query::new("SELECT id from events WHERE id >= ? ORDER BY id DESC")
+                .bind(&index)
+                .query(db)?;
             for mut values in matrix {
                 if let Some(id) = values.pop() {
                     let now_id = id.as_i64();
-                    let sql = format!(
-                        "UPDATE events SET id = {} WHERE id = {}",
-                        now_id + 1,
-                        now_id
-                    );
-                    db.update(&sql)?;
+                    Query::new("UPDATE events SET id = ? WHERE id = ?")
+                        .bind(&(now_id + 1))
+                        .bind(&now_id)
+                        .update(db)?;
                 }
             }
         }
 
-        let sql = format!(
-            "INSERT INTO events (id, hash, db_table, row) VALUES ({}, '{}', {}, {})",
-            index,
-            hash.to_hex(),
-            path,
-            row,
-        );
-        db.insert(&sql)?;
+        Query::new("INSERT INTO events (id, hash, db_table, row) VALUES (?, ?, ?, ?)")
+            .bind(&index)
+            .bind(&hash)
+            .bind(&path)
+            .bind(&row)
+            .insert(db)?;
 
         Ok(())
     }
@@ -8,7 +8,10 @@ use std::env::args;
 
 mod account;
 mod apps;
+mod backup;
+mod cli;
 mod consensus;
+mod embed;
 mod event;
 mod group;
 mod layer;
@@ -17,16 +20,91 @@ mod primitives;
 mod rpc;
 mod server;
 mod session;
+mod settings;
 mod storage;
 mod utils;
 
 #[tokio::main]
 async fn main() {
-    let db_path = args().nth(1).unwrap_or("./.tdn".to_owned());
+    let command = match cli::Command::parse(args().skip(1).collect()) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(1);
+        }
+    };
 
-    if std::fs::metadata(&db_path).is_err() {
-        std::fs::create_dir(&db_path).unwrap();
+    if let Err(e) = run(command).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(command: cli::Command) -> anyhow::Result<()> {
+    match command {
+        cli::Command::Run {
+            data_dir,
+            ws_addr,
+            p2p_addr,
+            seeds,
+        } => {
+            if std::fs::metadata(&data_dir).is_err() {
+                std::fs::create_dir(&data_dir)?;
+            }
+            tokio::spawn(shutdown_signal());
+            server::start_with(data_dir, ws_addr, p2p_addr, seeds).await?;
+        }
+        cli::Command::Rpc {
+            client,
+            gid,
+            method,
+            params,
+        } => {
+            let result = client.call(&gid, method, params).await?;
+            println!("{}", result);
+        }
+        cli::Command::AccountCreate {
+            client,
+            lang,
+            seed,
+            params,
+        } => {
+            let seed = match seed {
+                Some(seed) => seed,
+                None => {
+                    let words = client
+                        .call("", "account-generate", vec![lang.into()])
+                        .await?;
+                    let words = words[0].as_str().ok_or(anyhow!("generate seed failure"))?;
+                    println!("Mnemonic: {}", words);
+                    words.to_owned()
+                }
+            };
+            let mut all = vec![lang.into(), seed.into()];
+            all.extend(params);
+            let result = client.call("", "account-create", all).await?;
+            println!("{}", result);
+        }
+        cli::Command::Help => println!("{}", cli::USAGE),
+    }
+    Ok(())
+}
+
+/// SIGINT or SIGTERM, stop the daemon gracefully.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {},
+                _ = term.recv() => {},
+            }
+            server::shutdown();
+            return;
+        }
     }
 
-    let _ = server::start(db_path).await;
+    let _ = tokio::signal::ctrl_c().await;
+    server::shutdown();
 }
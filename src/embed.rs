@@ -0,0 +1,100 @@
+use once_cell::sync::Lazy;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::Mutex;
+use tdn::{
+    prelude::{ReceiveMessage, SendMessage},
+    types::{primitive::Result, rpc::RpcParam},
+};
+use tokio::sync::mpsc::{self, Receiver, Sender};
+
+/// Host callback of rpc responses and notifications (json string).
+/// it is called in the core's threads, the string only valid during the call.
+pub type Callback = extern "C" fn(*const c_char);
+
+/// rpc uid of the embedded host.
+pub(crate) const EMBED_UID: u64 = u64::MAX;
+
+/// the host's callback, none if not embedded.
+static CALLBACK: Lazy<Mutex<Option<Callback>>> = Lazy::new(|| Mutex::new(None));
+
+/// the requests channel to the running core.
+static REQUESTS: Lazy<Mutex<Option<Sender<ReceiveMessage>>>> = Lazy::new(|| Mutex::new(None));
+
+pub(crate) fn set_callback(callback: Option<Callback>) {
+    if let Ok(mut c) = CALLBACK.lock() {
+        *c = callback;
+    }
+}
+
+fn callback() -> Option<Callback> {
+    CALLBACK.lock().ok().and_then(|c| *c)
+}
+
+/// proxy the channels of tdn when embedded, the host's rpc requests merged to
+/// received messages, and the responses and notifications sent to callback.
+pub(crate) fn attach(
+    sender: Sender<SendMessage>,
+    mut recver: Receiver<ReceiveMessage>,
+) -> (Sender<SendMessage>, Receiver<ReceiveMessage>) {
+    if callback().is_none() {
+        return (sender, recver);
+    }
+
+    let (in_send, in_recv) = mpsc::channel::<ReceiveMessage>(1024);
+    let (out_send, mut out_recv) = mpsc::channel::<SendMessage>(1024);
+    if let Ok(mut requests) = REQUESTS.lock() {
+        *requests = Some(in_send.clone());
+    }
+
+    tokio::spawn(async move {
+        while let Some(msg) = recver.recv().await {
+            if in_send.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Some(msg) = out_recv.recv().await {
+            match msg {
+                SendMessage::Rpc(EMBED_UID, param, _) => deliver(&param),
+                SendMessage::Rpc(0, param, true) => {
+                    // broadcast notification.
+                    deliver(&param);
+                    let _ = sender.send(SendMessage::Rpc(0, param, true)).await;
+                }
+                msg => {
+                    if sender.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    (out_send, in_recv)
+}
+
+/// the core stopped, not accept requests.
+pub(crate) fn detach() {
+    if let Ok(mut requests) = REQUESTS.lock() {
+        *requests = None;
+    }
+}
+
+/// submit the rpc request (jsonrpc string) from the host.
+pub(crate) fn request(json: &str) -> Result<()> {
+    let params: RpcParam = serde_json::from_str(json)?;
+    let requests = REQUESTS.lock().ok().and_then(|r| r.clone());
+    let sender = requests.ok_or(anyhow!("core is not running"))?;
+    sender
+        .try_send(ReceiveMessage::Rpc(EMBED_UID, params, true))
+        .map_err(|_| anyhow!("core is busy"))
+}
+
+fn deliver(param: &RpcParam) {
+    if let (Some(callback), Ok(s)) = (callback(), CString::new(param.to_string())) {
+        callback(s.as_ptr());
+    }
+}
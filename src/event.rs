@@ -19,14 +19,21 @@ use crate::consensus::Event as OldEvent;
 use crate::group::{Group, GroupEvent};
 use crate::layer::Layer;
 use crate::migrate::consensus::{
-    ACCOUNT_TABLE_PATH, FILE_TABLE_PATH, FRIEND_TABLE_PATH, MESSAGE_TABLE_PATH, REQUEST_TABLE_PATH,
+    ACCOUNT_TABLE_PATH, APP_TABLE_PATH, FILE_TABLE_PATH, FRIEND_TABLE_PATH,
+    MESSAGE_EDIT_TABLE_PATH, MESSAGE_TABLE_PATH, REACTION_TABLE_PATH, REQUEST_TABLE_PATH,
+    SESSION_TABLE_PATH,
 };
 
 use crate::apps::chat::rpc as chat_rpc;
-use crate::apps::chat::{from_model, Friend, Message, Request};
+use crate::apps::chat::{
+    from_model, mailbox_reshare, Friend, Message, MessageEdit, Reaction, Request,
+};
+use crate::apps::device::{rpc as device_rpc, Device};
 use crate::apps::file::{FileDid, RootDirectory};
 use crate::rpc;
+use crate::session::{Session, SessionType};
 use crate::storage::{delete_avatar_sync, read_avatar_sync, write_avatar_sync};
+use crate::utils::sql::Query;
 
 /// Online state synchronization.
 #[derive(Serialize, Deserialize)]
@@ -79,10 +86,19 @@ pub(crate) enum InnerEvent {
     /// Sesson's friend delete.
     SessionFriendDelete(GroupId),
     /// Session's message create.
-    /// params: f_gid, is_me, message_type,
-    SessionMessageCreate(GroupId, bool, EventId, NetworkMessage),
+    /// params: f_gid, is_me, message_type, replied message.
+    SessionMessageCreate(GroupId, bool, EventId, NetworkMessage, Option<EventId>),
     /// Session's message delete.
     SessionMessageDelete(EventId),
+    /// Session's message edit.
+    /// params: message hash, new content.
+    SessionMessageEdit(EventId, String),
+    /// Session's message reaction add or remove.
+    /// params: message hash, is_me, emoji, is_add.
+    SessionMessageReact(EventId, bool, String, bool),
+    /// Session's messages retention timer.
+    /// params: remote gid, session type, ttl seconds.
+    SessionTtl(GroupId, i64, i64),
     /// create a file.
     /// params: file_id, file_parent_id, file_directory, file_name, file_desc, device_addr.
     FileCreate(FileDid, FileDid, RootDirectory, String, String, PeerId),
@@ -94,6 +110,9 @@ pub(crate) enum InnerEvent {
     FileBackup(FileDid, PeerId),
     /// delete a file.
     FileDelete(FileDid),
+    /// registered app's event, handled by the app.
+    /// params: app name, app's event data.
+    App(String, Vec<u8>),
 }
 
 /// Event that not update status. only change UI.
@@ -103,6 +122,14 @@ pub(crate) enum StatusEvent {
     SessionFriendOnline(GroupId),
     /// Session's friend offline.
     SessionFriendOffline(GroupId),
+    /// Session's messages read up to the message.
+    /// params: f_gid, message hash, is_me (friend read my messages).
+    SessionMessageRead(GroupId, EventId, bool),
+    /// Account's data key rotated in other device, replayed when device connected.
+    /// params: the lost device which removed, the wrapped new key, rotated time.
+    AccountKeyRotated(PeerId, Vec<u8>, i64),
+    /// Account deleted in the device, remove the device.
+    AccountDeleted,
 }
 
 /// event for sync models. use in sync consensus.
@@ -127,8 +154,21 @@ pub(crate) enum SyncEvent {
     /// eid, friend_gid, addr, name, avatar, remark, is_closed
     Friend(EventId, GroupId, PeerId, String, Vec<u8>, String, bool),
     FriendHad(EventId, GroupId),
-    /// eid, friend_gid, msg_id, is_me, message.
-    Message(EventId, GroupId, EventId, bool, NetworkMessage),
+    /// eid, friend_gid, msg_id, is_me, message, replied msg_id.
+    Message(
+        EventId,
+        GroupId,
+        EventId,
+        bool,
+        NetworkMessage,
+        Option<EventId>,
+    ),
+    /// eid, msg_id, new content.
+    MessageEdit(EventId, EventId, String),
+    /// eid, msg_id, is_me, emoji, is_add.
+    MessageReact(EventId, EventId, bool, String, bool),
+    /// eid, remote gid, session type, ttl seconds.
+    SessionTtl(EventId, GroupId, i64, i64),
     None,
 }
 
@@ -331,7 +371,7 @@ impl InnerEvent {
                     return Ok(());
                 }
             }
-            InnerEvent::SessionMessageCreate(rgid, is_me, hash, m) => {
+            InnerEvent::SessionMessageCreate(rgid, is_me, hash, m, parent) => {
                 let db = group.chat_db(&gid)?;
                 if Message::exist(&db, &hash)? {
                     return Ok(());
@@ -343,7 +383,7 @@ impl InnerEvent {
                         let ggid = gid.clone();
                         let fgid = f.gid;
                         let sender = group.sender();
-                        let layer_event = LayerEvent::Message(hash, m.clone());
+                        let layer_event = LayerEvent::Message(hash, m.clone(), parent);
                         tokio::spawn(InnerEvent::direct_layer_session(
                             sender,
                             layer_lock,
@@ -373,6 +413,47 @@ impl InnerEvent {
                     return Ok(());
                 }
             }
+            InnerEvent::SessionMessageEdit(hash, content) => {
+                let db = group.chat_db(&gid)?;
+                if let Ok(mut m) = Message::get_by_hash(&db, &hash) {
+                    let edit = m.edit(&db, content)?;
+                    results
+                        .rpcs
+                        .push(chat_rpc::message_edit(gid, m.id, &m.content));
+                    (MESSAGE_EDIT_TABLE_PATH, edit.id)
+                } else {
+                    return Ok(());
+                }
+            }
+            InnerEvent::SessionMessageReact(hash, is_me, emoji, is_add) => {
+                let db = group.chat_db(&gid)?;
+                if let Ok(m) = Message::get_by_hash(&db, &hash) {
+                    let rid = if is_add {
+                        let mut reaction = Reaction::new(m.id, is_me, emoji.clone());
+                        reaction.insert(&db)?;
+                        reaction.id
+                    } else {
+                        Reaction::remove(&db, &m.id, is_me, &emoji)?
+                            .ok_or(anyhow!("reaction is missing."))?
+                    };
+                    results
+                        .rpcs
+                        .push(chat_rpc::message_react(gid, m.id, is_me, &emoji, is_add));
+                    (REACTION_TABLE_PATH, rid)
+                } else {
+                    return Ok(());
+                }
+            }
+            InnerEvent::SessionTtl(rgid, s_type, ttl) => {
+                let db = group.session_db(&gid)?;
+                let s_type = SessionType::from_int(s_type);
+                if let Ok(sid) = Session::update_ttl_by_gid(&db, &rgid, &s_type, ttl) {
+                    results.rpcs.push(rpc::session_ttl(gid, &sid, ttl));
+                    (SESSION_TABLE_PATH, sid)
+                } else {
+                    return Ok(());
+                }
+            }
             InnerEvent::SessionFriendInfo(rgid, raddr, rname, ravatar) => {
                 let db = group.chat_db(&gid)?;
                 if let Ok(mut f) = Friend::get_id(&db, &rgid) {
@@ -477,6 +558,13 @@ impl InnerEvent {
                 // TODO
                 (FILE_TABLE_PATH, 0)
             }
+            InnerEvent::App(name, data) => {
+                let app = crate::apps::registry()
+                    .get(&name)
+                    .ok_or(anyhow!("app is not registered"))?;
+                let id = app.sync(group, &gid, data, results)?;
+                (APP_TABLE_PATH, id)
+            }
         };
 
         OldEvent::merge(&db, eid, path, id, merge_height)?;
@@ -498,7 +586,7 @@ impl StatusEvent {
         group: &mut Group,
         gid: GroupId,
         addr: PeerId,
-        _results: &mut HandleResult,
+        results: &mut HandleResult,
         layer: &Arc<RwLock<Layer>>,
         _uid: u64,
     ) -> Result<()> {
@@ -526,6 +614,49 @@ impl StatusEvent {
                     });
                 }
             }
+            StatusEvent::SessionMessageRead(rgid, hash, is_me) => {
+                let db = group.chat_db(&gid)?;
+                if let (Ok(f), Ok(m)) =
+                    (Friend::get_id(&db, &rgid), Message::get_by_hash(&db, &hash))
+                {
+                    Message::read(&db, &f.id, &m.id, is_me)?;
+                    results
+                        .rpcs
+                        .push(chat_rpc::message_read(gid, f.id, m.id, is_me));
+                }
+            }
+            StatusEvent::AccountKeyRotated(lost, wrapped, rotated) => {
+                // the older rotation or had applied.
+                if !group.remote_rotation(&gid, wrapped, rotated)? {
+                    return Ok(());
+                }
+                if lost != PeerId::default() {
+                    let db = group.consensus_db(&gid)?;
+                    Device::delete_by_addr(&db, &lost)?;
+                    group.running_mut(&gid)?.distributes.remove(&lost);
+                }
+
+                // mailbox keys changed, share to online friends again.
+                let layer = layer.clone();
+                let sender = group.sender();
+                tokio::spawn(async move {
+                    let mut results = HandleResult::new();
+                    mailbox_reshare(&*layer.read().await, gid, &mut results).await;
+                    for (fgid, tgid, msg) in results.layers {
+                        let _ = sender.send(SendMessage::Layer(fgid, tgid, msg)).await;
+                    }
+                });
+                results
+                    .rpcs
+                    .push(rpc::account_key_rotated(gid, &addr, &lost));
+            }
+            StatusEvent::AccountDeleted => {
+                let db = group.consensus_db(&gid)?;
+                Device::delete_by_addr(&db, &addr)?;
+                if let Some((_, id, _)) = group.running_mut(&gid)?.distributes.remove(&addr) {
+                    results.rpcs.push(device_rpc::device_remove(gid, id));
+                }
+            }
         }
         Ok(())
     }
@@ -541,11 +672,11 @@ impl SyncEvent {
         to: u64,
     ) -> Result<Vec<Self>> {
         let db = group.consensus_db(gid)?;
-        let sql = format!(
-            "SELECT id, hash, db_table, row from events WHERE id BETWEEN {} AND {}",
-            from, to
-        );
-        let matrix = db.query(&sql)?;
+        let matrix =
+            Query::new("SELECT id, hash, db_table, row from events WHERE id BETWEEN ? AND ?")
+                .bind(&from)
+                .bind(&to)
+                .query(&db)?;
         drop(db);
         let mut pre_keys: Vec<(i64, i64)> = vec![];
         let mut events: Vec<SyncEvent> = vec![];
@@ -650,8 +781,53 @@ impl SyncEvent {
                         // create
                         let mid = msg.hash;
                         let is_me = msg.is_me;
+                        let parent = msg.parent;
                         let nm = from_model(base, gid, msg).await?;
-                        SyncEvent::Message(hash, fgid, mid, is_me, nm)
+                        SyncEvent::Message(hash, fgid, mid, is_me, nm, parent)
+                    } else {
+                        SyncEvent::None
+                    };
+
+                    events.push(event);
+                }
+                MESSAGE_EDIT_TABLE_PATH => {
+                    let db = group.chat_db(gid)?;
+                    let event = if let Ok(edit) = MessageEdit::get(&db, &row) {
+                        if let Ok(msg) = Message::get(&db, &edit.message) {
+                            SyncEvent::MessageEdit(hash, msg.hash, edit.new_content)
+                        } else {
+                            SyncEvent::None
+                        }
+                    } else {
+                        SyncEvent::None
+                    };
+
+                    events.push(event);
+                }
+                REACTION_TABLE_PATH => {
+                    let db = group.chat_db(gid)?;
+                    let event = if let Ok(reaction) = Reaction::get(&db, &row) {
+                        if let Ok(msg) = Message::get(&db, &reaction.message) {
+                            SyncEvent::MessageReact(
+                                hash,
+                                msg.hash,
+                                reaction.is_me,
+                                reaction.emoji,
+                                !reaction.is_deleted,
+                            )
+                        } else {
+                            SyncEvent::None
+                        }
+                    } else {
+                        SyncEvent::None
+                    };
+
+                    events.push(event);
+                }
+                SESSION_TABLE_PATH => {
+                    let db = group.session_db(gid)?;
+                    let event = if let Ok(s) = Session::get(&db, &row) {
+                        SyncEvent::SessionTtl(hash, s.gid, s.s_type.to_int(), s.ttl)
                     } else {
                         SyncEvent::None
                     };
@@ -661,6 +837,10 @@ impl SyncEvent {
                 FILE_TABLE_PATH => {
                     //
                 }
+                APP_TABLE_PATH => {
+                    // apps' events are not regenerated.
+                    events.push(SyncEvent::None);
+                }
                 _ => {}
             }
         }
@@ -700,7 +880,10 @@ impl SyncEvent {
                 | SyncEvent::RequestHad(eid, ..)
                 | SyncEvent::Friend(eid, ..)
                 | SyncEvent::FriendHad(eid, ..)
-                | SyncEvent::Message(eid, ..) => {
+                | SyncEvent::Message(eid, ..)
+                | SyncEvent::MessageEdit(eid, ..)
+                | SyncEvent::MessageReact(eid, ..)
+                | SyncEvent::SessionTtl(eid, ..) => {
                     if OldEvent::contains_hash(&consensus_db, eid)? {
                         continue;
                     }
@@ -827,7 +1010,7 @@ impl SyncEvent {
                     };
                     (eid, FRIEND_TABLE_PATH, id)
                 }
-                SyncEvent::Message(eid, fgid, meid, _is_me, _m) => {
+                SyncEvent::Message(eid, fgid, meid, _is_me, _m, _parent) => {
                     let chat_db = group.chat_db(&gid)?;
                     if Message::exist(&chat_db, &meid)? {
                         continue;
@@ -843,6 +1026,56 @@ impl SyncEvent {
 
                     (eid, MESSAGE_TABLE_PATH, id)
                 }
+                SyncEvent::MessageEdit(eid, meid, content) => {
+                    let chat_db = group.chat_db(&gid)?;
+                    let id = if let Ok(mut msg) = Message::get_by_hash(&chat_db, &meid) {
+                        let edit = msg.edit(&chat_db, content)?;
+                        results
+                            .rpcs
+                            .push(chat_rpc::message_edit(gid, msg.id, &msg.content));
+                        edit.id
+                    } else {
+                        -1
+                    };
+                    (eid, MESSAGE_EDIT_TABLE_PATH, id)
+                }
+                SyncEvent::MessageReact(eid, meid, is_me, emoji, is_add) => {
+                    let chat_db = group.chat_db(&gid)?;
+                    let id = if let Ok(msg) = Message::get_by_hash(&chat_db, &meid) {
+                        let rid = if is_add {
+                            let mut reaction = Reaction::new(msg.id, is_me, emoji.clone());
+                            reaction.insert(&chat_db)?;
+                            Some(reaction.id)
+                        } else {
+                            Reaction::remove(&chat_db, &msg.id, is_me, &emoji)?
+                        };
+                        // the removed reaction maybe not had.
+                        if let Some(rid) = rid {
+                            results
+                                .rpcs
+                                .push(chat_rpc::message_react(gid, msg.id, is_me, &emoji, is_add));
+                            rid
+                        } else {
+                            -1
+                        }
+                    } else {
+                        -1
+                    };
+                    (eid, REACTION_TABLE_PATH, id)
+                }
+                SyncEvent::SessionTtl(eid, rgid, s_type, ttl) => {
+                    let session_db = group.session_db(&gid)?;
+                    let s_type = SessionType::from_int(s_type);
+                    let id = if let Ok(sid) =
+                        Session::update_ttl_by_gid(&session_db, &rgid, &s_type, ttl)
+                    {
+                        results.rpcs.push(rpc::session_ttl(gid, &sid, ttl));
+                        sid
+                    } else {
+                        -1
+                    };
+                    (eid, SESSION_TABLE_PATH, id)
+                }
                 SyncEvent::None => {
                     continue;
                 }
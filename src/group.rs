@@ -1,7 +1,9 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tdn::types::{
     group::{EventId, GroupId},
     message::{RecvType, SendMessage, SendType},
@@ -12,20 +14,24 @@ use tokio::sync::{mpsc::Sender, RwLock};
 
 use tdn_storage::local::DStorage;
 
-use crate::account::{Account, User};
+use crate::account::{Account, Rotation, User};
 use crate::apps::device::rpc as device_rpc;
 use crate::apps::device::Device;
+use crate::backup::{backup_key, Manifest};
 use crate::consensus::Event;
 use crate::event::{InnerEvent, StatusEvent, SyncEvent};
 use crate::layer::Layer;
 use crate::migrate::{
-    ACCOUNT_DB, CHAT_DB, CLOUD_DB, CONSENSUS_DB, DAO_DB, DOMAIN_DB, FILE_DB, GROUP_DB, JARVIS_DB,
-    SERVICE_DB, SESSION_DB, WALLET_DB,
+    account_db_names, ACCOUNT_DB, CHAT_DB, CLOUD_DB, CONSENSUS_DB, DAO_DB, DOMAIN_DB, FILE_DB,
+    GROUP_DB, JARVIS_DB, SERVICE_DB, SESSION_DB, WALLET_DB,
 };
 use crate::rpc;
 use crate::storage::{account_init, write_avatar};
-use crate::utils::crypto::{decrypt, encrypt};
+use crate::utils::crypto::{
+    decrypt, decrypt_bytes, decrypt_key, encrypt, encrypt_bytes, encrypt_key,
+};
 use crate::utils::device_status::{device_info, device_status as local_device_status};
+use crate::utils::sql::Query;
 
 pub(crate) mod running;
 
@@ -80,6 +86,10 @@ pub(crate) enum GroupEvent {
     SyncRequest(u64, u64),
     /// Sync height from..last_to, to, response.
     SyncResponse(u64, u64, u64, Vec<SyncEvent>),
+    /// the identities (index, gid) derived from the mnemonic, when restore.
+    IdentitiesRequest(Vec<(u32, GroupId)>),
+    /// the identities had in the device.
+    IdentitiesResponse(Vec<(u32, GroupId)>),
 }
 
 impl Group {
@@ -122,8 +132,8 @@ impl Group {
                 return GroupEvent::handle(self, event, gid, addr, layer, uid).await;
             }
             RecvType::Stream(_uid, _stream, _bytes) => {
-                todo!();
-                // TODO stream
+                // device sync not use stream, file chunks only in chat.
+                debug!("distribute layer not support stream.");
             }
             RecvType::Delivery(_t, _tid, _is_ok) => {}
         }
@@ -233,6 +243,16 @@ impl Group {
             }
         };
 
+        // ask the device which identities are used, when restore.
+        let running = self.running_mut(gid)?;
+        if !running.scans.is_empty() {
+            let event = GroupEvent::IdentitiesRequest(std::mem::take(&mut running.scans));
+            let data = bincode::serialize(&event).unwrap_or(vec![]);
+            results
+                .groups
+                .push((*gid, SendType::Event(0, peer_id, data)));
+        }
+
         let account = self.account(gid)?;
         if account.own_height != remote_height || account.event != remote_event {
             results.groups.push((
@@ -241,6 +261,18 @@ impl Group {
             ));
         }
 
+        // replay the data key rotation, the device maybe offline when rotated.
+        if account.rotated > 0 {
+            if let Ok(wrapped) = self.wrapped_key(gid) {
+                let lost = PeerId::default();
+                let event = StatusEvent::AccountKeyRotated(lost, wrapped, account.rotated);
+                let data = bincode::serialize(&GroupEvent::Status(event)).unwrap_or(vec![]);
+                results
+                    .groups
+                    .push((*gid, SendType::Event(0, peer_id, data)));
+            }
+        }
+
         // connect to others.
         for addr in others {
             results
@@ -393,6 +425,35 @@ impl Group {
         addrs
     }
 
+    /// account had operation, delay the idle auto-lock.
+    pub fn active(&mut self, gid: &GroupId, now: u32) {
+        if let Some(running) = self.runnings.get_mut(gid) {
+            running.active = now;
+        }
+    }
+
+    /// running accounts which idle timeout.
+    pub fn idle_accounts(&self, now: u32) -> Vec<GroupId> {
+        let mut gids = vec![];
+        for (gid, running) in &self.runnings {
+            if let Some(account) = self.accounts.get(gid) {
+                if account.idle > 0 && (now.saturating_sub(running.active) as i64) >= account.idle {
+                    gids.push(*gid);
+                }
+            }
+        }
+        gids
+    }
+
+    /// lock the account, remove running and clear the cached key.
+    pub fn lock_account(&mut self, gid: &GroupId) -> HashMap<PeerId, ()> {
+        let addrs = self.remove_running(gid);
+        if let Some(account) = self.accounts.get_mut(gid) {
+            account.evict_plainkey();
+        }
+        addrs
+    }
+
     pub fn remove_running(&mut self, gid: &GroupId) -> HashMap<PeerId, ()> {
         // check close the stable connection.
         let mut addrs: HashMap<PeerId, ()> = HashMap::new();
@@ -417,9 +478,18 @@ impl Group {
     }
 
     pub fn add_running(&mut self, gid: &GroupId, lock: &str) -> Result<(i64, bool)> {
+        self.unlock(gid, lock)?;
+
         let (keypair, id, key) = if let Some(u) = self.accounts.get_mut(gid) {
             let keypair = u.secret(&self.secret, lock)?;
-            u.cache_plainkey(&self.secret, lock)?;
+            // migrate the legacy ciphertexts to current format.
+            if u.upgrade(&self.secret, lock)? {
+                let mut db_path = self.base.clone();
+                db_path.push(ACCOUNT_DB);
+                let account_db = DStorage::open(db_path, &hex::encode(&self.secret))?;
+                u.update_key(&account_db)?;
+                account_db.close()?;
+            }
             (keypair, u.id, u.plainkey())
         } else {
             return Err(anyhow!("user missing."));
@@ -435,6 +505,28 @@ impl Group {
         }
     }
 
+    /// cache the databases key by pin, the account maybe not running.
+    pub fn unlock(&mut self, gid: &GroupId, lock: &str) -> Result<()> {
+        // continue the broken data key rotation.
+        let account_db = self.account_db()?;
+        let rotation = Rotation::get(&account_db, gid);
+        account_db.close()?;
+        if let Ok(mut rotation) = rotation {
+            let account = self.account(gid)?;
+            account.check_lock(lock)?;
+            if !rotation.wrapped.is_empty() {
+                // rotated in other device, save the new key by pin.
+                let keypair = account.secret(&self.secret, lock)?;
+                let key = decrypt_bytes(&wrap_key(&keypair.to_bytes()), &rotation.wrapped)?;
+                rotation.new = encrypt_key(&self.secret, lock, &key)?;
+            }
+            self.finish_rotation(gid, lock, rotation)?;
+        }
+
+        let salt = self.secret;
+        self.account_mut(gid)?.cache_plainkey(&salt, lock)
+    }
+
     pub fn clone_user(&self, gid: &GroupId) -> Result<User> {
         if let Some(u) = self.accounts.get(gid) {
             Ok(User::new(
@@ -462,8 +554,25 @@ impl Group {
         &self.accounts
     }
 
+    /// identities derived from the mnemonic, and if had in local.
+    pub fn identities(
+        &self,
+        lang: i64,
+        seed: &str,
+        pass: &str,
+        count: u32,
+    ) -> Result<Vec<(u32, GroupId, bool)>> {
+        let mut ids = vec![];
+        for index in 0..count {
+            let gid = Account::derive_id(index, lang, seed, pass)?;
+            ids.push((index, gid, self.accounts.contains_key(&gid)));
+        }
+        Ok(ids)
+    }
+
     pub async fn add_account(
         &mut self,
+        index: u32,
         lang: i64,
         seed: &str,
         pass: &str,
@@ -471,9 +580,8 @@ impl Group {
         lock: &str,
         avatar_bytes: Vec<u8>,
     ) -> Result<(i64, GroupId)> {
-        let account_index = self.accounts.len() as u32;
         let (mut account, sk) = Account::generate(
-            account_index,
+            index,
             &self.secret,
             lang,
             seed,
@@ -545,6 +653,157 @@ impl Group {
         }
     }
 
+    /// the key of backup archive, keyed from account secret.
+    pub fn backup_key(&self, gid: &GroupId) -> Result<[u8; 32]> {
+        Ok(backup_key(&self.running(gid)?.keypair.to_bytes()))
+    }
+
+    /// adopt the imported account data, all databases encrypted by the backup's key.
+    /// the account is offline, it will running when login.
+    pub fn import_account(&mut self, gid: &GroupId, lock: &str, manifest: Manifest) -> Result<()> {
+        let account_db = self.account_db()?;
+        if let Some(u) = self.accounts.get_mut(gid) {
+            u.rekey(&self.secret, lock, manifest.key)?;
+            u.own_height = manifest.height;
+            u.event = manifest.event;
+            u.update(&account_db)?;
+            u.update_key(&account_db)?;
+        } else {
+            return Err(anyhow!("user missing."));
+        }
+        account_db.close()?;
+
+        // this device is the only device.
+        let db = self.consensus_db(gid)?;
+        Device::delete_all(&db)?;
+        let (device_name, device_info) = device_info();
+        let mut device = Device::new(device_name, device_info, self.addr);
+        device.insert(&db)?;
+        db.close()
+    }
+
+    /// delete the account's info, the running had been removed.
+    pub fn delete_account(&mut self, gid: &GroupId) -> Result<()> {
+        let account = self.accounts.remove(gid).ok_or(anyhow!("user missing"))?;
+        let account_db = self.account_db()?;
+        if let Ok(rotation) = Rotation::get(&account_db, gid) {
+            rotation.delete(&account_db)?;
+        }
+        account.delete(&account_db)?;
+        account_db.close()
+    }
+
+    /// rotate account's data key, all databases encrypted by the new key.
+    pub fn rotate_key(&mut self, gid: &GroupId, lock: &str) -> Result<()> {
+        let account = self.account(gid)?;
+        account.check_lock(lock)?;
+        let key = rand::thread_rng().gen::<[u8; 32]>();
+        let ckey = encrypt_key(&self.secret, lock, &key)?;
+        let mut rotation = Rotation::new(*gid, account.encrypt.clone(), ckey);
+
+        let account_db = self.account_db()?;
+        rotation.insert(&account_db)?;
+        account_db.close()?;
+
+        self.finish_rotation(gid, lock, rotation)?;
+
+        let rotated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+        let account_db = self.account_db()?;
+        let account = self.account_mut(gid)?;
+        account.rotated = rotated;
+        account.update_rotated(&account_db)?;
+        account_db.close()
+    }
+
+    /// the new data key wrapped by account's secret, send to other devices.
+    pub fn wrapped_key(&self, gid: &GroupId) -> Result<Vec<u8>> {
+        let key = hex::decode(self.db_key(gid)?)?;
+        encrypt_bytes(&wrap_key(&self.running(gid)?.keypair.to_bytes()), &key)
+    }
+
+    /// data key rotated in other device, encrypt databases by the new key now,
+    /// and the new key is saved by pin when login. return false if not newer.
+    pub fn remote_rotation(
+        &mut self,
+        gid: &GroupId,
+        wrapped: Vec<u8>,
+        rotated: i64,
+    ) -> Result<bool> {
+        let account = self.account(gid)?;
+        if rotated <= account.rotated {
+            return Ok(false);
+        }
+        let keypair = self.running(gid)?.keypair.to_bytes();
+        let key = decrypt_bytes(&wrap_key(&keypair), &wrapped)?;
+        let (old, new) = (account.plainkey(), hex::encode(&key));
+        if old == new {
+            return Ok(false);
+        }
+
+        let account_db = self.account_db()?;
+        if let Ok(rotation) = Rotation::get(&account_db, gid) {
+            rotation.delete(&account_db)?;
+        }
+        let mut rotation = Rotation::new(*gid, account.encrypt.clone(), vec![]);
+        rotation.wrapped = wrapped;
+        rotation.insert(&account_db)?;
+        self.rekey_dbs(gid, &account_db, &mut rotation, &old, &new)?;
+
+        let account = self.account_mut(gid)?;
+        account.cache_key(key);
+        account.rotated = rotated;
+        account.update_rotated(&account_db)?;
+        account_db.close()?;
+        Ok(true)
+    }
+
+    /// encrypt databases by the new key, and save the new key to account.
+    fn finish_rotation(&mut self, gid: &GroupId, lock: &str, mut rotation: Rotation) -> Result<()> {
+        let old = hex::encode(decrypt_key(&self.secret, lock, &rotation.old)?);
+        let key = decrypt_key(&self.secret, lock, &rotation.new)?;
+        let new = hex::encode(&key);
+
+        let account_db = self.account_db()?;
+        self.rekey_dbs(gid, &account_db, &mut rotation, &old, &new)?;
+
+        if let Some(u) = self.accounts.get_mut(gid) {
+            // had saved if broken after account updated.
+            if u.encrypt != rotation.new {
+                u.rekey(&self.secret, lock, key)?;
+                u.update_key(&account_db)?;
+            }
+        }
+        rotation.delete(&account_db)?;
+        account_db.close()
+    }
+
+    /// encrypt the databases which not done in the rotation.
+    fn rekey_dbs(
+        &self,
+        gid: &GroupId,
+        account_db: &DStorage,
+        rotation: &mut Rotation,
+        old: &str,
+        new: &str,
+    ) -> Result<()> {
+        let mut path = self.base.clone();
+        path.push(gid.to_hex());
+        for name in account_db_names() {
+            if rotation.done.iter().any(|d| d == name) {
+                continue;
+            }
+            let db_path = path.join(name);
+            if db_path.exists() {
+                rekey_db(db_path, old, new)?;
+            }
+            rotation.done(account_db, name)?;
+        }
+        Ok(())
+    }
+
     pub fn encrypt(&self, gid: &GroupId, lock: &str, bytes: &[u8]) -> Result<Vec<u8>> {
         let ckey = &self.account(gid)?.encrypt;
         encrypt(&self.secret, lock, ckey, bytes)
@@ -694,7 +953,7 @@ impl Group {
         Ok(())
     }
 
-    pub fn _status(
+    pub fn status(
         &mut self,
         gid: &GroupId,
         event: StatusEvent,
@@ -714,7 +973,25 @@ impl Group {
 
 impl Group {
     fn db_key(&self, gid: &GroupId) -> Result<String> {
-        Ok(self.account(gid)?.plainkey())
+        let account = self.account(gid)?;
+        if account.is_locked() {
+            return Err(anyhow!("account is locked"));
+        }
+        Ok(account.plainkey())
+    }
+
+    /// my mailbox key to the friend, friend uses it to open my mails in cloud.
+    /// every friend has a different key.
+    pub(crate) fn mailbox_key(&self, gid: &GroupId, fgid: &GroupId) -> Result<[u8; 32]> {
+        let mut key = self.db_key(gid)?.into_bytes();
+        key.extend_from_slice(&fgid.0);
+        Ok(blake3::derive_key("ESSE cloud mailbox", &key))
+    }
+
+    /// my key to encrypt the recovery shares held for friends.
+    pub(crate) fn share_key(&self, gid: &GroupId) -> Result<[u8; 32]> {
+        let key = self.db_key(gid)?;
+        Ok(blake3::derive_key("ESSE recovery share", key.as_bytes()))
     }
 
     pub(crate) fn account_db(&self) -> Result<DStorage> {
@@ -723,81 +1000,93 @@ impl Group {
         DStorage::open(db_path, &hex::encode(&self.secret))
     }
 
-    pub(crate) fn consensus_db(&self, gid: &GroupId) -> Result<DStorage> {
+    fn open_db(&self, gid: &GroupId, name: &str) -> Result<DStorage> {
         let mut db_path = self.base.clone();
         db_path.push(gid.to_hex());
-        db_path.push(CONSENSUS_DB);
+        db_path.push(name);
         DStorage::open(db_path, &self.db_key(gid)?)
     }
 
+    pub(crate) fn consensus_db(&self, gid: &GroupId) -> Result<DStorage> {
+        self.open_db(gid, CONSENSUS_DB)
+    }
+
     pub(crate) fn session_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(SESSION_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, SESSION_DB)
     }
 
     pub(crate) fn chat_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(CHAT_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, CHAT_DB)
     }
 
     pub(crate) fn file_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(FILE_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, FILE_DB)
     }
 
     pub(crate) fn _service_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(SERVICE_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, SERVICE_DB)
     }
 
     pub(crate) fn jarvis_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(JARVIS_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, JARVIS_DB)
     }
 
     pub(crate) fn group_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(GROUP_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, GROUP_DB)
     }
 
     pub(crate) fn _dao_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(DAO_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, DAO_DB)
     }
 
     pub(crate) fn domain_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(DOMAIN_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, DOMAIN_DB)
     }
 
     pub(crate) fn wallet_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(WALLET_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+        self.open_db(gid, WALLET_DB)
     }
 
-    pub(crate) fn _cloud_db(&self, gid: &GroupId) -> Result<DStorage> {
-        let mut db_path = self.base.clone();
-        db_path.push(gid.to_hex());
-        db_path.push(CLOUD_DB);
-        DStorage::open(db_path, &self.db_key(gid)?)
+    pub(crate) fn cloud_db(&self, gid: &GroupId) -> Result<DStorage> {
+        self.open_db(gid, CLOUD_DB)
+    }
+}
+
+/// the key to wrap the data key, when send it to other devices.
+fn wrap_key(secret: &[u8]) -> [u8; 32] {
+    blake3::derive_key("ESSE account rotation", secret)
+}
+
+/// encrypt the database by the new key, do nothing if had encrypted.
+fn rekey_db(path: PathBuf, old: &str, new: &str) -> Result<()> {
+    if !can_open(&path, old) {
+        // rekeyed before the rotation interrupted.
+        return if can_open(&path, new) {
+            Ok(())
+        } else {
+            Err(anyhow!("database key is invalid"))
+        };
+    }
+
+    let db = DStorage::open(path.clone(), old)?;
+    Query::new("PRAGMA rekey = ?").bind(new).execute(&db)?;
+    db.close()?;
+
+    // storage without SQLCipher ignores the rekey, the old key still works.
+    if can_open(&path, old) || !can_open(&path, new) {
+        return Err(anyhow!("database rekey failure"));
+    }
+    Ok(())
+}
+
+/// the database can be read by the key.
+fn can_open(path: &PathBuf, key: &str) -> bool {
+    if let Ok(db) = DStorage::open(path.clone(), key) {
+        let ok = db.query("SELECT count(*) FROM sqlite_master").is_ok();
+        let _ = db.close();
+        ok
+    } else {
+        false
     }
 }
 
@@ -859,6 +1148,23 @@ impl GroupEvent {
             ) => results.rpcs.push(device_rpc::device_status(
                 gid, cpu_n, mem_s, swap_s, disk_s, cpu_p, mem_p, swap_p, disk_p, uptime,
             )),
+            GroupEvent::IdentitiesRequest(ids) => {
+                if group.running(&gid)?.distributes.contains_key(&addr) {
+                    let had = ids
+                        .into_iter()
+                        .filter(|(_, id)| group.accounts.contains_key(id))
+                        .collect();
+                    let data = bincode::serialize(&GroupEvent::IdentitiesResponse(had))?;
+                    results.groups.push((gid, SendType::Event(0, addr, data)));
+                }
+            }
+            GroupEvent::IdentitiesResponse(ids) => {
+                let ids = ids
+                    .into_iter()
+                    .map(|(index, id)| (index, id, group.accounts.contains_key(&id)))
+                    .collect();
+                results.rpcs.push(rpc::account_identities(gid, ids));
+            }
             GroupEvent::Event(eheight, eid, pre, inner_event) => {
                 inner_event.handle(group, gid, addr, eheight, eid, pre, &mut results, layer)?;
             }
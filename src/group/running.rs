@@ -22,6 +22,10 @@ pub(crate) struct RunningAccount {
     pub distributes: HashMap<PeerId, (Peer, i64, bool)>,
     /// uptime
     pub uptime: u32,
+    /// last active time, use in idle auto-lock.
+    pub active: u32,
+    /// identities (index, gid) to scan in the remote device, when restore.
+    pub scans: Vec<(u32, GroupId)>,
 }
 
 impl RunningAccount {
@@ -46,6 +50,8 @@ impl RunningAccount {
             device_name,
             device_info,
             uptime,
+            active: uptime,
+            scans: vec![],
         })
     }
 
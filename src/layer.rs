@@ -3,14 +3,14 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tdn::types::{
-    group::GroupId,
+    group::{EventId, GroupId},
     message::SendType,
     primitive::{HandleResult, Peer, PeerId, Result},
 };
 use tokio::sync::RwLock;
 
 use crate::account::User;
-use crate::apps::chat::{chat_conn, LayerEvent as ChatLayerEvent};
+use crate::apps::chat::{chat_conn, FileStream, LayerEvent as ChatLayerEvent};
 use crate::apps::group::{group_conn, GROUP_ID};
 use crate::group::Group;
 use crate::session::{Session, SessionType};
@@ -33,6 +33,8 @@ pub(crate) struct Layer {
     pub runnings: HashMap<GroupId, RunningLayer>,
     /// message delivery tracking. uuid, me_gid, db_id.
     pub delivery: HashMap<u64, (GroupId, i64)>,
+    /// receiving file transfers' streams. message hash => stream.
+    pub streams: HashMap<EventId, FileStream>,
     /// storage base path.
     pub base: PathBuf,
     /// self peer addr.
@@ -49,6 +51,7 @@ impl Layer {
             addr,
             runnings: HashMap::new(),
             delivery: HashMap::new(),
+            streams: HashMap::new(),
         })
     }
 
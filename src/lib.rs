@@ -4,12 +4,20 @@ extern crate log;
 #[macro_use]
 extern crate anyhow;
 
+use once_cell::sync::Lazy;
 use std::ffi::CStr;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, PoisonError};
+use std::thread::JoinHandle;
+
+pub use embed::Callback;
 
 mod account;
 mod apps;
+mod backup;
 mod consensus;
+mod embed;
 mod event;
 mod group;
 mod layer;
@@ -18,6 +26,7 @@ mod primitives;
 mod rpc;
 mod server;
 mod session;
+mod settings;
 mod storage;
 mod utils;
 
@@ -26,8 +35,9 @@ mod utils;
 pub mod android {
     extern crate jni;
 
-    use self::jni::objects::{JClass, JString};
-    use self::jni::JNIEnv;
+    use self::jni::objects::{GlobalRef, JClass, JObject, JString, JValue};
+    use self::jni::sys::jint;
+    use self::jni::{JNIEnv, JavaVM};
     use super::*;
 
     #[no_mangle]
@@ -36,19 +46,209 @@ pub mod android {
         _: JClass,
         java_pattern: JString,
     ) {
-        start(
-            env.get_string(java_pattern)
-                .expect("invalid pattern string")
-                .as_ptr(),
-        );
+        if let Ok(pattern) = env.get_string(java_pattern) {
+            start(pattern.as_ptr());
+        }
+    }
+
+    /// java listener, `void onMessage(String message)` called with responses and notifications.
+    static LISTENER: Lazy<Mutex<Option<(JavaVM, GlobalRef)>>> = Lazy::new(|| Mutex::new(None));
+
+    extern "C" fn on_message(message: *const c_char) {
+        let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+        if let Ok(listener) = LISTENER.lock() {
+            if let Some((vm, listener)) = listener.as_ref() {
+                if let Ok(env) = vm.attach_current_thread() {
+                    if let Ok(jmessage) = env.new_string(message.as_ref()) {
+                        let _ = env.call_method(
+                            listener.as_obj(),
+                            "onMessage",
+                            "(Ljava/lang/String;)V",
+                            &[JValue::Object(jmessage.into())],
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn Java_com_esse_1core_esse_1core_RustCore_startWithOptions(
+        env: JNIEnv,
+        _: JClass,
+        db_path: JString,
+        ws_addr: JString,
+        p2p_addr: JString,
+        listener: JObject,
+    ) -> jint {
+        let string = |s: JString| -> String {
+            if s.is_null() {
+                String::new()
+            } else {
+                env.get_string(s).map(|s| s.into()).unwrap_or(String::new())
+            }
+        };
+        let (db_path, ws_addr, p2p_addr) = (string(db_path), string(ws_addr), string(p2p_addr));
+
+        let callback: Option<Callback> = if listener.is_null() {
+            None
+        } else {
+            match (env.get_java_vm(), env.new_global_ref(listener)) {
+                (Ok(vm), Ok(listener)) => match LISTENER.lock() {
+                    Ok(mut l) => {
+                        *l = Some((vm, listener));
+                        Some(on_message)
+                    }
+                    Err(_) => return -1,
+                },
+                _ => return -1,
+            }
+        };
+
+        start_thread(db_path, &ws_addr, &p2p_addr, callback)
     }
+
+    #[no_mangle]
+    pub extern "C" fn Java_com_esse_1core_esse_1core_RustCore_rpcRequest(
+        env: JNIEnv,
+        _: JClass,
+        request: JString,
+    ) -> jint {
+        match env.get_string(request) {
+            Ok(request) => rpc_request_str(&String::from(request)),
+            Err(_) => -1,
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn Java_com_esse_1core_esse_1core_RustCore_stop(_: JNIEnv, _: JClass) {
+        stop();
+        if let Ok(mut listener) = LISTENER.lock() {
+            *listener = None;
+        }
+    }
+}
+
+/// run the exported function's body, the panic is not unwound into the host.
+fn guard<T>(failure: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(failure)
 }
 
 #[no_mangle]
 pub extern "C" fn start(db_path: *const c_char) {
-    let c_str = unsafe { CStr::from_ptr(db_path) };
-    let s_path = c_str.to_str().unwrap_or("./tdn").to_owned();
+    guard((), || {
+        let c_str = unsafe { CStr::from_ptr(db_path) };
+        let s_path = c_str.to_str().unwrap_or("./tdn").to_owned();
 
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    let _ = rt.block_on(server::start(s_path));
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let _ = rt.block_on(server::start(s_path));
+    })
+}
+
+/// the core thread started by `start_with_options`.
+static RUNNING: Lazy<Mutex<Option<JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+
+fn c_string(s: *const c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(s) }
+            .to_str()
+            .unwrap_or("")
+            .to_owned()
+    }
+}
+
+fn start_thread(
+    db_path: String,
+    ws_addr: &str,
+    p2p_addr: &str,
+    callback: Option<Callback>,
+) -> c_int {
+    let mut running = RUNNING.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(handle) = running.take() {
+        if !handle.is_finished() {
+            *running = Some(handle);
+            return -1;
+        }
+        // the core exited by failure, clear it and start again.
+        let _ = handle.join();
+        server::reset();
+    }
+    let ws_addr = if ws_addr.is_empty() {
+        None
+    } else {
+        match ws_addr.parse() {
+            Ok(addr) => Some(addr),
+            Err(_) => return -1,
+        }
+    };
+    let p2p_addr = if p2p_addr.is_empty() {
+        None
+    } else {
+        match p2p_addr.parse() {
+            Ok(addr) => Some(addr),
+            Err(_) => return -1,
+        }
+    };
+
+    embed::set_callback(callback);
+    *running = Some(std::thread::spawn(move || {
+        let result = tokio::runtime::Runtime::new()
+            .map_err(|e| anyhow!(e))
+            .and_then(|rt| rt.block_on(server::start_with(db_path, ws_addr, p2p_addr, vec![])));
+        if let Err(e) = result {
+            error!("Core start failure: {}", e);
+        }
+    }));
+    0
+}
+
+fn rpc_request_str(request: &str) -> c_int {
+    match embed::request(request) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Start the core in background thread, return 0 if success, -1 if running or invalid.
+/// `ws_addr` and `p2p_addr` can be null or empty to use the settings.
+/// `callback` receives rpc responses and notifications (json string) of `rpc_request`,
+/// null callback only serves the websocket.
+#[no_mangle]
+pub extern "C" fn start_with_options(
+    db_path: *const c_char,
+    ws_addr: *const c_char,
+    p2p_addr: *const c_char,
+    callback: Option<Callback>,
+) -> c_int {
+    guard(-1, || {
+        let mut s_path = c_string(db_path);
+        if s_path.is_empty() {
+            s_path = "./tdn".to_owned();
+        }
+        start_thread(s_path, &c_string(ws_addr), &c_string(p2p_addr), callback)
+    })
+}
+
+/// Submit the jsonrpc request, the response is sent to the callback.
+/// return 0 if submitted, -1 if not running or invalid.
+#[no_mangle]
+pub extern "C" fn rpc_request(request: *const c_char) -> c_int {
+    guard(-1, || rpc_request_str(&c_string(request)))
+}
+
+/// Stop the core started by `start_with_options`, block until it stopped,
+/// then it can start again.
+#[no_mangle]
+pub extern "C" fn stop() {
+    guard((), || {
+        let mut running = RUNNING.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(handle) = running.take() {
+            server::shutdown();
+            let _ = handle.join();
+        }
+        server::reset();
+        embed::set_callback(None);
+    })
 }
@@ -2,6 +2,8 @@ use std::path::PathBuf;
 use tdn::types::primitive::Result;
 use tdn_storage::local::DStorage;
 
+use crate::utils::sql::Query;
+
 pub mod consensus;
 
 mod account;
@@ -17,17 +19,20 @@ mod session;
 mod wallet;
 
 use account::ACCOUNT_VERSIONS;
-use chat::CHAT_VERSIONS;
-use cloud::CLOUD_VERSIONS;
 use consensus::CONSENSUS_VERSIONS;
 use dao::DAO_VERSIONS;
-use domain::DOMAIN_VERSIONS;
-use file::FILE_VERSIONS;
-use group::GROUP_VERSIONS;
-use jarvis::JARVIS_VERSIONS;
 use service::SERVICE_VERSIONS;
 use session::SESSION_VERSIONS;
-use wallet::WALLET_VERSIONS;
+
+pub(crate) use chat::CHAT_VERSIONS;
+pub(crate) use cloud::CLOUD_VERSIONS;
+pub(crate) use domain::DOMAIN_VERSIONS;
+pub(crate) use file::FILE_VERSIONS;
+pub(crate) use group::GROUP_VERSIONS;
+pub(crate) use jarvis::JARVIS_VERSIONS;
+pub(crate) use wallet::WALLET_VERSIONS;
+
+use crate::apps::registry;
 
 // Account's main database name.
 pub(crate) const ACCOUNT_DB: &'static str = "account.db";
@@ -65,6 +70,45 @@ pub(crate) const WALLET_DB: &'static str = "wallet.db";
 /// Account's cloud database name
 pub(crate) const CLOUD_DB: &'static str = "cloud.db";
 
+/// Account's core databases, apps' databases are registered by apps.
+const CORE_DBS: [(&'static str, &'static [&'static str]); 4] = [
+    (CONSENSUS_DB, &CONSENSUS_VERSIONS),
+    (SESSION_DB, &SESSION_VERSIONS),
+    (SERVICE_DB, &SERVICE_VERSIONS),
+    (DAO_DB, &DAO_VERSIONS),
+];
+
+/// Account's all databases and migrations, encrypted by account's data key.
+pub(crate) fn account_dbs() -> Vec<(&'static str, &'static [&'static str])> {
+    let mut dbs = CORE_DBS.to_vec();
+    dbs.extend(registry().dbs());
+    dbs
+}
+
+/// Account's all databases names.
+pub(crate) fn account_db_names() -> Vec<&'static str> {
+    account_dbs().into_iter().map(|(name, _)| name).collect()
+}
+
+/// save the database version, the new app's database will be inserted.
+fn save_version(db: &DStorage, name: &str, version: usize) -> Result<()> {
+    let matrix = Query::new("select version from migrates where db_name = ?")
+        .bind(name)
+        .query(db)?;
+    if matrix.len() > 0 {
+        Query::new("UPDATE migrates SET version = ? where db_name = ?")
+            .bind(&version)
+            .bind(name)
+            .update(db)?;
+    } else {
+        Query::new("INSERT INTO migrates (db_name, version) values (?, ?)")
+            .bind(name)
+            .bind(&version)
+            .insert(db)?;
+    }
+    Ok(())
+}
+
 pub(crate) fn main_migrate(path: &PathBuf, key: &str) -> Result<()> {
     let mut db_path = path.clone();
     db_path.push(ACCOUNT_DB);
@@ -81,28 +125,37 @@ pub(crate) fn main_migrate(path: &PathBuf, key: &str) -> Result<()> {
                 db.execute(i)?;
             }
 
-            db.update(&format!(
-                "UPDATE migrates SET version = {} where db_name = '{}'",
-                ACCOUNT_VERSIONS.len(),
-                ACCOUNT_DB,
-            ))?;
+            Query::new("UPDATE migrates SET version = ? where db_name = ?")
+                .bind(&ACCOUNT_VERSIONS.len())
+                .bind(ACCOUNT_DB)
+                .update(&db)?;
         }
 
-        let mut account_matrix = db.query(&format!(
-            "select version from migrates where db_name = '{}'",
-            ACCOUNT_DB
-        ))?;
+        let mut account_matrix = Query::new("select version from migrates where db_name = ?")
+            .bind(ACCOUNT_DB)
+            .query(&db)?;
         let account_version = account_matrix.pop().unwrap().pop().unwrap().as_i64() as usize;
         if account_version != ACCOUNT_VERSIONS.len() {
             // 2. migrate.
             for i in &ACCOUNT_VERSIONS[account_version..] {
                 db.execute(i)?;
             }
-            db.update(&format!(
-                "UPDATE migrates SET version = {} where db_name = '{}'",
-                ACCOUNT_VERSIONS.len(),
-                ACCOUNT_DB,
-            ))?;
+            Query::new("UPDATE migrates SET version = ? where db_name = ?")
+                .bind(&ACCOUNT_VERSIONS.len())
+                .bind(ACCOUNT_DB)
+                .update(&db)?;
+        }
+
+        let dbs = account_dbs();
+        let mut names = vec![];
+        for mut values in db.query("select db_name from migrates")? {
+            names.push(values.pop().unwrap().as_string());
+        }
+        for (name, _) in &dbs {
+            if !names.iter().any(|n| n == name) {
+                // new app installed, migrate it for all accounts.
+                save_version(&db, name, 0)?;
+            }
         }
 
         let matrix = db.query("select db_name, version from migrates")?;
@@ -117,28 +170,17 @@ pub(crate) fn main_migrate(path: &PathBuf, key: &str) -> Result<()> {
                         for i in &ACCOUNT_VERSIONS[db_version..] {
                             db.execute(i)?;
                         }
-                        db.update(&format!(
-                            "UPDATE migrates SET version = {} where db_name = '{}'",
-                            ACCOUNT_VERSIONS.len(),
-                            db_name,
-                        ))?;
+                        Query::new("UPDATE migrates SET version = ? where db_name = ?")
+                            .bind(&ACCOUNT_VERSIONS.len())
+                            .bind(&db_name)
+                            .update(&db)?;
                     }
                     continue;
                 }
-                CONSENSUS_DB => CONSENSUS_VERSIONS.as_ref(),
-                SESSION_DB => SESSION_VERSIONS.as_ref(),
-                FILE_DB => FILE_VERSIONS.as_ref(),
-                SERVICE_DB => SERVICE_VERSIONS.as_ref(),
-                JARVIS_DB => JARVIS_VERSIONS.as_ref(),
-                GROUP_DB => GROUP_VERSIONS.as_ref(),
-                DAO_DB => DAO_VERSIONS.as_ref(),
-                CHAT_DB => CHAT_VERSIONS.as_ref(),
-                DOMAIN_DB => DOMAIN_VERSIONS.as_ref(),
-                WALLET_DB => WALLET_VERSIONS.as_ref(),
-                CLOUD_DB => CLOUD_VERSIONS.as_ref(),
-                _ => {
-                    continue;
-                }
+                name => match dbs.iter().find(|(n, _)| *n == name) {
+                    Some((_, versions)) => *versions,
+                    None => continue,
+                },
             };
 
             if db_version != current_versions.len() {
@@ -155,11 +197,10 @@ pub(crate) fn main_migrate(path: &PathBuf, key: &str) -> Result<()> {
                     account_db.close()?;
                 }
 
-                db.update(&format!(
-                    "UPDATE migrates SET version = {} where db_name = '{}'",
-                    current_versions.len(),
-                    db_name,
-                ))?;
+                Query::new("UPDATE migrates SET version = ? where db_name = ?")
+                    .bind(&current_versions.len())
+                    .bind(&db_name)
+                    .update(&db)?;
             }
         }
 
@@ -171,77 +212,10 @@ pub(crate) fn main_migrate(path: &PathBuf, key: &str) -> Result<()> {
             db.execute(i)?;
         }
 
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            ACCOUNT_VERSIONS.len(),
-            ACCOUNT_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            CONSENSUS_VERSIONS.len(),
-            CONSENSUS_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            SESSION_VERSIONS.len(),
-            SESSION_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            FILE_VERSIONS.len(),
-            FILE_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            SERVICE_VERSIONS.len(),
-            SERVICE_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            JARVIS_VERSIONS.len(),
-            JARVIS_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            GROUP_VERSIONS.len(),
-            GROUP_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            DAO_VERSIONS.len(),
-            DAO_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            CHAT_VERSIONS.len(),
-            CHAT_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            DOMAIN_VERSIONS.len(),
-            DOMAIN_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            WALLET_VERSIONS.len(),
-            WALLET_DB,
-        ))?;
-
-        db.update(&format!(
-            "UPDATE migrates SET version = {} where db_name = '{}'",
-            CLOUD_VERSIONS.len(),
-            CLOUD_DB,
-        ))?;
+        save_version(&db, ACCOUNT_DB, ACCOUNT_VERSIONS.len())?;
+        for (name, versions) in account_dbs() {
+            save_version(&db, name, versions.len())?;
+        }
 
         db.close()?;
     }
@@ -250,91 +224,14 @@ pub(crate) fn main_migrate(path: &PathBuf, key: &str) -> Result<()> {
 }
 
 pub(crate) fn account_init_migrate(path: &PathBuf, key: &str) -> Result<()> {
-    let mut db_path = path.clone();
-    db_path.push(CONSENSUS_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &CONSENSUS_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(SESSION_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &SESSION_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(FILE_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &FILE_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(SERVICE_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &SERVICE_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(JARVIS_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &JARVIS_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(GROUP_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &GROUP_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(DAO_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &DAO_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(CHAT_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &CHAT_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(DOMAIN_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &DOMAIN_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(WALLET_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &WALLET_VERSIONS {
-        db.execute(i)?;
-    }
-    db.close()?;
-
-    let mut db_path = path.clone();
-    db_path.push(CLOUD_DB);
-    let db = DStorage::open(db_path, key)?;
-    for i in &CLOUD_VERSIONS {
-        db.execute(i)?;
+    for (name, versions) in account_dbs() {
+        let mut db_path = path.clone();
+        db_path.push(name);
+        let db = DStorage::open(db_path, key)?;
+        for i in versions {
+            db.execute(i)?;
+        }
+        db.close()?;
     }
-    db.close()
+    Ok(())
 }
@@ -1,5 +1,5 @@
 #[rustfmt::skip]
-pub(super) const ACCOUNT_VERSIONS: [&str; 13] = [
+pub(super) const ACCOUNT_VERSIONS: [&str; 22] = [
   "CREATE TABLE IF NOT EXISTS accounts(
     id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
     gid TEXT NOT NULL,
@@ -31,4 +31,30 @@ pub(super) const ACCOUNT_VERSIONS: [&str; 13] = [
   "INSERT INTO migrates (db_name, version) values ('wallet.db', 0)",
   "INSERT INTO migrates (db_name, version) values ('cloud.db', 0)",
   "INSERT INTO migrates (db_name, version) values ('dao.db', 0)",
+  "CREATE TABLE IF NOT EXISTS mails(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    hash TEXT NOT NULL,
+    gid TEXT NOT NULL,
+    sender TEXT NOT NULL,
+    data TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    datetime INTEGER NOT NULL);",
+  "CREATE TABLE IF NOT EXISTS rotations(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    gid TEXT NOT NULL,
+    old TEXT NOT NULL,
+    new TEXT NOT NULL,
+    done TEXT NOT NULL);",
+  "ALTER TABLE accounts ADD COLUMN idle INTEGER NOT NULL DEFAULT 0;", // auto-lock idle seconds, 0 is never.
+  "CREATE TABLE IF NOT EXISTS settings(
+    name TEXT NOT NULL,
+    value TEXT NOT NULL);",
+  "CREATE TABLE IF NOT EXISTS seeds(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    socket TEXT NOT NULL,
+    transport TEXT NOT NULL);",
+  "INSERT INTO seeds (socket, transport) values ('1.15.156.199:7364', 'quic')",
+  "INSERT INTO seeds (socket, transport) values ('184.170.220.231:7364', 'quic')",
+  "ALTER TABLE rotations ADD COLUMN wrapped TEXT NOT NULL DEFAULT '';", // new key from other device.
+  "ALTER TABLE accounts ADD COLUMN rotated INTEGER NOT NULL DEFAULT 0;", // data key rotated time, the newest wins.
 ];
@@ -1,5 +1,5 @@
 #[rustfmt::skip]
-pub(super) const CHAT_VERSIONS: [&str; 3] = [
+pub(super) const CHAT_VERSIONS: [&str; 15] = [
   "CREATE TABLE IF NOT EXISTS friends(
     id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
     gid TEXT NOT NULL,
@@ -30,4 +30,50 @@ pub(super) const CHAT_VERSIONS: [&str; 3] = [
     content TEXT NOT NULL,
     is_delivery INTEGER NOT NULL,
     datetime INTEGER NOT NULL);",
+  "CREATE VIRTUAL TABLE IF NOT EXISTS message_index USING fts5(
+    content,
+    tokenize = 'unicode61 remove_diacritics 2');",
+  "INSERT INTO message_index (rowid, content) SELECT id, content FROM messages WHERE m_type IN (0, 2, 9);", // index history String, File, Transfer.
+  "CREATE TABLE IF NOT EXISTS message_edits(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    message INTEGER NOT NULL,
+    old_content TEXT NOT NULL,
+    new_content TEXT NOT NULL,
+    datetime INTEGER NOT NULL);",
+  "CREATE TABLE IF NOT EXISTS reactions(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    message INTEGER NOT NULL,
+    is_me INTEGER NOT NULL,
+    emoji TEXT NOT NULL,
+    datetime INTEGER NOT NULL);",
+  "ALTER TABLE messages ADD COLUMN parent TEXT NOT NULL DEFAULT '';", // replied message's hash.
+  "CREATE TABLE IF NOT EXISTS transfers(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    hash TEXT NOT NULL,
+    fid INTEGER NOT NULL,
+    is_me INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    file_hash TEXT NOT NULL,
+    chunks TEXT NOT NULL,
+    received TEXT NOT NULL,
+    is_over INTEGER NOT NULL,
+    datetime INTEGER NOT NULL);",
+  "ALTER TABLE messages ADD COLUMN is_read INTEGER NOT NULL DEFAULT 0;",
+  "CREATE TABLE IF NOT EXISTS outbox(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    message INTEGER NOT NULL,
+    fid INTEGER NOT NULL,
+    retries INTEGER NOT NULL,
+    datetime INTEGER NOT NULL);",
+  "CREATE TABLE IF NOT EXISTS shares(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    owner TEXT NOT NULL,
+    sender TEXT NOT NULL,
+    data TEXT NOT NULL,
+    is_held INTEGER NOT NULL,
+    datetime INTEGER NOT NULL);",
+  "ALTER TABLE reactions ADD COLUMN is_deleted INTEGER NOT NULL DEFAULT 0;", // keep removed reaction for sync.
+  "ALTER TABLE message_edits ADD COLUMN is_delivery INTEGER NOT NULL DEFAULT 1;", // resend when friend online.
+  "ALTER TABLE reactions ADD COLUMN is_delivery INTEGER NOT NULL DEFAULT 1;", // resend when friend online.
 ];
@@ -1,3 +1,15 @@
 #[rustfmt::skip]
-pub(super) const CLOUD_VERSIONS: [&str; 0] = [
+pub(super) const CLOUD_VERSIONS: [&str; 2] = [
+  "CREATE TABLE IF NOT EXISTS providers(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    addr TEXT NOT NULL,
+    is_ok INTEGER NOT NULL,
+    is_default INTEGER NOT NULL,
+    used INTEGER NOT NULL,
+    quota INTEGER NOT NULL);",
+  "CREATE TABLE IF NOT EXISTS keys(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    gid TEXT NOT NULL,
+    key TEXT NOT NULL);",
 ];
@@ -3,9 +3,13 @@ pub(crate) const FRIEND_TABLE_PATH: i64 = 1;
 pub(crate) const REQUEST_TABLE_PATH: i64 = 2;
 pub(crate) const MESSAGE_TABLE_PATH: i64 = 3;
 pub(crate) const FILE_TABLE_PATH: i64 = 4;
+pub(crate) const MESSAGE_EDIT_TABLE_PATH: i64 = 5;
+pub(crate) const REACTION_TABLE_PATH: i64 = 6;
+pub(crate) const SESSION_TABLE_PATH: i64 = 7;
+pub(crate) const APP_TABLE_PATH: i64 = 8;
 
 #[rustfmt::skip]
-pub(super) const CONSENSUS_VERSIONS: [&str; 9] = [
+pub(super) const CONSENSUS_VERSIONS: [&str; 12] = [
   "CREATE TABLE IF NOT EXISTS devices(
     id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
     name TEXT NOT NULL,
@@ -29,4 +33,7 @@ pub(super) const CONSENSUS_VERSIONS: [&str; 9] = [
   "INSERT INTO db_tables (db_name, table_name) values ('session.db', 'requests')",
   "INSERT INTO db_tables (db_name, table_name) values ('session.db', 'messages')",
   "INSERT INTO db_tables (db_name, table_name) values ('file.db', 'files')",
+  "INSERT INTO db_tables (db_name, table_name) values ('chat.db', 'message_edits')",
+  "INSERT INTO db_tables (db_name, table_name) values ('chat.db', 'reactions')",
+  "INSERT INTO db_tables (db_name, table_name) values ('session.db', 'sessions')",
 ];
@@ -1,5 +1,5 @@
 #[rustfmt::skip]
-pub(super) const GROUP_VERSIONS: [&str; 3] = [
+pub(super) const GROUP_VERSIONS: [&str; 8] = [
   "CREATE TABLE IF NOT EXISTS groups(
     id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
     height INTEGER NOT NULL,
@@ -26,4 +26,22 @@ pub(super) const GROUP_VERSIONS: [&str; 3] = [
     content TEXT NOT NULL,
     is_delivery INTEGER NOT NULL,
     datetime INTEGER NOT NULL);",
+  "CREATE VIRTUAL TABLE IF NOT EXISTS message_index USING fts5(
+    content,
+    tokenize = 'unicode61 remove_diacritics 2');",
+  "INSERT INTO message_index (rowid, content) SELECT id, content FROM messages WHERE m_type IN (0, 2, 9);", // index history String, File, Transfer.
+  "ALTER TABLE messages ADD COLUMN hash TEXT NOT NULL DEFAULT '';",
+  "ALTER TABLE messages ADD COLUMN parent TEXT NOT NULL DEFAULT '';", // replied message's hash.
+  "CREATE TABLE IF NOT EXISTS transfers(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    hash TEXT NOT NULL,
+    fid INTEGER NOT NULL,
+    is_me INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    file_hash TEXT NOT NULL,
+    chunks TEXT NOT NULL,
+    received TEXT NOT NULL,
+    is_over INTEGER NOT NULL,
+    datetime INTEGER NOT NULL);",
 ];
@@ -1,9 +1,13 @@
 #[rustfmt::skip]
-pub(crate) const JARVIS_VERSIONS: [&str; 1] = [
+pub(crate) const JARVIS_VERSIONS: [&str; 3] = [
   "CREATE TABLE IF NOT EXISTS messages(
     id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
     is_me INTEGER NOT NULL,
     m_type INTEGER NOT NULL,
     content TEXT NOT NULL,
     datetime INTEGER NOT NULL);",
+  "CREATE VIRTUAL TABLE IF NOT EXISTS message_index USING fts5(
+    content,
+    tokenize = 'unicode61 remove_diacritics 2');",
+  "INSERT INTO message_index (rowid, content) SELECT id, content FROM messages WHERE m_type IN (0, 2, 9);", // index history String, File, Transfer.
 ];
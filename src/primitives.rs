@@ -1,15 +1,17 @@
 use tdn::prelude::Peer;
 
-#[inline]
-pub(crate) fn network_seeds() -> Vec<Peer> {
-    #[rustfmt::skip]
-    let seeds: Vec<(&str, &str)> = vec![
-        ("1.15.156.199:7364", "quic"),
-        ("184.170.220.231:7364", "quic"),
-    ];
+/// default transport of seed peers.
+pub(crate) const DEFAULT_TRANSPORT: &'static str = "quic";
 
-    seeds
-        .iter()
-        .map(|(v, t)| Peer::socket_transport(v.parse().unwrap(), t))
+/// parse seeds like `1.15.156.199:7364/quic,184.170.220.231:7364`,
+/// transport is quic if not given, invalid seed is ignored.
+pub(crate) fn parse_seeds(s: &str) -> Vec<Peer> {
+    s.split(',')
+        .filter_map(|seed| {
+            let mut parts = seed.trim().splitn(2, '/');
+            let socket = parts.next()?.parse().ok()?;
+            let transport = parts.next().unwrap_or(DEFAULT_TRANSPORT);
+            Some(Peer::socket_transport(socket, transport))
+        })
         .collect()
 }
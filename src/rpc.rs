@@ -1,5 +1,23 @@
-use std::collections::HashMap;
+use rand::Rng;
+use rpc_types::account::{
+    AccountBackupParams, AccountCreateParams, AccountGenerateParams, AccountGid,
+    AccountIdentitiesParams, AccountIdleParams, AccountInfo, AccountKeyRotateParams,
+    AccountLockParams, AccountLoginParams, AccountPinCheckParams, AccountPinParams,
+    AccountRestoreParams, AccountUpdate, AccountUpdateParams, BackupPath, BackupProgress, Identity,
+    KeyRotated, Mnemonic, Pin, PinCheck, SystemInfo,
+};
+use rpc_types::node::{
+    AddBootstrapParams, RpcScope as ScopeInfo, SeedRemoveParams, Setting as SettingInfo,
+    SettingSetParams, StablePeer,
+};
+use rpc_types::session::{
+    NoticeMenu, SessionAddr, SessionConnectParams, SessionLast, SessionSuspendParams, SessionTtl,
+    SessionUpdate, SessionUpdateParams,
+};
+use rpc_types::{Id, Record};
+use std::collections::{HashMap, VecDeque};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tdn::types::{
     group::GroupId,
@@ -15,12 +33,18 @@ use tokio::sync::{
 
 use crate::account::lang_from_i64;
 use crate::apps::app_rpc_inject;
-use crate::apps::chat::chat_conn;
-use crate::apps::group::{add_layer, group_conn, GroupChat};
-use crate::event::InnerEvent;
+use crate::apps::chat::{chat_conn, mailbox_reshare, LayerEvent as ChatLayerEvent};
+use crate::apps::device::Device;
+use crate::apps::domain::remove_all as domain_remove_all;
+use crate::apps::group::{add_layer, group_conn, leave_all as group_leave_all, GroupChat};
+use crate::backup::{self, Manifest};
+use crate::event::{InnerEvent, StatusEvent};
 use crate::group::Group;
 use crate::layer::{Layer, LayerEvent, Online};
+use crate::migrate::consensus::SESSION_TABLE_PATH;
 use crate::session::{connect_session, Session, SessionType};
+use crate::settings::{Seed, Setting};
+use crate::storage::account_wipe;
 
 pub(crate) fn init_rpc(
     addr: PeerId,
@@ -37,9 +61,284 @@ pub(crate) struct RpcState {
     pub layer: Arc<RwLock<Layer>>,
 }
 
+/// rpc tokens file in data directory, one `scope token` each line.
+pub(crate) const RPC_TOKEN_FILE: &'static str = "rpc.token";
+
+/// max identities derived from one mnemonic, in list and restore.
+const MAX_IDENTITIES: i64 = 32;
+
+/// max authenticated websocket clients, tdn not reports the websocket closed,
+/// the oldest client is removed when more.
+const MAX_WS_CLIENTS: usize = 16;
+
+/// methods can be called without authentication.
+const PUBLIC_METHODS: [&'static str; 2] = ["echo", "rpc-auth"];
+
+/// methods the read-only client can call, only local queries,
+/// not the network requests (device-status, domain-search, wallet-gas-price),
+/// and not the secrets (chat-recovery-list, setting-list).
+const READ_METHODS: [&'static str; 28] = [
+    "rpc-describe",
+    "account-list",
+    "account-system-info",
+    "network-stable",
+    "network-dht",
+    "search-messages",
+    "session-list",
+    "device-list",
+    "chat-friend-list",
+    "chat-request-list",
+    "chat-message-list",
+    "chat-message-history",
+    "chat-detail",
+    "chat-outbox-list",
+    "chat-transfer-list",
+    "chat-emoji-list",
+    "group-list",
+    "group-detail",
+    "group-chat-list",
+    "group-chat-detail",
+    "group-chat-request-list",
+    "group-chat-provider-list",
+    "cloud-provider-list",
+    "dc-list",
+    "domain-list",
+    "jarvis-list",
+    "seed-list",
+    "wallet-list",
+];
+
+/// Permission of the rpc client.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RpcScope {
+    /// only query methods.
+    Read,
+    /// all methods.
+    Full,
+}
+
+impl RpcScope {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "read" => Some(RpcScope::Read),
+            "full" => Some(RpcScope::Full),
+            _ => None,
+        }
+    }
+
+    fn to_str(&self) -> &'static str {
+        match self {
+            RpcScope::Read => "read",
+            RpcScope::Full => "full",
+        }
+    }
+
+    fn allow(&self, method: &str) -> bool {
+        match self {
+            RpcScope::Full => true,
+            RpcScope::Read => READ_METHODS.contains(&method),
+        }
+    }
+}
+
+/// Token authentication of rpc clients.
+/// client carries `token` in request, or calls `rpc-auth [token]` once for websocket.
+pub(crate) struct RpcAuth {
+    tokens: Vec<(RpcScope, String)>,
+    /// authenticated websocket clients, the latest at the back.
+    clients: VecDeque<(u64, RpcScope)>,
+}
+
+impl RpcAuth {
+    /// load tokens from data directory, generate full and read tokens if missing.
+    pub fn load(base: &PathBuf) -> Result<Self> {
+        let mut path = base.clone();
+        path.push(RPC_TOKEN_FILE);
+
+        let mut tokens = vec![];
+        if path.exists() {
+            set_private(&path)?;
+            for line in std::fs::read_to_string(&path)?.lines() {
+                if let Some((scope, token)) = line.trim().split_once(' ') {
+                    if let Some(scope) = RpcScope::from_str(scope) {
+                        tokens.push((scope, token.trim().to_owned()));
+                    }
+                }
+            }
+        }
+
+        if tokens.is_empty() {
+            let mut rng = rand::thread_rng();
+            for scope in [RpcScope::Full, RpcScope::Read] {
+                tokens.push((scope, hex::encode(rng.gen::<[u8; 32]>())));
+            }
+            let content: Vec<String> = tokens
+                .iter()
+                .map(|(s, t)| format!("{} {}", s.to_str(), t))
+                .collect();
+            write_private(&path, content.join("\n").as_bytes())?;
+        }
+
+        Ok(Self {
+            tokens,
+            clients: VecDeque::new(),
+        })
+    }
+
+    fn scope(&self, token: &str) -> Option<RpcScope> {
+        self.tokens
+            .iter()
+            .find(|(_, t)| constant_eq(t.as_bytes(), token.as_bytes()))
+            .map(|(s, _)| *s)
+    }
+
+    /// keep the authenticated websocket client.
+    fn keep(&mut self, uid: u64, scope: RpcScope) {
+        self.remove(uid);
+        if self.clients.len() >= MAX_WS_CLIENTS {
+            self.clients.pop_front();
+        }
+        self.clients.push_back((uid, scope));
+    }
+
+    /// remove the websocket client.
+    fn remove(&mut self, uid: u64) {
+        self.clients.retain(|(u, _)| *u != uid);
+    }
+
+    /// check the request before dispatch, return the response if it is finished here,
+    /// (rejected, or `rpc-auth` answered), none will continue dispatch.
+    pub fn check(&mut self, uid: u64, params: &RpcParam, is_ws: bool) -> Option<RpcParam> {
+        let id = params["id"].as_u64().unwrap_or(0);
+        let method = params["method"].as_str().unwrap_or("");
+
+        if method == "rpc-auth" {
+            // it is checked before normalize, the params maybe named.
+            let token = params["params"][0]
+                .as_str()
+                .or(params["params"]["token"].as_str())
+                .unwrap_or("");
+            return match self.scope(token) {
+                Some(scope) => {
+                    if is_ws {
+                        self.keep(uid, scope);
+                    }
+                    Some(rpc_response(
+                        id,
+                        method,
+                        json!(ScopeInfo {
+                            scope: scope.to_str().to_owned()
+                        }),
+                        GroupId::default(),
+                    ))
+                }
+                None => Some(rpc_reject(id, "rpc token is invalid")),
+            };
+        }
+
+        if PUBLIC_METHODS.contains(&method) {
+            return None;
+        }
+
+        let scope = match params["token"].as_str().and_then(|t| self.scope(t)) {
+            Some(scope) => {
+                if is_ws {
+                    self.keep(uid, scope);
+                }
+                Some(scope)
+            }
+            None => self
+                .clients
+                .iter()
+                .find(|(u, _)| *u == uid)
+                .map(|(_, s)| *s),
+        };
+
+        match scope {
+            Some(scope) if scope.allow(method) => None,
+            Some(_) => Some(rpc_reject(id, "rpc method is not permitted")),
+            None => Some(rpc_reject(id, "rpc unauthorized")),
+        }
+    }
+}
+
+#[inline]
+fn rpc_reject(id: u64, msg: &str) -> RpcParam {
+    rpc_error(id, -32001, msg)
+}
+
+/// the named params is invalid.
+#[inline]
+pub(crate) fn rpc_invalid_params(id: u64, msg: &str) -> RpcParam {
+    rpc_error(id, -32602, msg)
+}
+
+/// ack the method without result (`rpc_types::no_response`), if it is not failure.
+pub(crate) fn rpc_ack(id: u64, method: &str, gid: GroupId, results: &mut HandleResult) {
+    if !rpc_types::no_response(method) {
+        return;
+    }
+    if let Some(first) = results.rpcs.first() {
+        if !first["error"].is_null() {
+            return;
+        }
+    }
+    let ack = rpc_response(id, method, RpcParam::Null, gid);
+    results.rpcs.insert(0, ack);
+}
+
+#[inline]
+fn rpc_error(id: u64, code: i64, msg: &str) -> RpcParam {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": msg,
+        }
+    })
+}
+
+/// compare without early return, not leak the token by timing.
+fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |r, (x, y)| r | (x ^ y)) == 0
+}
+
+/// write the file only the owner can read and write.
+fn write_private(path: &PathBuf, bytes: &[u8]) -> Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    // mode only works when create, the file maybe had.
+    set_private(path)
+}
+
+/// only the owner can read and write the file.
+#[cfg(unix)]
+fn set_private(path: &PathBuf) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_private(_path: &PathBuf) -> Result<()> {
+    Ok(())
+}
+
 #[inline]
 pub(crate) fn network_stable(peers: Vec<(PeerId, bool)>) -> RpcParam {
-    let s_peers: Vec<Vec<String>> = peers
+    let s_peers: Vec<StablePeer> = peers
         .iter()
         .map(|(p, is_d)| {
             let d = if *is_d {
@@ -47,7 +346,10 @@ pub(crate) fn network_stable(peers: Vec<(PeerId, bool)>) -> RpcParam {
             } else {
                 String::from("0")
             };
-            vec![p.to_hex(), d]
+            StablePeer {
+                addr: p.to_hex(),
+                is_direct: d,
+            }
         })
         .collect();
     rpc_response(0, "network-stable", json!(s_peers), GroupId::default())
@@ -64,14 +366,69 @@ pub(crate) fn account_update(mgid: GroupId, name: &str, avatar: String) -> RpcPa
     rpc_response(
         0,
         "account-update",
-        json!([mgid.to_hex(), name, avatar]),
+        json!(AccountUpdate {
+            gid: mgid.to_hex(),
+            name: name.to_owned(),
+            avatar,
+        }),
         mgid,
     )
 }
 
+#[inline]
+pub(crate) fn account_backup_progress(
+    mgid: GroupId,
+    is_export: bool,
+    done: usize,
+    total: usize,
+) -> RpcParam {
+    rpc_response(
+        0,
+        "account-backup-progress",
+        json!(BackupProgress {
+            is_export,
+            done: done as u64,
+            total: total as u64,
+        }),
+        mgid,
+    )
+}
+
+#[inline]
+pub(crate) fn account_identities(mgid: GroupId, ids: Vec<(u32, GroupId, bool)>) -> RpcParam {
+    let ids: Vec<Identity> = ids
+        .into_iter()
+        .map(|(index, gid, had)| Identity {
+            index,
+            gid: gid.to_hex(),
+            had,
+        })
+        .collect();
+    rpc_response(0, "account-identities-found", json!(ids), mgid)
+}
+
+#[inline]
+pub(crate) fn account_key_rotated(mgid: GroupId, device: &PeerId, lost: &PeerId) -> RpcParam {
+    rpc_response(
+        0,
+        "account-key-rotate",
+        json!(KeyRotated {
+            device: device.to_hex(),
+            lost: lost.to_hex(),
+        }),
+        mgid,
+    )
+}
+
+#[inline]
+pub(crate) fn account_locked(mgid: GroupId) -> RpcParam {
+    let data = AccountGid { gid: mgid.to_hex() };
+    rpc_response(0, "account-lock", json!(data), mgid)
+}
+
 #[inline]
 pub(crate) fn session_create(mgid: GroupId, session: &Session) -> RpcParam {
-    rpc_response(0, "session-create", session.to_rpc(), mgid)
+    rpc_response(0, "session-create", json!(session.to_rpc()), mgid)
 }
 
 #[inline]
@@ -82,17 +439,30 @@ pub(crate) fn session_last(
     content: &str,
     readed: bool,
 ) -> RpcParam {
-    rpc_response(0, "session-last", json!([id, time, content, readed]), mgid)
+    let data = SessionLast {
+        id: *id,
+        time: *time,
+        content: content.to_owned(),
+        readed,
+    };
+    rpc_response(0, "session-last", json!(data), mgid)
 }
 
 #[inline]
 pub(crate) fn notice_menu(mgid: GroupId, t: &SessionType) -> RpcParam {
-    rpc_response(0, "notice-menu", json!([t.to_int()]), mgid)
+    let data = NoticeMenu { s_type: t.to_int() };
+    rpc_response(0, "notice-menu", json!(data), mgid)
 }
 
 #[inline]
 pub(crate) fn session_update_name(mgid: GroupId, id: &i64, name: &str) -> RpcParam {
-    rpc_response(0, "session-update", json!([id, "", name, false]), mgid)
+    let data = SessionUpdate {
+        id: *id,
+        addr: String::new(),
+        name: name.to_owned(),
+        is_top: false,
+    };
+    rpc_response(0, "session-update", json!(data), mgid)
 }
 
 #[inline]
@@ -103,37 +473,47 @@ pub(crate) fn session_update(
     name: &str,
     is_top: bool,
 ) -> RpcParam {
-    rpc_response(
-        0,
-        "session-update",
-        json!([id, addr.to_hex(), name, is_top]),
-        mgid,
-    )
+    let data = SessionUpdate {
+        id: *id,
+        addr: addr.to_hex(),
+        name: name.to_owned(),
+        is_top,
+    };
+    rpc_response(0, "session-update", json!(data), mgid)
 }
 
 #[inline]
 pub(crate) fn session_connect(mgid: GroupId, id: &i64, addr: &PeerId) -> RpcParam {
-    rpc_response(0, "session-connect", json!([id, addr.to_hex()]), mgid)
+    let data = SessionAddr {
+        id: *id,
+        addr: addr.to_hex(),
+    };
+    rpc_response(0, "session-connect", json!(data), mgid)
 }
 
 #[inline]
 pub(crate) fn session_suspend(mgid: GroupId, id: &i64) -> RpcParam {
-    rpc_response(0, "session-suspend", json!([id]), mgid)
+    rpc_response(0, "session-suspend", json!(Id { id: *id }), mgid)
 }
 
 #[inline]
 pub(crate) fn session_lost(mgid: GroupId, id: &i64) -> RpcParam {
-    rpc_response(0, "session-lost", json!([id]), mgid)
+    rpc_response(0, "session-lost", json!(Id { id: *id }), mgid)
 }
 
 #[inline]
 pub(crate) fn session_delete(mgid: GroupId, id: &i64) -> RpcParam {
-    rpc_response(0, "session-delete", json!([id]), mgid)
+    rpc_response(0, "session-delete", json!(Id { id: *id }), mgid)
 }
 
 #[inline]
 pub(crate) fn session_close(mgid: GroupId, id: &i64) -> RpcParam {
-    rpc_response(0, "session-close", json!([id]), mgid)
+    rpc_response(0, "session-close", json!(Id { id: *id }), mgid)
+}
+
+#[inline]
+pub(crate) fn session_ttl(mgid: GroupId, id: &i64, ttl: i64) -> RpcParam {
+    rpc_response(0, "session-ttl", json!(SessionTtl { id: *id, ttl }), mgid)
 }
 
 #[inline]
@@ -145,6 +525,12 @@ fn session_list(sessions: Vec<Session>) -> RpcParam {
     json!(results)
 }
 
+/// parse the params to the typed params record.
+#[inline]
+pub(crate) fn parse<T: Record>(params: Vec<RpcParam>) -> std::result::Result<T, RpcError> {
+    serde_json::from_value(RpcParam::Array(params)).map_err(|_| RpcError::ParseError)
+}
+
 #[inline]
 pub(crate) async fn sleep_waiting_close_stable(
     sender: Sender<SendMessage>,
@@ -152,6 +538,15 @@ pub(crate) async fn sleep_waiting_close_stable(
     layers: HashMap<PeerId, GroupId>,
 ) -> std::result::Result<(), SendError<SendMessage>> {
     tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    close_stable(&sender, groups, layers).await
+}
+
+/// disconnect the devices and friends.
+pub(crate) async fn close_stable(
+    sender: &Sender<SendMessage>,
+    groups: HashMap<PeerId, ()>,
+    layers: HashMap<PeerId, GroupId>,
+) -> std::result::Result<(), SendError<SendMessage>> {
     for (addr, _) in groups {
         sender
             .send(SendMessage::Group(
@@ -174,6 +569,74 @@ pub(crate) async fn sleep_waiting_close_stable(
     Ok(())
 }
 
+/// all running accounts offline, send the offline events to online friends,
+/// return the connections need close.
+pub(crate) async fn accounts_logout(
+    group: &Arc<RwLock<Group>>,
+    layer: &Arc<RwLock<Layer>>,
+    results: &mut HandleResult,
+) -> Result<(HashMap<PeerId, ()>, HashMap<PeerId, GroupId>)> {
+    let group_lock = group.read().await;
+    let layer_lock = layer.read().await;
+    let keys = group_lock.list_running_user();
+
+    for gid in keys {
+        for (fgid, addr) in layer_lock.running(&gid)?.onlines() {
+            // send a event that is offline.
+            let data = bincode::serialize(&LayerEvent::Offline(*fgid))?;
+            let msg = SendType::Event(0, *addr, data);
+            results.layers.push((gid, *fgid, msg));
+        }
+
+        debug!("Account Offline: {}.", gid.to_hex());
+        // add Remove Group to TDN.
+        results.networks.push(NetworkType::DelGroup(gid));
+    }
+    drop(group_lock);
+    drop(layer_lock);
+
+    let layers = layer.write().await.remove_all_running();
+    let groups = group.write().await.remove_all_running();
+    Ok((groups, layers))
+}
+
+/// account offline, if lock, the cached key also cleared.
+pub(crate) async fn account_offline(
+    group: &Arc<RwLock<Group>>,
+    layer: &Arc<RwLock<Layer>>,
+    gid: GroupId,
+    lock: bool,
+    results: &mut HandleResult,
+) -> Result<()> {
+    let layer_lock = layer.read().await;
+    for (fgid, addr) in layer_lock.running(&gid)?.onlines() {
+        // send a event that is offline.
+        let data = bincode::serialize(&LayerEvent::Offline(*fgid))?;
+        let msg = SendType::Event(0, *addr, data);
+        results.layers.push((gid, *fgid, msg));
+    }
+    drop(layer_lock);
+
+    let layers = layer.write().await.remove_running(&gid);
+    let mut group_lock = group.write().await;
+    let groups = if lock {
+        group_lock.lock_account(&gid)
+    } else {
+        group_lock.remove_running(&gid)
+    };
+    let sender = group_lock.sender();
+    drop(group_lock);
+    tokio::spawn(sleep_waiting_close_stable(sender, groups, layers));
+
+    debug!("Account Offline: {}.", gid.to_hex());
+    // add Remove Group to TDN.
+    results.networks.push(NetworkType::DelGroup(gid));
+    if lock {
+        results.rpcs.push(account_locked(gid));
+    }
+    Ok(())
+}
+
 #[inline]
 pub(crate) async fn inner_rpc(uid: u64, method: &str, sender: &Sender<SendMessage>) -> Result<()> {
     // Inner network default rpc method. only use in http-rpc.
@@ -220,37 +683,104 @@ fn new_rpc_handler(
         Ok(HandleResult::rpc(json!(params)))
     });
 
+    handler.add_method("rpc-describe", |_, _, _| async move {
+        Ok(HandleResult::rpc(rpc_types::describe()))
+    });
+
     handler.add_method("account-system-info", move |_, _, _| async move {
-        Ok(HandleResult::rpc(json!(vec![addr.to_hex()])))
+        let info = SystemInfo {
+            addr: addr.to_hex(),
+        };
+        Ok(HandleResult::rpc(json!(info)))
     });
 
     handler.add_method(
         "add-bootstrap",
-        |_gid, params: Vec<RpcParam>, _| async move {
-            let socket = params[0].as_str().ok_or(RpcError::ParseError)?;
-            let transport = params[1].as_str().ok_or(RpcError::ParseError)?;
+        |_gid, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let AddBootstrapParams { socket, transport } = parse(params)?;
 
             if let Ok(addr) = socket.parse::<SocketAddr>() {
-                Ok(HandleResult::network(NetworkType::Connect(
-                    Peer::socket_transport(addr, transport),
-                )))
+                // persist it, as seed when next start.
+                let mut seed = Seed::new(addr, transport);
+                let db = state.group.read().await.account_db()?;
+                seed.insert(&db)?;
+                db.close()?;
+
+                Ok(HandleResult::network(NetworkType::Connect(seed.peer())))
             } else {
                 Err(RpcError::InvalidRequest)
             }
         },
     );
 
+    handler.add_method(
+        "seed-list",
+        |_gid, _params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let db = state.group.read().await.account_db()?;
+            let seeds = Seed::list(&db)?;
+            db.close()?;
+            let seeds: Vec<_> = seeds.iter().map(|s| s.to_rpc()).collect();
+            Ok(HandleResult::rpc(json!(seeds)))
+        },
+    );
+
+    handler.add_method(
+        "seed-remove",
+        |_gid, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let SeedRemoveParams { socket } = parse(params)?;
+            let addr = socket
+                .parse::<SocketAddr>()
+                .map_err(|_| RpcError::InvalidRequest)?;
+
+            let db = state.group.read().await.account_db()?;
+            Seed::delete(&db, &addr)?;
+            db.close()?;
+            Ok(HandleResult::new())
+        },
+    );
+
+    handler.add_method(
+        "setting-list",
+        |_gid, _params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let db = state.group.read().await.account_db()?;
+            let settings: Vec<SettingInfo> = Setting::list(&db)?
+                .into_iter()
+                .map(|(name, value)| SettingInfo { name, value })
+                .collect();
+            db.close()?;
+            Ok(HandleResult::rpc(json!(settings)))
+        },
+    );
+
+    handler.add_method(
+        "setting-set",
+        |_gid, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let SettingSetParams { key, value } = parse(params)?;
+
+            // empty value is reset to default.
+            let db = state.group.read().await.account_db()?;
+            if value.is_empty() {
+                Setting::remove(&db, &key)?;
+            } else {
+                Setting::set(&db, &key, &value).map_err(|e| RpcError::Custom(e.to_string()))?;
+            }
+            db.close()?;
+            Ok(HandleResult::new())
+        },
+    );
+
     handler.add_method(
         "account-list",
         |_gid, _params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let mut users: Vec<Vec<String>> = vec![];
+            let mut users: Vec<AccountInfo> = vec![];
             let group_lock = state.group.read().await;
             for (gid, user) in group_lock.list_users().iter() {
-                users.push(vec![
-                    gid.to_hex(),
-                    user.name.clone(),
-                    base64::encode(&user.avatar),
-                ]);
+                users.push(AccountInfo {
+                    gid: gid.to_hex(),
+                    name: user.name.clone(),
+                    avatar: base64::encode(&user.avatar),
+                    index: user.index.to_string(),
+                });
             }
             drop(group_lock);
 
@@ -261,34 +791,65 @@ fn new_rpc_handler(
     handler.add_method(
         "account-generate",
         |_gid, params: Vec<RpcParam>, _state: Arc<RpcState>| async move {
-            let lang = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let AccountGenerateParams { lang } = parse(params)?;
             let language = lang_from_i64(lang);
             let words = generate_mnemonic(language, Count::Words12);
-            Ok(HandleResult::rpc(json!([words])))
+            Ok(HandleResult::rpc(json!(Mnemonic { words })))
         },
     );
 
     handler.add_method(
-        "account-create",
+        "account-identities",
         |_gid, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let lang = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let seed = params[1].as_str().ok_or(RpcError::ParseError)?;
-            let pass = params[2].as_str().ok_or(RpcError::ParseError)?;
+            let AccountIdentitiesParams {
+                lang,
+                seed,
+                pass,
+                count,
+            } = parse(params)?;
+
+            let count = count.clamp(1, MAX_IDENTITIES) as u32;
+            let ids = state
+                .group
+                .read()
+                .await
+                .identities(lang, &seed, &pass, count)?;
+            let ids: Vec<Identity> = ids
+                .into_iter()
+                .map(|(index, gid, had)| Identity {
+                    index,
+                    gid: gid.to_hex(),
+                    had,
+                })
+                .collect();
+            Ok(HandleResult::rpc(json!(ids)))
+        },
+    );
 
-            let name = params[3].as_str().ok_or(RpcError::ParseError)?;
-            let lock = params[4].as_str().ok_or(RpcError::ParseError)?;
-            let avatar = params[5].as_str().ok_or(RpcError::ParseError)?;
+    handler.add_method(
+        "account-create",
+        |_gid, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            // identity index in the mnemonic, default is 0.
+            let AccountCreateParams {
+                lang,
+                seed,
+                pass,
+                name,
+                lock,
+                avatar,
+                index,
+            } = parse(params)?;
 
             let avatar_bytes = base64::decode(avatar).unwrap_or(vec![]);
             let (id, gid) = state
                 .group
                 .write()
                 .await
-                .add_account(lang, seed, pass, name, lock, avatar_bytes)
+                .add_account(index as u32, lang, &seed, &pass, &name, &lock, avatar_bytes)
                 .await?;
             state.layer.write().await.add_running(&gid, gid, id, 0)?;
 
-            let mut results = HandleResult::rpc(json!(vec![gid.to_hex()]));
+            let mut results = HandleResult::rpc(json!(AccountGid { gid: gid.to_hex() }));
             results.networks.push(NetworkType::AddGroup(gid)); // add AddGroup to TDN.
 
             debug!("Account Logined: {}.", gid.to_hex());
@@ -300,38 +861,54 @@ fn new_rpc_handler(
     handler.add_method(
         "account-restore",
         |_gid, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let lang = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let seed = params[1].as_str().ok_or(RpcError::ParseError)?;
-            let pass = params[2].as_str().ok_or(RpcError::ParseError)?;
+            let AccountRestoreParams {
+                lang,
+                seed,
+                pass,
+                name,
+                lock,
+                addr,
+                count,
+            } = parse(params)?;
+
+            let some_addr = PeerId::from_hex(&addr).ok();
+            // scan the identities at index 1..count in the remote device, default is 1.
+            let count = count.unwrap_or(1).clamp(1, MAX_IDENTITIES) as u32;
 
-            let name = params[3].as_str().ok_or(RpcError::ParseError)?;
-            let lock = params[4].as_str().ok_or(RpcError::ParseError)?;
-
-            let some_addr = PeerId::from_hex(params[5].as_str().ok_or(RpcError::ParseError)?).ok();
-
-            let (id, gid) = state
-                .group
-                .write()
-                .await
-                .add_account(lang, seed, pass, name, lock, vec![])
+            let mut results = HandleResult::new();
+            let mut group_lock = state.group.write().await;
+            let scans = if some_addr.is_some() {
+                let ids = group_lock.identities(lang, &seed, &pass, count)?;
+                ids.into_iter()
+                    .skip(1)
+                    .map(|(i, gid, _)| (i, gid))
+                    .collect()
+            } else {
+                vec![]
+            };
+            let (id, gid) = group_lock
+                .add_account(0, lang, &seed, &pass, &name, &lock, vec![])
                 .await?;
-            state.layer.write().await.add_running(&gid, gid, id, 0)?;
+            group_lock.running_mut(&gid)?.scans = scans;
+            let sender = group_lock.sender();
+            drop(group_lock);
 
-            let mut results = HandleResult::rpc(json!(vec![gid.to_hex()]));
+            state.layer.write().await.add_running(&gid, gid, id, 0)?;
             results.networks.push(NetworkType::AddGroup(gid)); // add AddGroup to TDN.
-
             debug!("Account Logined: {}.", gid.to_hex());
 
             if let Some(addr) = some_addr {
-                let group_lock = state.group.read().await;
-                let sender = group_lock.sender();
-                let msg = group_lock.create_message(&gid, Peer::peer(addr))?;
-                drop(group_lock);
+                let msg = state
+                    .group
+                    .read()
+                    .await
+                    .create_message(&gid, Peer::peer(addr))?;
                 tokio::spawn(async move {
                     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
                     let _ = sender.send(SendMessage::Group(gid, msg)).await;
                 });
             }
+            results.rpcs.push(json!(vec![gid.to_hex()]));
 
             Ok(results)
         },
@@ -340,19 +917,18 @@ fn new_rpc_handler(
     handler.add_method(
         "account-update",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let name = params[0].as_str().ok_or(RpcError::ParseError)?;
-            let avatar = params[1].as_str().ok_or(RpcError::ParseError)?;
+            let AccountUpdateParams { name, avatar } = parse(params)?;
 
             let avatar_bytes = base64::decode(avatar).unwrap_or(vec![]);
 
             let mut group_lock = state.group.write().await;
-            group_lock.update_account(gid, name, avatar_bytes.clone())?;
+            group_lock.update_account(gid, &name, avatar_bytes.clone())?;
             let user = group_lock.clone_user(&gid)?;
 
             let mut results = HandleResult::new();
             group_lock.broadcast(
                 &gid,
-                InnerEvent::UserInfo(name.to_owned(), avatar_bytes.clone()),
+                InnerEvent::UserInfo(name, avatar_bytes.clone()),
                 0,
                 0,
                 &mut results,
@@ -369,20 +945,19 @@ fn new_rpc_handler(
     handler.add_method(
         "account-pin-check",
         |_gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let gid = GroupId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
-            let lock = params[1].as_str().ok_or(RpcError::ParseError)?;
-            let res = state.group.read().await.check_lock(&gid, lock);
-            Ok(HandleResult::rpc(json!([res])))
+            let AccountPinCheckParams { gid, lock } = parse(params)?;
+            let gid = GroupId::from_hex(&gid)?;
+            let valid = state.group.read().await.check_lock(&gid, &lock);
+            Ok(HandleResult::rpc(json!(PinCheck { valid })))
         },
     );
 
     handler.add_method(
         "account-pin",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let old = params[0].as_str().ok_or(RpcError::ParseError)?;
-            let new = params[1].as_str().ok_or(RpcError::ParseError)?;
-            let result = HandleResult::rpc(json!([new]));
-            state.group.write().await.pin(&gid, old, new)?;
+            let AccountPinParams { old, new } = parse(params)?;
+            state.group.write().await.pin(&gid, &old, &new)?;
+            let result = HandleResult::rpc(json!(Pin { lock: new }));
             Ok(result)
         },
     );
@@ -390,22 +965,160 @@ fn new_rpc_handler(
     handler.add_method(
         "account-mnemonic",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let lock = params[0].as_str().ok_or(RpcError::ParseError)?;
+            let AccountLockParams { lock } = parse(params)?;
+
+            let words = state.group.read().await.mnemonic(&gid, &lock)?;
+            Ok(HandleResult::rpc(json!(Mnemonic { words })))
+        },
+    );
+
+    handler.add_method(
+        "account-backup-export",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let AccountBackupParams { path, lock } = parse(params)?;
+            let path = PathBuf::from(path);
+
+            let group_lock = state.group.read().await;
+            let account = group_lock.account(&gid)?;
+            account.check_lock(&lock)?;
+            let manifest = Manifest {
+                gid,
+                key: hex::decode(account.plainkey())?,
+                height: account.own_height,
+                event: account.event,
+                entries: vec![],
+            };
+            let key = group_lock.backup_key(&gid)?;
+            let base = group_lock.base().clone();
+            let sender = group_lock.sender();
+            drop(group_lock);
+
+            backup::export(&base, manifest, &key, &path, sender).await?;
+            let path = path.to_string_lossy().into_owned();
+            Ok(HandleResult::rpc(json!(BackupPath { path })))
+        },
+    );
+
+    handler.add_method(
+        "account-backup-import",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let AccountBackupParams { path, lock } = parse(params)?;
+            let path = PathBuf::from(path);
+
+            let group_lock = state.group.read().await;
+            group_lock.account(&gid)?.check_lock(&lock)?;
+            let key = group_lock.backup_key(&gid)?;
+            let base = group_lock.base().clone();
+            let sender = group_lock.sender();
+            drop(group_lock);
+
+            let manifest = backup::stage(&base, &gid, &key, &path, sender).await?;
+
+            // the databases will be replaced, login again after imported.
+            let mut results = HandleResult::rpc(json!(AccountGid { gid: gid.to_hex() }));
+            account_offline(&state.group, &state.layer, gid, false, &mut results).await?;
+            backup::swap(&base, &gid).await?;
+            state
+                .group
+                .write()
+                .await
+                .import_account(&gid, &lock, manifest)?;
+
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "account-key-rotate",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let AccountKeyRotateParams { lock, lost } = parse(params)?;
+            let lost = if let Some(addr) = lost {
+                PeerId::from_hex(&addr)?
+            } else {
+                PeerId::default()
+            };
+
+            let mut results = HandleResult::rpc(json!(AccountGid { gid: gid.to_hex() }));
+            let mut group_lock = state.group.write().await;
+            group_lock.rotate_key(&gid, &lock)?;
+            if lost != PeerId::default() {
+                let db = group_lock.consensus_db(&gid)?;
+                Device::delete_by_addr(&db, &lost)?;
+                drop(db);
+                group_lock.running_mut(&gid)?.distributes.remove(&lost);
+            }
+            let wrapped = group_lock.wrapped_key(&gid)?;
+            let rotated = group_lock.account(&gid)?.rotated;
+            let event = StatusEvent::AccountKeyRotated(lost, wrapped, rotated);
+            group_lock.status(&gid, event, &mut results)?;
+            drop(group_lock);
+
+            // mailbox keys changed, share to online friends again.
+            mailbox_reshare(&*state.layer.read().await, gid, &mut results).await;
+
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "account-delete",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let AccountLockParams { lock } = parse(params)?;
 
-            let mnemonic = state.group.read().await.mnemonic(&gid, lock)?;
-            Ok(HandleResult::rpc(json!([mnemonic])))
+            let mut results = HandleResult::rpc(json!(AccountGid { gid: gid.to_hex() }));
+            let layer_lock = state.layer.read().await;
+            let mut group_lock = state.group.write().await;
+            group_lock.account(&gid)?.check_lock(&lock)?;
+            // the account maybe not login, unlock the databases by pin.
+            group_lock.unlock(&gid, &lock)?;
+            let is_running = group_lock.running(&gid).is_ok();
+
+            // leave all group chats and domains.
+            let group_db = group_lock.group_db(&gid)?;
+            group_leave_all(&layer_lock, &group_db, gid, &mut results)?;
+            let group_chats = GroupChat::local(&group_db)?;
+            drop(group_db);
+            drop(layer_lock);
+            domain_remove_all(&group_lock.domain_db(&gid)?, gid, &mut results)?;
+
+            // tell my other devices.
+            if is_running {
+                group_lock.status(&gid, StatusEvent::AccountDeleted, &mut results)?;
+            }
+            let base = group_lock.base().clone();
+            let sender = group_lock.sender();
+            drop(group_lock);
+
+            if is_running {
+                account_offline(&state.group, &state.layer, gid, false, &mut results).await?;
+            }
+
+            // stop my group chats.
+            let mut layers = HashMap::new();
+            let mut layer_lock = state.layer.write().await;
+            for g in group_chats {
+                layers.extend(layer_lock.remove_running(&g.g_id));
+                results.networks.push(NetworkType::DelGroup(g.g_id));
+            }
+            drop(layer_lock);
+            tokio::spawn(sleep_waiting_close_stable(sender, HashMap::new(), layers));
+
+            state.group.write().await.delete_account(&gid)?;
+            account_wipe(&base, &gid).await?;
+
+            Ok(results)
         },
     );
 
     handler.add_method(
         "account-login",
         |_gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let ogid = GroupId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
-            let me_lock = params[1].as_str().ok_or(RpcError::ParseError)?;
+            let AccountLoginParams { gid, lock } = parse(params)?;
+            let ogid = GroupId::from_hex(&gid)?;
 
-            let mut results = HandleResult::rpc(json!([ogid.to_hex()]));
+            let mut results = HandleResult::rpc(json!(AccountGid { gid }));
 
-            let (id, running) = state.group.write().await.add_running(&ogid, me_lock)?;
+            let (id, running) = state.group.write().await.add_running(&ogid, &lock)?;
             if running {
                 return Ok(results);
             }
@@ -442,6 +1155,9 @@ fn new_rpc_handler(
             }
             drop(layer_lock);
 
+            // 3. fetch the mails when offline.
+            let _ = crate::apps::cloud::fetch(&state.group, ogid, &mut results).await;
+
             debug!("Account Logined: {}.", ogid.to_hex());
 
             Ok(results)
@@ -452,44 +1168,28 @@ fn new_rpc_handler(
         "account-logout",
         |_gid: GroupId, _params: Vec<RpcParam>, state: Arc<RpcState>| async move {
             let mut results = HandleResult::new();
-
-            let group_lock = state.group.read().await;
-            let layer_lock = state.layer.read().await;
-            let keys = group_lock.list_running_user();
-
-            for gid in keys {
-                for (fgid, addr) in layer_lock.running(&gid)?.onlines() {
-                    // send a event that is offline.
-                    let data = bincode::serialize(&LayerEvent::Offline(*fgid))?;
-                    let msg = SendType::Event(0, *addr, data);
-                    results.layers.push((gid, *fgid, msg));
-                }
-
-                debug!("Account Offline: {}.", gid.to_hex());
-                // add Remove Group to TDN.
-                results.networks.push(NetworkType::DelGroup(gid));
-            }
-            drop(group_lock);
-            drop(layer_lock);
-
-            let mut layer_lock = state.layer.write().await;
-            let layers = layer_lock.remove_all_running();
-            drop(layer_lock);
-
-            let mut group_lock = state.group.write().await;
-            let groups = group_lock.remove_all_running();
-            let sender = group_lock.sender();
-            drop(group_lock);
+            let (groups, layers) =
+                accounts_logout(&state.group, &state.layer, &mut results).await?;
+            let sender = state.group.read().await.sender();
             tokio::spawn(sleep_waiting_close_stable(sender, groups, layers));
 
             Ok(results)
         },
     );
 
+    handler.add_method(
+        "shutdown",
+        |_gid: GroupId, _params: Vec<RpcParam>, _state: Arc<RpcState>| async move {
+            crate::server::shutdown();
+            Ok(HandleResult::new())
+        },
+    );
+
     handler.add_method(
         "account-online",
         |_gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let gid = GroupId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
+            let AccountGid { gid } = parse(params)?;
+            let gid = GroupId::from_hex(&gid)?;
 
             let mut results = HandleResult::new();
 
@@ -509,30 +1209,28 @@ fn new_rpc_handler(
     handler.add_method(
         "account-offline",
         |_gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let gid = GroupId::from_hex(params[0].as_str().ok_or(RpcError::ParseError)?)?;
+            let AccountGid { gid } = parse(params)?;
+            let gid = GroupId::from_hex(&gid)?;
 
             let mut results = HandleResult::new();
-            let layer_lock = state.layer.read().await;
-            for (fgid, addr) in layer_lock.running(&gid)?.onlines() {
-                // send a event that is offline.
-                let data = bincode::serialize(&LayerEvent::Offline(*fgid))?;
-                let msg = SendType::Event(0, *addr, data);
-                results.layers.push((gid, *fgid, msg));
-            }
-            drop(layer_lock);
+            account_offline(&state.group, &state.layer, gid, false, &mut results).await?;
+            Ok(results)
+        },
+    );
+
+    handler.add_method(
+        "account-idle",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let AccountIdleParams { idle } = parse(params)?;
 
-            let layers = state.layer.write().await.remove_running(&gid);
             let mut group_lock = state.group.write().await;
-            let groups = group_lock.remove_running(&gid);
-            let sender = group_lock.sender();
+            let db = group_lock.account_db()?;
+            let account = group_lock.account_mut(&gid)?;
+            account.idle = if idle > 0 { idle } else { 0 };
+            account.update_idle(&db)?;
             drop(group_lock);
-            tokio::spawn(sleep_waiting_close_stable(sender, groups, layers));
 
-            debug!("Account Offline: {}.", gid.to_hex());
-            // add Remove Group to TDN.
-            results.networks.push(NetworkType::DelGroup(gid));
-
-            Ok(results)
+            Ok(HandleResult::rpc(json!(AccountIdleParams { idle })))
         },
     );
 
@@ -547,8 +1245,8 @@ fn new_rpc_handler(
     handler.add_method(
         "session-connect",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let remote = GroupId::from_hex(params[1].as_str().ok_or(RpcError::ParseError)?)?;
+            let SessionConnectParams { id, remote } = parse(params)?;
+            let remote = GroupId::from_hex(&remote)?;
 
             let group_lock = state.group.read().await;
             let db = group_lock.session_db(&gid)?;
@@ -558,7 +1256,8 @@ fn new_rpc_handler(
             let online = layer_lock.running_mut(&gid)?.active(&remote, true);
             drop(layer_lock);
             if let Some(addr) = online {
-                return Ok(HandleResult::rpc(json!([id, addr.to_hex()])));
+                let addr = addr.to_hex();
+                return Ok(HandleResult::rpc(json!(SessionAddr { id, addr })));
             }
 
             let s = Session::get(&db, &id)?;
@@ -589,9 +1288,8 @@ fn new_rpc_handler(
     handler.add_method(
         "session-suspend",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let remote = GroupId::from_hex(params[1].as_str().ok_or(RpcError::ParseError)?)?;
-            let must = params[2].as_bool().ok_or(RpcError::ParseError)?; // if need must suspend.
+            let SessionSuspendParams { id, remote, must } = parse(params)?;
+            let remote = GroupId::from_hex(&remote)?;
 
             let db = state.group.read().await.session_db(&gid)?;
             let s = Session::get(&db, &id)?;
@@ -614,7 +1312,7 @@ fn new_rpc_handler(
 
             let mut results = HandleResult::new();
             if suspend {
-                results.rpcs.push(json!([id]))
+                results.rpcs.push(json!(Id { id }))
             }
 
             match s.s_type {
@@ -634,7 +1332,7 @@ fn new_rpc_handler(
     handler.add_method(
         "session-readed",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
+            let Id { id } = parse(params)?;
             let db = state.group.read().await.session_db(&gid)?;
             Session::readed(&db, &id)?;
             Ok(HandleResult::new())
@@ -644,9 +1342,11 @@ fn new_rpc_handler(
     handler.add_method(
         "session-update",
         |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
-            let id = params[0].as_i64().ok_or(RpcError::ParseError)?;
-            let is_top = params[1].as_bool().ok_or(RpcError::ParseError)?;
-            let is_close = params[2].as_bool().ok_or(RpcError::ParseError)?;
+            let SessionUpdateParams {
+                id,
+                is_top,
+                is_close,
+            } = parse(params)?;
 
             let db = state.group.read().await.session_db(&gid)?;
             Session::update(&db, &id, is_top, is_close)?;
@@ -654,5 +1354,42 @@ fn new_rpc_handler(
         },
     );
 
+    handler.add_method(
+        "session-ttl",
+        |gid: GroupId, params: Vec<RpcParam>, state: Arc<RpcState>| async move {
+            let SessionTtl { id, ttl } = parse(params)?;
+            if Session::check_ttl(ttl).is_err() {
+                return Err(RpcError::Custom("session ttl is invalid".to_owned()));
+            }
+
+            let db = state.group.read().await.session_db(&gid)?;
+            let s = Session::get(&db, &id)?;
+            // group chat's members cannot agree the retention, only friend's chat.
+            if !matches!(s.s_type, SessionType::Chat) {
+                return Err(RpcError::Custom("only chat session has ttl".to_owned()));
+            }
+            Session::update_ttl(&db, &id, ttl)?;
+            drop(db);
+
+            let mut results = HandleResult::new();
+            // tell friend to do the same.
+            if let Ok(addr) = state.layer.read().await.running(&gid)?.online(&s.gid) {
+                let data = bincode::serialize(&ChatLayerEvent::SessionTtl(ttl))?;
+                results
+                    .layers
+                    .push((gid, s.gid, SendType::Event(0, addr, data)));
+            }
+
+            state.group.write().await.broadcast(
+                &gid,
+                InnerEvent::SessionTtl(s.gid, s.s_type.to_int(), ttl),
+                SESSION_TABLE_PATH,
+                id,
+                &mut results,
+            )?;
+            Ok(results)
+        },
+    );
+
     handler
 }
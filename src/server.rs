@@ -1,33 +1,88 @@
-use once_cell::sync::OnceCell;
+use once_cell::sync::Lazy;
 use simplelog::{CombinedLogger, Config as LogConfig, LevelFilter};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Once};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tdn::{
     prelude::*,
-    types::primitive::{HandleResult, Result},
+    types::{
+        primitive::{HandleResult, Result},
+        rpc::RpcParam,
+    },
 };
 use tokio::{
     sync::mpsc::{error::SendError, Sender},
-    sync::RwLock,
+    sync::{Notify, RwLock},
 };
 
 use tdn_storage::local::DStorage;
 
 use crate::account::Account;
 use crate::apps::app_layer_handle;
+use crate::apps::chat::{retry_streams, rpc as chat_rpc, Message as ChatMessage, TRANSFER_TIMEOUT};
+use crate::apps::cloud;
+use crate::apps::group::{rpc as group_rpc, Message as GroupMessage};
+use crate::embed::{self, EMBED_UID};
 use crate::group::Group;
 use crate::layer::Layer;
 use crate::migrate::{main_migrate, ACCOUNT_DB};
-use crate::primitives::network_seeds;
-use crate::rpc::{init_rpc, inner_rpc};
+use crate::rpc::{
+    account_offline, accounts_logout, close_stable, init_rpc, inner_rpc, rpc_ack,
+    rpc_invalid_params, RpcAuth,
+};
+use crate::session::{Session, SessionType};
+use crate::settings::Settings;
+use crate::storage::flush_writes;
 
 pub const DEFAULT_WS_ADDR: &'static str = "127.0.0.1:8080";
 pub const DEFAULT_LOG_FILE: &'static str = "esse.log.txt";
 
-pub static RPC_WS_UID: OnceCell<u64> = OnceCell::new();
+/// first websocket rpc client's uid, 0 is none.
+static RPC_WS_UID: AtomicU64 = AtomicU64::new(0);
+
+/// notify the running core to stop.
+static SHUTDOWN: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// the core is asked to stop, it keeps until `reset`.
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+/// the logger is global, it is only init once in the process.
+static LOG: Once = Once::new();
+
+/// stop the running core, it returns from `start`.
+pub fn shutdown() {
+    STOPPING.store(true, Ordering::SeqCst);
+    SHUTDOWN.notify_waiters();
+}
+
+/// clear the stopped core's state, then it can start again in the process.
+pub(crate) fn reset() {
+    STOPPING.store(false, Ordering::SeqCst);
+    RPC_WS_UID.store(0, Ordering::SeqCst);
+}
+
+#[inline]
+fn rpc_ws_uid() -> Option<u64> {
+    match RPC_WS_UID.load(Ordering::SeqCst) {
+        0 => None,
+        uid => Some(uid),
+    }
+}
 
 pub async fn start(db_path: String) -> Result<()> {
+    start_with(db_path, None, None, vec![]).await
+}
+
+/// start with the rpc ws, p2p address and extra seeds, override the settings.
+pub async fn start_with(
+    db_path: String,
+    ws_addr: Option<SocketAddr>,
+    p2p_addr: Option<SocketAddr>,
+    mut seeds: Vec<Peer>,
+) -> Result<()> {
     let db_path = PathBuf::from(db_path);
     if !db_path.exists() {
         tokio::fs::create_dir_all(&db_path).await?;
@@ -37,12 +92,34 @@ pub async fn start(db_path: String) -> Result<()> {
 
     let mut config = Config::load_save(db_path.clone()).await;
     config.db_path = Some(db_path.clone());
-    config.p2p_allowlist.append(&mut network_seeds());
-    // use self sign to bootstrap peer.
+
+    let rand_secret = config.secret.clone();
+    main_migrate(&db_path, &hex::encode(&rand_secret))?;
+    info!("Core storage path {:?}", db_path);
+    let mut rpc_auth = RpcAuth::load(&db_path)?;
+
+    let mut account_db_path = db_path.clone();
+    account_db_path.push(ACCOUNT_DB);
+    let account_db = DStorage::open(account_db_path, &hex::encode(&rand_secret))?;
+
+    // priority: command args > environment > settings store > config file.
+    let mut settings = Settings::load(&account_db)?;
+    if let Err(e) = settings.pin.init() {
+        warn!("Pin params: {}", e);
+    }
+    config.p2p_allowlist.append(&mut settings.seeds);
+    config.p2p_allowlist.append(&mut seeds);
+    if let Some(addr) = ws_addr.or(settings.ws_addr) {
+        config.rpc_ws = Some(addr);
+    }
     if config.rpc_ws.is_none() {
         // set default ws addr.
         config.rpc_ws = Some(DEFAULT_WS_ADDR.parse().unwrap());
     }
+    if let Some(addr) = p2p_addr.or(settings.p2p_addr) {
+        config.p2p_peer = Peer::socket_transport(addr, config.p2p_peer.transport.to_str());
+    }
+    cloud::set_provider(settings.cloud_provider);
 
     info!("Config RPC HTTP : {:?}", config.rpc_addr);
     info!("Config RPC WS   : {:?}", config.rpc_ws);
@@ -51,14 +128,8 @@ pub async fn start(db_path: String) -> Result<()> {
         config.p2p_peer.transport.to_str(),
         config.p2p_peer.socket
     );
+    info!("Config Seeds    : {}", config.p2p_allowlist.len());
 
-    let rand_secret = config.secret.clone();
-    main_migrate(&db_path, &hex::encode(&rand_secret))?;
-    info!("Core storage path {:?}", db_path);
-
-    let mut account_db_path = db_path.clone();
-    account_db_path.push(ACCOUNT_DB);
-    let account_db = DStorage::open(account_db_path, &hex::encode(&rand_secret))?;
     let accounts = Account::all(&account_db)?;
     account_db.close()?;
     let mut me: HashMap<GroupId, Account> = HashMap::new();
@@ -67,7 +138,8 @@ pub async fn start(db_path: String) -> Result<()> {
     }
     config.group_ids = me.keys().cloned().collect();
 
-    let (peer_id, sender, mut recver) = start_with_config(config).await?;
+    let (peer_id, sender, recver) = start_with_config(config).await?;
+    let (sender, mut recver) = embed::attach(sender, recver);
     info!("Network Peer id : {}", peer_id.to_hex());
 
     let group = Arc::new(RwLock::new(
@@ -84,7 +156,29 @@ pub async fn start(db_path: String) -> Result<()> {
     // running session remain task.
     tokio::spawn(session_remain(peer_id, layer.clone(), sender.clone()));
 
-    while let Some(message) = recver.recv().await {
+    // disappearing messages task.
+    tokio::spawn(session_expire(group.clone(), sender.clone()));
+
+    // idle auto-lock task.
+    tokio::spawn(idle_lock(group.clone(), layer.clone(), sender.clone()));
+
+    // file transfers lost chunks task.
+    tokio::spawn(transfer_retry(layer.clone(), sender.clone()));
+
+    loop {
+        // created before checking, the shutdown between them is not missed.
+        let stopped = SHUTDOWN.notified();
+        if STOPPING.load(Ordering::SeqCst) {
+            break;
+        }
+        let message = tokio::select! {
+            msg = recver.recv() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = stopped => break,
+        };
+
         match message {
             ReceiveMessage::Group(fgid, g_msg) => {
                 if let Ok(handle_result) = group
@@ -101,7 +195,23 @@ pub async fn start(db_path: String) -> Result<()> {
                     handle(handle_result, now_rpc_uid, true, &sender).await;
                 }
             }
-            ReceiveMessage::Rpc(uid, params, is_ws) => {
+            ReceiveMessage::Rpc(uid, mut params, is_ws) => {
+                // reject unauthenticated calls before dispatch, embedded host is trusted.
+                if uid != EMBED_UID {
+                    if let Some(res) = rpc_auth.check(uid, &params, is_ws) {
+                        let _ = sender.send(SendMessage::Rpc(uid, res, is_ws)).await;
+                        continue;
+                    }
+                }
+
+                // named params to positional params.
+                if let Err(e) = rpc_types::normalize(&mut params) {
+                    let id = params["id"].as_u64().unwrap_or(0);
+                    let res = rpc_invalid_params(id, &e);
+                    let _ = sender.send(SendMessage::Rpc(uid, res, is_ws)).await;
+                    continue;
+                }
+
                 if !is_ws {
                     if inner_rpc(uid, params["method"].as_str().unwrap(), &sender)
                         .await
@@ -111,12 +221,25 @@ pub async fn start(db_path: String) -> Result<()> {
                     }
                 }
 
+                // account operation, delay the idle auto-lock.
+                if let Some(Ok(gid)) = params["gid"].as_str().map(GroupId::from_hex) {
+                    group.write().await.active(&gid, now_secs());
+                }
+
                 if now_rpc_uid != uid && is_ws {
-                    let _ = RPC_WS_UID.set(uid);
+                    let _ = RPC_WS_UID.compare_exchange(0, uid, Ordering::SeqCst, Ordering::SeqCst);
                     now_rpc_uid = uid
                 }
 
-                if let Ok(handle_result) = rpc.handle(params).await {
+                let id = params["id"].as_u64().unwrap_or(0);
+                let method = params["method"].as_str().unwrap_or("").to_owned();
+                let gid = params["gid"]
+                    .as_str()
+                    .and_then(|g| GroupId::from_hex(g).ok())
+                    .unwrap_or_default();
+                if let Ok(mut handle_result) = rpc.handle(params).await {
+                    // the method without result, ack it.
+                    rpc_ack(id, &method, gid, &mut handle_result);
                     handle(handle_result, uid, is_ws, &sender).await;
                 }
             }
@@ -138,6 +261,26 @@ pub async fn start(db_path: String) -> Result<()> {
         }
     }
 
+    // graceful shutdown, notify peers and logout all accounts.
+    info!("Core shutting down...");
+    embed::detach();
+    let mut results = HandleResult::new();
+    match accounts_logout(&group, &layer, &mut results).await {
+        Ok((groups, layers)) => {
+            handle(results, now_rpc_uid, true, &sender).await;
+            // waiting the offline events sent.
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            let _ = close_stable(&sender, groups, layers).await;
+        }
+        Err(e) => warn!("logout failure: {:?}", e),
+    }
+
+    // waiting the background file writes, and no database in use.
+    flush_writes().await;
+    drop(group.write().await);
+    drop(layer.write().await);
+
+    info!("Core stopped");
     Ok(())
 }
 
@@ -171,7 +314,7 @@ async fn session_remain(
 ) -> Result<()> {
     loop {
         tokio::time::sleep(std::time::Duration::from_secs(120)).await;
-        if let Some(uid) = RPC_WS_UID.get() {
+        if let Some(uid) = rpc_ws_uid() {
             let mut layer_lock = layer.write().await;
             let mut rpcs = vec![];
             let mut addrs = HashMap::new();
@@ -196,7 +339,7 @@ async fn session_remain(
             drop(layer_lock);
 
             for rpc in rpcs {
-                let _ = sender.send(SendMessage::Rpc(*uid, rpc, true)).await;
+                let _ = sender.send(SendMessage::Rpc(uid, rpc, true)).await;
             }
 
             for (addr, keep) in addrs {
@@ -214,6 +357,99 @@ async fn session_remain(
     }
 }
 
+async fn session_expire(group: Arc<RwLock<Group>>, sender: Sender<SendMessage>) -> Result<()> {
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|s| s.as_secs())
+            .unwrap_or(0) as i64; // safe for all life.
+
+        let group_lock = group.read().await;
+        let mut rpcs = vec![];
+        for gid in group_lock.list_running_user() {
+            if let Err(e) = expire_messages(&group_lock, &gid, now, &mut rpcs) {
+                warn!("expire messages failure: {:?}", e);
+            }
+        }
+        drop(group_lock);
+
+        if let Some(uid) = rpc_ws_uid() {
+            for rpc in rpcs {
+                let _ = sender.send(SendMessage::Rpc(uid, rpc, true)).await;
+            }
+        }
+    }
+}
+
+#[inline]
+fn now_secs() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|s| s.as_secs())
+        .unwrap_or(0) as u32 // safe for all life.
+}
+
+/// lock the accounts which idle timeout.
+async fn idle_lock(
+    group: Arc<RwLock<Group>>,
+    layer: Arc<RwLock<Layer>>,
+    sender: Sender<SendMessage>,
+) -> Result<()> {
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+        let gids = group.read().await.idle_accounts(now_secs());
+
+        for gid in gids {
+            let mut results = HandleResult::new();
+            if let Err(e) = account_offline(&group, &layer, gid, true, &mut results).await {
+                warn!("idle lock failure: {:?}", e);
+                continue;
+            }
+            info!("Account idle locked: {}.", gid.to_hex());
+            let uid = rpc_ws_uid().unwrap_or(0);
+            handle(results, uid, true, &sender).await;
+        }
+    }
+}
+
+/// request the lost chunks again, when the receiving file transfer stalled.
+async fn transfer_retry(layer: Arc<RwLock<Layer>>, sender: Sender<SendMessage>) -> Result<()> {
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(TRANSFER_TIMEOUT)).await;
+        let results = retry_streams(&mut *layer.write().await).await;
+        let uid = rpc_ws_uid().unwrap_or(0);
+        handle(results, uid, true, &sender).await;
+    }
+}
+
+/// delete the account's messages which out of the session's ttl.
+fn expire_messages(group: &Group, gid: &GroupId, now: i64, rpcs: &mut Vec<RpcParam>) -> Result<()> {
+    let s_db = group.session_db(gid)?;
+    let sessions = Session::list_ttl(&s_db)?;
+    drop(s_db);
+
+    for s in sessions {
+        let before = now - s.ttl;
+        match s.s_type {
+            SessionType::Chat => {
+                let db = group.chat_db(gid)?;
+                for id in ChatMessage::delete_before(&db, &s.fid, &before)? {
+                    rpcs.push(chat_rpc::message_delete(*gid, id));
+                }
+            }
+            SessionType::Group => {
+                let db = group.group_db(gid)?;
+                for id in GroupMessage::delete_before(&db, &s.fid, &before)? {
+                    rpcs.push(group_rpc::message_delete(*gid, id));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 #[inline]
 async fn handle(handle_result: HandleResult, uid: u64, is_ws: bool, sender: &Sender<SendMessage>) {
     let HandleResult {
@@ -272,24 +508,27 @@ async fn handle(handle_result: HandleResult, uid: u64, is_ws: bool, sender: &Sen
     }
 }
 
+/// init the logger, it is kept when the core start again.
 #[inline]
 pub fn init_log(mut db_path: PathBuf) {
     db_path.push(DEFAULT_LOG_FILE);
 
-    #[cfg(debug_assertions)]
-    CombinedLogger::init(vec![simplelog::TermLogger::new(
-        LevelFilter::Debug,
-        LogConfig::default(),
-        simplelog::TerminalMode::Mixed,
-        simplelog::ColorChoice::Auto,
-    )])
-    .unwrap();
-
-    #[cfg(not(debug_assertions))]
-    CombinedLogger::init(vec![simplelog::WriteLogger::new(
-        LevelFilter::Info,
-        LogConfig::default(),
-        std::fs::File::create(db_path).unwrap(),
-    )])
-    .unwrap();
+    LOG.call_once(|| {
+        #[cfg(debug_assertions)]
+        let _ = CombinedLogger::init(vec![simplelog::TermLogger::new(
+            LevelFilter::Debug,
+            LogConfig::default(),
+            simplelog::TerminalMode::Mixed,
+            simplelog::ColorChoice::Auto,
+        )]);
+
+        #[cfg(not(debug_assertions))]
+        if let Ok(file) = std::fs::File::create(db_path) {
+            let _ = CombinedLogger::init(vec![simplelog::WriteLogger::new(
+                LevelFilter::Info,
+                LogConfig::default(),
+                file,
+            )]);
+        }
+    });
 }
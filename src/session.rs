@@ -1,10 +1,16 @@
 use tdn::types::{
     group::GroupId,
     primitive::{PeerId, Result},
-    rpc::{json, RpcParam},
 };
 use tdn_storage::local::{DStorage, DsValue};
 
+use crate::utils::sql::Query;
+
+/// the shortest messages retention, the expire timer runs every minute.
+pub(crate) const MIN_TTL: i64 = 60;
+/// the longest messages retention, one year.
+pub(crate) const MAX_TTL: i64 = 365 * 24 * 60 * 60;
+
 pub(crate) enum SessionType {
     Chat,
     Group,
@@ -22,7 +28,7 @@ impl SessionType {
         }
     }
 
-    fn from_int(i: i64) -> Self {
+    pub fn from_int(i: i64) -> Self {
         match i {
             0 => SessionType::Chat,
             1 => SessionType::Group,
@@ -35,7 +41,7 @@ impl SessionType {
 
 pub(crate) struct Session {
     pub id: i64,
-    fid: i64,
+    pub fid: i64,
     pub gid: GroupId,
     pub addr: PeerId,
     pub s_type: SessionType,
@@ -45,6 +51,8 @@ pub(crate) struct Session {
     pub last_datetime: i64,
     pub last_content: String,
     pub last_readed: bool,
+    /// messages retention seconds, 0 is forever.
+    pub ttl: i64,
 }
 
 impl Session {
@@ -68,27 +76,30 @@ impl Session {
             last_datetime: datetime,
             last_content: "".to_owned(),
             last_readed: true,
+            ttl: 0,
         }
     }
 
-    pub fn to_rpc(&self) -> RpcParam {
-        json!([
-            self.id,
-            self.fid,
-            self.gid.to_hex(),
-            self.addr.to_hex(),
-            self.s_type.to_int(),
-            self.name,
-            self.is_top,
-            self.is_close,
-            self.last_datetime,
-            self.last_content,
-            self.last_readed,
-        ])
+    pub fn to_rpc(&self) -> rpc_types::session::Session {
+        rpc_types::session::Session {
+            id: self.id,
+            fid: self.fid,
+            gid: self.gid.to_hex(),
+            addr: self.addr.to_hex(),
+            s_type: self.s_type.to_int(),
+            name: self.name.clone(),
+            is_top: self.is_top,
+            is_close: self.is_close,
+            last_datetime: self.last_datetime,
+            last_content: self.last_content.clone(),
+            last_readed: self.last_readed,
+            ttl: self.ttl,
+        }
     }
 
     fn from_values(mut v: Vec<DsValue>) -> Self {
         Self {
+            ttl: v.pop().unwrap().as_i64(),
             last_readed: v.pop().unwrap().as_bool(),
             last_content: v.pop().unwrap().as_string(),
             last_datetime: v.pop().unwrap().as_i64(),
@@ -104,37 +115,34 @@ impl Session {
     }
 
     pub fn insert(&mut self, db: &DStorage) -> Result<()> {
-        let mut unique_check = db.query(&format!(
-            "SELECT id from sessions WHERE fid = {} AND s_type = {}",
-            self.fid,
-            self.s_type.to_int()
-        ))?;
+        let mut unique_check = Query::new("SELECT id from sessions WHERE fid = ? AND s_type = ?")
+            .bind(&self.fid)
+            .bind(&self.s_type.to_int())
+            .query(db)?;
         if unique_check.len() > 0 {
             let id = unique_check.pop().unwrap().pop().unwrap().as_i64();
             self.id = id;
 
-            let sql = format!("UPDATE sessions SET gid = '{}', addr='{}', name = '{}', is_top = '{}', is_close = false WHERE id = {}",
-                self.gid.to_hex(),
-                self.addr.to_hex(),
-                self.name,
-                self.is_top,
-                self.id,
-            );
-            db.update(&sql)?;
+            Query::new("UPDATE sessions SET gid = ?, addr = ?, name = ?, is_top = ?, is_close = false WHERE id = ?")
+                .bind(&self.gid)
+                .bind(&self.addr)
+                .bind(&self.name)
+                .bind(&self.is_top)
+                .bind(&self.id)
+                .update(db)?;
         } else {
-            let sql = format!("INSERT INTO sessions (fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed) VALUES ({}, '{}', '{}', {}, '{}', {}, {}, {}, '{}', {})",
-            self.fid,
-            self.gid.to_hex(),
-            self.addr.to_hex(),
-            self.s_type.to_int(),
-            self.name,
-            self.is_top,
-            self.is_close,
-            self.last_datetime,
-            self.last_content,
-            self.last_readed,
-        );
-            let id = db.insert(&sql)?;
+            let id = Query::new("INSERT INTO sessions (fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+                .bind(&self.fid)
+                .bind(&self.gid)
+                .bind(&self.addr)
+                .bind(&self.s_type.to_int())
+                .bind(&self.name)
+                .bind(&self.is_top)
+                .bind(&self.is_close)
+                .bind(&self.last_datetime)
+                .bind(&self.last_content)
+                .bind(&self.last_readed)
+                .insert(db)?;
             self.id = id;
         }
 
@@ -142,8 +150,9 @@ impl Session {
     }
 
     pub fn get(db: &DStorage, id: &i64) -> Result<Session> {
-        let sql = format!("SELECT id, fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed FROM sessions WHERE id = {}", id);
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id, fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed, ttl FROM sessions WHERE id = ?")
+            .bind(id)
+            .query(db)?;
         if matrix.len() > 0 {
             Ok(Session::from_values(matrix.pop().unwrap())) // safe unwrap()
         } else {
@@ -152,7 +161,7 @@ impl Session {
     }
 
     pub fn list(db: &DStorage) -> Result<Vec<Session>> {
-        let matrix = db.query("SELECT id, fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed FROM sessions ORDER BY last_datetime DESC")?;
+        let matrix = db.query("SELECT id, fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed, ttl FROM sessions ORDER BY last_datetime DESC")?;
         let mut sessions = vec![];
         for values in matrix {
             sessions.push(Session::from_values(values));
@@ -161,17 +170,18 @@ impl Session {
     }
 
     pub fn update(db: &DStorage, id: &i64, is_top: bool, is_close: bool) -> Result<usize> {
-        db.update(&format!(
-            "UPDATE sessions SET is_top = {}, is_close = {} WHERE id = {}",
-            is_top, is_close, id
-        ))
+        Query::new("UPDATE sessions SET is_top = ?, is_close = ? WHERE id = ?")
+            .bind(&is_top)
+            .bind(&is_close)
+            .bind(id)
+            .update(db)
     }
 
     pub fn update_name(db: &DStorage, id: &i64, name: &str) -> Result<usize> {
-        db.update(&format!(
-            "UPDATE sessions SET name='{}' WHERE id = {}",
-            name, id
-        ))
+        Query::new("UPDATE sessions SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(id)
+            .update(db)
     }
 
     pub fn update_name_by_id(
@@ -180,16 +190,16 @@ impl Session {
         s_type: &SessionType,
         name: &str,
     ) -> Result<i64> {
-        let sql = format!(
-            "SELECT id from sessions WHERE fid = {} AND s_type = {}",
-            fid,
-            s_type.to_int()
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id from sessions WHERE fid = ? AND s_type = ?")
+            .bind(fid)
+            .bind(&s_type.to_int())
+            .query(db)?;
         if let Some(mut values) = matrix.pop() {
             let id = values.pop().unwrap().as_i64(); // safe unwrap.
-            let s = format!("UPDATE sessions SET name = '{}' WHERE id = {}", name, id);
-            db.update(&s)?;
+            Query::new("UPDATE sessions SET name = ? WHERE id = ?")
+                .bind(name)
+                .bind(&id)
+                .update(db)?;
             Ok(id)
         } else {
             Err(anyhow!("session missing"))
@@ -197,15 +207,15 @@ impl Session {
     }
 
     pub fn delete(db: &DStorage, fid: &i64, s_type: &SessionType) -> Result<i64> {
-        let sql = format!(
-            "SELECT id from sessions WHERE fid = {} AND s_type = {}",
-            fid,
-            s_type.to_int()
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id from sessions WHERE fid = ? AND s_type = ?")
+            .bind(fid)
+            .bind(&s_type.to_int())
+            .query(db)?;
         if let Some(mut values) = matrix.pop() {
             let id = values.pop().unwrap().as_i64(); // safe unwrap.
-            db.delete(&format!("DELETE FROM sessions WHERE id = {}", id))?;
+            Query::new("DELETE FROM sessions WHERE id = ?")
+                .bind(&id)
+                .delete(db)?;
             Ok(id)
         } else {
             Err(anyhow!("session missing"))
@@ -213,16 +223,15 @@ impl Session {
     }
 
     pub fn close(db: &DStorage, fid: &i64, s_type: &SessionType) -> Result<i64> {
-        let sql = format!(
-            "SELECT id from sessions WHERE fid = {} AND s_type = {}",
-            fid,
-            s_type.to_int()
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id from sessions WHERE fid = ? AND s_type = ?")
+            .bind(fid)
+            .bind(&s_type.to_int())
+            .query(db)?;
         if let Some(mut values) = matrix.pop() {
             let id = values.pop().unwrap().as_i64(); // safe unwrap.
-            let s = format!("UPDATE sessions SET is_close = 1 WHERE id = {}", id);
-            db.update(&s)?;
+            Query::new("UPDATE sessions SET is_close = 1 WHERE id = ?")
+                .bind(&id)
+                .update(db)?;
             Ok(id)
         } else {
             Err(anyhow!("session missing"))
@@ -237,16 +246,68 @@ impl Session {
         content: &str,
         readed: bool,
     ) -> Result<i64> {
-        let sql = format!(
-            "SELECT id from sessions WHERE fid = {} AND s_type = {}",
-            fid,
-            s_type.to_int()
-        );
-        let mut matrix = db.query(&sql)?;
+        let mut matrix = Query::new("SELECT id from sessions WHERE fid = ? AND s_type = ?")
+            .bind(fid)
+            .bind(&s_type.to_int())
+            .query(db)?;
 
         if let Some(mut values) = matrix.pop() {
             let id = values.pop().unwrap().as_i64();
-            db.update(&format!("UPDATE sessions SET is_close = false, last_datetime = {}, last_content = '{}', last_readed = {} WHERE id = {}", datetime, content, if readed { 1 } else { 0 }, id))?;
+            Query::new("UPDATE sessions SET is_close = false, last_datetime = ?, last_content = ?, last_readed = ? WHERE id = ?")
+                .bind(datetime)
+                .bind(content)
+                .bind(&readed)
+                .bind(&id)
+                .update(db)?;
+            Ok(id)
+        } else {
+            Err(anyhow!("session missing"))
+        }
+    }
+
+    /// list all sessions which had messages retention.
+    pub fn list_ttl(db: &DStorage) -> Result<Vec<Session>> {
+        let matrix = db.query("SELECT id, fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed, ttl FROM sessions WHERE ttl > 0")?;
+        let mut sessions = vec![];
+        for values in matrix {
+            sessions.push(Session::from_values(values));
+        }
+        Ok(sessions)
+    }
+
+    /// messages retention is 0 (forever) or between MIN_TTL and MAX_TTL.
+    pub fn check_ttl(ttl: i64) -> Result<()> {
+        if ttl == 0 || (MIN_TTL..=MAX_TTL).contains(&ttl) {
+            Ok(())
+        } else {
+            Err(anyhow!("session ttl is invalid"))
+        }
+    }
+
+    pub fn update_ttl(db: &DStorage, id: &i64, ttl: i64) -> Result<usize> {
+        Session::check_ttl(ttl)?;
+        Query::new("UPDATE sessions SET ttl = ? WHERE id = ?")
+            .bind(&ttl)
+            .bind(id)
+            .update(db)
+    }
+
+    pub fn update_ttl_by_gid(
+        db: &DStorage,
+        gid: &GroupId,
+        s_type: &SessionType,
+        ttl: i64,
+    ) -> Result<i64> {
+        if !matches!(s_type, SessionType::Chat) {
+            return Err(anyhow!("only chat session has ttl"));
+        }
+        let mut matrix = Query::new("SELECT id from sessions WHERE gid = ? AND s_type = ?")
+            .bind(gid)
+            .bind(&s_type.to_int())
+            .query(db)?;
+        if let Some(mut values) = matrix.pop() {
+            let id = values.pop().unwrap().as_i64(); // safe unwrap.
+            Session::update_ttl(db, &id, ttl)?;
             Ok(id)
         } else {
             Err(anyhow!("session missing"))
@@ -254,10 +315,9 @@ impl Session {
     }
 
     pub fn readed(db: &DStorage, id: &i64) -> Result<usize> {
-        db.update(&format!(
-            "UPDATE sessions SET last_readed = 1 WHERE id = {}",
-            id
-        ))
+        Query::new("UPDATE sessions SET last_readed = 1 WHERE id = ?")
+            .bind(id)
+            .update(db)
     }
 }
 
@@ -268,17 +328,17 @@ pub(crate) fn connect_session(
     fid: &i64,
     addr: &PeerId,
 ) -> Result<Option<Session>> {
-    let sql = format!("SELECT id, fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed FROM sessions WHERE s_type = {} AND fid = {}", s_type.to_int(), fid);
-
-    let mut matrix = db.query(&sql)?;
+    let mut matrix = Query::new("SELECT id, fid, gid, addr, s_type, name, is_top, is_close, last_datetime, last_content, last_readed, ttl FROM sessions WHERE s_type = ? AND fid = ?")
+        .bind(&s_type.to_int())
+        .bind(fid)
+        .query(db)?;
     if matrix.len() > 0 {
         let session = Session::from_values(matrix.pop().unwrap()); // safe unwrap()
 
-        let _ = db.update(&format!(
-            "UPDATE sessions SET addr = '{}' WHERE id = {}",
-            addr.to_hex(),
-            session.id,
-        ));
+        let _ = Query::new("UPDATE sessions SET addr = ? WHERE id = ?")
+            .bind(addr)
+            .bind(&session.id)
+            .update(db);
 
         Ok(Some(session))
     } else {
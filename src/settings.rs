@@ -0,0 +1,213 @@
+use std::net::SocketAddr;
+use tdn::types::primitive::{Peer, Result};
+use tdn_storage::local::{DStorage, DsValue};
+
+use crate::primitives::parse_seeds;
+use crate::utils::crypto::PinParams;
+use crate::utils::sql::Query;
+
+/// rpc websocket listening address.
+pub(crate) const WS_ADDR: &'static str = "ws_addr";
+/// p2p listening address.
+pub(crate) const P2P_ADDR: &'static str = "p2p_addr";
+/// argon2 memory size in KiB of pin hash.
+pub(crate) const PIN_M_COST: &'static str = "pin_m_cost";
+/// argon2 iterations of pin hash.
+pub(crate) const PIN_T_COST: &'static str = "pin_t_cost";
+/// argon2 parallelism of pin hash.
+pub(crate) const PIN_P_COST: &'static str = "pin_p_cost";
+/// keep the mails for others as cloud provider, `true` or `false` (default).
+pub(crate) const CLOUD_PROVIDER: &'static str = "cloud_provider";
+
+/// all supported setting keys and the environment variable override it.
+const KEYS: [(&'static str, &'static str); 6] = [
+    (WS_ADDR, "ESSE_WS_ADDR"),
+    (P2P_ADDR, "ESSE_P2P_ADDR"),
+    (PIN_M_COST, "ESSE_PIN_M_COST"),
+    (PIN_T_COST, "ESSE_PIN_T_COST"),
+    (PIN_P_COST, "ESSE_PIN_P_COST"),
+    (CLOUD_PROVIDER, "ESSE_CLOUD_PROVIDER"),
+];
+
+/// extra seeds from environment, e.g. `1.2.3.4:7364/quic,5.6.7.8:7364`.
+const SEEDS_ENV: &'static str = "ESSE_SEEDS";
+
+/// Node settings, persisted in account db and override by environment.
+pub(crate) struct Settings {
+    pub ws_addr: Option<SocketAddr>,
+    pub p2p_addr: Option<SocketAddr>,
+    pub seeds: Vec<Peer>,
+    pub pin: PinParams,
+    pub cloud_provider: bool,
+}
+
+impl Settings {
+    /// load from settings store, environment variables have higher priority.
+    pub fn load(db: &DStorage) -> Result<Settings> {
+        let mut values = Setting::list(db)?;
+        for (key, env) in KEYS {
+            if let Ok(value) = std::env::var(env) {
+                values.retain(|(k, _)| k != key);
+                values.push((key.to_owned(), value));
+            }
+        }
+        let get = |key: &str| {
+            values
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+
+        let default = PinParams::default();
+        let pin = PinParams {
+            m_cost: parse_or(get(PIN_M_COST), default.m_cost),
+            t_cost: parse_or(get(PIN_T_COST), default.t_cost),
+            p_cost: parse_or(get(PIN_P_COST), default.p_cost),
+        };
+
+        let mut seeds: Vec<Peer> = Seed::list(db)?.iter().map(|s| s.peer()).collect();
+        if let Ok(env) = std::env::var(SEEDS_ENV) {
+            seeds.extend(parse_seeds(&env));
+        }
+
+        Ok(Settings {
+            ws_addr: get(WS_ADDR).and_then(|v| v.parse().ok()),
+            p2p_addr: get(P2P_ADDR).and_then(|v| v.parse().ok()),
+            seeds,
+            pin,
+            cloud_provider: get(CLOUD_PROVIDER)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+        })
+    }
+}
+
+fn parse_or(value: Option<&str>, default: u32) -> u32 {
+    value.and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Key-value setting in settings store.
+pub(crate) struct Setting;
+
+impl Setting {
+    pub fn list(db: &DStorage) -> Result<Vec<(String, String)>> {
+        let matrix = db.query("SELECT name, value FROM settings")?;
+        let mut settings = vec![];
+        for mut values in matrix {
+            let value = values.pop().unwrap().as_string();
+            let key = values.pop().unwrap().as_string();
+            settings.push((key, value));
+        }
+        Ok(settings)
+    }
+
+    /// check the key and value, and save it, works after restart.
+    pub fn set(db: &DStorage, key: &str, value: &str) -> Result<()> {
+        match key {
+            WS_ADDR | P2P_ADDR => {
+                value
+                    .parse::<SocketAddr>()
+                    .map_err(|_| anyhow!("setting value is invalid"))?;
+            }
+            PIN_M_COST | PIN_T_COST | PIN_P_COST => {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| anyhow!("setting value is invalid"))?;
+            }
+            CLOUD_PROVIDER => {
+                value
+                    .parse::<bool>()
+                    .map_err(|_| anyhow!("setting value is invalid"))?;
+            }
+            _ => return Err(anyhow!("setting key is unknown")),
+        }
+
+        Query::new("DELETE FROM settings WHERE name = ?")
+            .bind(key)
+            .delete(db)?;
+        Query::new("INSERT INTO settings (name, value) VALUES (?, ?)")
+            .bind(key)
+            .bind(value)
+            .insert(db)?;
+        Ok(())
+    }
+
+    /// remove the setting, use the default value.
+    pub fn remove(db: &DStorage, key: &str) -> Result<usize> {
+        Query::new("DELETE FROM settings WHERE name = ?")
+            .bind(key)
+            .delete(db)
+    }
+}
+
+/// Bootstrap seed peer, persisted in settings store.
+pub(crate) struct Seed {
+    pub id: i64,
+    pub socket: SocketAddr,
+    pub transport: String,
+}
+
+impl Seed {
+    pub fn new(socket: SocketAddr, transport: String) -> Self {
+        Self {
+            id: 0,
+            socket,
+            transport,
+        }
+    }
+
+    pub fn peer(&self) -> Peer {
+        Peer::socket_transport(self.socket, &self.transport)
+    }
+
+    pub fn to_rpc(&self) -> rpc_types::node::Seed {
+        rpc_types::node::Seed {
+            id: self.id,
+            socket: self.socket.to_string(),
+            transport: self.transport.clone(),
+        }
+    }
+
+    /// here is zero-copy and unwrap is safe. checked.
+    fn from_values(mut v: Vec<DsValue>) -> Option<Self> {
+        let transport = v.pop().unwrap().as_string();
+        let socket = v.pop().unwrap().as_str().parse().ok()?;
+        let id = v.pop().unwrap().as_i64();
+        Some(Self {
+            id,
+            socket,
+            transport,
+        })
+    }
+
+    pub fn list(db: &DStorage) -> Result<Vec<Self>> {
+        let matrix = db.query("SELECT id, socket, transport FROM seeds")?;
+        Ok(matrix.into_iter().filter_map(Self::from_values).collect())
+    }
+
+    /// one seed for each socket, the new transport replace the old.
+    pub fn insert(&mut self, db: &DStorage) -> Result<()> {
+        let mut unique_check = Query::new("SELECT id from seeds WHERE socket = ?")
+            .bind(&self.socket.to_string())
+            .query(db)?;
+        if unique_check.len() > 0 {
+            self.id = unique_check.pop().unwrap().pop().unwrap().as_i64();
+            Query::new("UPDATE seeds SET transport = ? WHERE id = ?")
+                .bind(&self.transport)
+                .bind(&self.id)
+                .update(db)?;
+        } else {
+            self.id = Query::new("INSERT INTO seeds (socket, transport) VALUES (?, ?)")
+                .bind(&self.socket.to_string())
+                .bind(&self.transport)
+                .insert(db)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(db: &DStorage, socket: &SocketAddr) -> Result<usize> {
+        Query::new("DELETE FROM seeds WHERE socket = ?")
+            .bind(&socket.to_string())
+            .delete(db)
+    }
+}
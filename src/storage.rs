@@ -1,10 +1,18 @@
 use image::{load_from_memory, DynamicImage, GenericImageView};
+use once_cell::sync::Lazy;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use std::future::Future;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
+use tokio::sync::Notify;
 
-use tdn::types::{group::GroupId, primitive::Result};
+use tdn::types::{
+    group::{EventId, GroupId},
+    primitive::Result,
+};
 
 use crate::migrate::account_init_migrate;
 
@@ -15,6 +23,40 @@ const EMOJI_DIR: &'static str = "emojis";
 const RECORD_DIR: &'static str = "records";
 const AVATAR_DIR: &'static str = "avatars";
 
+/// account's all storage directories.
+pub(crate) const ACCOUNT_DIRS: [&'static str; 6] = [
+    FILES_DIR, IMAGE_DIR, THUMB_DIR, EMOJI_DIR, RECORD_DIR, AVATAR_DIR,
+];
+
+/// the background writes not finished.
+static PENDING_WRITES: AtomicUsize = AtomicUsize::new(0);
+static WRITES_DONE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// spawn the background write, it is awaited by `flush_writes` when shutdown.
+fn spawn_write<F>(task: F)
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    PENDING_WRITES.fetch_add(1, Ordering::SeqCst);
+    tokio::spawn(async move {
+        task.await;
+        PENDING_WRITES.fetch_sub(1, Ordering::SeqCst);
+        WRITES_DONE.notify_waiters();
+    });
+}
+
+/// wait all the background writes finished.
+pub(crate) async fn flush_writes() {
+    loop {
+        let done = WRITES_DONE.notified();
+        if PENDING_WRITES.load(Ordering::SeqCst) == 0 {
+            break;
+        }
+        done.await;
+    }
+}
+
 pub(crate) async fn init_local_files(base: &PathBuf) -> Result<()> {
     let mut files_path = base.clone();
     files_path.push(FILES_DIR);
@@ -49,6 +91,36 @@ pub(crate) async fn init_local_files(base: &PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// the file name is from the remote, not allow out of the files directory.
+/// `:` is windows drive prefix (`C:name`) or NTFS alternate data stream.
+pub(crate) fn is_file_name(name: &str) -> bool {
+    !(name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains(|c| c == '/' || c == '\\' || c == ':' || c == '\0'))
+}
+
+fn file_path(base: &PathBuf, gid: &GroupId, name: &str) -> Result<PathBuf> {
+    if !is_file_name(name) {
+        return Err(anyhow!("file name invalid"));
+    }
+
+    let mut path = base.clone();
+    path.push(gid.to_hex());
+    path.push(FILES_DIR);
+    path.push(name);
+    Ok(path)
+}
+
+/// the unfinished file of the transfer, it is named by the message hash.
+fn file_part_path(base: &PathBuf, gid: &GroupId, hash: &EventId) -> PathBuf {
+    let mut path = base.clone();
+    path.push(gid.to_hex());
+    path.push(FILES_DIR);
+    path.push(format!("{}.part", hash.to_hex()));
+    path
+}
+
 pub(crate) async fn read_file(base: &PathBuf) -> Result<Vec<u8>> {
     Ok(fs::read(base).await?)
 }
@@ -58,13 +130,12 @@ pub(crate) async fn copy_file(
     base: &PathBuf,
     gid: &GroupId,
     name: &str,
-) -> Result<()> {
-    let mut path = base.clone();
-    path.push(gid.to_hex());
-    path.push(FILES_DIR);
-    path.push(name);
-    fs::copy(target, path).await?;
-    Ok(())
+) -> Result<u64> {
+    Ok(fs::copy(target, file_path(base, gid, name)?).await?)
+}
+
+pub(crate) async fn file_size(base: &PathBuf, gid: &GroupId, name: &str) -> Result<u64> {
+    Ok(fs::metadata(file_path(base, gid, name)?).await?.len())
 }
 
 pub(crate) async fn write_file(
@@ -73,11 +144,7 @@ pub(crate) async fn write_file(
     name: &str,
     bytes: &[u8],
 ) -> Result<String> {
-    let mut path = base.clone();
-    path.push(gid.to_hex());
-    path.push(FILES_DIR);
-    path.push(name);
-    fs::write(path, bytes).await?;
+    fs::write(file_path(base, gid, name)?, bytes).await?;
     Ok(name.to_owned())
 }
 
@@ -87,20 +154,135 @@ pub(crate) fn write_file_sync(
     name: &str,
     bytes: Vec<u8>,
 ) -> Result<String> {
-    let mut path = base.clone();
-    path.push(gid.to_hex());
-    path.push(FILES_DIR);
-    path.push(name);
-    tokio::spawn(async move { fs::write(path, bytes).await });
+    let path = file_path(base, gid, name)?;
+    spawn_write(async move { fs::write(path, bytes).await });
 
     Ok(name.to_owned())
 }
 
+async fn read_chunk(path: PathBuf, offset: u64, size: usize) -> Result<Vec<u8>> {
+    let mut file = fs::File::open(path).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut bytes = Vec::with_capacity(size);
+    file.take(size as u64).read_to_end(&mut bytes).await?;
+    Ok(bytes)
+}
+
+/// read a chunk of the file, when transfer big file.
+pub(crate) async fn read_file_chunk(
+    base: &PathBuf,
+    gid: &GroupId,
+    name: &str,
+    offset: u64,
+    size: usize,
+) -> Result<Vec<u8>> {
+    read_chunk(file_path(base, gid, name)?, offset, size).await
+}
+
+/// read a received chunk of the part file, when relay the unfinished file.
+pub(crate) async fn read_part_chunk(
+    base: &PathBuf,
+    gid: &GroupId,
+    hash: &EventId,
+    offset: u64,
+    size: usize,
+) -> Result<Vec<u8>> {
+    read_chunk(file_part_path(base, gid, hash), offset, size).await
+}
+
+/// write a received chunk to the unfinished part file.
+pub(crate) async fn write_part_chunk(
+    base: &PathBuf,
+    gid: &GroupId,
+    hash: &EventId,
+    offset: u64,
+    bytes: &[u8],
+) -> Result<()> {
+    let path = file_part_path(base, gid, hash);
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(path)
+        .await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+    file.write_all(bytes).await?;
+    Ok(())
+}
+
+/// hash the file from the disk, returns the size, file's hash and every
+/// chunk's hash.
+pub(crate) async fn file_hashes(
+    base: &PathBuf,
+    gid: &GroupId,
+    name: &str,
+    chunk_size: usize,
+) -> Result<(u64, [u8; 32], Vec<[u8; 32]>)> {
+    let mut file = fs::File::open(file_path(base, gid, name)?).await?;
+    let mut hasher = blake3::Hasher::new();
+    let mut chunks = vec![];
+    let mut size = 0u64;
+    let mut buf = vec![0u8; chunk_size];
+    loop {
+        let n = read_full(&mut file, &mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        chunks.push(*blake3::hash(&buf[..n]).as_bytes());
+        size += n as u64;
+    }
+    Ok((size, *hasher.finalize().as_bytes(), chunks))
+}
+
+/// fill the buffer until the end of file.
+async fn read_full(file: &mut fs::File, buf: &mut [u8]) -> Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        let m = file.read(&mut buf[n..]).await?;
+        if m == 0 {
+            break;
+        }
+        n += m;
+    }
+    Ok(n)
+}
+
+/// check the finished part file by hashing it from the disk, and move it to
+/// the files. the part file is removed when it is broken.
+pub(crate) async fn commit_file_part(
+    base: &PathBuf,
+    gid: &GroupId,
+    hash: &EventId,
+    name: &str,
+    size: u64,
+    file_hash: &[u8; 32],
+) -> Result<bool> {
+    let target = file_path(base, gid, name)?;
+    let path = file_part_path(base, gid, hash);
+    let mut file = fs::File::open(&path).await?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut len = 0u64;
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        len += n as u64;
+    }
+    drop(file);
+
+    if len != size || hasher.finalize().as_bytes() != file_hash {
+        fs::remove_file(path).await?;
+        return Ok(false);
+    }
+    fs::rename(path, target).await?;
+    Ok(true)
+}
+
 pub(crate) async fn read_db_file(base: &PathBuf, gid: &GroupId, name: &str) -> Result<Vec<u8>> {
-    let mut path = base.clone();
-    path.push(gid.to_hex());
-    path.push(FILES_DIR);
-    path.push(name);
+    let path = file_path(base, gid, name)?;
     if path.exists() {
         Ok(fs::read(path).await?)
     } else {
@@ -153,13 +335,13 @@ pub(crate) fn write_image_sync(base: &PathBuf, gid: &GroupId, bytes: Vec<u8>) ->
     let mut thumb_path = path.clone();
     thumb_path.push(THUMB_DIR);
     thumb_path.push(name.clone());
-    tokio::spawn(async move {
+    spawn_write(async move {
         let _ = thumb.save(thumb_path);
     });
 
     path.push(IMAGE_DIR);
     path.push(name.clone());
-    tokio::spawn(async move { fs::write(path, bytes).await });
+    spawn_write(async move { fs::write(path, bytes).await });
 
     Ok(name)
 }
@@ -174,7 +356,7 @@ pub(crate) async fn write_image(base: &PathBuf, gid: &GroupId, bytes: &[u8]) ->
     let mut thumb_path = path.clone();
     thumb_path.push(THUMB_DIR);
     thumb_path.push(name.clone());
-    tokio::spawn(async move {
+    spawn_write(async move {
         let _ = thumb.save(thumb_path);
     });
 
@@ -249,7 +431,7 @@ pub(crate) fn write_avatar_sync(
     path.push(gid.to_hex());
     path.push(AVATAR_DIR);
     path.push(avatar_png(remote));
-    tokio::spawn(async move { fs::write(path, bytes).await });
+    spawn_write(async move { fs::write(path, bytes).await });
     Ok(())
 }
 
@@ -271,7 +453,7 @@ pub(crate) fn delete_avatar_sync(base: &PathBuf, gid: &GroupId, remote: &GroupId
     path.push(AVATAR_DIR);
     path.push(avatar_png(remote));
     if path.exists() {
-        tokio::spawn(async move { fs::remove_file(path).await });
+        spawn_write(async move { fs::remove_file(path).await });
     }
     Ok(())
 }
@@ -304,7 +486,7 @@ pub(crate) fn write_record_sync(
     path.push(gid.to_hex());
     path.push(RECORD_DIR);
     path.push(format!("{}.m4a", datetime));
-    tokio::spawn(async move { fs::write(path, bytes).await });
+    spawn_write(async move { fs::write(path, bytes).await });
 
     Ok(format!("{}_{}.m4a", t, datetime))
 }
@@ -317,11 +499,100 @@ pub(crate) async fn _delete_record(base: &PathBuf, gid: &GroupId, name: &str) ->
     Ok(fs::remove_file(path).await?)
 }
 
-pub(crate) fn _write_emoji(base: &PathBuf, gid: &GroupId) -> Result<()> {
+/// sticker path, name is `pack/sticker`, not allow out of the emoji directory.
+fn emoji_path(base: &PathBuf, gid: &GroupId, name: &str) -> Result<PathBuf> {
+    let mut parts = name.split('/');
+    let (pack, sticker) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(pack), Some(sticker), None) => (pack, sticker),
+        _ => return Err(anyhow!("emoji name invalid")),
+    };
+    for part in [pack, sticker] {
+        if !is_file_name(part) {
+            return Err(anyhow!("emoji name invalid"));
+        }
+    }
+
     let mut path = base.clone();
     path.push(gid.to_hex());
     path.push(EMOJI_DIR);
-    Ok(())
+    path.push(pack);
+    path.push(sticker);
+    Ok(path)
+}
+
+pub(crate) async fn read_emoji(base: &PathBuf, gid: &GroupId, name: &str) -> Result<Vec<u8>> {
+    Ok(fs::read(emoji_path(base, gid, name)?).await?)
+}
+
+pub(crate) fn write_emoji_sync(
+    base: &PathBuf,
+    gid: &GroupId,
+    name: &str,
+    bytes: Vec<u8>,
+) -> Result<String> {
+    let path = emoji_path(base, gid, name)?;
+    if !path.exists() {
+        spawn_write(async move {
+            if let Some(pack) = path.parent() {
+                fs::create_dir_all(pack).await?;
+            }
+            fs::write(path, bytes).await
+        });
+    }
+
+    Ok(name.to_owned())
+}
+
+/// add a sticker to the pack, return the sticker name.
+pub(crate) async fn add_emoji(
+    base: &PathBuf,
+    gid: &GroupId,
+    pack: &str,
+    target: &PathBuf,
+) -> Result<String> {
+    let sticker = target
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or(anyhow!("emoji file invalid"))?;
+    let name = format!("{}/{}", pack, sticker);
+    let path = emoji_path(base, gid, &name)?;
+    if let Some(pack) = path.parent() {
+        fs::create_dir_all(pack).await?;
+    }
+    fs::copy(target, path).await?;
+    Ok(name)
+}
+
+pub(crate) async fn delete_emoji(base: &PathBuf, gid: &GroupId, name: &str) -> Result<()> {
+    Ok(fs::remove_file(emoji_path(base, gid, name)?).await?)
+}
+
+/// list all sticker packs and stickers.
+pub(crate) async fn list_emojis(
+    base: &PathBuf,
+    gid: &GroupId,
+) -> Result<Vec<(String, Vec<String>)>> {
+    let mut path = base.clone();
+    path.push(gid.to_hex());
+    path.push(EMOJI_DIR);
+
+    let mut packs = vec![];
+    let mut dirs = fs::read_dir(path).await?;
+    while let Some(dir) = dirs.next_entry().await? {
+        if !dir.file_type().await?.is_dir() {
+            continue;
+        }
+        let pack = dir.file_name().to_string_lossy().into_owned();
+        let mut stickers = vec![];
+        let mut files = fs::read_dir(dir.path()).await?;
+        while let Some(file) = files.next_entry().await? {
+            stickers.push(file.file_name().to_string_lossy().into_owned());
+        }
+        stickers.sort();
+        packs.push((pack, stickers));
+    }
+    packs.sort();
+    Ok(packs)
 }
 
 /// account independent db and storage directory.
@@ -333,3 +604,36 @@ pub(crate) async fn account_init(base: &PathBuf, key: &str, gid: &GroupId) -> Re
     // Inner Database.
     account_init_migrate(&db_path, key)
 }
+
+/// overwrite all account's files with zeros, then remove the account's directory.
+pub(crate) async fn account_wipe(base: &PathBuf, gid: &GroupId) -> Result<()> {
+    let mut root = base.clone();
+    root.push(gid.to_hex());
+    if !root.exists() {
+        return Ok(());
+    }
+
+    let mut dirs = vec![root.clone()];
+    while let Some(dir) = dirs.pop() {
+        let mut reads = fs::read_dir(dir).await?;
+        while let Some(entry) = reads.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else {
+                let len = entry.metadata().await?.len() as usize;
+                let mut file = fs::OpenOptions::new().write(true).open(path).await?;
+                let zeros = vec![0u8; 1024 * 1024];
+                let mut done = 0;
+                while done < len {
+                    let n = std::cmp::min(zeros.len(), len - done);
+                    file.write_all(&zeros[..n]).await?;
+                    done += n;
+                }
+                file.sync_all().await?;
+            }
+        }
+    }
+
+    Ok(fs::remove_dir_all(root).await?)
+}
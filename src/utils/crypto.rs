@@ -4,25 +4,85 @@ use aes_gcm::{
 };
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
 use sha2::{Digest, Sha256};
+use std::sync::RwLock;
 
 const FIX_PADDING: [u8; 19] = [
     69, 83, 83, 69, 70, 111, 114, 68, 97, 116, 97, 83, 101, 99, 117, 114, 105, 116, 121,
 ];
 
+/// current ciphertext format version.
+const CIPHER_VERSION: u8 = 1;
+
+/// ciphertext algorithm: AES-256-GCM with a random 96-bit nonce.
+const CIPHER_AES256GCM: u8 = 1;
+
+/// version (1) + algorithm (1) + nonce (12).
+const CIPHER_HEADER_LEN: usize = 14;
+
+/// AES-GCM tag length.
+const CIPHER_TAG_LEN: usize = 16;
+
+/// argon2 parameters used when hash pin.
+static PIN_PARAMS: RwLock<Option<PinParams>> = RwLock::new(None);
+
+/// Argon2id parameters of `hash_pin`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PinParams {
+    /// memory size in KiB.
+    pub m_cost: u32,
+    /// number of iterations.
+    pub t_cost: u32,
+    /// degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl Default for PinParams {
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl PinParams {
+    /// the parameters in use, default if not set.
+    pub fn current() -> Self {
+        PIN_PARAMS.read().ok().and_then(|p| *p).unwrap_or_default()
+    }
+
+    /// set the parameters when start, new pin hashes will use it.
+    pub fn init(self) -> anyhow::Result<()> {
+        self.argon2()?;
+        let mut params = PIN_PARAMS
+            .write()
+            .map_err(|_| anyhow!("pin params is unavailable!"))?;
+        *params = Some(self);
+        Ok(())
+    }
+
+    fn argon2(&self) -> anyhow::Result<Argon2<'static>> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, None)
+            .map_err(|_| anyhow!("pin params is invalid!"))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
 /// Hash the given pin.
 pub fn hash_pin(pin: &str) -> anyhow::Result<String> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
+    let argon2 = PinParams::current().argon2()?;
     Ok(argon2
         .hash_password(pin.as_bytes(), &salt)
         .map_err(|_| anyhow!("hash pin failure!"))?
         .to_string())
 }
 
-/// check the pin is the given hash pre-image.
+/// check the pin is the given hash pre-image. params read from the hash.
 pub fn check_pin(pin: &str, hash: &str) -> anyhow::Result<bool> {
     let parsed_hash = PasswordHash::new(hash).map_err(|_| anyhow!("hash pin failure!"))?;
     Ok(Argon2::default()
@@ -30,6 +90,39 @@ pub fn check_pin(pin: &str, hash: &str) -> anyhow::Result<bool> {
         .is_ok())
 }
 
+/// the pin hash is not built by current params, need hash again.
+pub fn pin_outdated(hash: &str) -> bool {
+    let current = PinParams::current();
+    match PasswordHash::new(hash).and_then(|h| Params::try_from(&h)) {
+        Ok(p) => {
+            p.m_cost() != current.m_cost
+                || p.t_cost() != current.t_cost
+                || p.p_cost() != current.p_cost
+        }
+        Err(_) => true,
+    }
+}
+
+/// the encrypted key is legacy format (nonce fixed), need encrypt again.
+pub fn is_legacy_key(salt: &[u8], pin: &str, ckey: &[u8]) -> anyhow::Result<bool> {
+    let (_, legacy) = open_format(&build_cipher(salt, pin), ckey, &legacy_pin_nonce(pin))?;
+    Ok(legacy)
+}
+
+/// the ciphertext is legacy format (nonce fixed), need encrypt again.
+pub fn is_legacy(salt: &[u8], pin: &str, ckey: &[u8], ctext: &[u8]) -> anyhow::Result<bool> {
+    let key = decrypt_key(salt, pin, ckey)?;
+    let (_, legacy) = open_format(&build_keycipher(&key), ctext, &legacy_salt_nonce(salt))?;
+    Ok(legacy)
+}
+
+/// the header maybe current format, the legacy ciphertext can also start with it.
+fn is_versioned(ctext: &[u8]) -> bool {
+    ctext.len() >= CIPHER_HEADER_LEN + CIPHER_TAG_LEN
+        && ctext[0] == CIPHER_VERSION
+        && ctext[1] == CIPHER_AES256GCM
+}
+
 fn build_cipher(salt: &[u8], pin: &str) -> Aes256Gcm {
     let mut hasher = blake3::Hasher::new();
     hasher.update(salt);
@@ -46,58 +139,85 @@ fn build_keycipher(key: &[u8]) -> Aes256Gcm {
     Aes256Gcm::new(GenericArray::from_slice(hash_key.as_bytes())) // 256-bit key.
 }
 
-/// encrypted key bytes.
-pub fn encrypt_key(salt: &[u8], pin: &str, ptext: &[u8]) -> anyhow::Result<Vec<u8>> {
-    let cipher = build_cipher(salt, pin);
-
+/// legacy nonce of key cipher, derived from pin.
+fn legacy_pin_nonce(pin: &str) -> [u8; 12] {
     let mut hasher = Sha256::new();
     hasher.update(pin.as_bytes());
     hasher.update(&FIX_PADDING);
     let res = hasher.finalize();
-    let nonce = GenericArray::from_slice(&res[0..12]); // 96-bit key.
-    cipher
-        .encrypt(nonce, ptext)
-        .or(Err(anyhow!("encrypt data failure.")))
+    let mut nonce = [0u8; 12];
+    nonce.copy_from_slice(&res[0..12]); // 96-bit nonce.
+    nonce
 }
 
-/// decrypted key bytes.
-pub fn decrypt_key(salt: &[u8], pin: &str, ctext: &[u8]) -> anyhow::Result<Vec<u8>> {
-    let cipher = build_cipher(salt, pin);
-
+/// legacy nonce of data cipher, derived from salt.
+fn legacy_salt_nonce(salt: &[u8]) -> [u8; 12] {
     let mut hasher = Sha256::new();
-    hasher.update(pin.as_bytes());
+    hasher.update(salt);
     hasher.update(&FIX_PADDING);
     let res = hasher.finalize();
-    let nonce = GenericArray::from_slice(&res[0..12]); // 96-bit key.
+    let mut nonce = [0u8; 12];
+    nonce.copy_from_slice(&res[0..12]); // 96-bit nonce.
+    nonce
+}
 
+/// encrypt with a random nonce: version | algorithm | nonce | ciphertext.
+fn seal(cipher: &Aes256Gcm, ptext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let nonce: [u8; 12] = rand::random(); // 96-bit nonce.
+    let mut ctext = cipher
+        .encrypt(GenericArray::from_slice(&nonce), ptext)
+        .or(Err(anyhow!("encrypt data failure.")))?;
+    let mut bytes = Vec::with_capacity(CIPHER_HEADER_LEN + ctext.len());
+    bytes.push(CIPHER_VERSION);
+    bytes.push(CIPHER_AES256GCM);
+    bytes.extend_from_slice(&nonce);
+    bytes.append(&mut ctext);
+    Ok(bytes)
+}
+
+/// decrypt the versioned ciphertext, fallback to legacy fixed nonce.
+fn open(cipher: &Aes256Gcm, ctext: &[u8], legacy_nonce: &[u8; 12]) -> anyhow::Result<Vec<u8>> {
+    open_format(cipher, ctext, legacy_nonce).map(|(ptext, _)| ptext)
+}
+
+/// decrypt the current format, none if not it.
+fn open_versioned(cipher: &Aes256Gcm, ctext: &[u8]) -> Option<Vec<u8>> {
+    if !is_versioned(ctext) {
+        return None;
+    }
+    let (nonce, body) = ctext[2..].split_at(12);
+    cipher.decrypt(GenericArray::from_slice(nonce), body).ok()
+}
+
+/// try current format first, then legacy format, return true if legacy.
+fn open_format(
+    cipher: &Aes256Gcm,
+    ctext: &[u8],
+    legacy_nonce: &[u8; 12],
+) -> anyhow::Result<(Vec<u8>, bool)> {
+    if let Some(ptext) = open_versioned(cipher, ctext) {
+        return Ok((ptext, false));
+    }
     cipher
-        .decrypt(nonce, ctext)
+        .decrypt(GenericArray::from_slice(legacy_nonce), ctext)
+        .map(|ptext| (ptext, true))
         .or(Err(anyhow!("decrypt data failure.")))
 }
 
-/// encrypted bytes.
-pub fn encrypt(salt: &[u8], pin: &str, ckey: &[u8], ptext: &[u8]) -> anyhow::Result<Vec<u8>> {
-    let cipher = build_cipher(salt, pin);
-
-    let mut hasher = Sha256::new();
-    hasher.update(pin.as_bytes());
-    hasher.update(&FIX_PADDING);
-    let res = hasher.finalize();
-    let nonce = GenericArray::from_slice(&res[0..12]); // 96-bit key.
+/// encrypted key bytes.
+pub fn encrypt_key(salt: &[u8], pin: &str, ptext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    seal(&build_cipher(salt, pin), ptext)
+}
 
-    let key = cipher
-        .decrypt(nonce, ckey)
-        .or(Err(anyhow!("decrypt data failure.")))?;
-    let c_cipher = build_keycipher(&key);
-    let mut c_hasher = Sha256::new();
-    c_hasher.update(salt);
-    c_hasher.update(&FIX_PADDING);
-    let c_res = c_hasher.finalize();
-    let c_nonce = GenericArray::from_slice(&c_res[0..12]); // 96-bit key.
+/// decrypted key bytes.
+pub fn decrypt_key(salt: &[u8], pin: &str, ctext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    open(&build_cipher(salt, pin), ctext, &legacy_pin_nonce(pin))
+}
 
-    c_cipher
-        .encrypt(c_nonce, ptext)
-        .or(Err(anyhow!("encrypt data failure.")))
+/// encrypted bytes.
+pub fn encrypt(salt: &[u8], pin: &str, ckey: &[u8], ptext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let key = decrypt_key(salt, pin, ckey)?;
+    seal(&build_keycipher(&key), ptext)
 }
 
 pub fn encrypt_multiple(
@@ -106,58 +226,20 @@ pub fn encrypt_multiple(
     ckey: &[u8],
     ptext: Vec<&[u8]>,
 ) -> anyhow::Result<Vec<Vec<u8>>> {
-    let cipher = build_cipher(salt, pin);
-
-    let mut hasher = Sha256::new();
-    hasher.update(pin);
-    hasher.update(&FIX_PADDING);
-    let res = hasher.finalize();
-    let nonce = GenericArray::from_slice(&res[0..12]); // 96-bit key.
-
-    let key = cipher
-        .decrypt(nonce, ckey)
-        .or(Err(anyhow!("decrypt data failure.")))?;
+    let key = decrypt_key(salt, pin, ckey)?;
     let c_cipher = build_keycipher(&key);
-    let mut c_hasher = Sha256::new();
-    c_hasher.update(salt);
-    c_hasher.update(&FIX_PADDING);
-    let c_res = c_hasher.finalize();
-    let c_nonce = GenericArray::from_slice(&c_res[0..12]); // 96-bit key.
 
     let mut ebytes = vec![];
     for p in ptext {
-        ebytes.push(
-            c_cipher
-                .encrypt(c_nonce, p)
-                .or(Err(anyhow!("encrypt data failure.")))?,
-        );
+        ebytes.push(seal(&c_cipher, p)?);
     }
     Ok(ebytes)
 }
 
 /// decrypted bytes.
 pub fn decrypt(salt: &[u8], pin: &str, ckey: &[u8], ctext: &[u8]) -> anyhow::Result<Vec<u8>> {
-    let cipher = build_cipher(salt, pin);
-
-    let mut hasher = Sha256::new();
-    hasher.update(pin.as_bytes());
-    hasher.update(&FIX_PADDING);
-    let res = hasher.finalize();
-    let nonce = GenericArray::from_slice(&res[0..12]); // 96-bit key.
-
-    let key = cipher
-        .decrypt(nonce, ckey)
-        .or(Err(anyhow!("decrypt data failure.")))?;
-    let c_cipher = build_keycipher(&key);
-    let mut c_hasher = Sha256::new();
-    c_hasher.update(salt);
-    c_hasher.update(&FIX_PADDING);
-    let c_res = c_hasher.finalize();
-    let c_nonce = GenericArray::from_slice(&c_res[0..12]); // 96-bit key.
-
-    c_cipher
-        .decrypt(c_nonce, ctext)
-        .or(Err(anyhow!("decrypt data failure.")))
+    let key = decrypt_key(salt, pin, ckey)?;
+    open(&build_keycipher(&key), ctext, &legacy_salt_nonce(salt))
 }
 
 pub fn _decrypt_multiple(
@@ -166,31 +248,33 @@ pub fn _decrypt_multiple(
     ckey: &[u8],
     ctext: Vec<&[u8]>,
 ) -> anyhow::Result<Vec<Vec<u8>>> {
-    let cipher = build_cipher(salt, pin);
-
-    let mut hasher = Sha256::new();
-    hasher.update(pin.as_bytes());
-    hasher.update(&FIX_PADDING);
-    let res = hasher.finalize();
-    let nonce = GenericArray::from_slice(&res[0..12]); // 96-bit key.
-
-    let key = cipher
-        .decrypt(nonce, ckey)
-        .or(Err(anyhow!("decrypt data failure.")))?;
+    let key = decrypt_key(salt, pin, ckey)?;
     let c_cipher = build_keycipher(&key);
-    let mut c_hasher = Sha256::new();
-    c_hasher.update(salt);
-    c_hasher.update(&FIX_PADDING);
-    let c_res = c_hasher.finalize();
-    let c_nonce = GenericArray::from_slice(&c_res[0..12]); // 96-bit key.
+    let nonce = legacy_salt_nonce(salt);
 
     let mut pbytes = vec![];
     for c in ctext {
-        pbytes.push(
-            c_cipher
-                .decrypt(c_nonce, c)
-                .or(Err(anyhow!("decrypt data failure.")))?,
-        );
+        pbytes.push(open(&c_cipher, c, &nonce)?);
     }
     Ok(pbytes)
 }
+
+/// encrypted bytes by the 256-bit key, versioned format as `seal`.
+pub fn encrypt_bytes(key: &[u8; 32], ptext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    seal(&Aes256Gcm::new(GenericArray::from_slice(key)), ptext)
+}
+
+/// decrypted bytes from `encrypt_bytes`, fallback to legacy format: nonce | ciphertext.
+pub fn decrypt_bytes(key: &[u8; 32], ctext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(key)); // 256-bit key.
+    if let Some(ptext) = open_versioned(&cipher, ctext) {
+        return Ok(ptext);
+    }
+    if ctext.len() < 12 {
+        return Err(anyhow!("decrypt data failure."));
+    }
+    let (nonce, ctext) = ctext.split_at(12);
+    cipher
+        .decrypt(GenericArray::from_slice(nonce), ctext)
+        .or(Err(anyhow!("decrypt data failure.")))
+}
@@ -0,0 +1,198 @@
+use rand::Rng;
+use tdn::types::primitive::Result;
+
+/// multiply in GF(256), AES polynomial x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut p = 0u8;
+    while b > 0 {
+        if b & 1 == 1 {
+            p ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    p
+}
+
+/// inverse in GF(256), a^254.
+fn gf_inv(a: u8) -> u8 {
+    let mut r = 1u8;
+    for _ in 0..254 {
+        r = gf_mul(r, a);
+    }
+    r
+}
+
+/// split the secret to `count` shares, any `threshold` shares can combine it.
+/// share is (x, y), x is in 1..=count.
+pub(crate) fn split(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<(u8, Vec<u8>)>> {
+    if threshold < 1 || threshold > count {
+        return Err(anyhow!("shares threshold is invalid"));
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut shares: Vec<(u8, Vec<u8>)> = (1..=count)
+        .map(|x| (x, Vec::with_capacity(secret.len())))
+        .collect();
+    for byte in secret {
+        // random polynomial, the constant term is the secret byte.
+        let mut coeffs = vec![*byte];
+        for _ in 1..threshold {
+            coeffs.push(rng.gen());
+        }
+        for (x, y) in shares.iter_mut() {
+            let mut v = 0u8;
+            for c in coeffs.iter().rev() {
+                v = gf_mul(v, *x) ^ c;
+            }
+            y.push(v);
+        }
+    }
+    Ok(shares)
+}
+
+/// combine the secret from shares, by lagrange interpolation at x = 0.
+pub(crate) fn combine(shares: &[(u8, Vec<u8>)]) -> Result<Vec<u8>> {
+    if shares.is_empty() {
+        return Err(anyhow!("shares is empty"));
+    }
+    let len = shares[0].1.len();
+    for (i, (x, y)) in shares.iter().enumerate() {
+        if *x == 0 || y.len() != len || shares[..i].iter().any(|(ox, _)| ox == x) {
+            return Err(anyhow!("shares is invalid"));
+        }
+    }
+
+    let mut secret = vec![0u8; len];
+    for (i, (xi, yi)) in shares.iter().enumerate() {
+        let mut basis = 1u8;
+        for (j, (xj, _)) in shares.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(basis, gf_mul(*xj, gf_inv(xi ^ xj)));
+            }
+        }
+        for (s, y) in secret.iter_mut().zip(yi) {
+            *s ^= gf_mul(*y, basis);
+        }
+    }
+    Ok(secret)
+}
+
+/// combine the secret from any `threshold` shares which passed the check,
+/// the bogus shares are skipped. every checked combination uses one of `tries`.
+pub(crate) fn combine_checked<F: Fn(&[u8]) -> bool>(
+    shares: &[(u8, Vec<u8>)],
+    threshold: usize,
+    tries: &mut usize,
+    check: F,
+) -> Option<Vec<u8>> {
+    let n = shares.len();
+    if threshold < 1 || threshold > n {
+        return None;
+    }
+
+    // shares indexes of the combination, in ascending order.
+    let mut indexes: Vec<usize> = (0..threshold).collect();
+    while *tries > 0 {
+        *tries -= 1;
+        let picked: Vec<(u8, Vec<u8>)> = indexes.iter().map(|i| shares[*i].clone()).collect();
+        if let Ok(secret) = combine(&picked) {
+            if check(&secret) {
+                return Some(secret);
+            }
+        }
+
+        // next combination, the last index which can move forward.
+        let mut i = threshold;
+        loop {
+            if i == 0 {
+                return None;
+            }
+            i -= 1;
+            if indexes[i] < n - threshold + i {
+                break;
+            }
+        }
+        indexes[i] += 1;
+        for j in i + 1..threshold {
+            indexes[j] = indexes[j - 1] + 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"abandon ability able about above absent absorb abstract";
+
+    #[test]
+    fn split_and_combine() {
+        let shares = split(SECRET, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert_eq!(combine(&shares).unwrap(), SECRET);
+
+        // any threshold shares can combine the secret.
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let picked = vec![shares[a].clone(), shares[b].clone(), shares[c].clone()];
+                    assert_eq!(combine(&picked).unwrap(), SECRET);
+                }
+            }
+        }
+
+        // fewer shares cannot.
+        assert_ne!(combine(&shares[..2]).unwrap(), SECRET);
+    }
+
+    #[test]
+    fn threshold_one() {
+        let shares = split(SECRET, 1, 3).unwrap();
+        for share in shares {
+            assert_eq!(share.1, SECRET);
+            assert_eq!(combine(&[share]).unwrap(), SECRET);
+        }
+    }
+
+    #[test]
+    fn invalid_shares() {
+        assert!(split(SECRET, 0, 3).is_err());
+        assert!(split(SECRET, 4, 3).is_err());
+        assert!(combine(&[]).is_err());
+
+        let shares = split(SECRET, 2, 3).unwrap();
+        let repeated = vec![shares[0].clone(), shares[0].clone()];
+        assert!(combine(&repeated).is_err());
+        let zero = vec![(0, shares[0].1.clone()), shares[1].clone()];
+        assert!(combine(&zero).is_err());
+        let short = vec![shares[0].clone(), (shares[1].0, vec![1, 2])];
+        assert!(combine(&short).is_err());
+    }
+
+    #[test]
+    fn combine_skip_bogus() {
+        let mut shares = split(SECRET, 3, 5).unwrap();
+        shares[1].1[0] ^= 1;
+        shares.push((9, vec![7; SECRET.len()]));
+        assert_ne!(combine(&shares).unwrap(), SECRET);
+
+        let mut tries = 1024;
+        let secret = combine_checked(&shares, 3, &mut tries, |s| s == SECRET);
+        assert_eq!(secret.unwrap(), SECRET);
+
+        // not enough good shares.
+        let mut tries = 1024;
+        assert!(combine_checked(&shares[..3], 3, &mut tries, |s| s == SECRET).is_none());
+
+        // tries is used up.
+        let mut tries = 1;
+        assert!(combine_checked(&shares, 3, &mut tries, |s| s == SECRET).is_none());
+        assert_eq!(tries, 0);
+    }
+}
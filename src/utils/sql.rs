@@ -0,0 +1,347 @@
+use tdn::types::{
+    group::{EventId, GroupId},
+    primitive::{PeerId, Result},
+};
+use tdn_storage::local::{DStorage, DsValue};
+
+/// Value bound to a `?` placeholder.
+pub(crate) enum SqlValue {
+    Null,
+    Integer(i64),
+    Text(String),
+    /// expanded to `a, b, c`, use in `IN (?)`.
+    List(Vec<SqlValue>),
+    /// integer out of the i64 range, rejected when render.
+    Overflow,
+}
+
+impl SqlValue {
+    /// render to a SQLite literal, text is always quoted and escaped.
+    fn literal(&self) -> Result<String> {
+        match self {
+            SqlValue::Null => Ok("NULL".to_owned()),
+            SqlValue::Integer(i) => Ok(i.to_string()),
+            SqlValue::Text(s) => {
+                // SQLite text literal cannot hold NUL, the statement ends there.
+                if s.contains('\0') {
+                    return Err(anyhow!("sql text cannot contain NUL."));
+                }
+                Ok(format!("'{}'", s.replace('\'', "''")))
+            }
+            SqlValue::List(vs) => {
+                let ls = vs.iter().map(|v| v.literal()).collect::<Result<Vec<_>>>()?;
+                Ok(ls.join(", "))
+            }
+            SqlValue::Overflow => Err(anyhow!("sql integer out of range.")),
+        }
+    }
+}
+
+/// Types can be bound to query's placeholder.
+pub(crate) trait ToSql {
+    fn to_sql(&self) -> SqlValue;
+}
+
+impl ToSql for i64 {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Integer(*self)
+    }
+}
+
+impl ToSql for i32 {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Integer(*self as i64)
+    }
+}
+
+impl ToSql for u32 {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Integer(*self as i64)
+    }
+}
+
+impl ToSql for u64 {
+    fn to_sql(&self) -> SqlValue {
+        i64::try_from(*self)
+            .map(SqlValue::Integer)
+            .unwrap_or(SqlValue::Overflow)
+    }
+}
+
+impl ToSql for usize {
+    fn to_sql(&self) -> SqlValue {
+        i64::try_from(*self)
+            .map(SqlValue::Integer)
+            .unwrap_or(SqlValue::Overflow)
+    }
+}
+
+impl ToSql for bool {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Integer(if *self { 1 } else { 0 })
+    }
+}
+
+impl ToSql for str {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Text(self.to_owned())
+    }
+}
+
+impl ToSql for String {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Text(self.clone())
+    }
+}
+
+impl ToSql for GroupId {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Text(self.to_hex())
+    }
+}
+
+impl ToSql for PeerId {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Text(self.to_hex())
+    }
+}
+
+impl ToSql for EventId {
+    fn to_sql(&self) -> SqlValue {
+        SqlValue::Text(self.to_hex())
+    }
+}
+
+impl<T: ToSql> ToSql for Option<T> {
+    fn to_sql(&self) -> SqlValue {
+        match self {
+            Some(v) => v.to_sql(),
+            None => SqlValue::Null,
+        }
+    }
+}
+
+impl<T: ToSql + ?Sized> ToSql for &T {
+    fn to_sql(&self) -> SqlValue {
+        (**self).to_sql()
+    }
+}
+
+/// Typed query with bound parameters, every `?` in the statement (not in a
+/// quoted literal, quoted identifier or comment) is replaced by the next bound
+/// value.
+///
+/// `DStorage` only executes statement strings, so the values are rendered as
+/// SQLite literals: integers in decimal, text single-quoted with `'` doubled,
+/// which is the only escape of SQLite text literal. Text with NUL is rejected.
+///
+/// ```ignore
+/// let matrix = Query::new("SELECT id FROM friends WHERE gid = ? AND is_closed = ?")
+///     .bind(&gid)
+///     .bind(&false)
+///     .query(&db)?;
+/// ```
+pub(crate) struct Query {
+    sql: String,
+    params: Vec<SqlValue>,
+}
+
+impl Query {
+    pub fn new(sql: &str) -> Self {
+        Self {
+            sql: sql.to_owned(),
+            params: vec![],
+        }
+    }
+
+    pub fn bind<T: ToSql + ?Sized>(mut self, value: &T) -> Self {
+        self.params.push(value.to_sql());
+        self
+    }
+
+    /// bind a list to one placeholder, use in `IN (?)`.
+    pub fn bind_list<T: ToSql>(mut self, values: &[T]) -> Self {
+        self.params
+            .push(SqlValue::List(values.iter().map(|v| v.to_sql()).collect()));
+        self
+    }
+
+    /// build the final statement.
+    pub fn render(&self) -> Result<String> {
+        let mut sql = String::with_capacity(self.sql.len() + self.params.len() * 8);
+        let mut params = self.params.iter();
+        let mut chars = self.sql.chars().peekable();
+        while let Some(c) = chars.next() {
+            sql.push(c);
+            match c {
+                // quoted literal or identifier, `''` is two quoted parts.
+                '\'' | '"' | '`' => {
+                    let close = c;
+                    for c in chars.by_ref() {
+                        sql.push(c);
+                        if c == close {
+                            break;
+                        }
+                    }
+                }
+                '[' => {
+                    for c in chars.by_ref() {
+                        sql.push(c);
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                '-' if chars.peek() == Some(&'-') => {
+                    for c in chars.by_ref() {
+                        sql.push(c);
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    sql.push(chars.next().unwrap()); // safe.
+                    let mut last = ' ';
+                    for c in chars.by_ref() {
+                        sql.push(c);
+                        if last == '*' && c == '/' {
+                            break;
+                        }
+                        last = c;
+                    }
+                }
+                '?' => {
+                    sql.pop();
+                    let value = params
+                        .next()
+                        .ok_or(anyhow!("sql params number not matching."))?;
+                    sql.push_str(&value.literal()?);
+                }
+                _ => {}
+            }
+        }
+
+        if params.next().is_some() {
+            return Err(anyhow!("sql params number not matching."));
+        }
+        Ok(sql)
+    }
+
+    pub fn query(&self, db: &DStorage) -> Result<Vec<Vec<DsValue>>> {
+        db.query(&self.render()?)
+    }
+
+    pub fn insert(&self, db: &DStorage) -> Result<i64> {
+        db.insert(&self.render()?)
+    }
+
+    pub fn update(&self, db: &DStorage) -> Result<usize> {
+        db.update(&self.render()?)
+    }
+
+    pub fn delete(&self, db: &DStorage) -> Result<usize> {
+        db.delete(&self.render()?)
+    }
+
+    /// statement without result, e.g. `PRAGMA`.
+    pub fn execute(&self, db: &DStorage) -> Result<()> {
+        db.execute(&self.render()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXTS: [&str; 6] = [
+        "it's",
+        "'; DROP TABLE t; --",
+        "a ? b",
+        "\"quoted\" /* c */ -- d",
+        "你好, 世界 🎉",
+        "",
+    ];
+
+    #[test]
+    fn render_text() {
+        let sql = Query::new("SELECT ? FROM t WHERE a = ?")
+            .bind("it's")
+            .bind(&1i64)
+            .render()
+            .unwrap();
+        assert_eq!(sql, "SELECT 'it''s' FROM t WHERE a = 1");
+
+        let sql = Query::new("SELECT ?").bind("; ?").render().unwrap();
+        assert_eq!(sql, "SELECT '; ?'");
+    }
+
+    #[test]
+    fn render_skip_quoted_and_comments() {
+        let sql = Query::new(
+            "SELECT 'a?', \"b?\", `c?`, [d?] -- e?\n/* f? */ FROM t WHERE g = ? AND h = 'i''?'",
+        )
+        .bind(&None::<i64>)
+        .render()
+        .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT 'a?', \"b?\", `c?`, [d?] -- e?\n/* f? */ FROM t WHERE g = NULL AND h = 'i''?'"
+        );
+    }
+
+    #[test]
+    fn render_list() {
+        let sql = Query::new("SELECT id FROM t WHERE id IN (?)")
+            .bind_list(&[1i64, 2, 3])
+            .render()
+            .unwrap();
+        assert_eq!(sql, "SELECT id FROM t WHERE id IN (1, 2, 3)");
+    }
+
+    #[test]
+    fn render_errors() {
+        assert!(Query::new("SELECT ?").render().is_err());
+        assert!(Query::new("SELECT 1").bind(&1i64).render().is_err());
+        assert!(Query::new("SELECT ?").bind("a\0b").render().is_err());
+        assert!(Query::new("SELECT ?").bind(&u64::MAX).render().is_err());
+        let list = [1usize, usize::MAX];
+        assert!(Query::new("SELECT ?").bind_list(&list).render().is_err());
+
+        let max = i64::MAX as u64;
+        let sql = Query::new("SELECT ?").bind(&max).render().unwrap();
+        assert_eq!(sql, format!("SELECT {}", max));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("esse-sql-{}.db", rand::random::<u64>()));
+        let db = DStorage::open(path.clone(), "test").unwrap();
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT, a TEXT, b INTEGER)")
+            .unwrap();
+
+        for (i, text) in TEXTS.iter().enumerate() {
+            let id = Query::new("INSERT INTO t (a, b) VALUES (?, ?)")
+                .bind(*text)
+                .bind(&i)
+                .insert(&db)
+                .unwrap();
+            let mut matrix = Query::new("SELECT a, b FROM t WHERE id = ? AND a = ?")
+                .bind(&id)
+                .bind(*text)
+                .query(&db)
+                .unwrap();
+            assert_eq!(matrix.len(), 1);
+            let mut v = matrix.pop().unwrap();
+            assert_eq!(v.pop().unwrap().as_i64(), i as i64);
+            assert_eq!(v.pop().unwrap().as_string(), *text);
+        }
+
+        let matrix = Query::new("SELECT id FROM t").query(&db).unwrap();
+        assert_eq!(matrix.len(), TEXTS.len());
+        drop(db);
+        let _ = std::fs::remove_file(path);
+    }
+}
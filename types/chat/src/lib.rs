@@ -1,19 +1,87 @@
 use serde::{Deserialize, Serialize};
-use tdn_types::{group::GroupId, primitive::PeerId};
+use tdn_types::{
+    group::{EventId, GroupId},
+    primitive::PeerId,
+};
 
 /// message type use in network.
 #[derive(Serialize, Deserialize, Clone)]
 pub enum NetworkMessage {
     String(String),                            // content
     Image(Vec<u8>),                            // image bytes.
-    File(String, Vec<u8>),                     // filename, file bytes.
+    File(String, Vec<u8>),                     // filename, file bytes (empty if chunked).
     Contact(String, GroupId, PeerId, Vec<u8>), // name, gid, addr, avatar bytes.
     Record(Vec<u8>, u32),                      // record audio bytes.
-    Emoji,
-    Phone,
-    Video,
+    Emoji(String, Vec<u8>),                    // sticker name, sticker bytes.
+    Phone(CallSignal),                         // phone call signal.
+    Video(CallSignal),                         // video call signal.
     Invite(String),
     Transfer(String),
+    Recovery(Vec<u8>), // recovery share piece bytes.
+}
+
+/// call signalling, every signal is a message.
+#[derive(Serialize, Deserialize, Clone)]
+pub enum CallSignal {
+    /// params: session description.
+    Offer(String),
+    /// params: session description.
+    Answer(String),
+    /// params: call duration seconds.
+    Hangup(u32),
+    /// call not answered.
+    Missed,
+}
+
+impl CallSignal {
+    /// stored message content.
+    pub fn to_content(&self) -> String {
+        match self {
+            CallSignal::Offer(sdp) => format!("offer;;{}", sdp),
+            CallSignal::Answer(sdp) => format!("answer;;{}", sdp),
+            CallSignal::Hangup(duration) => format!("hangup;;{}", duration),
+            CallSignal::Missed => "missed".to_owned(),
+        }
+    }
+
+    pub fn from_content(s: &str) -> Option<CallSignal> {
+        let (t, v) = s.split_once(";;").unwrap_or((s, ""));
+        match t {
+            "offer" => Some(CallSignal::Offer(v.to_owned())),
+            "answer" => Some(CallSignal::Answer(v.to_owned())),
+            "hangup" => Some(CallSignal::Hangup(v.parse().unwrap_or(0))),
+            "missed" => Some(CallSignal::Missed),
+            _ => None,
+        }
+    }
+}
+
+/// file chunk's size when transfer big file.
+pub const FILE_CHUNK_SIZE: usize = 256 * 1024; // 256KB
+
+/// chunked file transfer in the stream, the receiver pull chunks from the sender.
+#[derive(Serialize, Deserialize, Clone)]
+pub enum FileChunk {
+    /// params: message hash, file name, file size, file hash, every chunk's hash.
+    Start(EventId, String, u64, [u8; 32], Vec<[u8; 32]>),
+    /// params: message hash, chunk index, chunk bytes.
+    Data(EventId, u32, Vec<u8>),
+    /// request chunks from the sender. params: message hash, chunks index.
+    Request(EventId, Vec<u32>),
+    /// file received and verified. params: message hash.
+    Done(EventId),
+}
+
+impl FileChunk {
+    /// the transfer's message hash.
+    pub fn hash(&self) -> &EventId {
+        match self {
+            FileChunk::Start(hash, ..)
+            | FileChunk::Data(hash, ..)
+            | FileChunk::Request(hash, ..)
+            | FileChunk::Done(hash) => hash,
+        }
+    }
 }
 
 /// common message types.
@@ -29,6 +97,7 @@ pub enum MessageType {
     Video,
     Invite,
     Transfer,
+    Recovery,
 }
 
 impl MessageType {
@@ -44,6 +113,7 @@ impl MessageType {
             MessageType::Video => 7,
             MessageType::Invite => 8,
             MessageType::Transfer => 9,
+            MessageType::Recovery => 10,
         }
     }
 
@@ -59,6 +129,7 @@ impl MessageType {
             7 => MessageType::Video,
             8 => MessageType::Invite,
             9 => MessageType::Transfer,
+            10 => MessageType::Recovery,
             _ => MessageType::String,
         }
     }
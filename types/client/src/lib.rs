@@ -0,0 +1,324 @@
+//! ESSE daemon websocket rpc client.
+//!
+//! Call the methods with the typed params and results in `rpc_types`, and
+//! receive the notifications pushed by the daemon as typed events.
+//!
+//! ```ignore
+//! use rpc_client::{read_token, Client, DEFAULT_ADDR};
+//! use rpc_types::{account::AccountList, chat::OnChatMessageCreate, Empty};
+//!
+//! let token = read_token("./.tdn", "full")?;
+//! let client = Client::connect(DEFAULT_ADDR, &token).await?;
+//! let accounts = client.call::<AccountList>("", Empty {}).await?;
+//!
+//! let mut messages = client.subscribe::<OnChatMessageCreate>();
+//! while let Ok(event) = messages.recv().await {
+//!     println!("{} {}", event.gid, event.data.content);
+//! }
+//! ```
+
+use futures_util::{stream::Stream, SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+pub use rpc_types;
+use rpc_types::node::{RpcAuth, RpcAuthParams};
+use rpc_types::{Method, Notification};
+
+/// default websocket address of the daemon.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+/// default waiting time of the response.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// notifications buffered for the slow subscribers.
+const EVENTS_CAPACITY: usize = 1024;
+
+/// rpc token file in the daemon's data directory.
+const TOKEN_FILE: &str = "rpc.token";
+
+/// notification's (method, gid, result), none is the connection closed.
+type RawEvent = Option<(String, String, Value)>;
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// connect or authenticate failure.
+    Connect(String),
+    /// the connection is closed.
+    Closed,
+    /// no response in the waiting time.
+    Timeout,
+    /// error response of the daemon.
+    Rpc {
+        code: i64,
+        message: String,
+    },
+    /// the result or notification is not the declared type.
+    Json(serde_json::Error),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Connect(e) => write!(f, "connect failure: {}", e),
+            Error::Closed => write!(f, "connection closed"),
+            Error::Timeout => write!(f, "response timeout"),
+            Error::Rpc { code, message } => write!(f, "rpc error {}: {}", code, message),
+            Error::Json(e) => write!(f, "json: {}", e),
+            Error::Io(e) => write!(f, "io: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Json(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// read the token of the scope (`full` or `read`) in the daemon's data directory.
+pub fn read_token(data_dir: impl AsRef<Path>, scope: &str) -> Result<String> {
+    let content = std::fs::read_to_string(data_dir.as_ref().join(TOKEN_FILE))?;
+    content
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .find(|(s, _)| *s == scope)
+        .map(|(_, token)| token.trim().to_owned())
+        .ok_or(Error::Connect(format!("{} token not found", scope)))
+}
+
+/// notification pushed by the daemon, gid is the account's.
+#[derive(Clone, Debug)]
+pub struct Event<T> {
+    pub gid: String,
+    pub data: T,
+}
+
+/// Websocket rpc client of the running daemon.
+pub struct Client {
+    sender: mpsc::UnboundedSender<WsMessage>,
+    pending: Pending,
+    events: broadcast::Sender<RawEvent>,
+    closed: Arc<AtomicBool>,
+    next_id: AtomicU64,
+    timeout: Duration,
+}
+
+impl Client {
+    /// connect the daemon's websocket address, and authenticate by the token.
+    pub async fn connect(addr: &str, token: &str) -> Result<Client> {
+        let url = if addr.contains("://") {
+            addr.to_owned()
+        } else {
+            format!("ws://{}", addr)
+        };
+        let (ws, _) = connect_async(url.as_str())
+            .await
+            .map_err(|e| Error::Connect(e.to_string()))?;
+        let (mut sink, mut stream) = ws.split();
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        let closed = Arc::new(AtomicBool::new(false));
+
+        // write the requests, close the connection when client dropped.
+        tokio::spawn(async move {
+            while let Some(msg) = receiver.recv().await {
+                if sink.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            let _ = sink.close().await;
+        });
+
+        // dispatch the responses and the notifications.
+        let (r_pending, r_events, r_closed) = (pending.clone(), events.clone(), closed.clone());
+        tokio::spawn(async move {
+            while let Some(Ok(msg)) = stream.next().await {
+                let text = match msg {
+                    WsMessage::Text(text) => text,
+                    WsMessage::Close(_) => break,
+                    _ => continue,
+                };
+                let mut value: Value = match serde_json::from_str(&text) {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+
+                let id = value["id"].as_u64().unwrap_or(0);
+                if id != 0 {
+                    if let Some(tx) = r_pending.lock().unwrap().remove(&id) {
+                        let _ = tx.send(value);
+                    }
+                } else if let Some(method) = value["method"].as_str().map(|m| m.to_owned()) {
+                    let gid = value["gid"].as_str().unwrap_or("").to_owned();
+                    let _ = r_events.send(Some((method, gid, value["result"].take())));
+                }
+            }
+
+            // wake up the waiting calls and subscribers.
+            r_closed.store(true, Ordering::SeqCst);
+            r_pending.lock().unwrap().clear();
+            let _ = r_events.send(None);
+        });
+
+        let client = Client {
+            sender,
+            pending,
+            events,
+            closed,
+            next_id: AtomicU64::new(1),
+            timeout: DEFAULT_TIMEOUT,
+        };
+
+        let params = RpcAuthParams {
+            token: token.to_owned(),
+        };
+        client
+            .call::<RpcAuth>("", params)
+            .await
+            .map_err(|e| match e {
+                Error::Rpc { message, .. } => Error::Connect(message),
+                e => e,
+            })?;
+
+        Ok(client)
+    }
+
+    /// change the waiting time of the responses.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// the connection is closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// call the method, gid is the account's, empty for the node's methods.
+    /// the methods without result (`Method::NO_RESPONSE`) are success when the
+    /// daemon acks with null, no response in the waiting time is `Error::Timeout`.
+    pub async fn call<M: Method>(&self, gid: &str, params: M::Params) -> Result<M::Result> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        if self.is_closed() {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(Error::Closed);
+        }
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "gid": gid,
+            "method": M::NAME,
+            "params": params,
+        });
+        if self
+            .sender
+            .send(WsMessage::Text(request.to_string()))
+            .is_err()
+        {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(Error::Closed);
+        }
+
+        let mut response = match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => return Err(Error::Closed),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                return Err(Error::Timeout);
+            }
+        };
+
+        let error = &response["error"];
+        if !error.is_null() {
+            return Err(Error::Rpc {
+                code: error["code"].as_i64().unwrap_or(0),
+                message: error["message"].as_str().unwrap_or("").to_owned(),
+            });
+        }
+        if M::NO_RESPONSE {
+            return decode(Value::Null);
+        }
+        decode(response["result"].take())
+    }
+
+    /// subscribe the notification. the daemon pushes the notifications to the
+    /// latest connected websocket client.
+    pub fn subscribe<N: Notification>(&self) -> Subscription<N> {
+        Subscription {
+            receiver: self.events.subscribe(),
+            closed: self.is_closed(),
+            _n: PhantomData,
+        }
+    }
+}
+
+/// the result of the method, it is `()` from the null ack.
+fn decode<T: DeserializeOwned>(value: Value) -> Result<T> {
+    serde_json::from_value(value).map_err(Error::Json)
+}
+
+/// Typed notifications of the client.
+pub struct Subscription<N: Notification> {
+    receiver: broadcast::Receiver<RawEvent>,
+    closed: bool,
+    _n: PhantomData<N>,
+}
+
+impl<N: Notification> Subscription<N> {
+    /// next notification, the lagged notifications are skipped.
+    pub async fn recv(&mut self) -> Result<Event<N::Data>> {
+        loop {
+            if self.closed {
+                return Err(Error::Closed);
+            }
+            let (method, gid, data) = match self.receiver.recv().await {
+                Ok(Some(event)) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Ok(None) | Err(broadcast::error::RecvError::Closed) => {
+                    self.closed = true;
+                    continue;
+                }
+            };
+            if method == N::NAME {
+                let data = serde_json::from_value(data)?;
+                return Ok(Event { gid, data });
+            }
+        }
+    }
+
+    /// the notifications as stream, it ends when the connection closed.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Event<N::Data>>> {
+        futures_util::stream::unfold(self, |mut subscription| async move {
+            match subscription.recv().await {
+                Err(Error::Closed) => None,
+                item => Some((item, subscription)),
+            }
+        })
+    }
+}
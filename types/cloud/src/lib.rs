@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use tdn_did::Proof;
-use tdn_types::group::GroupId;
+use tdn_types::group::{EventId, GroupId};
 
 /// Personal data cloud service default TDN GROUP ID.
 #[rustfmt::skip]
@@ -11,6 +11,21 @@ pub const CLOUD_ID: GroupId = GroupId([
     0, 0, 0, 0, 0, 0, 0, 5,
 ]);
 
+/// Default mailbox quota (bytes) for every receiver.
+pub const MAILBOX_QUOTA: u64 = 64 * 1024 * 1024;
+
+/// Deposit quota (bytes) for every sender, in all receivers' mailboxes.
+pub const DEPOSIT_QUOTA: u64 = 16 * 1024 * 1024;
+
+/// Provider's capacity (bytes) of all mails.
+pub const PROVIDER_QUOTA: u64 = 1024 * 1024 * 1024;
+
+/// Mails expire after it (seconds), even not fetched.
+pub const MAIL_EXPIRE: i64 = 30 * 24 * 60 * 60;
+
+/// Max mails count in one fetch page.
+pub const FETCH_LIMIT: usize = 32;
+
 /// ESSE service to peer layer Event.
 #[derive(Serialize, Deserialize)]
 pub struct LayerServerEvent(pub ServerEvent, pub Proof);
@@ -23,8 +38,14 @@ pub struct LayerPeerEvent(pub PeerEvent, pub Proof);
 #[derive(Serialize, Deserialize)]
 pub enum ServerEvent {
     /// check result status.
-    /// params: provider name, is support request proxy.
-    Status(String, bool),
+    /// params: provider name, is support request proxy, used bytes, quota bytes.
+    Status(String, bool, u64, u64),
+    /// deposit result, failure when receiver's mailbox is full.
+    /// params: mail id, is_ok.
+    Deposited(EventId, bool),
+    /// a page of mails waiting for the peer.
+    /// params: page offset, [(mail id, sender, encrypted data, datetime)], has more.
+    Mails(u32, Vec<(EventId, GroupId, Vec<u8>, i64)>, bool),
 }
 
 /// ESSE peer to service Event.
@@ -32,4 +53,12 @@ pub enum ServerEvent {
 pub enum PeerEvent {
     /// check service status is ok.
     Check,
+    /// deposit a encrypted mail for a offline receiver.
+    /// params: receiver, mail id, encrypted data.
+    Deposit(GroupId, EventId, Vec<u8>),
+    /// fetch the first page of my mails.
+    Fetch,
+    /// mails had received, provider can delete them, and fetch next page.
+    /// params: mail ids, next page offset (the kept mails count).
+    Ack(Vec<EventId>, Option<u32>),
 }
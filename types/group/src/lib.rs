@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use tdn_did::Proof;
-use tdn_types::{group::GroupId, primitive::PeerId};
+use tdn_types::{
+    group::{EventId, GroupId},
+    primitive::PeerId,
+};
 
 use chat_types::NetworkMessage;
 
@@ -52,7 +55,7 @@ pub enum LayerEvent {
     /// Group ID, current height, from height, to height,
     /// add members(height, member id, addr, name, avatar),
     /// leaved members(height, member id),
-    /// add messages(height, member id, message, time).
+    /// add messages(height, member id, message, time, message hash, replied message hash).
     SyncRes(
         GroupId,
         i64,
@@ -60,7 +63,7 @@ pub enum LayerEvent {
         i64,
         Vec<(i64, GroupId, PeerId, String, Vec<u8>)>,
         Vec<(i64, GroupId)>,
-        Vec<(i64, GroupId, NetworkMessage, i64)>,
+        Vec<(i64, GroupId, NetworkMessage, i64, EventId, Option<EventId>)>,
     ),
 }
 
@@ -91,6 +94,6 @@ pub enum Event {
     MemberJoin(GroupId, PeerId, String, Vec<u8>),
     /// params: member id,
     MemberLeave(GroupId),
-    /// params: member id, message, message time.
-    MessageCreate(GroupId, NetworkMessage, i64),
+    /// params: member id, message, message time, message hash, replied message hash.
+    MessageCreate(GroupId, NetworkMessage, i64, EventId, Option<EventId>),
 }
@@ -0,0 +1,174 @@
+//! account's methods and notifications, gid is hex string.
+
+use crate::Empty;
+
+record!(SystemInfo { addr: String });
+
+record!(
+    /// avatar is base64 encoded, index is the identity index in the mnemonic.
+    AccountInfo {
+        gid: String,
+        name: String,
+        avatar: String,
+        index: String,
+    }
+);
+
+record!(
+    /// lang is the mnemonic's language.
+    AccountGenerateParams { lang: i64 }
+);
+
+record!(Mnemonic { words: String });
+
+record!(
+    /// count is at most 32.
+    AccountIdentitiesParams {
+        lang: i64,
+        seed: String,
+        pass: String,
+        count: i64,
+    }
+);
+
+record!(
+    /// had is the identity had been in this node.
+    Identity {
+        index: u32,
+        gid: String,
+        had: bool,
+    }
+);
+
+record!(
+    /// avatar is base64 encoded, index default is 0.
+    AccountCreateParams {
+        lang: i64,
+        seed: String,
+        pass: String,
+        name: String,
+        lock: String,
+        avatar: String,
+        #[serde(default)]
+        index: i64,
+    }
+);
+
+record!(AccountGid { gid: String });
+
+record!(
+    /// addr is the device to sync from, restore the identity at index 0, and scan
+    /// index 1..count (default is 1, max is 32) in the device, the used identities
+    /// are notified by `account-identities-found`, create them by `account-create`.
+    AccountRestoreParams {
+        lang: i64,
+        seed: String,
+        pass: String,
+        name: String,
+        lock: String,
+        addr: String,
+        #[serde(default)]
+        count: Option<i64>,
+    }
+);
+
+record!(AccountUpdateParams {
+    name: String,
+    avatar: String,
+});
+
+record!(AccountPinCheckParams {
+    gid: String,
+    lock: String,
+});
+
+record!(PinCheck { valid: bool });
+
+record!(AccountPinParams {
+    old: String,
+    new: String,
+});
+
+record!(Pin { lock: String });
+
+record!(AccountLockParams { lock: String });
+
+record!(
+    /// path is the backup file.
+    AccountBackupParams { path: String, lock: String }
+);
+
+record!(BackupPath { path: String });
+
+record!(
+    /// lost is the lost device's addr, it will be removed.
+    AccountKeyRotateParams {
+        lock: String,
+        #[serde(default)]
+        lost: Option<String>,
+    }
+);
+
+record!(AccountLoginParams {
+    gid: String,
+    lock: String,
+});
+
+record!(AccountIdleParams { idle: i64 });
+
+record!(AccountUpdate {
+    gid: String,
+    name: String,
+    avatar: String,
+});
+
+record!(BackupProgress {
+    is_export: bool,
+    done: u64,
+    total: u64,
+});
+
+record!(KeyRotated {
+    device: String,
+    lost: String,
+});
+
+methods! {
+    /// this node's addr.
+    AccountSystemInfo = "account-system-info" (Empty) -> SystemInfo;
+    AccountList = "account-list" (Empty) -> Vec<AccountInfo>;
+    /// generate new mnemonic words.
+    AccountGenerate = "account-generate" (AccountGenerateParams) -> Mnemonic;
+    /// the identities derived from the mnemonic.
+    AccountIdentities = "account-identities" (AccountIdentitiesParams) -> Vec<Identity>;
+    AccountCreate = "account-create" (AccountCreateParams) -> AccountGid;
+    AccountRestore = "account-restore" (AccountRestoreParams) -> Vec<String>;
+    AccountUpdateInfo = "account-update" (AccountUpdateParams);
+    AccountPinCheck = "account-pin-check" (AccountPinCheckParams) -> PinCheck;
+    AccountPin = "account-pin" (AccountPinParams) -> Pin;
+    AccountMnemonic = "account-mnemonic" (AccountLockParams) -> Mnemonic;
+    AccountBackupExport = "account-backup-export" (AccountBackupParams) -> BackupPath;
+    /// the account is offline after imported, login again.
+    AccountBackupImport = "account-backup-import" (AccountBackupParams) -> AccountGid;
+    /// rotate the data key, and remove the lost device.
+    AccountKeyRotate = "account-key-rotate" (AccountKeyRotateParams) -> AccountGid;
+    AccountDelete = "account-delete" (AccountLockParams) -> AccountGid;
+    AccountLogin = "account-login" (AccountLoginParams) -> AccountGid;
+    /// logout all accounts.
+    AccountLogout = "account-logout" (Empty);
+    AccountOnline = "account-online" (AccountGid);
+    AccountOffline = "account-offline" (AccountGid);
+    /// auto-lock after idle seconds, 0 is never.
+    AccountIdle = "account-idle" (AccountIdleParams) -> AccountIdleParams;
+}
+
+notifications! {
+    OnAccountUpdate = "account-update" -> AccountUpdate;
+    OnAccountBackupProgress = "account-backup-progress" -> BackupProgress;
+    /// the used identities in the device, when restore.
+    OnAccountIdentitiesFound = "account-identities-found" -> Vec<Identity>;
+    /// data key rotated in other device.
+    OnAccountKeyRotate = "account-key-rotate" -> KeyRotated;
+    /// locked when idle.
+    OnAccountLock = "account-lock" -> AccountGid;
+}
@@ -0,0 +1,311 @@
+//! chat's methods and notifications, friends, requests and messages.
+//! message type: 0 string, 1 image, 2 file, 3 contact, 4 record, 5 emoji,
+//! 6 phone, 7 video, 8 invite, 9 transfer, 10 recovery.
+
+use crate::node::EchoParams;
+use crate::{Empty, Id};
+
+record!(Friend {
+    id: i64,
+    gid: String,
+    addr: String,
+    name: String,
+    wallet: String,
+    remark: String,
+    is_closed: bool,
+    datetime: i64,
+});
+
+record!(
+    /// friend with online status.
+    FriendOnline {
+        id: i64,
+        gid: String,
+        addr: String,
+        name: String,
+        wallet: String,
+        remark: String,
+        is_closed: bool,
+        datetime: i64,
+        #[serde(default)]
+        online: bool,
+    }
+);
+
+record!(Request {
+    id: i64,
+    gid: String,
+    addr: String,
+    name: String,
+    remark: String,
+    is_me: bool,
+    is_ok: bool,
+    is_over: bool,
+    is_delivery: bool,
+    datetime: i64,
+});
+
+record!(
+    /// hash and parent (replied message, empty if none) are hex string.
+    Message {
+        id: i64,
+        hash: String,
+        fid: i64,
+        is_me: bool,
+        m_type: i64,
+        content: String,
+        is_delivery: bool,
+        datetime: i64,
+        parent: String,
+        is_read: bool,
+    }
+);
+
+record!(MessageEdit {
+    id: i64,
+    message: i64,
+    old_content: String,
+    new_content: String,
+    datetime: i64,
+});
+
+record!(Reaction {
+    id: i64,
+    message: i64,
+    is_me: bool,
+    emoji: String,
+    datetime: i64,
+});
+
+record!(Outbox {
+    id: i64,
+    message: i64,
+    fid: i64,
+    retries: i64,
+    datetime: i64,
+});
+
+record!(
+    /// recovery share held for the owner.
+    Share {
+        id: i64,
+        owner: String,
+        sender: String,
+        is_held: bool,
+        datetime: i64,
+    }
+);
+
+record!(
+    /// chunked file transfer, done and total are chunks.
+    Transfer {
+        id: i64,
+        hash: String,
+        fid: i64,
+        is_me: bool,
+        name: String,
+        size: i64,
+        done: u64,
+        total: u64,
+        is_over: bool,
+    }
+);
+
+record!(FriendListParams { need_online: bool });
+
+record!(FriendParams { fid: i64 });
+
+record!(FriendRemark {
+    id: i64,
+    remark: String,
+});
+
+record!(RequestCreateParams {
+    gid: String,
+    addr: String,
+    name: String,
+    remark: String,
+});
+
+record!(RequestAgree {
+    id: i64,
+    friend: Friend,
+});
+
+record!(ChatDetail {
+    friend: Friend,
+    messages: Vec<Message>,
+});
+
+record!(
+    /// fgid is friend's gid, parent is the replied message's hash.
+    MessageCreateParams {
+        fid: i64,
+        fgid: String,
+        m_type: i64,
+        content: String,
+        #[serde(default)]
+        parent: Option<String>,
+    }
+);
+
+record!(MessageEditParams {
+    id: i64,
+    content: String,
+});
+
+record!(MessageReactParams {
+    id: i64,
+    emoji: String,
+    is_add: bool,
+});
+
+record!(MessageReact {
+    id: i64,
+    is_me: bool,
+    emoji: String,
+    is_add: bool,
+});
+
+record!(MessageHistory {
+    edits: Vec<MessageEdit>,
+    reactions: Vec<Reaction>,
+});
+
+record!(MessageReadParams { fid: i64, id: i64 });
+
+record!(FriendTyping {
+    fid: i64,
+    is_typing: bool,
+});
+
+record!(EmojiPack {
+    pack: String,
+    stickers: Vec<String>,
+});
+
+record!(
+    /// path is the sticker file to copy.
+    EmojiAddParams { pack: String, path: String }
+);
+
+record!(
+    /// name is `pack/sticker`.
+    EmojiName { name: String }
+);
+
+record!(
+    /// split the mnemonic to the friends, threshold shares can recover it.
+    /// every share is sent as a recovery message, fids can not repeat.
+    RecoverySplitParams {
+        lock: String,
+        threshold: i64,
+        fids: Vec<i64>,
+    }
+);
+
+record!(RecoverySplit {
+    threshold: i64,
+    count: u64,
+});
+
+record!(
+    /// return the share to the owner's new account (gid, addr).
+    RecoverySendParams {
+        id: i64,
+        gid: String,
+        addr: String,
+    }
+);
+
+record!(RecoveryCombineParams { owner: String });
+
+record!(Recovered {
+    lang: i64,
+    index: i64,
+    mnemonic: String,
+    pass: String,
+});
+
+record!(Delivery {
+    id: i64,
+    is_delivery: bool,
+});
+
+record!(MessageEditContent {
+    id: i64,
+    content: String,
+});
+
+record!(MessageRead {
+    fid: i64,
+    id: i64,
+    is_me: bool,
+});
+
+record!(TransferProgress {
+    id: i64,
+    done: u64,
+    total: u64,
+});
+
+methods! {
+    ChatEcho = "chat-echo" (EchoParams) -> EchoParams;
+    /// online is included if need.
+    ChatFriendList = "chat-friend-list" (FriendListParams) -> Vec<FriendOnline>;
+    ChatFriendUpdate = "chat-friend-update" (FriendRemark);
+    ChatFriendClose = "chat-friend-close" (Id);
+    ChatFriendDelete = "chat-friend-delete" (Id);
+    ChatRequestList = "chat-request-list" (Empty) -> Vec<Request>;
+    ChatRequestCreate = "chat-request-create" (RequestCreateParams) -> Request;
+    ChatRequestAgree = "chat-request-agree" (Id) -> RequestAgree;
+    ChatRequestReject = "chat-request-reject" (Id);
+    ChatRequestDelete = "chat-request-delete" (Id);
+    /// friend and the messages.
+    ChatDetailQuery = "chat-detail" (Id) -> ChatDetail;
+    ChatMessageList = "chat-message-list" (FriendParams) -> Vec<Message>;
+    ChatMessageCreate = "chat-message-create" (MessageCreateParams) -> Message;
+    ChatMessageDelete = "chat-message-delete" (Id);
+    /// only my text message.
+    ChatMessageEdit = "chat-message-edit" (MessageEditParams) -> Message;
+    ChatMessageReact = "chat-message-react" (MessageReactParams) -> MessageReact;
+    ChatMessageHistory = "chat-message-history" (Id) -> MessageHistory;
+    /// read the messages up to the message.
+    ChatMessageRead = "chat-message-read" (MessageReadParams);
+    ChatFriendTyping = "chat-friend-typing" (FriendTyping);
+    ChatEmojiList = "chat-emoji-list" (Empty) -> Vec<EmojiPack>;
+    ChatEmojiAdd = "chat-emoji-add" (EmojiAddParams) -> EmojiName;
+    ChatEmojiDelete = "chat-emoji-delete" (EmojiName) -> EmojiName;
+    /// unfinished transfers.
+    ChatTransferList = "chat-transfer-list" (FriendParams) -> Vec<Transfer>;
+    ChatOutboxList = "chat-outbox-list" (FriendParams) -> Vec<Outbox>;
+    ChatRecoverySplit = "chat-recovery-split" (RecoverySplitParams) -> RecoverySplit;
+    /// shares held for friends.
+    ChatRecoveryList = "chat-recovery-list" (Empty) -> Vec<Share>;
+    ChatRecoverySend = "chat-recovery-send" (RecoverySendParams) -> Id;
+    /// recover the owner's mnemonic from the returned shares, bogus shares are skipped.
+    ChatRecoveryCombine = "chat-recovery-combine" (RecoveryCombineParams) -> Recovered;
+    /// delete the held or returned share.
+    ChatRecoveryDelete = "chat-recovery-delete" (Id);
+}
+
+notifications! {
+    OnChatFriendInfo = "chat-friend-info" -> Friend;
+    OnChatFriendUpdate = "chat-friend-update" -> FriendRemark;
+    OnChatFriendClose = "chat-friend-close" -> Id;
+    OnChatFriendDelete = "chat-friend-delete" -> Id;
+    OnChatFriendTyping = "chat-friend-typing" -> FriendTyping;
+    OnChatRequestCreate = "chat-request-create" -> Request;
+    OnChatRequestDelivery = "chat-request-delivery" -> Delivery;
+    OnChatRequestAgree = "chat-request-agree" -> RequestAgree;
+    OnChatRequestReject = "chat-request-reject" -> Id;
+    OnChatRequestDelete = "chat-request-delete" -> Id;
+    OnChatMessageCreate = "chat-message-create" -> Message;
+    OnChatMessageDelivery = "chat-message-delivery" -> Delivery;
+    OnChatMessageDelete = "chat-message-delete" -> Id;
+    OnChatMessageEdit = "chat-message-edit" -> MessageEditContent;
+    OnChatMessageReact = "chat-message-react" -> MessageReact;
+    OnChatMessageRead = "chat-message-read" -> MessageRead;
+    OnChatTransferProgress = "chat-transfer-progress" -> TransferProgress;
+    OnChatRecoveryShare = "chat-recovery-share" -> Share;
+}
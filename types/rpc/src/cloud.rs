@@ -0,0 +1,44 @@
+//! cloud's methods and notifications, deposit the mails for the offline
+//! friends in the providers.
+
+use crate::node::EchoParams;
+use crate::{Empty, Id};
+
+record!(
+    /// used and quota are bytes.
+    Provider {
+        id: i64,
+        name: String,
+        addr: String,
+        is_ok: bool,
+        is_default: bool,
+        used: i64,
+        quota: i64,
+    }
+);
+
+record!(
+    /// provider is the provider's addr.
+    ProviderAddParams { provider: String }
+);
+
+record!(
+    /// hash is the deposited message's hash.
+    MailDeposited { hash: String, is_ok: bool }
+);
+
+methods! {
+    CloudEcho = "cloud-echo" (EchoParams) -> EchoParams;
+    CloudProviderList = "cloud-provider-list" (Empty) -> Vec<Provider>;
+    /// the first provider is default, the status will be notified.
+    CloudProviderAdd = "cloud-provider-add" (ProviderAddParams);
+    CloudProviderDefault = "cloud-provider-default" (Id);
+    CloudProviderRemove = "cloud-provider-remove" (Id);
+    /// fetch the mails from the default provider.
+    CloudMailFetch = "cloud-mail-fetch" (Empty);
+}
+
+notifications! {
+    OnCloudProviderStatus = "cloud-provider-status" -> Provider;
+    OnCloudMailDeposited = "cloud-mail-deposited" -> MailDeposited;
+}
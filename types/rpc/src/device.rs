@@ -0,0 +1,52 @@
+//! device's methods and notifications, the devices of the same account.
+
+use crate::node::EchoParams;
+use crate::{Empty, Id};
+
+record!(
+    /// online is `1` or `0`.
+    Device {
+        id: i64,
+        name: String,
+        info: String,
+        addr: String,
+        lasttime: i64,
+        online: String,
+    }
+);
+
+record!(DeviceAddr { addr: String });
+
+record!(
+    /// sizes in MB, and used percents.
+    DeviceStatus {
+        cpu: u32,
+        memory: u32,
+        swap: u32,
+        disk: u32,
+        cpu_p: u16,
+        memory_p: u16,
+        swap_p: u16,
+        disk_p: u16,
+        uptime: u32,
+    }
+);
+
+methods! {
+    DeviceEcho = "device-echo" (EchoParams) -> EchoParams;
+    DeviceList = "device-list" (Empty) -> Vec<Device>;
+    /// this device's status in result, others' notify by `device-status`.
+    DeviceStatusQuery = "device-status" (DeviceAddr) -> DeviceStatus;
+    /// sync the account to the new device.
+    DeviceCreate = "device-create" (DeviceAddr);
+    DeviceConnect = "device-connect" (DeviceAddr);
+    DeviceDelete = "device-delete" (Id);
+}
+
+notifications! {
+    OnDeviceCreate = "device-create" -> Device;
+    OnDeviceRemove = "device-remove" -> Id;
+    OnDeviceOnline = "device-online" -> Id;
+    OnDeviceOffline = "device-offline" -> Id;
+    OnDeviceStatus = "device-status" -> DeviceStatus;
+}
@@ -0,0 +1,100 @@
+//! domain's methods and notifications, register the unique name in providers.
+
+use crate::{Empty, Id};
+
+record!(Provider {
+    id: i64,
+    name: String,
+    addr: String,
+    is_ok: bool,
+    is_default: bool,
+    is_proxy: bool,
+    is_actived: bool,
+});
+
+record!(
+    /// provider is the provider's id.
+    Name {
+        id: i64,
+        provider: i64,
+        name: String,
+        bio: String,
+        is_ok: bool,
+        is_actived: bool,
+    }
+);
+
+record!(DomainName { name: String });
+
+record!(DomainList {
+    providers: Vec<Provider>,
+    names: Vec<Name>,
+});
+
+record!(
+    /// provider is the provider's addr.
+    ProviderAddParams { provider: String }
+);
+
+record!(
+    /// provider is the provider's id, addr is the provider's addr.
+    DomainRegisterParams {
+        provider: i64,
+        addr: String,
+        name: String,
+        bio: String,
+    }
+);
+
+record!(DomainActiveParams {
+    name: String,
+    provider: String,
+    active: bool,
+});
+
+record!(DomainRemoveParams {
+    name: String,
+    provider: String,
+});
+
+record!(
+    /// addr is the provider's addr.
+    DomainSearchParams { addr: String, name: String }
+);
+
+record!(
+    /// only the name if not found, the avatar is base64.
+    DomainSearch {
+        name: String,
+        #[serde(default)]
+        gid: String,
+        #[serde(default)]
+        addr: String,
+        #[serde(default)]
+        bio: String,
+        #[serde(default)]
+        avatar: String,
+    }
+);
+
+methods! {
+    DomainListQuery = "domain-list" (Empty) -> DomainList;
+    /// the provider will be notified when checked.
+    DomainProviderAdd = "domain-provider-add" (ProviderAddParams);
+    DomainProviderDefault = "domain-provider-default" (Id);
+    /// only the provider without names.
+    DomainProviderRemove = "domain-provider-remove" (Id);
+    DomainRegister = "domain-register" (DomainRegisterParams);
+    DomainActive = "domain-active" (DomainActiveParams);
+    DomainRemove = "domain-remove" (DomainRemoveParams);
+    /// the result will be notified.
+    DomainSearchQuery = "domain-search" (DomainSearchParams);
+}
+
+notifications! {
+    OnDomainProviderAdd = "domain-provider-add" -> Provider;
+    OnDomainRegisterSuccess = "domain-register-success" -> Name;
+    OnDomainRegisterFailure = "domain-register-failure" -> DomainName;
+    OnDomainList = "domain-list" -> DomainList;
+    OnDomainSearch = "domain-search" -> DomainSearch;
+}
@@ -0,0 +1,63 @@
+//! file's methods, the files and folders in the root directories.
+//! root directory: 0 star, 1 trash, 2 session, 3 document, 4 image, 5 music,
+//! 6 video.
+
+use crate::node::EchoParams;
+use crate::Id;
+
+record!(
+    /// did is the file's hex id.
+    File {
+        id: i64,
+        did: String,
+        parent: i64,
+        root: i64,
+        name: String,
+        starred: bool,
+        datetime: i64,
+    }
+);
+
+record!(FileListParams {
+    root: i64,
+    parent: i64,
+});
+
+record!(FileCreateParams {
+    root: i64,
+    parent: i64,
+    name: String,
+});
+
+record!(
+    /// path is the local file to copy.
+    FileUploadParams {
+        root: i64,
+        parent: i64,
+        path: String,
+    }
+);
+
+record!(FileUpdateParams {
+    id: i64,
+    root: i64,
+    parent: i64,
+    name: String,
+});
+
+record!(FileStarParams {
+    id: i64,
+    starred: bool,
+});
+
+methods! {
+    FileEcho = "dc-echo" (EchoParams) -> EchoParams;
+    FileList = "dc-list" (FileListParams) -> Vec<File>;
+    FileCreate = "dc-file-create" (FileCreateParams) -> File;
+    FileUpload = "dc-file-upload" (FileUploadParams) -> File;
+    FolderCreate = "dc-folder-create" (FileCreateParams) -> File;
+    FileUpdate = "dc-file-update" (FileUpdateParams) -> File;
+    FileStar = "dc-file-star" (FileStarParams);
+    FileTrash = "dc-file-trash" (Id);
+    FileDelete = "dc-file-delete" (Id);
+}
@@ -0,0 +1,114 @@
+//! group chat's methods and notifications.
+
+use crate::chat::TransferProgress;
+use crate::{Empty, Id};
+
+record!(
+    /// gid and addr are the group's id and the server's addr.
+    Group {
+        id: i64,
+        gid: String,
+        addr: String,
+        name: String,
+        close: bool,
+        local: bool,
+    }
+);
+
+record!(
+    /// fid is the group's id, mid is the member's gid.
+    Member {
+        id: i64,
+        fid: i64,
+        mid: String,
+        addr: String,
+        name: String,
+        leave: bool,
+    }
+);
+
+record!(
+    /// fid is the group's id, mid is the member's id.
+    Message {
+        id: i64,
+        height: i64,
+        fid: i64,
+        mid: i64,
+        is_me: bool,
+        m_type: i64,
+        content: String,
+        is_delivery: bool,
+        datetime: i64,
+        hash: String,
+        parent: String,
+    }
+);
+
+record!(GroupDetail {
+    group: Group,
+    members: Vec<Member>,
+    messages: Vec<Message>,
+});
+
+record!(GroupCreateParams { name: String });
+
+record!(
+    /// sid is the session's id.
+    GroupCreated { sid: i64, id: i64 }
+);
+
+record!(
+    /// invite the friend to the group.
+    GroupMemberJoinParams { id: i64, fid: i64 }
+);
+
+record!(
+    /// parent is the replied message's hash.
+    GroupMessageCreateParams {
+        id: i64,
+        m_type: i64,
+        content: String,
+        #[serde(default)]
+        parent: Option<String>,
+    }
+);
+
+record!(GroupName {
+    id: i64,
+    name: String,
+});
+
+record!(
+    /// id is the group's id, mid is the member's id.
+    GroupMember { id: i64, mid: i64 }
+);
+
+record!(MemberOnline {
+    id: i64,
+    mid: i64,
+    addr: String,
+});
+
+methods! {
+    GroupList = "group-list" (Empty) -> Vec<Group>;
+    GroupDetailQuery = "group-detail" (Id) -> GroupDetail;
+    GroupCreate = "group-create" (GroupCreateParams) -> GroupCreated;
+    /// the member is returned only in the local group.
+    GroupMemberJoin = "group-member-join" (GroupMemberJoinParams) -> Member;
+    /// the message is returned only in the local group.
+    GroupMessageCreate = "group-message-create" (GroupMessageCreateParams) -> Message;
+    /// the name is returned only in the local group.
+    GroupNameUpdate = "group-name" (GroupName) -> GroupName;
+    GroupDelete = "group-delete" (Id);
+}
+
+notifications! {
+    OnGroupMemberJoin = "group-member-join" -> Member;
+    OnGroupMemberLeave = "group-member-leave" -> GroupMember;
+    OnGroupMemberOnline = "group-member-online" -> MemberOnline;
+    OnGroupMemberOffline = "group-member-offline" -> GroupMember;
+    OnGroupName = "group-name" -> GroupName;
+    OnGroupMessageCreate = "group-message-create" -> Message;
+    OnGroupMessageDelete = "group-message-delete" -> Id;
+    OnGroupTransferProgress = "group-transfer-progress" -> TransferProgress;
+}
@@ -0,0 +1,35 @@
+//! jarvis' methods and notifications, the chat with self assistant.
+
+use crate::{Empty, Id};
+
+record!(
+    /// m_type is same as chat's message type.
+    Message {
+        id: i64,
+        is_me: bool,
+        m_type: i64,
+        content: String,
+        datetime: i64,
+    }
+);
+
+record!(
+    /// lang is the answer's language.
+    JarvisCreateParams {
+        lang: i64,
+        m_type: i64,
+        content: String,
+    }
+);
+
+methods! {
+    JarvisList = "jarvis-list" (Empty) -> Vec<Message>;
+    /// jarvis will reply by notification.
+    JarvisCreate = "jarvis-create" (JarvisCreateParams) -> Message;
+    JarvisDelete = "jarvis-delete" (Id);
+}
+
+notifications! {
+    /// jarvis' reply.
+    OnJarvisCreate = "jarvis-create" -> Message;
+}
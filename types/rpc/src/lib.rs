@@ -0,0 +1,424 @@
+//! ESSE rpc methods and notifications types.
+//!
+//! All params and results are positional json arrays in the wire, the
+//! records here keep the fields' names, so the params also can be sent
+//! as a named object, and the schema can be described to generate clients.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+/// Positional record: named fields, serialize to (and deserialize from) a json
+/// array in fields' order, also deserialize from a named object.
+macro_rules! record {
+    ($(#[$meta:meta])* $name:ident { $($(#[$fmeta:meta])* $field:ident: $ty:ty),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Default, serde::Deserialize)]
+        pub struct $name {
+            $($(#[$fmeta])* pub $field: $ty,)*
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeTuple;
+                // the record without fields not use it.
+                #[allow(unused_mut)]
+                let mut tuple = serializer.serialize_tuple(<Self as $crate::Record>::FIELDS.len())?;
+                $(tuple.serialize_element(&self.$field)?;)*
+                tuple.end()
+            }
+        }
+
+        impl $crate::Record for $name {
+            const FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+        }
+
+        impl $crate::Schema for $name {
+            fn schema() -> serde_json::Value {
+                $crate::record_schema(vec![
+                    $((stringify!($field), <$ty as $crate::Schema>::schema()),)*
+                ])
+            }
+        }
+    };
+}
+
+/// Declare the methods: `Name = "method-name" (Params) -> Result;`,
+/// the method without result when success is `Name = "method-name" (Params);`.
+macro_rules! methods {
+    (@result) => { () };
+    (@result $result:ty) => { $result };
+    (@none) => { true };
+    (@none $result:ty) => { false };
+    ($($(#[doc = $doc:literal])* $name:ident = $method:literal ($params:ty) $(-> $result:ty)?;)*) => {
+        $(
+            $(#[doc = $doc])*
+            pub struct $name;
+
+            impl $crate::Method for $name {
+                const NAME: &'static str = $method;
+                const NO_RESPONSE: bool = methods!(@none $($result)?);
+                type Params = $params;
+                type Result = methods!(@result $($result)?);
+            }
+        )*
+
+        pub(crate) fn methods() -> Vec<serde_json::Value> {
+            vec![$($crate::method_schema::<$name>(&[$($doc),*]),)*]
+        }
+
+        pub(crate) fn fields(method: &str) -> Option<&'static [&'static str]> {
+            match method {
+                $($method => Some(<$params as $crate::Record>::FIELDS),)*
+                _ => None,
+            }
+        }
+
+        pub(crate) fn no_response(method: &str) -> bool {
+            match method {
+                $($method => methods!(@none $($result)?),)*
+                _ => false,
+            }
+        }
+    };
+}
+
+/// Declare the notifications: `Name = "method-name" -> Data;`.
+macro_rules! notifications {
+    ($($(#[doc = $doc:literal])* $name:ident = $method:literal -> $data:ty;)*) => {
+        $(
+            $(#[doc = $doc])*
+            pub struct $name;
+
+            impl $crate::Notification for $name {
+                const NAME: &'static str = $method;
+                type Data = $data;
+            }
+        )*
+
+        pub(crate) fn notifications() -> Vec<serde_json::Value> {
+            vec![$($crate::notification_schema::<$name>(&[$($doc),*]),)*]
+        }
+    };
+}
+
+pub mod account;
+pub mod chat;
+pub mod cloud;
+pub mod device;
+pub mod domain;
+pub mod file;
+pub mod group;
+pub mod jarvis;
+pub mod node;
+pub mod search;
+pub mod session;
+pub mod wallet;
+
+/// Schema version of `rpc-describe`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Json schema of the type, used in `rpc-describe`.
+pub trait Schema {
+    fn schema() -> Value;
+}
+
+/// Positional record, fields' names in wire order.
+pub trait Record: Schema + Serialize + DeserializeOwned {
+    const FIELDS: &'static [&'static str];
+}
+
+/// Rpc method, request with params and response with result.
+pub trait Method {
+    const NAME: &'static str;
+    /// no result when success, the daemon acks with null, the result is `()`.
+    const NO_RESPONSE: bool = false;
+    type Params: Record;
+    type Result: Schema + Serialize + DeserializeOwned;
+}
+
+/// Notification pushed by the core, the data is in the `result`.
+pub trait Notification {
+    const NAME: &'static str;
+    type Data: Schema + Serialize + DeserializeOwned;
+}
+
+record!(
+    /// params of the methods without params.
+    Empty {}
+);
+
+record!(
+    /// model's id.
+    Id { id: i64 }
+);
+
+macro_rules! schema_type {
+    ($t:literal, $($ty:ty),*) => {
+        $(impl Schema for $ty {
+            fn schema() -> Value {
+                json!({ "type": $t })
+            }
+        })*
+    };
+}
+
+schema_type!("integer", i32, i64, u8, u16, u32, u64, usize);
+schema_type!("number", f64);
+schema_type!("boolean", bool);
+schema_type!("string", String);
+schema_type!("null", ());
+
+impl Schema for Value {
+    fn schema() -> Value {
+        json!({})
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: Schema> Schema for Option<T> {
+    fn schema() -> Value {
+        json!({ "anyOf": [T::schema(), { "type": "null" }] })
+    }
+}
+
+impl<A: Schema, B: Schema> Schema for (A, B) {
+    fn schema() -> Value {
+        json!({ "type": "array", "prefixItems": [A::schema(), B::schema()], "items": false })
+    }
+}
+
+#[doc(hidden)]
+pub fn record_schema(fields: Vec<(&str, Value)>) -> Value {
+    let items: Vec<Value> = fields
+        .into_iter()
+        .map(|(name, mut schema)| {
+            schema["title"] = json!(name);
+            schema
+        })
+        .collect();
+    json!({ "type": "array", "prefixItems": items, "items": false })
+}
+
+fn doc(lines: &[&str]) -> String {
+    let lines: Vec<&str> = lines.iter().map(|l| l.trim()).collect();
+    lines.join(" ")
+}
+
+#[doc(hidden)]
+pub fn method_schema<M: Method>(docs: &[&str]) -> Value {
+    json!({
+        "name": M::NAME,
+        "description": doc(docs),
+        "params": M::Params::schema(),
+        "result": M::Result::schema(),
+    })
+}
+
+#[doc(hidden)]
+pub fn notification_schema<N: Notification>(docs: &[&str]) -> Value {
+    json!({
+        "name": N::NAME,
+        "description": doc(docs),
+        "data": N::Data::schema(),
+    })
+}
+
+/// Schema of all methods and notifications, the result of `rpc-describe`.
+pub fn describe() -> Value {
+    let mut methods = vec![];
+    methods.extend(node::methods());
+    methods.extend(account::methods());
+    methods.extend(session::methods());
+    methods.extend(device::methods());
+    methods.extend(chat::methods());
+    methods.extend(jarvis::methods());
+    methods.extend(domain::methods());
+    methods.extend(file::methods());
+    methods.extend(group::methods());
+    methods.extend(wallet::methods());
+    methods.extend(cloud::methods());
+    methods.extend(search::methods());
+
+    let mut notifications = vec![];
+    notifications.extend(node::notifications());
+    notifications.extend(account::notifications());
+    notifications.extend(session::notifications());
+    notifications.extend(device::notifications());
+    notifications.extend(chat::notifications());
+    notifications.extend(jarvis::notifications());
+    notifications.extend(domain::notifications());
+    notifications.extend(group::notifications());
+    notifications.extend(wallet::notifications());
+    notifications.extend(cloud::notifications());
+
+    json!({
+        "version": SCHEMA_VERSION,
+        "methods": methods,
+        "notifications": notifications,
+    })
+}
+
+/// params' names of the method.
+pub fn fields(method: &str) -> Option<&'static [&'static str]> {
+    node::fields(method)
+        .or_else(|| account::fields(method))
+        .or_else(|| session::fields(method))
+        .or_else(|| device::fields(method))
+        .or_else(|| chat::fields(method))
+        .or_else(|| jarvis::fields(method))
+        .or_else(|| domain::fields(method))
+        .or_else(|| file::fields(method))
+        .or_else(|| group::fields(method))
+        .or_else(|| wallet::fields(method))
+        .or_else(|| cloud::fields(method))
+        .or_else(|| search::fields(method))
+}
+
+/// the method has no result when success, the daemon acks it with null.
+pub fn no_response(method: &str) -> bool {
+    node::no_response(method)
+        || account::no_response(method)
+        || session::no_response(method)
+        || device::no_response(method)
+        || chat::no_response(method)
+        || jarvis::no_response(method)
+        || domain::no_response(method)
+        || file::no_response(method)
+        || group::no_response(method)
+        || wallet::no_response(method)
+        || cloud::no_response(method)
+        || search::no_response(method)
+}
+
+/// change the named object params of the request to positional params,
+/// the missing params are null, the positional params keep.
+pub fn normalize(request: &mut Value) -> Result<(), String> {
+    if !request["params"].is_object() {
+        return Ok(());
+    }
+    let method = request["method"].as_str().unwrap_or("");
+    let fields = fields(method).ok_or(format!("method {} is unknown", method))?;
+
+    let mut named = request["params"].take();
+    let object = named.as_object_mut().unwrap();
+    let mut params: Vec<Value> = fields
+        .iter()
+        .map(|f| object.remove(*f).unwrap_or(Value::Null))
+        .collect();
+    if let Some(key) = object.keys().next() {
+        return Err(format!("param {} is unknown", key));
+    }
+    // optional params in the tail use default.
+    while params.last() == Some(&Value::Null) {
+        params.pop();
+    }
+
+    request["params"] = Value::Array(params);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chat::MessageCreateParams;
+    use super::*;
+
+    #[test]
+    fn record_positional() {
+        assert_eq!(serde_json::to_value(Empty {}).unwrap(), json!([]));
+        assert_eq!(serde_json::to_value(Id { id: 7 }).unwrap(), json!([7]));
+
+        let params = MessageCreateParams {
+            fid: 1,
+            fgid: "gid".to_owned(),
+            m_type: 0,
+            content: "hi".to_owned(),
+            parent: Some("hash".to_owned()),
+        };
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value, json!([1, "gid", 0, "hi", "hash"]));
+        let back: MessageCreateParams = serde_json::from_value(value).unwrap();
+        assert_eq!(back.fgid, params.fgid);
+        assert_eq!(back.parent, params.parent);
+    }
+
+    #[test]
+    fn record_from_named_and_short() {
+        let id: Id = serde_json::from_value(json!({ "id": 7 })).unwrap();
+        assert_eq!(id.id, 7);
+
+        // optional field in the tail can be omitted.
+        let params: MessageCreateParams =
+            serde_json::from_value(json!([1, "gid", 0, "hi"])).unwrap();
+        assert_eq!(params.content, "hi");
+        assert_eq!(params.parent, None);
+
+        assert!(serde_json::from_value::<MessageCreateParams>(json!([1, "gid"])).is_err());
+    }
+
+    fn no_response<M: Method>() -> bool {
+        M::NO_RESPONSE
+    }
+
+    #[test]
+    fn method_no_response() {
+        assert!(no_response::<chat::ChatMessageDelete>());
+        assert!(!no_response::<chat::ChatMessageCreate>());
+        let schema = method_schema::<chat::ChatMessageDelete>(&[]);
+        assert_eq!(schema["result"], json!({ "type": "null" }));
+
+        // lookup by name, the daemon acks these methods.
+        assert!(crate::no_response("chat-message-delete"));
+        assert!(!crate::no_response("chat-message-create"));
+        assert!(!crate::no_response("unknown-method"));
+    }
+
+    #[test]
+    fn normalize_named() {
+        let mut request = json!({
+            "method": "chat-message-create",
+            "params": { "content": "hi", "fid": 1, "m_type": 0, "fgid": "gid" },
+        });
+        normalize(&mut request).unwrap();
+        assert_eq!(request["params"], json!([1, "gid", 0, "hi"]));
+
+        // the missing params in the middle are null.
+        let mut request = json!({
+            "method": "chat-message-create",
+            "params": { "fid": 1, "parent": "hash" },
+        });
+        normalize(&mut request).unwrap();
+        assert_eq!(request["params"], json!([1, null, null, null, "hash"]));
+
+        let mut request = json!({ "method": "chat-recovery-list", "params": {} });
+        normalize(&mut request).unwrap();
+        assert_eq!(request["params"], json!([]));
+    }
+
+    #[test]
+    fn normalize_keep_positional() {
+        let mut request = json!({ "method": "chat-message-delete", "params": [7] });
+        normalize(&mut request).unwrap();
+        assert_eq!(request["params"], json!([7]));
+
+        // unknown method with positional params is handled by the core.
+        let mut request = json!({ "method": "unknown", "params": [] });
+        assert!(normalize(&mut request).is_ok());
+    }
+
+    #[test]
+    fn normalize_invalid() {
+        let mut request = json!({ "method": "unknown", "params": { "id": 1 } });
+        assert!(normalize(&mut request).is_err());
+
+        let mut request = json!({
+            "method": "chat-message-delete",
+            "params": { "id": 1, "other": 2 },
+        });
+        assert!(normalize(&mut request).is_err());
+    }
+}
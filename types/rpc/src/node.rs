@@ -0,0 +1,78 @@
+//! node's methods: authentication, network, seeds and settings.
+
+use serde_json::Value;
+
+use crate::Empty;
+
+record!(
+    /// any params, echo them back.
+    EchoParams {
+        #[serde(default)]
+        message: Value,
+    }
+);
+
+record!(RpcAuthParams { token: String });
+
+record!(
+    /// scope is `full` or `read`.
+    RpcScope { scope: String }
+);
+
+record!(
+    /// socket is `ip:port`, transport is `quic`, `tcp`...
+    AddBootstrapParams {
+        socket: String,
+        transport: String,
+    }
+);
+
+record!(Seed {
+    id: i64,
+    socket: String,
+    transport: String,
+});
+
+record!(SeedRemoveParams { socket: String });
+
+record!(Setting {
+    name: String,
+    value: String,
+});
+
+record!(
+    /// empty value is reset to default.
+    SettingSetParams { key: String, value: String }
+);
+
+record!(
+    /// is_direct is `1` or `0`.
+    StablePeer {
+        addr: String,
+        is_direct: String,
+    }
+);
+
+methods! {
+    /// echo the params.
+    Echo = "echo" (EchoParams) -> EchoParams;
+    /// authenticate the websocket client by token.
+    RpcAuth = "rpc-auth" (RpcAuthParams) -> RpcScope;
+    /// schema of all methods and notifications.
+    RpcDescribe = "rpc-describe" (Empty) -> Value;
+    /// connect the bootstrap peer, and save it as seed.
+    AddBootstrap = "add-bootstrap" (AddBootstrapParams);
+    SeedList = "seed-list" (Empty) -> Vec<Seed>;
+    SeedRemove = "seed-remove" (SeedRemoveParams);
+    SettingList = "setting-list" (Empty) -> Vec<Setting>;
+    /// works after restart.
+    SettingSet = "setting-set" (SettingSetParams);
+    /// stable connected peers, only http client.
+    NetworkStable = "network-stable" (Empty) -> Vec<StablePeer>;
+    /// dht peers, only http client.
+    NetworkDht = "network-dht" (Empty) -> Vec<String>;
+    /// stop the core gracefully.
+    Shutdown = "shutdown" (Empty);
+}
+
+notifications! {}
@@ -0,0 +1,35 @@
+//! search's methods, full-text search the messages.
+//! source: 0 chat, 1 group, 2 jarvis.
+
+record!(
+    /// sources default is all, limit default is 50.
+    SearchParams {
+        query: String,
+        #[serde(default)]
+        sources: Option<Vec<i64>>,
+        #[serde(default)]
+        limit: Option<i64>,
+    }
+);
+
+record!(
+    /// sid is the session's id, fid is the friend's or group's id, mid is
+    /// the friend's or member's id, id is the message's id.
+    SearchHit {
+        source: i64,
+        sid: i64,
+        fid: i64,
+        mid: i64,
+        name: String,
+        id: i64,
+        is_me: bool,
+        m_type: i64,
+        snippet: String,
+        datetime: i64,
+    }
+);
+
+methods! {
+    /// the newest hits first.
+    SearchMessages = "search-messages" (SearchParams) -> Vec<SearchHit>;
+}
@@ -0,0 +1,90 @@
+//! session's methods and notifications.
+//! session type: 0 chat, 1 group, 2 device, 3 jarvis.
+
+use crate::{Empty, Id};
+
+record!(
+    /// ttl is messages retention seconds, 0 is forever, others in 60 ~ 31536000 (one year).
+    Session {
+        id: i64,
+        fid: i64,
+        gid: String,
+        addr: String,
+        s_type: i64,
+        name: String,
+        is_top: bool,
+        is_close: bool,
+        last_datetime: i64,
+        last_content: String,
+        last_readed: bool,
+        ttl: i64,
+    }
+);
+
+record!(
+    /// remote is the session's gid.
+    SessionConnectParams { id: i64, remote: String }
+);
+
+record!(SessionAddr {
+    id: i64,
+    addr: String,
+});
+
+record!(
+    /// must is need suspend when others use the connection.
+    SessionSuspendParams {
+        id: i64,
+        remote: String,
+        must: bool,
+    }
+);
+
+record!(SessionUpdateParams {
+    id: i64,
+    is_top: bool,
+    is_close: bool,
+});
+
+record!(SessionTtl { id: i64, ttl: i64 });
+
+record!(SessionLast {
+    id: i64,
+    time: i64,
+    content: String,
+    readed: bool,
+});
+
+record!(NoticeMenu { s_type: i64 });
+
+record!(SessionUpdate {
+    id: i64,
+    addr: String,
+    name: String,
+    is_top: bool,
+});
+
+methods! {
+    SessionList = "session-list" (Empty) -> Vec<Session>;
+    /// connect the session, result when online, or notify by `session-connect`.
+    SessionConnect = "session-connect" (SessionConnectParams) -> SessionAddr;
+    /// suspend the session, result when suspended.
+    SessionSuspend = "session-suspend" (SessionSuspendParams) -> Id;
+    SessionReaded = "session-readed" (Id);
+    SessionUpdateInfo = "session-update" (SessionUpdateParams);
+    /// set the messages retention, sync to the friend. only chat session.
+    SessionSetTtl = "session-ttl" (SessionTtl);
+}
+
+notifications! {
+    OnSessionCreate = "session-create" -> Session;
+    OnSessionLast = "session-last" -> SessionLast;
+    OnNoticeMenu = "notice-menu" -> NoticeMenu;
+    OnSessionUpdate = "session-update" -> SessionUpdate;
+    OnSessionConnect = "session-connect" -> SessionAddr;
+    OnSessionSuspend = "session-suspend" -> Id;
+    OnSessionLost = "session-lost" -> Id;
+    OnSessionDelete = "session-delete" -> Id;
+    OnSessionClose = "session-close" -> Id;
+    OnSessionTtl = "session-ttl" -> SessionTtl;
+}
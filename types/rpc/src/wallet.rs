@@ -0,0 +1,153 @@
+//! wallet's methods and notifications, the addresses, tokens and NFTs.
+//! chain: 1 ETH, 2 ERC20, 3 ERC721, 4 BTC. amount and balance are decimal
+//! string.
+
+use crate::node::EchoParams;
+use crate::{Empty, Id};
+
+record!(
+    /// is_gen is generated from the mnemonic, or imported.
+    Address {
+        id: i64,
+        chain: i64,
+        index: i64,
+        name: String,
+        address: String,
+        is_gen: bool,
+        main: bool,
+        balance: String,
+    }
+);
+
+record!(Token {
+    id: i64,
+    chain: i64,
+    network: i64,
+    name: String,
+    contract: String,
+    decimal: i64,
+});
+
+record!(TokenList {
+    network: i64,
+    tokens: Vec<Token>,
+});
+
+record!(WalletGenerateParams {
+    chain: i64,
+    lock: String,
+});
+
+record!(
+    /// secret is the hex secret key.
+    WalletImportParams {
+        chain: i64,
+        secret: String,
+        lock: String,
+    }
+);
+
+record!(
+    /// token is the token's id, if only load this token's balance.
+    WalletTokenParams {
+        network: i64,
+        address: String,
+        #[serde(default)]
+        token: Option<i64>,
+    }
+);
+
+record!(WalletTokenImportParams {
+    chain: i64,
+    network: i64,
+    address: String,
+    contract: String,
+});
+
+record!(
+    /// contract is empty for the main coin.
+    WalletGasPriceParams {
+        chain: i64,
+        network: i64,
+        from: String,
+        to: String,
+        amount: String,
+        contract: String,
+    }
+);
+
+record!(GasPrice {
+    price: String,
+    gas: String,
+});
+
+record!(
+    /// from is the address' id, amount is the NFT's token id in ERC721.
+    WalletTransferParams {
+        chain: i64,
+        network: i64,
+        from: i64,
+        to: String,
+        amount: String,
+        contract: String,
+        lock: String,
+    }
+);
+
+record!(
+    /// tx is the transaction's hash and the receiver.
+    Transferred {
+        from: i64,
+        network: i64,
+        tx: (String, String),
+    }
+);
+
+record!(
+    /// address and token are the ids.
+    WalletNftParams { address: i64, token: i64 }
+);
+
+record!(Nfts {
+    address: i64,
+    token: i64,
+    hashes: Vec<String>,
+});
+
+record!(Nft {
+    address: i64,
+    token: i64,
+    hash: String,
+});
+
+record!(
+    /// token is null for the main coin.
+    WalletBalance {
+        address: String,
+        network: i64,
+        balance: String,
+        #[serde(default)]
+        token: Option<Token>,
+    }
+);
+
+methods! {
+    WalletEcho = "wallet-echo" (EchoParams) -> EchoParams;
+    WalletList = "wallet-list" (Empty) -> Vec<Address>;
+    WalletGenerate = "wallet-generate" (WalletGenerateParams) -> Address;
+    WalletImport = "wallet-import" (WalletImportParams) -> Address;
+    /// the balances will be notified.
+    WalletTokenQuery = "wallet-token" (WalletTokenParams) -> TokenList;
+    /// the balance will be notified.
+    WalletTokenImport = "wallet-token-import" (WalletTokenImportParams);
+    WalletGasPrice = "wallet-gas-price" (WalletGasPriceParams) -> GasPrice;
+    WalletTransfer = "wallet-transfer" (WalletTransferParams) -> Transferred;
+    WalletNft = "wallet-nft" (WalletNftParams) -> Nfts;
+    /// check the owner and add the NFT.
+    WalletNftAdd = "wallet-nft-add" (Nft) -> Nft;
+    WalletMain = "wallet-main" (Id);
+}
+
+notifications! {
+    OnWalletBalance = "wallet-balance" -> WalletBalance;
+}